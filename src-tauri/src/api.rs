@@ -0,0 +1,465 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::capture::{capture_region, CaptureRegion};
+use crate::error::AppError;
+use crate::history::search::{search_history, HistoryFilters};
+use crate::history::{self, HistoryOrigin, DEFAULT_PAGE};
+use crate::japanese::kana::is_kana;
+use crate::{ocr, settings, translation};
+
+// Images for /ocr are the largest bodies anyone has reason to send
+const MAX_BODY: usize = 20 * 1024 * 1024;
+const MAX_HEADERS: usize = 64;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PAGE: usize = 500;
+// Requests handled at once; each has a thread
+const MAX_CONNECTIONS: usize = 16;
+
+// A REST server on 127.0.0.1 for scripts and editor plugins, so they can use
+// the configured provider without holding its key. Every request needs
+// `Authorization: Bearer <token>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Generated the first time the server starts
+    pub token: String,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47823,
+            token: String::new(),
+        }
+    }
+}
+
+impl ApiServerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port < 1024 {
+            return Err("API port must be 1024 or higher".to_string());
+        }
+        if !self.token.is_empty() && self.token.len() < 16 {
+            return Err("API token must be at least 16 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+struct Server {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    worker: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct ApiServerState(Mutex<(Option<Server>, Option<String>)>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    // Why the server isn't running although it is enabled, e.g. a taken port
+    pub error: Option<String>,
+}
+
+pub(crate) fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 24];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// Compares every byte so the time taken doesn't give away how much matched
+pub(crate) fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// Caps how many connections a local server serves at once. Each gets a
+// thread, so any local process could otherwise pile them up without end;
+// past the cap new ones are closed straight away.
+#[derive(Clone)]
+pub(crate) struct ConnectionLimit {
+    open: Arc<AtomicUsize>,
+    max: usize,
+}
+
+// Frees its place in the limit when the connection's thread is done
+pub(crate) struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionLimit {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            open: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    pub(crate) fn open(&self) -> usize {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn acquire(&self) -> Option<ConnectionSlot> {
+        self.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| (open < self.max).then_some(open + 1))
+            .ok()
+            .map(|_| ConnectionSlot(self.open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Request {
+    method: String,
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        self.url
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, ApiError> {
+        serde_json::from_slice(&self.body).map_err(|e| ApiError(400, format!("Invalid request body: {}", e)))
+    }
+}
+
+struct ApiError(u16, String);
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(500, message)
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        let status = match error {
+            AppError::NotFound(_) | AppError::WindowNotFound(_) => 404,
+            AppError::InvalidInput(_) | AppError::Settings(_) | AppError::Hotkey(_) => 400,
+            AppError::QuotaExceeded(_) | AppError::RateLimited(_) => 429,
+            AppError::Unauthorized(_) | AppError::Network(_) | AppError::Provider { .. } => 502,
+            AppError::Other(_) => 500,
+        };
+        ApiError(status, error.to_string())
+    }
+}
+
+// Checks the token as soon as the headers are in, so a client without it
+// never gets as far as making this buffer a body
+fn read_request(stream: &TcpStream, token: &str) -> Result<Request, ApiError> {
+    let bad = |message: &str| ApiError(400, message.to_string());
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| bad("Unreadable request"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad("Malformed request line"));
+    };
+    let method = method.to_string();
+    let url = Url::parse(&format!("http://localhost{}", target)).map_err(|_| bad("Malformed request path"))?;
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|_| bad("Unreadable request"))?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(bad("Too many headers"));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = Request {
+        method,
+        url,
+        headers,
+        body: Vec::new(),
+    };
+    let given = request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if !same_token(given.trim(), token) {
+        return Err(ApiError(401, "Missing or wrong API token".to_string()));
+    }
+    let length = match request.header("Content-Length") {
+        Some(length) => length.parse::<usize>().map_err(|_| bad("Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(ApiError(413, format!("Request bodies are limited to {} bytes", MAX_BODY)));
+    }
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .map_err(|_| bad("Request body is shorter than Content-Length"))?;
+    Ok(request)
+}
+
+fn respond(mut stream: TcpStream, status: u16, body: &Value) {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        429 => "Too Many Requests",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    if status == 401 {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    let _ = stream.write_all(format!("{}\r\n{}", head, body).as_bytes());
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateBody {
+    text: String,
+    source_lang: Option<String>,
+    target_lang: Option<String>,
+    // Leave the translation out of history
+    #[serde(default)]
+    skip_history: bool,
+}
+
+#[derive(Deserialize)]
+struct DetectBody {
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OcrBody {
+    region: CaptureRegion,
+    profile: Option<String>,
+    language: Option<String>,
+}
+
+fn page_param(request: &Request, name: &str) -> Result<Option<usize>, ApiError> {
+    request
+        .param(name)
+        .map(|value| value.parse::<usize>().map_err(|_| ApiError(400, format!("Invalid {}", name))))
+        .transpose()
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| ApiError(500, format!("Failed to encode response: {}", e)))
+}
+
+async fn route(app: &AppHandle, request: Request) -> Result<Value, ApiError> {
+    match (request.method.as_str(), request.url.path()) {
+        ("POST", "/translate") => {
+            let body: TranslateBody = request.json()?;
+            let result = translation::translate(app, &body.text, body.source_lang.as_deref(), body.target_lang.as_deref())
+                .await?;
+            if !body.skip_history {
+                history::record(app, &result, HistoryOrigin::Api, None);
+            }
+            encode(&result)
+        }
+        // Kana can only be Japanese; anything else is left to the provider,
+        // which counts against its quota like a translation
+        ("POST", "/detect") => {
+            let body: DetectBody = request.json()?;
+            if body.text.trim().is_empty() {
+                return Err(ApiError(400, "Nothing to detect".to_string()));
+            }
+            let language = if body.text.chars().any(is_kana) {
+                "ja".to_string()
+            } else {
+                translation::translate(app, &body.text, Some("auto"), None)
+                    .await?
+                    .source_lang
+            };
+            Ok(json!({ "language": language }))
+        }
+        ("GET", "/history") => {
+            let filters = HistoryFilters {
+                limit: Some(page_param(&request, "limit")?.unwrap_or(DEFAULT_PAGE).min(MAX_PAGE)),
+                offset: page_param(&request, "offset")?,
+                ..HistoryFilters::default()
+            };
+            let query = request.param("q").unwrap_or_default();
+            encode(&search_history(app.clone(), query, Some(filters)).await?)
+        }
+        // A PNG in the body, or a JSON screen region to capture
+        ("POST", "/ocr") => {
+            let content_type = request.header("Content-Type").unwrap_or("").to_ascii_lowercase();
+            let recognition = if content_type.starts_with("image/png") {
+                let image = image::load_from_memory_with_format(&request.body, image::ImageFormat::Png)
+                    .map_err(|e| ApiError(400, format!("Failed to decode image: {}", e)))?
+                    .to_rgba8();
+                ocr::recognize(app, image, request.param("profile"), request.param("language"), None).await?
+            } else if content_type.starts_with("application/json") {
+                let body: OcrBody = request.json()?;
+                let image = tauri::async_runtime::spawn_blocking(move || capture_region(&body.region))
+                    .await
+                    .map_err(|e| format!("Capture task failed: {}", e))??;
+                ocr::recognize(app, image, body.profile, body.language, None).await?
+            } else {
+                return Err(ApiError(415, "Send image/png or application/json".to_string()));
+            };
+            encode(&recognition)
+        }
+        (_, "/translate" | "/detect" | "/history" | "/ocr") => Err(ApiError(405, "Method not allowed".to_string())),
+        _ => Err(ApiError(404, "No such endpoint".to_string())),
+    }
+}
+
+fn handle(app: &AppHandle, stream: TcpStream, token: &str) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let result = match read_request(&stream, token) {
+        Ok(request) => tauri::async_runtime::block_on(route(app, request)),
+        Err(error) => Err(error),
+    };
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(ApiError(status, message)) => (status, json!({ "error": message })),
+    };
+    respond(stream, status, &body);
+}
+
+fn serve(app: AppHandle, listener: TcpListener, token: String, stop: Arc<AtomicBool>) {
+    let limit = ConnectionLimit::new(MAX_CONNECTIONS);
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        let (Ok(stream), Some(slot)) = (stream, limit.acquire()) else {
+            continue;
+        };
+        let app = app.clone();
+        let token = token.clone();
+        std::thread::spawn(move || {
+            handle(&app, stream, &token);
+            drop(slot);
+        });
+    }
+}
+
+fn stop(state: &ApiServerState) {
+    let Some(server) = state.0.lock().unwrap().0.take() else {
+        return;
+    };
+    server.stop.store(true, Ordering::SeqCst);
+    // Wakes the accept loop so it sees the flag and drops the listener
+    let _ = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, server.port).into(), READ_TIMEOUT);
+    let _ = server.worker.join();
+}
+
+fn start(app: &AppHandle, settings: &ApiServerSettings) -> Result<Server, String> {
+    let token = if settings.token.is_empty() {
+        let token = new_token()?;
+        settings::update(app, |s| s.api_server.token = token.clone())?;
+        token
+    } else {
+        settings.token.clone()
+    };
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))
+        .map_err(|e| format!("Failed to open port {}: {}", settings.port, e))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let app = app.clone();
+        let stop = stop.clone();
+        std::thread::Builder::new()
+            .name("api-server".to_string())
+            .spawn(move || serve(app, listener, token, stop))
+            .map_err(|e| format!("Failed to start the API server: {}", e))?
+    };
+    Ok(Server {
+        port: settings.port,
+        stop,
+        worker,
+    })
+}
+
+// Called at startup and whenever the server's settings change
+pub fn apply(app: &AppHandle) {
+    let state = app.state::<ApiServerState>();
+    stop(&state);
+    let settings = settings::load(app).api_server;
+    let mut current = state.0.lock().unwrap();
+    *current = (None, None);
+    if !settings.enabled {
+        return;
+    }
+    match start(app, &settings) {
+        Ok(server) => current.0 = Some(server),
+        Err(e) => {
+            tracing::error!("{}", e);
+            current.1 = Some(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_api_server_status(app: AppHandle) -> Result<ApiServerStatus, AppError> {
+    let state = app.state::<ApiServerState>();
+    let current = state.0.lock().unwrap();
+    Ok(ApiServerStatus {
+        running: current.0.is_some(),
+        port: current.0.as_ref().map(|server| server.port),
+        error: current.1.clone(),
+    })
+}
+
+// Settings only ever show the token masked; this is for copying it into a
+// script's configuration
+#[tauri::command]
+pub async fn get_api_token(app: AppHandle) -> Result<String, AppError> {
+    Ok(settings::load(&app).api_server.token)
+}
+
+// Locks out every client holding the old token
+#[tauri::command]
+pub async fn regenerate_api_token(app: AppHandle) -> Result<String, AppError> {
+    let token = new_token()?;
+    settings::update(&app, |s| s.api_server.token = token.clone())?;
+    apply(&app);
+    Ok(token)
+}
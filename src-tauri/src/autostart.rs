@@ -0,0 +1,239 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+// Passed by the login entry when the app should come up in the tray only
+pub const HIDDEN_FLAG: &str = "--minimized";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    // Start without showing the main window
+    pub hidden: bool,
+}
+
+// AppImages run from a temporary mount, so the login entry has to point at
+// the image itself
+pub(crate) fn executable() -> Result<PathBuf, String> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))
+}
+
+fn status(app: &AppHandle) -> Result<AutostartStatus, String> {
+    Ok(match platform::read_entry(app)? {
+        Some(entry) => AutostartStatus {
+            enabled: true,
+            hidden: entry.contains(HIDDEN_FLAG),
+        },
+        None => AutostartStatus {
+            enabled: false,
+            hidden: false,
+        },
+    })
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::PathBuf;
+    use tauri::{AppHandle, Manager};
+
+    fn plist_path(app: &AppHandle) -> Result<PathBuf, String> {
+        let home = app
+            .path()
+            .home_dir()
+            .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+        Ok(home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", app.config().identifier)))
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    pub fn read_entry(app: &AppHandle) -> Result<Option<String>, String> {
+        Ok(std::fs::read_to_string(plist_path(app)?).ok())
+    }
+
+    pub fn write_entry(app: &AppHandle, executable: &str, args: &[&str]) -> Result<(), String> {
+        let path = plist_path(app)?;
+        let arguments: String = std::iter::once(executable)
+            .chain(args.iter().copied())
+            .map(|arg| format!("        <string>{}</string>\n", escape(arg)))
+            .collect();
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n    <key>Label</key>\n    <string>{}</string>\n    <key>ProgramArguments</key>\n    <array>\n{}    </array>\n    <key>RunAtLoad</key>\n    <true/>\n</dict>\n</plist>\n",
+            escape(&app.config().identifier),
+            arguments
+        );
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create LaunchAgents folder: {}", e))?;
+        }
+        std::fs::write(&path, plist).map_err(|e| format!("Failed to write login item: {}", e))
+    }
+
+    pub fn remove_entry(app: &AppHandle) -> Result<(), String> {
+        match std::fs::remove_file(plist_path(app)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove login item: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::AppHandle;
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+    use windows::Win32::System::Registry::{
+        RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ,
+    };
+
+    const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+    fn value_name(app: &AppHandle) -> HSTRING {
+        HSTRING::from(app.package_info().name.as_str())
+    }
+
+    pub fn read_entry(app: &AppHandle) -> Result<Option<String>, String> {
+        let key = HSTRING::from(RUN_KEY);
+        let name = value_name(app);
+
+        let mut size = 0u32;
+        let status = unsafe { RegGetValueW(HKEY_CURRENT_USER, &key, &name, RRF_RT_REG_SZ, None, None, Some(&mut size)) };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        status.ok().map_err(|e| format!("Failed to read login item: {}", e))?;
+
+        let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+        unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                &key,
+                &name,
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        }
+        .ok()
+        .map_err(|e| format!("Failed to read login item: {}", e))?;
+
+        let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+        Ok(Some(String::from_utf16_lossy(&buffer[..len])))
+    }
+
+    pub fn write_entry(app: &AppHandle, executable: &str, args: &[&str]) -> Result<(), String> {
+        let command = std::iter::once(format!("\"{}\"", executable))
+            .chain(args.iter().map(|arg| arg.to_string()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let data: Vec<u16> = command.encode_utf16().chain(std::iter::once(0)).collect();
+
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(RUN_KEY),
+                &value_name(app),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                (data.len() * 2) as u32,
+            )
+        }
+        .ok()
+        .map_err(|e| format!("Failed to write login item: {}", e))
+    }
+
+    pub fn remove_entry(app: &AppHandle) -> Result<(), String> {
+        let status = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, &HSTRING::from(RUN_KEY), &value_name(app)) };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        status.ok().map_err(|e| format!("Failed to remove login item: {}", e))
+    }
+}
+
+// XDG autostart entry, honoured by GNOME, KDE and most other desktops
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::path::PathBuf;
+    use tauri::{AppHandle, Manager};
+
+    fn desktop_path(app: &AppHandle) -> Result<PathBuf, String> {
+        let config = app
+            .path()
+            .config_dir()
+            .map_err(|e| format!("Failed to resolve config directory: {}", e))?;
+        Ok(config
+            .join("autostart")
+            .join(format!("{}.desktop", app.config().identifier)))
+    }
+
+    pub fn read_entry(app: &AppHandle) -> Result<Option<String>, String> {
+        Ok(std::fs::read_to_string(desktop_path(app)?).ok())
+    }
+
+    pub fn write_entry(app: &AppHandle, executable: &str, args: &[&str]) -> Result<(), String> {
+        let path = desktop_path(app)?;
+        let exec = std::iter::once(format!("\"{}\"", executable.replace('"', "\\\"")))
+            .chain(args.iter().map(|arg| arg.to_string()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+            app.package_info().name,
+            exec
+        );
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create autostart folder: {}", e))?;
+        }
+        std::fs::write(&path, entry).map_err(|e| format!("Failed to write autostart entry: {}", e))
+    }
+
+    pub fn remove_entry(app: &AppHandle) -> Result<(), String> {
+        match std::fs::remove_file(desktop_path(app)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove autostart entry: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_autostart(app: AppHandle) -> Result<AutostartStatus, AppError> {
+    Ok(status(&app)?)
+}
+
+// `hidden` keeps its current value when left out
+#[tauri::command]
+pub async fn set_autostart(app: AppHandle, enabled: bool, hidden: Option<bool>) -> Result<AutostartStatus, AppError> {
+    if enabled {
+        let hidden = match hidden {
+            Some(hidden) => hidden,
+            None => status(&app)?.hidden,
+        };
+        let args: &[&str] = if hidden { &[HIDDEN_FLAG] } else { &[] };
+        platform::write_entry(&app, &executable()?.to_string_lossy(), args)?;
+    } else {
+        platform::remove_entry(&app)?;
+    }
+    Ok(status(&app)?)
+}
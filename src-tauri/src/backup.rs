@@ -0,0 +1,208 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::AppError;
+use crate::history::{self, thumbnails};
+use crate::portable;
+use crate::settings::{self, migrations, SettingsError};
+
+// Bumped when the archive layout changes, not for settings or history schema
+// changes; those carry their own versions
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const SETTINGS: &str = "settings.json";
+const HISTORY: &str = "history.sqlite3";
+const THUMBNAILS: &str = "thumbnails/";
+// Safety snapshots taken before a restore, under the data dir
+const SNAPSHOT_DIR: &str = "backups";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub settings_schema: u64,
+    pub history_schema: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub manifest: BackupManifest,
+    pub history_entries: usize,
+    pub thumbnails: usize,
+    // Archive of what was there before, for undoing the restore by hand
+    pub safety_backup: String,
+}
+
+fn zip_error(e: zip::result::ZipError) -> String {
+    format!("Backup archive error: {}", e)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize backup: {}", e))
+}
+
+fn add_file(archive: &mut ZipWriter<File>, name: &str, contents: &[u8]) -> Result<(), String> {
+    archive
+        .start_file(name, SimpleFileOptions::default())
+        .map_err(zip_error)?;
+    archive
+        .write_all(contents)
+        .map_err(|e| format!("Failed to write backup: {}", e))
+}
+
+// Settings go in without API keys, like an export. History is written
+// unencrypted; the archive is for the user to keep somewhere safe.
+pub fn create(app: &AppHandle, path: &Path) -> Result<BackupManifest, String> {
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        settings_schema: migrations::CURRENT_VERSION,
+        history_schema: history::schema_version(),
+        created_at: Utc::now(),
+    };
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut archive = ZipWriter::new(file);
+
+    add_file(&mut archive, MANIFEST, to_json(&manifest)?.as_bytes())?;
+    let settings = settings::shareable(&settings::load(app))?;
+    add_file(&mut archive, SETTINGS, to_json(&settings)?.as_bytes())?;
+
+    let snapshot = portable::data_dir(app)?.join(format!("{}.backup", HISTORY));
+    let written = history::snapshot(app, &snapshot).and_then(|_| {
+        std::fs::read(&snapshot).map_err(|e| format!("Failed to read history snapshot: {}", e))
+    });
+    let _ = std::fs::remove_file(&snapshot);
+    add_file(&mut archive, HISTORY, &written?)?;
+
+    if let Ok(files) = std::fs::read_dir(thumbnails::dir(app)?) {
+        for file in files.flatten() {
+            let name = file.file_name().to_string_lossy().into_owned();
+            if !thumbnails::is_name(&name) {
+                continue;
+            }
+            if let Ok(contents) = std::fs::read(file.path()) {
+                add_file(&mut archive, &format!("{}{}", THUMBNAILS, name), &contents)?;
+            }
+        }
+    }
+
+    archive.finish().map_err(zip_error)?;
+    Ok(manifest)
+}
+
+fn read_file(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(|_| format!("The backup has no {}", name))?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to read {} from backup: {}", name, e))?;
+    Ok(contents)
+}
+
+fn read_thumbnails(archive: &mut ZipArchive<File>) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let names: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| name.strip_prefix(THUMBNAILS))
+        .filter(|name| thumbnails::is_name(name))
+        .map(str::to_string)
+        .collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let contents = read_file(archive, &format!("{}{}", THUMBNAILS, name))?;
+            Ok((name, contents))
+        })
+        .collect()
+}
+
+fn restore_thumbnails(app: &AppHandle, files: Vec<(String, Vec<u8>)>) -> Result<usize, String> {
+    let dir = thumbnails::dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let count = files.len();
+    for (name, contents) in files {
+        std::fs::write(dir.join(name), contents).map_err(|e| format!("Failed to restore thumbnail: {}", e))?;
+    }
+    Ok(count)
+}
+
+// Everything in the archive is read and checked before anything is replaced,
+// and the current state is backed up first
+pub fn restore(app: &AppHandle, path: &Path) -> Result<RestoreReport, SettingsError> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(zip_error)?;
+    let manifest: BackupManifest = serde_json::from_slice(&read_file(&mut archive, MANIFEST)?)
+        .map_err(|e| format!("Not a Shunyaku backup: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION || manifest.history_schema > history::schema_version() {
+        return Err(format!(
+            "This backup was made by a newer version of Shunyaku ({})",
+            manifest.app_version
+        )
+        .into());
+    }
+    let shared: Map<String, Value> = serde_json::from_slice(&read_file(&mut archive, SETTINGS)?)
+        .map_err(|e| format!("Invalid settings in backup: {}", e))?;
+    let previous = settings::load(app);
+    let restored = settings::from_shared(&previous, shared, manifest.settings_schema)?;
+
+    let data_dir = portable::data_dir(app)?;
+    let staged = data_dir.join(format!("{}.restoring", HISTORY));
+    let thumbnail_files = read_thumbnails(&mut archive)?;
+    std::fs::write(&staged, read_file(&mut archive, HISTORY)?)
+        .map_err(|e| format!("Failed to unpack history: {}", e))?;
+
+    let safety_backup = safety_path(&data_dir);
+    let snapshot = std::fs::create_dir_all(safety_backup.parent().unwrap_or(&data_dir))
+        .map_err(|e| format!("Failed to create backup folder: {}", e))
+        .and_then(|_| create(app, &safety_backup));
+    if let Err(e) = snapshot {
+        let _ = std::fs::remove_file(&staged);
+        return Err(format!("Failed to back up current data, nothing was restored: {}", e).into());
+    }
+
+    // Settings go last: if history can't be replaced, the old settings still
+    // match the old data
+    let history_entries = history::replace(app, &staged)?;
+    let thumbnails = if restored.encrypt_history {
+        // Thumbnails are plain files, which encryption keeps off the disk
+        thumbnails::discard_all(app)?;
+        0
+    } else {
+        restore_thumbnails(app, thumbnail_files)?
+    };
+    settings::save(app, &restored)?;
+    settings::apply(app, &previous, &restored);
+
+    Ok(RestoreReport {
+        manifest,
+        history_entries,
+        thumbnails,
+        safety_backup: safety_backup.to_string_lossy().into_owned(),
+    })
+}
+
+fn safety_path(data_dir: &Path) -> PathBuf {
+    data_dir
+        .join(SNAPSHOT_DIR)
+        .join(format!("pre-restore-{}.zip", Utc::now().format("%Y%m%d-%H%M%S")))
+}
+
+#[tauri::command]
+pub async fn create_backup(app: AppHandle, path: String) -> Result<BackupManifest, AppError> {
+    Ok(create(&app, Path::new(&path))?)
+}
+
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, path: String) -> Result<RestoreReport, AppError> {
+    Ok(restore(&app, Path::new(&path))?)
+}
@@ -0,0 +1,270 @@
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::capture::{capture_region, CaptureRegion};
+use crate::error::AppError;
+use crate::ocr::profiles::{self, OcrEngineKind};
+use crate::ocr::{self, OcrCache};
+use crate::popup::{self, PopupAnchor};
+use crate::translation::{self, usage, ProviderKind};
+
+const DEFAULT_ITERATIONS: usize = 20;
+const MAX_ITERATIONS: usize = 200;
+// Provider and OCR rounds are slow and the provider ones go over the network
+const MAX_PROVIDER_ROUNDS: usize = 10;
+const MAX_OCR_ROUNDS: usize = 10;
+// The screen corner recognized for the OCR stage, where a menu bar or title
+// usually gives it some text
+const OCR_REGION: (u32, u32) = (480, 160);
+// Long enough for the window system to settle between popup shows
+const POPUP_PAUSE: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BenchmarkStage {
+    ClipboardRead,
+    // An OCR cache lookup that hits: hashing the image and finding it
+    CacheHit,
+    // An authenticated request that uses no quota
+    ProviderRoundTrip,
+    Ocr,
+    // Positioning and showing the already built popup window
+    PopupShow,
+}
+
+// In milliseconds
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResult {
+    pub stage: BenchmarkStage,
+    // None when the stage couldn't run, with the reason in error
+    pub stats: Option<LatencyStats>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MachineInfo {
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub cpu: String,
+    pub cores: usize,
+    pub total_memory: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub version: String,
+    pub machine: MachineInfo,
+    pub provider: ProviderKind,
+    pub ocr_engine: OcrEngineKind,
+    pub iterations: usize,
+    pub stages: Vec<StageResult>,
+    pub ran_at: DateTime<Utc>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Nearest-rank percentiles
+fn stats(mut samples: Vec<Duration>) -> LatencyStats {
+    samples.sort_unstable();
+    let count = samples.len();
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * count as f64).ceil() as usize;
+        millis(samples[rank.clamp(1, count) - 1])
+    };
+    LatencyStats {
+        samples: count,
+        min: millis(samples[0]),
+        mean: millis(samples.iter().sum::<Duration>()) / count as f64,
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p99: percentile(99.0),
+        max: millis(samples[count - 1]),
+    }
+}
+
+fn result(stage: BenchmarkStage, samples: Result<Vec<Duration>, String>) -> StageResult {
+    match samples {
+        Ok(samples) if !samples.is_empty() => StageResult {
+            stage,
+            stats: Some(stats(samples)),
+            error: None,
+        },
+        Ok(_) => StageResult {
+            stage,
+            stats: None,
+            error: Some("No samples".to_string()),
+        },
+        Err(e) => StageResult {
+            stage,
+            stats: None,
+            error: Some(e),
+        },
+    }
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (Duration, T) {
+    let started = Instant::now();
+    let value = f();
+    (started.elapsed(), value)
+}
+
+fn clipboard(app: &AppHandle, rounds: usize) -> Vec<Duration> {
+    // An empty or non-text clipboard is still a read
+    (0..rounds).map(|_| time(|| app.clipboard().read_text()).0).collect()
+}
+
+fn cache_hit(image: &RgbaImage, engine: OcrEngineKind, language: &str, rounds: usize) -> Vec<Duration> {
+    // A cache of its own, so the real one's entries and hit counts stay as they were
+    let mut cache = OcrCache::new(1);
+    cache.insert(ocr::cache_key(image, engine, language), Vec::new());
+    (0..rounds)
+        .map(|_| time(|| cache.get(ocr::cache_key(image, engine, language))).0)
+        .collect()
+}
+
+async fn provider(app: &AppHandle, rounds: usize) -> Result<Vec<Duration>, String> {
+    let config = translation::load_config(app)?;
+    config.api_key(config.provider)?;
+    let mut samples = Vec::new();
+    for _ in 0..rounds {
+        let started = Instant::now();
+        usage::fetch(app).await?;
+        samples.push(started.elapsed());
+    }
+    Ok(samples)
+}
+
+async fn recognition(
+    app: &AppHandle,
+    image: Result<RgbaImage, String>,
+    engine: OcrEngineKind,
+    language: &str,
+    rounds: usize,
+) -> Result<Vec<Duration>, String> {
+    let image = image?;
+    let mut samples = Vec::new();
+    for _ in 0..rounds {
+        let started = Instant::now();
+        ocr::recognize_uncached(app, image.clone(), engine, language.to_string()).await?;
+        samples.push(started.elapsed());
+    }
+    Ok(samples)
+}
+
+async fn popup_show(app: &AppHandle, rounds: usize) -> Result<Vec<Duration>, String> {
+    let mut samples = Vec::new();
+    for _ in 0..rounds {
+        let (elapsed, shown) = time(|| popup::show_near(app, PopupAnchor::Cursor));
+        shown?;
+        samples.push(elapsed);
+        popup::hide(app)?;
+        tokio::time::sleep(POPUP_PAUSE).await;
+    }
+    Ok(samples)
+}
+
+fn screen_corner(app: &AppHandle) -> Result<RgbaImage, String> {
+    let monitor = app
+        .primary_monitor()
+        .map_err(|e| format!("Failed to find the primary monitor: {}", e))?
+        .ok_or("No monitor to capture")?;
+    let position = monitor.position();
+    let region = CaptureRegion {
+        x: position.x,
+        y: position.y,
+        width: OCR_REGION.0.min(monitor.size().width),
+        height: OCR_REGION.1.min(monitor.size().height),
+    };
+    capture_region(&region)
+}
+
+fn machine() -> MachineInfo {
+    let system = System::new_with_specifics(
+        RefreshKind::new()
+            .with_cpu(CpuRefreshKind::new())
+            .with_memory(MemoryRefreshKind::new().with_ram()),
+    );
+    MachineInfo {
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        cpu: system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+        cores: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+        total_memory: system.total_memory(),
+    }
+}
+
+// Times each step of the hotkey flow on this machine. The popup flashes by
+// the cursor while it runs. `ocr_profile` picks the recognizer as capture
+// does; stages that can't run (no API key, no screen access) say why and the
+// rest still report.
+#[tauri::command]
+pub async fn run_benchmark(
+    app: AppHandle,
+    iterations: Option<usize>,
+    ocr_profile: Option<String>,
+) -> Result<BenchmarkReport, AppError> {
+    let iterations = iterations.unwrap_or(DEFAULT_ITERATIONS).clamp(1, MAX_ITERATIONS);
+    let profile = profiles::find_profile(&app, ocr_profile.as_deref().unwrap_or(profiles::DEFAULT_PROFILE))?;
+    let provider_kind = translation::load_config(&app)?.provider;
+
+    let capture_app = app.clone();
+    let image = tauri::async_runtime::spawn_blocking(move || screen_corner(&capture_app))
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))?;
+    // Cache hits cost the same for any image of the size
+    let sample = image
+        .clone()
+        .unwrap_or_else(|_| RgbaImage::new(OCR_REGION.0, OCR_REGION.1));
+
+    let stages = vec![
+        result(BenchmarkStage::ClipboardRead, Ok(clipboard(&app, iterations))),
+        result(
+            BenchmarkStage::CacheHit,
+            Ok(cache_hit(&sample, profile.engine, &profile.language, iterations)),
+        ),
+        result(
+            BenchmarkStage::ProviderRoundTrip,
+            provider(&app, iterations.min(MAX_PROVIDER_ROUNDS)).await,
+        ),
+        result(
+            BenchmarkStage::Ocr,
+            recognition(&app, image, profile.engine, &profile.language, iterations.min(MAX_OCR_ROUNDS)).await,
+        ),
+        result(BenchmarkStage::PopupShow, popup_show(&app, iterations).await),
+    ];
+
+    let machine = tauri::async_runtime::spawn_blocking(machine)
+        .await
+        .map_err(|e| format!("Failed to read machine info: {}", e))?;
+    Ok(BenchmarkReport {
+        version: app.package_info().version.to_string(),
+        machine,
+        provider: provider_kind,
+        ocr_engine: profile.engine,
+        iterations,
+        stages,
+        ran_at: Utc::now(),
+    })
+}
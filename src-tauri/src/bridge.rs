@@ -0,0 +1,381 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::{Message, WebSocket};
+
+use crate::api::{new_token, same_token, ConnectionLimit};
+use crate::error::AppError;
+use crate::history::{self, HistoryOrigin};
+use crate::settings;
+use crate::translation::{self, TranslationResult};
+
+// Origins browsers give extension pages, used when no origin is listed
+const EXTENSION_SCHEMES: [&str; 3] = ["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+const MAX_TEXT: usize = 5000;
+// Open extension connections at once; each holds a thread while it lasts
+const MAX_CONNECTIONS: usize = 32;
+// Connections that haven't sent a valid hello by then are dropped
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+// How often an idle connection checks whether the bridge was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// A WebSocket server on 127.0.0.1 for the companion browser extension. The
+// connection must come from an allowed origin and its first message must
+// carry the token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Generated the first time the bridge starts
+    pub token: String,
+    // e.g. chrome-extension://<id>; empty allows any extension
+    pub allowed_origins: Vec<String>,
+    // Translations each connection may request per minute
+    pub requests_per_minute: u32,
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47824,
+            token: String::new(),
+            allowed_origins: Vec::new(),
+            requests_per_minute: 30,
+        }
+    }
+}
+
+impl BridgeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port < 1024 {
+            return Err("Bridge port must be 1024 or higher".to_string());
+        }
+        if !self.token.is_empty() && self.token.len() < 16 {
+            return Err("Bridge token must be at least 16 characters".to_string());
+        }
+        if self.requests_per_minute == 0 || self.requests_per_minute > 600 {
+            return Err("Bridge rate limit must be between 1 and 600 requests per minute".to_string());
+        }
+        if let Some(origin) = self.allowed_origins.iter().find(|origin| !origin.contains("://")) {
+            return Err(format!("'{}' is not an origin", origin));
+        }
+        Ok(())
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/');
+        if self.allowed_origins.is_empty() {
+            return EXTENSION_SCHEMES.iter().any(|scheme| origin.starts_with(scheme));
+        }
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    }
+}
+
+struct Server {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    connections: ConnectionLimit,
+    worker: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct BridgeState(Mutex<(Option<Server>, Option<String>)>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub connections: usize,
+    pub error: Option<String>,
+}
+
+// What the extension sends. `id` is echoed back on everything answering a
+// translate request.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ClientMessage {
+    Hello {
+        token: String,
+    },
+    Translate {
+        id: u64,
+        text: String,
+        #[serde(rename = "sourceLang")]
+        source_lang: Option<String>,
+        #[serde(rename = "targetLang")]
+        target_lang: Option<String>,
+    },
+}
+
+struct Connection {
+    socket: WebSocket<TcpStream>,
+    recent: VecDeque<Instant>,
+}
+
+impl Connection {
+    fn send(&mut self, message: Value) -> bool {
+        self.socket.send(Message::text(message.to_string())).is_ok()
+    }
+
+    // Sliding window over the last minute of requests
+    fn limited(&mut self, per_minute: u32) -> bool {
+        let now = Instant::now();
+        while self.recent.front().is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW) {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= per_minute as usize {
+            return true;
+        }
+        self.recent.push_back(now);
+        false
+    }
+}
+
+// Paragraph by paragraph, each sent as soon as it is back, then the whole
+// result as one history entry
+fn translate(
+    app: &AppHandle,
+    connection: &mut Connection,
+    id: u64,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> Result<TranslationResult, String> {
+    let paragraphs: Vec<&str> = text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()).collect();
+    if paragraphs.is_empty() {
+        return Err("Nothing to translate".to_string());
+    }
+    let mut combined: Option<TranslationResult> = None;
+    for (index, paragraph) in paragraphs.iter().enumerate() {
+        let result = tauri::async_runtime::block_on(translation::translate(app, paragraph, source_lang, target_lang))?;
+        connection.send(json!({ "type": "chunk", "id": id, "index": index, "text": result.translated_text }));
+        combined = Some(match combined {
+            None => result,
+            Some(mut combined) => {
+                combined.original_text = format!("{}\n\n{}", combined.original_text, result.original_text);
+                combined.translated_text = format!("{}\n\n{}", combined.translated_text, result.translated_text);
+                combined.processing_time += result.processing_time;
+                combined
+            }
+        });
+    }
+    let result = combined.unwrap();
+    history::record(app, &result, HistoryOrigin::Browser, None);
+    Ok(result)
+}
+
+fn respond(app: &AppHandle, connection: &mut Connection, message: ClientMessage, settings: &BridgeSettings) {
+    let ClientMessage::Translate {
+        id,
+        text,
+        source_lang,
+        target_lang,
+    } = message
+    else {
+        connection.send(json!({ "type": "error", "error": "Already connected" }));
+        return;
+    };
+    let error = if connection.limited(settings.requests_per_minute) {
+        format!("Limited to {} translations a minute", settings.requests_per_minute)
+    } else if text.chars().count() > MAX_TEXT {
+        format!("Text is longer than {} characters", MAX_TEXT)
+    } else {
+        match translate(app, connection, id, &text, source_lang.as_deref(), target_lang.as_deref()) {
+            Ok(result) => {
+                connection.send(json!({ "type": "done", "id": id, "result": result }));
+                return;
+            }
+            Err(e) => e,
+        }
+    };
+    connection.send(json!({ "type": "error", "id": id, "error": error }));
+}
+
+fn is_timeout(error: &tungstenite::Error) -> bool {
+    matches!(error, tungstenite::Error::Io(e)
+        if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut))
+}
+
+fn serve_connection(app: &AppHandle, stream: TcpStream, settings: &BridgeSettings, stop: &AtomicBool) {
+    let _ = stream.set_read_timeout(Some(HELLO_TIMEOUT));
+    // The signature is tungstenite's
+    #[allow(clippy::result_large_err)]
+    let check_origin = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let origin = request
+            .headers()
+            .get("Origin")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        if settings.allows(origin) {
+            return Ok(response);
+        }
+        let mut rejection = ErrorResponse::new(Some(format!("Origin '{}' is not allowed", origin)));
+        *rejection.status_mut() = tungstenite::http::StatusCode::FORBIDDEN;
+        Err(rejection)
+    };
+    let Ok(socket) = tungstenite::accept_hdr(stream, check_origin) else {
+        return;
+    };
+    let _ = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL));
+    let mut connection = Connection {
+        socket,
+        recent: VecDeque::new(),
+    };
+
+    let connected = Instant::now();
+    let mut authenticated = false;
+    while !stop.load(Ordering::SeqCst) {
+        let text = match connection.socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return,
+            Ok(_) => continue,
+            Err(e) if is_timeout(&e) => {
+                if !authenticated && connected.elapsed() >= HELLO_TIMEOUT {
+                    break;
+                }
+                continue;
+            }
+            Err(_) => return,
+        };
+        let message = match serde_json::from_str::<ClientMessage>(text.as_str()) {
+            Ok(message) => message,
+            Err(e) => {
+                connection.send(json!({ "type": "error", "error": format!("Invalid message: {}", e) }));
+                continue;
+            }
+        };
+        if !authenticated {
+            let ClientMessage::Hello { token } = &message else {
+                connection.send(json!({ "type": "error", "error": "Send hello with the token first" }));
+                break;
+            };
+            if !same_token(token, &settings.token) {
+                connection.send(json!({ "type": "error", "error": "Wrong token" }));
+                break;
+            }
+            authenticated = true;
+            connection.send(json!({ "type": "ready", "version": app.package_info().version.to_string() }));
+            continue;
+        }
+        respond(app, &mut connection, message, settings);
+    }
+    let _ = connection.socket.close(None);
+    let _ = connection.socket.flush();
+}
+
+fn serve(app: AppHandle, listener: TcpListener, settings: BridgeSettings, stop: Arc<AtomicBool>, connections: ConnectionLimit) {
+    let settings = Arc::new(settings);
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        let (Ok(stream), Some(slot)) = (stream, connections.acquire()) else {
+            continue;
+        };
+        let (app, settings, stop) = (app.clone(), settings.clone(), stop.clone());
+        std::thread::spawn(move || {
+            serve_connection(&app, stream, &settings, &stop);
+            drop(slot);
+        });
+    }
+}
+
+// Open connections notice the stop flag within POLL_INTERVAL
+fn stop(state: &BridgeState) {
+    let Some(server) = state.0.lock().unwrap().0.take() else {
+        return;
+    };
+    server.stop.store(true, Ordering::SeqCst);
+    // Wakes the accept loop so it sees the flag and drops the listener
+    let _ = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, server.port).into(), HELLO_TIMEOUT);
+    let _ = server.worker.join();
+}
+
+fn start(app: &AppHandle, settings: &BridgeSettings) -> Result<Server, String> {
+    let mut settings = settings.clone();
+    if settings.token.is_empty() {
+        let token = new_token()?;
+        settings::update(app, |s| s.extension_bridge.token = token.clone())?;
+        settings.token = token;
+    }
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))
+        .map_err(|e| format!("Failed to open port {}: {}", settings.port, e))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let connections = ConnectionLimit::new(MAX_CONNECTIONS);
+    let port = settings.port;
+    let worker = {
+        let (app, stop, connections) = (app.clone(), stop.clone(), connections.clone());
+        std::thread::Builder::new()
+            .name("extension-bridge".to_string())
+            .spawn(move || serve(app, listener, settings, stop, connections))
+            .map_err(|e| format!("Failed to start the extension bridge: {}", e))?
+    };
+    Ok(Server {
+        port,
+        stop,
+        connections,
+        worker,
+    })
+}
+
+// Called at startup and whenever the bridge settings change; connections
+// made under the old settings are closed
+pub fn apply(app: &AppHandle) {
+    let state = app.state::<BridgeState>();
+    stop(&state);
+    let settings = settings::load(app).extension_bridge;
+    let mut current = state.0.lock().unwrap();
+    *current = (None, None);
+    if !settings.enabled {
+        return;
+    }
+    match start(app, &settings) {
+        Ok(server) => current.0 = Some(server),
+        Err(e) => {
+            tracing::error!("{}", e);
+            current.1 = Some(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_extension_bridge_status(app: AppHandle) -> Result<BridgeStatus, AppError> {
+    let state = app.state::<BridgeState>();
+    let current = state.0.lock().unwrap();
+    Ok(BridgeStatus {
+        running: current.0.is_some(),
+        port: current.0.as_ref().map(|server| server.port),
+        connections: current
+            .0
+            .as_ref()
+            .map_or(0, |server| server.connections.open()),
+        error: current.1.clone(),
+    })
+}
+
+// For pasting into the extension's options page
+#[tauri::command]
+pub async fn get_extension_bridge_token(app: AppHandle) -> Result<String, AppError> {
+    Ok(settings::load(&app).extension_bridge.token)
+}
+
+#[tauri::command]
+pub async fn regenerate_extension_bridge_token(app: AppHandle) -> Result<String, AppError> {
+    let token = new_token()?;
+    settings::update(&app, |s| s.extension_bridge.token = token.clone())?;
+    apply(&app);
+    Ok(token)
+}
@@ -0,0 +1,39 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use xcap::Monitor;
+
+// Screen rectangle in global physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn capture_region(region: &CaptureRegion) -> Result<RgbaImage, String> {
+    if region.width == 0 || region.height == 0 {
+        return Err("Capture region is empty".to_string());
+    }
+
+    let monitor = Monitor::from_point(region.x, region.y)
+        .map_err(|e| format!("Failed to find monitor for region: {}", e))?;
+    let monitor_x = monitor.x().map_err(|e| format!("Failed to read monitor position: {}", e))?;
+    let monitor_y = monitor.y().map_err(|e| format!("Failed to read monitor position: {}", e))?;
+
+    let screen = monitor
+        .capture_image()
+        .map_err(|e| format!("Failed to capture screen: {}", e))?;
+
+    // Clamp to the monitor; regions spanning displays are cut at the edge
+    let left = (region.x - monitor_x).max(0) as u32;
+    let top = (region.y - monitor_y).max(0) as u32;
+    if left >= screen.width() || top >= screen.height() {
+        return Err("Capture region is outside the monitor".to_string());
+    }
+    let width = region.width.min(screen.width() - left);
+    let height = region.height.min(screen.height() - top);
+
+    Ok(image::imageops::crop_imm(&screen, left, top, width, height).to_image())
+}
@@ -0,0 +1,220 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Builder, Context, Manager, Runtime, State};
+
+use crate::autostart::HIDDEN_FLAG;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::history::HistoryOrigin;
+use crate::hotkeys::HotkeyAction;
+use crate::metrics::MetricsState;
+use crate::monitoring;
+use crate::native_host;
+use crate::popup::{self, PopupAnchor};
+use crate::portable::PORTABLE_FLAG;
+use crate::settings::{self, profiles};
+use crate::translation::{self, queue::TranslationQueue};
+
+const TRANSLATE_FLAG: &str = "--translate";
+const PAUSED_FLAG: &str = "--paused";
+const PROFILE_FLAG: &str = "--profile";
+const CAPTURE_FLAG: &str = "--capture";
+const TEXT_FLAG: &str = "--text";
+const STDIN_FLAG: &str = "--stdin";
+const JSON_FLAG: &str = "--json";
+const FROM_FLAG: &str = "--from";
+const TO_FLAG: &str = "--to";
+// Chrome adds this on Windows when starting a native messaging host
+const PARENT_WINDOW_FLAG: &str = "--parent-window";
+
+// Flags understood at startup and, through single-instance forwarding, by an
+// app that is already running
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub translate: Option<String>,
+    pub paused: bool,
+    pub profile: Option<String>,
+    // Stay in the tray instead of showing the main window
+    pub minimized: bool,
+    pub capture: bool,
+    // Headless mode: translate this, or what arrives on stdin, print it and
+    // exit without opening a window
+    pub text: Option<String>,
+    pub stdin: bool,
+    // Print the whole result as JSON rather than the translated text
+    pub json: bool,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // Started by a browser as its native messaging host
+    pub native_host: bool,
+    // First bare argument: text to translate or a file to open
+    pub target: Option<String>,
+}
+
+// Values may follow as the next argument or after '=', e.g. --profile=Work.
+// Unknown flags are ignored so older launchers keep working.
+pub fn parse(args: &[String]) -> CliArgs {
+    let mut parsed = CliArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || inline.clone().or_else(|| args.next().cloned());
+        match flag {
+            TRANSLATE_FLAG => parsed.translate = value().filter(|text| !text.trim().is_empty()),
+            PROFILE_FLAG => parsed.profile = value(),
+            PAUSED_FLAG => parsed.paused = true,
+            HIDDEN_FLAG => parsed.minimized = true,
+            CAPTURE_FLAG => parsed.capture = true,
+            TEXT_FLAG => parsed.text = value(),
+            STDIN_FLAG => parsed.stdin = true,
+            JSON_FLAG => parsed.json = true,
+            FROM_FLAG => parsed.from = value(),
+            TO_FLAG => parsed.to = value(),
+            PORTABLE_FLAG | PARENT_WINDOW_FLAG => {}
+            _ if native_host::is_launch_arg(arg) => parsed.native_host = true,
+            _ if flag.starts_with("--") => eprintln!("Ignoring unknown flag: {}", flag),
+            _ if parsed.target.is_none() && !arg.trim().is_empty() => parsed.target = Some(arg.clone()),
+            _ => {}
+        }
+    }
+    parsed
+}
+
+pub fn from_env() -> CliArgs {
+    parse(&std::env::args().skip(1).collect::<Vec<_>>())
+}
+
+impl CliArgs {
+    pub fn is_headless(&self) -> bool {
+        self.text.is_some() || self.stdin
+    }
+}
+
+fn headless_input(args: &CliArgs) -> Result<String, String> {
+    let text = match &args.text {
+        Some(text) if !args.stdin => text.clone(),
+        _ => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|e| format!("Failed to read stdin: {}", e))?;
+            text
+        }
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Nothing to translate".to_string());
+    }
+    Ok(text.to_string())
+}
+
+// The settings store and the state a translation goes through (the provider
+// queue, usage metrics) and nothing else
+pub(crate) fn headless_builder<R: Runtime>(builder: Builder<R>) -> Builder<R> {
+    builder
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .manage(TranslationQueue::default())
+        .manage(MetricsState::default())
+}
+
+// An app with no windows and no tray, for modes that run alongside a running
+// app rather than being forwarded to it
+pub(crate) fn headless_app(mut context: Context) -> Result<tauri::App, String> {
+    context.config_mut().app.windows.clear();
+    #[allow(unused_mut)]
+    let mut app = headless_builder(Builder::default())
+        .build(context)
+        .map_err(|e| format!("Failed to start: {}", e))?;
+    #[cfg(target_os = "macos")]
+    app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+    if let Err(e) = settings::migrations::run(app.handle()) {
+        eprintln!("Settings migration failed: {}", e);
+    }
+    Ok(app)
+}
+
+// Translates with the same settings, provider and key as the app and
+// returns the exit code. It runs alongside a running app rather than being
+// forwarded to it, and never opens a window or the tray. Exit codes: 0 done,
+// 1 translation failed, 2 nothing to translate.
+pub fn run_headless(args: &CliArgs, context: Context) -> i32 {
+    // Release builds on Windows have no console of their own to print to
+    #[cfg(target_os = "windows")]
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+    let text = match headless_input(args) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let app = match headless_app(context) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let result = tauri::async_runtime::block_on(translation::translate(
+        app.handle(),
+        &text,
+        args.from.as_deref(),
+        args.to.as_deref(),
+    ));
+    match result {
+        Ok(result) if args.json => match serde_json::to_string(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize result: {}", e);
+                return 1;
+            }
+        },
+        Ok(result) => println!("{}", result.translated_text),
+        Err(e) => {
+            eprintln!("Translation failed: {}", e);
+            return 1;
+        }
+    }
+    0
+}
+
+// Set when --capture came with the first launch, before any page could
+// listen; the main window picks it up once loaded
+#[derive(Default)]
+pub struct PendingCapture(AtomicBool);
+
+// Profile and pause state apply before anything is shown. A capture for the
+// first launch waits for the frontend; a running app starts it right away.
+pub fn apply(app: &AppHandle, args: &CliArgs, startup: bool) {
+    if let Some(profile) = &args.profile {
+        if let Err(e) = profiles::switch(app, profile) {
+            eprintln!("Failed to switch profile: {}", e);
+        }
+    }
+    if args.paused {
+        monitoring::set_paused(app, true);
+    }
+    if args.capture {
+        if startup {
+            app.state::<PendingCapture>().0.store(true, Ordering::SeqCst);
+        } else {
+            events::broadcast(app, AppEvent::HotkeyTriggered(HotkeyAction::CaptureRegion));
+        }
+    }
+}
+
+// Shows the usual popup and hands back the translated text for printing
+pub async fn translate(app: &AppHandle, text: &str) -> Result<String, String> {
+    let result = popup::show_translation(app, text, PopupAnchor::Cursor, HistoryOrigin::Manual, None, None).await?;
+    Ok(result.translated_text)
+}
+
+#[tauri::command]
+pub async fn take_pending_capture(pending: State<'_, PendingCapture>) -> Result<bool, AppError> {
+    Ok(pending.0.swap(false, Ordering::SeqCst))
+}
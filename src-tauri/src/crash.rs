@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::logging::LogState;
+use crate::portable;
+
+const REPORT_PREFIX: &str = "crash-";
+// Log lines copied into each report
+const LOG_LINES: usize = 200;
+// Older reports are deleted at startup past this
+const MAX_REPORTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub file_name: String,
+    pub path: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub size: u64,
+    // The panic message, from the report's first lines
+    pub summary: String,
+}
+
+fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(portable::data_dir(app)?.join("crashes"))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Panic with a non-string payload".to_string()
+    }
+}
+
+fn report(app: &AppHandle, message: &str, location: Option<String>) -> String {
+    let thread = std::thread::current();
+    let mut report = String::new();
+    let _ = writeln!(report, "Panic: {}", message);
+    if let Some(location) = location {
+        let _ = writeln!(report, "Location: {}", location);
+    }
+    let _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("unnamed"));
+    let _ = writeln!(report, "Time: {}", Utc::now().to_rfc3339());
+    let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "OS: {} {} ({})", std::env::consts::OS, std::env::consts::ARCH, std::env::consts::FAMILY);
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "\nRecent log:");
+    if let Some(logs) = app.try_state::<LogState>() {
+        for entry in logs.tail(LOG_LINES) {
+            let time = entry.timestamp.to_rfc3339();
+            let _ = writeln!(report, "{} {:>5} {}: {}", time, entry.level, entry.target, entry.message);
+        }
+    }
+    report
+}
+
+fn prune(dir: &Path) {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).filter(|path| is_report(path)).collect())
+        .unwrap_or_default();
+    // Names carry the time, so they sort oldest first
+    reports.sort();
+    let excess = reports.len().saturating_sub(MAX_REPORTS);
+    for path in &reports[..excess] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn is_report(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(REPORT_PREFIX) && name.ends_with(".txt"))
+}
+
+// Writes a report for every panic, on any thread, then hands over to the
+// default hook so the message still reaches stderr. Called right after
+// logging starts so reports can include its recent lines.
+pub fn install(app: &AppHandle) {
+    let dir = match crash_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::error!("Crash reports disabled: {}", e);
+            return;
+        }
+    };
+    prune(&dir);
+    let app = app.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info.location().map(|at| format!("{}:{}:{}", at.file(), at.line(), at.column()));
+        let report = report(&app, &panic_message(info.payload()), location);
+        let path = dir.join(format!("{}{}.txt", REPORT_PREFIX, Utc::now().format("%Y%m%d-%H%M%S%.3f")));
+        let written = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, report));
+        match written {
+            Ok(()) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        previous(info);
+    }));
+}
+
+fn summary(path: &Path) -> String {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| text.lines().next().map(|line| line.trim_start_matches("Panic: ").to_string()))
+        .unwrap_or_default()
+}
+
+// Newest first
+#[tauri::command]
+pub async fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, AppError> {
+    let dir = crash_dir(&app)?;
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_report(path))
+        .filter_map(|path| {
+            let meta = std::fs::metadata(&path).ok()?;
+            Some(CrashReport {
+                file_name: path.file_name()?.to_string_lossy().into_owned(),
+                created_at: meta.modified().ok().map(DateTime::<Utc>::from),
+                size: meta.len(),
+                summary: summary(&path),
+                path: path.to_string_lossy().into_owned(),
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(reports)
+}
+
+// Selects the file in the file manager, or opens `path` when it's a folder
+fn reveal(path: &Path) -> std::io::Result<std::process::Child> {
+    #[cfg(target_os = "macos")]
+    return match path.is_dir() {
+        true => Command::new("open").arg(path).spawn(),
+        false => Command::new("open").arg("-R").arg(path).spawn(),
+    };
+    #[cfg(target_os = "windows")]
+    return match path.is_dir() {
+        true => Command::new("explorer").arg(path).spawn(),
+        false => Command::new("explorer").arg(format!("/select,{}", path.display())).spawn(),
+    };
+    // File managers differ in how to select a file, so its folder is opened
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    return match path.is_dir() {
+        true => Command::new("xdg-open").arg(path).spawn(),
+        false => Command::new("xdg-open").arg(path.parent().unwrap_or(path)).spawn(),
+    };
+}
+
+// Shows a report in the file manager, or the reports folder without a name
+#[tauri::command]
+pub async fn reveal_crash_report(app: AppHandle, file_name: Option<String>) -> Result<(), AppError> {
+    let dir = crash_dir(&app)?;
+    let path = match file_name {
+        Some(name) => {
+            let path = dir.join(&name);
+            // Only plain names of reports in the folder
+            if name.contains(['/', '\\']) || !is_report(&path) || !path.is_file() {
+                return Err(AppError::NotFound(format!("Crash report {} not found", name)));
+            }
+            path
+        }
+        None => {
+            std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            dir
+        }
+    };
+    reveal(&path).map_err(|e| format!("Failed to open the file manager: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_crash_reports(app: AppHandle) -> Result<usize, AppError> {
+    let reports = list_crash_reports(app).await?;
+    let deleted = reports.iter().filter(|report| std::fs::remove_file(&report.path).is_ok()).count();
+    Ok(deleted)
+}
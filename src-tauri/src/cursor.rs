@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    // Position in available_monitors(), as taken by show_translation_overlay
+    pub index: usize,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+// Cursor location in global physical pixels, plus the monitor it is on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorInfo {
+    pub x: f64,
+    pub y: f64,
+    pub monitor: Option<MonitorInfo>,
+}
+
+// Listing monitors and reading the cursor are round trips to the window
+// server, and a hotkey press may do both several times before the popup is
+// up. Monitors change rarely and invalidate() drops them when they do; the
+// refresh is only a backstop for changes no window hears about.
+const MONITOR_REFRESH: Duration = Duration::from_secs(10);
+// Long enough to share one read across a single press
+const CURSOR_REFRESH: Duration = Duration::from_millis(30);
+
+#[derive(Default)]
+pub struct LayoutCache {
+    monitors: Option<(Instant, Vec<MonitorInfo>)>,
+    cursor: Option<(Instant, f64, f64)>,
+}
+
+pub type LayoutCacheState = Mutex<LayoutCache>;
+
+// Called when a display is added, removed, rearranged or rescaled
+pub fn invalidate(app: &AppHandle) {
+    let state = app.state::<LayoutCacheState>();
+    let mut cache = state.lock().unwrap();
+    cache.monitors = None;
+    cache.cursor = None;
+}
+
+pub fn monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let state = app.state::<LayoutCacheState>();
+    if let Some((at, monitors)) = &state.lock().unwrap().monitors {
+        if at.elapsed() < MONITOR_REFRESH {
+            return Ok(monitors.clone());
+        }
+    }
+
+    let monitors: Vec<MonitorInfo> = app
+        .available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        })
+        .collect();
+    state.lock().unwrap().monitors = Some((Instant::now(), monitors.clone()));
+    Ok(monitors)
+}
+
+// Monitor containing the given point in global physical pixels
+pub fn monitor_at(app: &AppHandle, x: f64, y: f64) -> Result<Option<MonitorInfo>, String> {
+    Ok(monitors(app)?.into_iter().find(|monitor| {
+        x >= monitor.x as f64
+            && y >= monitor.y as f64
+            && x < monitor.x as f64 + monitor.width as f64
+            && y < monitor.y as f64 + monitor.height as f64
+    }))
+}
+
+// Cursor location in global physical pixels
+pub fn position(app: &AppHandle) -> Result<(f64, f64), String> {
+    let state = app.state::<LayoutCacheState>();
+    if let Some((at, x, y)) = state.lock().unwrap().cursor {
+        if at.elapsed() < CURSOR_REFRESH {
+            return Ok((x, y));
+        }
+    }
+
+    let cursor = app
+        .cursor_position()
+        .map_err(|e| format!("Failed to read cursor position: {}", e))?;
+    state.lock().unwrap().cursor = Some((Instant::now(), cursor.x, cursor.y));
+    Ok((cursor.x, cursor.y))
+}
+
+pub fn cursor_info(app: &AppHandle) -> Result<CursorInfo, String> {
+    let (x, y) = position(app)?;
+    Ok(CursorInfo {
+        x,
+        y,
+        monitor: monitor_at(app, x, y)?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_cursor_position(app: AppHandle) -> Result<CursorInfo, AppError> {
+    Ok(cursor_info(&app)?)
+}
@@ -0,0 +1,201 @@
+use reqwest::Url;
+use tauri::AppHandle;
+
+use crate::history::HistoryOrigin;
+use crate::popup::{self, PopupAnchor};
+
+pub const SCHEME: &str = "shunyaku";
+// Any web page can open a link, so what one can ask for is kept small
+const MAX_TEXT: usize = 5000;
+
+// What a shunyaku:// link asks for, e.g.
+// shunyaku://translate?text=%E7%BF%BB%E8%A8%B3&from=ja&to=en
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    Translate {
+        text: String,
+        source_lang: Option<String>,
+        target_lang: Option<String>,
+    },
+}
+
+pub fn is_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", SCHEME)))
+}
+
+fn check_language(lang: &str) -> Result<String, String> {
+    if lang.is_empty() || lang.len() > 10 || !lang.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+        return Err(format!("Invalid language '{}' in link", lang));
+    }
+    Ok(lang.to_string())
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, String> {
+    let url = Url::parse(link).map_err(|e| format!("Invalid link: {}", e))?;
+    if !url.scheme().eq_ignore_ascii_case(SCHEME) {
+        return Err(format!("Not a {} link", SCHEME));
+    }
+    // shunyaku://translate puts the action in the host, shunyaku:translate
+    // in the path
+    let action = match url.host_str() {
+        Some(host) if !host.is_empty() => host.to_string(),
+        _ => url.path().trim_matches('/').to_string(),
+    };
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    match action.to_ascii_lowercase().as_str() {
+        "translate" => {
+            let text = param("text").ok_or("Link has no text to translate")?;
+            if text.chars().count() > MAX_TEXT {
+                return Err(format!("Linked text is longer than {} characters", MAX_TEXT));
+            }
+            Ok(DeepLink::Translate {
+                text,
+                source_lang: param("from").map(|lang| check_language(&lang)).transpose()?,
+                target_lang: param("to").map(|lang| check_language(&lang)).transpose()?,
+            })
+        }
+        _ => Err(format!("Unknown link action '{}'", action)),
+    }
+}
+
+// Links reach here from the command line of a first or forwarded launch, or
+// from the system on macOS, and go through the same popup as the hotkeys
+pub fn open(app: &AppHandle, link: &str) {
+    let link = match parse(link) {
+        Ok(link) => link,
+        Err(e) => {
+            tracing::warn!("Ignoring link: {}", e);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match link {
+            DeepLink::Translate {
+                text,
+                source_lang,
+                target_lang,
+            } => {
+                let translated = popup::show_translation(
+                    &app,
+                    &text,
+                    PopupAnchor::Cursor,
+                    HistoryOrigin::Link,
+                    source_lang.as_deref(),
+                    target_lang.as_deref(),
+                )
+                .await;
+                if let Err(e) = translated {
+                    tracing::error!("Failed to translate linked text: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// macOS reads the scheme from Info.plist. Elsewhere the app claims it for
+// the current user on every start, so the entry follows the executable when
+// it moves; portable copies leave the system alone.
+pub fn register(app: &AppHandle) {
+    if crate::portable::portable_dir().is_some() {
+        return;
+    }
+    if let Err(e) = platform::register(app) {
+        tracing::error!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn register(_app: &AppHandle) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::AppHandle;
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    use super::SCHEME;
+
+    // An empty name sets the key's default value
+    fn set(key: &str, name: &str, value: &str) -> Result<(), String> {
+        let data: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(key),
+                &HSTRING::from(name),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                (data.len() * 2) as u32,
+            )
+        }
+        .ok()
+        .map_err(|e| format!("Failed to write {}: {}", key, e))
+    }
+
+    pub fn register(app: &AppHandle) -> Result<(), String> {
+        let executable = std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+        let key = format!("Software\\Classes\\{}", SCHEME);
+        set(&key, "", &format!("URL:{}", app.package_info().name))?;
+        set(&key, "URL Protocol", "")?;
+        set(
+            &format!("{}\\shell\\open\\command", key),
+            "",
+            &format!("\"{}\" \"%1\"", executable.display()),
+        )
+    }
+}
+
+// A desktop entry for the scheme, made the default handler through xdg-mime.
+// Rewritten only when the executable has changed.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::process::Command;
+    use tauri::{AppHandle, Manager};
+
+    use super::SCHEME;
+
+    pub fn register(app: &AppHandle) -> Result<(), String> {
+        let executable = match std::env::var_os("APPIMAGE") {
+            Some(appimage) => std::path::PathBuf::from(appimage),
+            None => std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?,
+        };
+        let name = format!("{}-url-handler.desktop", app.config().identifier);
+        let dir = app
+            .path()
+            .data_dir()
+            .map_err(|e| format!("Failed to resolve data directory: {}", e))?
+            .join("applications");
+        let path = dir.join(&name);
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            app.package_info().name,
+            executable.display().to_string().replace('"', "\\\""),
+            SCHEME
+        );
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == entry) {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::write(&path, entry).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Command::new("xdg-mime")
+            .args(["default", &name, &format!("x-scheme-handler/{}", SCHEME)])
+            .status()
+            .map_err(|e| format!("Failed to run xdg-mime: {}", e))
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use sysinfo::Disks;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::permissions::{self, PermissionKind, PermissionState};
+use crate::translation::{self, usage};
+use crate::{history, hotkeys, portable};
+
+// Free space under the data dir below which history writes and model
+// downloads start to be at risk, and below which they're likely to fail
+const LOW_DISK: u64 = 500 * 1024 * 1024;
+const CRITICAL_DISK: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckCategory {
+    Provider,
+    Permissions,
+    Hotkeys,
+    Database,
+    Disk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    // Didn't apply, e.g. the provider check with no key to try
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    // Stable per check, e.g. "permission.accessibility"
+    pub id: String,
+    pub category: CheckCategory,
+    pub status: CheckStatus,
+    pub message: String,
+    // What the user can do about it
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn new(id: &str, category: CheckCategory, status: CheckStatus, message: String) -> Self {
+        Self {
+            id: id.to_string(),
+            category,
+            status,
+            message,
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticReport {
+    pub version: String,
+    pub os: &'static str,
+    pub checks: Vec<DiagnosticCheck>,
+    // The worst status among the checks
+    pub status: CheckStatus,
+    pub generated_at: DateTime<Utc>,
+}
+
+// Asks the provider for the quota, which needs both a reachable server and a
+// key it accepts, without touching the cached usage
+async fn provider(app: &AppHandle) -> DiagnosticCheck {
+    let id = "provider";
+    let config = match translation::load_config(app) {
+        Ok(config) => config,
+        Err(e) => return DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Failed, e),
+    };
+    let name = config.provider.display_name();
+    if let Err(e) = config.api_key(config.provider) {
+        return DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Skipped, e)
+            .with_hint("Add an API key in Settings to translate");
+    }
+    match usage::fetch(app).await {
+        Ok(usage) if usage.character_limit > 0 && usage.character_count >= usage.character_limit => {
+            DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Warning, usage.summary())
+                .with_hint("The quota for this billing period is used up")
+        }
+        Ok(usage) => DiagnosticCheck::new(
+            id,
+            CheckCategory::Provider,
+            CheckStatus::Ok,
+            format!("{} is reachable and accepts the key ({})", name, usage.summary()),
+        ),
+        Err(e @ AppError::Unauthorized(_)) => {
+            DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Failed, e.to_string())
+                .with_hint("Check the API key in Settings; it may have been revoked or mistyped")
+        }
+        Err(e @ AppError::Network(_)) => {
+            DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Failed, e.to_string())
+                .with_hint("Check the network connection and any proxy or firewall")
+        }
+        Err(e @ (AppError::QuotaExceeded(_) | AppError::RateLimited(_))) => {
+            DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Warning, e.to_string())
+        }
+        Err(e) => DiagnosticCheck::new(id, CheckCategory::Provider, CheckStatus::Failed, e.to_string()),
+    }
+}
+
+fn permission(kind: PermissionKind) -> DiagnosticCheck {
+    let (id, name) = match kind {
+        PermissionKind::ScreenRecording => ("permission.screenRecording", "Screen Recording"),
+        PermissionKind::Accessibility => ("permission.accessibility", "Accessibility"),
+    };
+    let status = permissions::permission_status(kind);
+    let (result, message) = match status.state {
+        PermissionState::Granted => (CheckStatus::Ok, format!("{} is granted", name)),
+        PermissionState::NotRequired => (CheckStatus::Ok, format!("{} needs no permission here", name)),
+        PermissionState::Denied => (CheckStatus::Failed, format!("{} is not granted", name)),
+        PermissionState::Unsupported => (CheckStatus::Warning, format!("{} isn't available in this session", name)),
+    };
+    let mut check = DiagnosticCheck::new(id, CheckCategory::Permissions, result, message);
+    check.hint = status.hint;
+    if status.requires_restart {
+        check.status = CheckStatus::Warning;
+        check.hint = Some("Restart the app for the new permission to apply".to_string());
+    }
+    check
+}
+
+fn shortcuts(app: &AppHandle) -> DiagnosticCheck {
+    let id = "hotkeys";
+    let bindings = hotkeys::current_bindings(app);
+    let bound: Vec<_> = bindings.iter().filter(|binding| !binding.accelerator.is_empty()).collect();
+    let failed: Vec<&str> = bound
+        .iter()
+        .filter(|binding| !binding.registered)
+        .map(|binding| binding.accelerator.as_str())
+        .collect();
+    if bound.is_empty() {
+        return DiagnosticCheck::new(id, CheckCategory::Hotkeys, CheckStatus::Warning, "No shortcuts are bound".into())
+            .with_hint("Bind a shortcut in Settings to translate without the tray");
+    }
+    if failed.is_empty() {
+        let message = format!("All {} bound shortcuts are registered", bound.len());
+        return DiagnosticCheck::new(id, CheckCategory::Hotkeys, CheckStatus::Ok, message);
+    }
+    let message = format!("{} of {} shortcuts aren't registered: {}", failed.len(), bound.len(), failed.join(", "));
+    DiagnosticCheck::new(id, CheckCategory::Hotkeys, CheckStatus::Failed, message)
+        .with_hint("Another app may own them; pick different shortcuts in Settings")
+}
+
+fn database(app: &AppHandle) -> DiagnosticCheck {
+    let id = "database.history";
+    match history::integrity_problems(app) {
+        Ok(problems) if problems.is_empty() => {
+            DiagnosticCheck::new(id, CheckCategory::Database, CheckStatus::Ok, "History database is intact".into())
+        }
+        Ok(problems) => DiagnosticCheck::new(
+            id,
+            CheckCategory::Database,
+            CheckStatus::Failed,
+            format!("History database is damaged: {}", problems.join("; ")),
+        )
+        .with_hint("Restore a backup, or export what's readable and reset history"),
+        Err(e) => DiagnosticCheck::new(id, CheckCategory::Database, CheckStatus::Failed, e)
+            .with_hint("If history is encrypted, check that the key is still in the system keychain"),
+    }
+}
+
+// The disk holding the data dir is the one with the longest mount point it
+// sits under
+fn free_space(dir: &Path) -> Option<u64> {
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn disk(app: &AppHandle) -> DiagnosticCheck {
+    let id = "disk";
+    let dir = match portable::data_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => return DiagnosticCheck::new(id, CheckCategory::Disk, CheckStatus::Failed, e),
+    };
+    let Some(available) = free_space(&dir) else {
+        let message = format!("Couldn't find the disk holding {}", dir.display());
+        return DiagnosticCheck::new(id, CheckCategory::Disk, CheckStatus::Skipped, message);
+    };
+    let message = format!("{} MB free for {}", available / (1024 * 1024), dir.display());
+    let status = if available < CRITICAL_DISK {
+        CheckStatus::Failed
+    } else if available < LOW_DISK {
+        CheckStatus::Warning
+    } else {
+        return DiagnosticCheck::new(id, CheckCategory::Disk, CheckStatus::Ok, message);
+    };
+    DiagnosticCheck::new(id, CheckCategory::Disk, status, message)
+        .with_hint("Free up space; history and downloads may fail to save")
+}
+
+// Everything a troubleshooting page needs in one go. Each check reports its
+// own failure, so one broken part never hides the others.
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Result<DiagnosticReport, AppError> {
+    let mut checks = vec![provider(&app).await];
+    checks.push(permission(PermissionKind::ScreenRecording));
+    checks.push(permission(PermissionKind::Accessibility));
+    checks.push(shortcuts(&app));
+    let local = app.clone();
+    let local = tauri::async_runtime::spawn_blocking(move || [database(&local), disk(&local)])
+        .await
+        .map_err(|e| format!("Failed to run diagnostics: {}", e))?;
+    checks.extend(local);
+
+    let status = if checks.iter().any(|check| check.status == CheckStatus::Failed) {
+        CheckStatus::Failed
+    } else if checks.iter().any(|check| check.status == CheckStatus::Warning) {
+        CheckStatus::Warning
+    } else {
+        CheckStatus::Ok
+    };
+    Ok(DiagnosticReport {
+        version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        checks,
+        status,
+        generated_at: Utc::now(),
+    })
+}
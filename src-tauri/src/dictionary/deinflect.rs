@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::sync::OnceLock;
+
+// Word classes a form can belong to, as bit flags. END marks a form nothing
+// else was conjugated onto yet (the word as selected, or what precedes たら);
+// TE and MASU are the te-form and the polite ます forms, which take
+// auxiliaries of their own.
+pub const V1: u16 = 1 << 0;
+pub const V5: u16 = 1 << 1;
+pub const VS: u16 = 1 << 2;
+pub const VK: u16 = 1 << 3;
+pub const ADJ_I: u16 = 1 << 4;
+const TE: u16 = 1 << 5;
+const MASU: u16 = 1 << 6;
+const END: u16 = 1 << 7;
+// The selected word itself can be anything
+const ANY: u16 = u16::MAX;
+
+// Longest chains seen in practice are around five steps
+// (食べさせられなかった); this bounds pathological input
+const MAX_STEPS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Inflection {
+    Negative,
+    Past,
+    Te,
+    Polite,
+    Volitional,
+    Imperative,
+    Provisional,
+    Conditional,
+    Potential,
+    Passive,
+    // られる forms, which can be either
+    PotentialOrPassive,
+    Causative,
+    Desire,
+    Progressive,
+    // てしまう / ちゃう
+    Completion,
+    // てある / ておく
+    Preparation,
+    Adverbial,
+    Noun,
+    // A する verb's noun, e.g. 勉強 for 勉強する
+    SuruNoun,
+}
+
+struct Rule {
+    from: String,
+    to: String,
+    // Classes the form must be in for the rule to apply, and the class of
+    // what it yields
+    types_in: u16,
+    types_out: u16,
+    reason: Inflection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub term: String,
+    pub types: u16,
+    // In the order they read: 食べられなかった is potential, negative, past
+    pub reasons: Vec<Inflection>,
+}
+
+// Endings of the godan rows: dictionary form, then the a-, i-, e- and
+// o-stems
+const GODAN: [(&str, &str, &str, &str, &str); 9] = [
+    ("う", "わ", "い", "え", "お"),
+    ("く", "か", "き", "け", "こ"),
+    ("ぐ", "が", "ぎ", "げ", "ご"),
+    ("す", "さ", "し", "せ", "そ"),
+    ("つ", "た", "ち", "て", "と"),
+    ("ぬ", "な", "に", "ね", "の"),
+    ("ぶ", "ば", "び", "べ", "ぼ"),
+    ("む", "ま", "み", "め", "も"),
+    ("る", "ら", "り", "れ", "ろ"),
+];
+
+// Past and te-forms of godan verbs, by the sound change before た/て
+const GODAN_PAST: [(&str, &str); 10] = [
+    ("った", "う"),
+    ("った", "つ"),
+    ("った", "る"),
+    ("いた", "く"),
+    ("いだ", "ぐ"),
+    ("した", "す"),
+    ("んだ", "ぬ"),
+    ("んだ", "ぶ"),
+    ("んだ", "む"),
+    // 行く is the one irregular godan verb
+    ("った", "く"),
+];
+
+fn rule(from: &str, to: &str, types_in: u16, types_out: u16, reason: Inflection) -> Rule {
+    Rule {
+        from: from.to_string(),
+        to: to.to_string(),
+        types_in,
+        types_out,
+        reason,
+    }
+}
+
+// Both the past and the te-form rule for an ending like "いた"
+fn past_and_te(rules: &mut Vec<Rule>, past: &str, to: &str, types_out: u16) {
+    let te = past.replace('た', "て").replace('だ', "で");
+    rules.push(rule(past, to, END, types_out, Inflection::Past));
+    rules.push(rule(&te, to, TE | END, types_out, Inflection::Te));
+}
+
+fn build_rules() -> Vec<Rule> {
+    use Inflection::*;
+    let mut rules = Vec::new();
+
+    for (dictionary, a, i, e, o) in GODAN {
+        let to = dictionary;
+        rules.push(rule(&format!("{}ない", a), to, ADJ_I, V5, Negative));
+        rules.push(rule(&format!("{}ます", i), to, MASU, V5, Polite));
+        rules.push(rule(&format!("{}たい", i), to, ADJ_I, V5, Desire));
+        rules.push(rule(&format!("{}れる", a), to, V1, V5, Passive));
+        rules.push(rule(&format!("{}せる", a), to, V1, V5, Causative));
+        rules.push(rule(&format!("{}される", a), to, V1, V5, Causative));
+        rules.push(rule(&format!("{}る", e), to, V1, V5, Potential));
+        rules.push(rule(e, to, END, V5, Imperative));
+        rules.push(rule(&format!("{}ば", e), to, END, V5, Provisional));
+        rules.push(rule(&format!("{}う", o), to, END, V5, Volitional));
+    }
+    for (past, to) in GODAN_PAST {
+        past_and_te(&mut rules, past, to, V5);
+    }
+
+    // Ichidan: the stem is the word minus る
+    for (from, reason) in [
+        ("ない", Negative),
+        ("ます", Polite),
+        ("たい", Desire),
+        ("られる", PotentialOrPassive),
+        ("させる", Causative),
+    ] {
+        let types_in = match reason {
+            Negative | Desire => ADJ_I,
+            Polite => MASU,
+            _ => V1,
+        };
+        rules.push(rule(from, "る", types_in, V1, reason));
+    }
+    past_and_te(&mut rules, "た", "る", V1);
+    rules.push(rule("ろ", "る", END, V1, Imperative));
+    rules.push(rule("よ", "る", END, V1, Imperative));
+    rules.push(rule("れば", "る", END, V1, Provisional));
+    rules.push(rule("よう", "る", END, V1, Volitional));
+
+    // する and 来る, which is written in kana or with its kanji
+    rules.push(rule("しない", "する", ADJ_I, VS, Negative));
+    rules.push(rule("します", "する", MASU, VS, Polite));
+    rules.push(rule("したい", "する", ADJ_I, VS, Desire));
+    past_and_te(&mut rules, "した", "する", VS);
+    for (kuru, ko, ki, kure) in [("くる", "こ", "き", "くれ"), ("来る", "来", "来", "来れ")] {
+        rules.push(rule(&format!("{}ない", ko), kuru, ADJ_I, VK, Negative));
+        rules.push(rule(&format!("{}ます", ki), kuru, MASU, VK, Polite));
+        rules.push(rule(&format!("{}たい", ki), kuru, ADJ_I, VK, Desire));
+        rules.push(rule(&format!("{}られる", ko), kuru, V1, VK, PotentialOrPassive));
+        rules.push(rule(&format!("{}させる", ko), kuru, V1, VK, Causative));
+        rules.push(rule(&format!("{}い", ko), kuru, END, VK, Imperative));
+        rules.push(rule(&format!("{}よう", ko), kuru, END, VK, Volitional));
+        rules.push(rule(&format!("{}ば", kure), kuru, END, VK, Provisional));
+        past_and_te(&mut rules, &format!("{}た", ki), kuru, VK);
+    }
+    rules.push(rule("される", "する", V1, VS, Passive));
+    rules.push(rule("させる", "する", V1, VS, Causative));
+    rules.push(rule("できる", "する", V1, VS, Potential));
+    rules.push(rule("しろ", "する", END, VS, Imperative));
+    rules.push(rule("せよ", "する", END, VS, Imperative));
+    rules.push(rule("しよう", "する", END, VS, Volitional));
+    rules.push(rule("すれば", "する", END, VS, Provisional));
+    rules.push(rule("する", "", VS, VS, SuruNoun));
+
+    // い-adjectives
+    rules.push(rule("くない", "い", ADJ_I, ADJ_I, Negative));
+    rules.push(rule("かった", "い", END, ADJ_I, Past));
+    rules.push(rule("くて", "い", TE | END, ADJ_I, Te));
+    rules.push(rule("く", "い", END, ADJ_I, Adverbial));
+    rules.push(rule("ければ", "い", END, ADJ_I, Provisional));
+    rules.push(rule("さ", "い", END, ADJ_I, Noun));
+
+    // The polite forms, and auxiliaries on the te-form
+    rules.push(rule("ました", "ます", END, MASU, Past));
+    rules.push(rule("ません", "ます", END, MASU, Negative));
+    rules.push(rule("ませんでした", "ません", END, END, Past));
+    rules.push(rule("ましょう", "ます", END, MASU, Volitional));
+    rules.push(rule("まして", "ます", TE | END, MASU, Te));
+    rules.push(rule("ている", "て", V1, TE, Progressive));
+    rules.push(rule("でいる", "で", V1, TE, Progressive));
+    rules.push(rule("てる", "て", V1, TE, Progressive));
+    rules.push(rule("でる", "で", V1, TE, Progressive));
+    rules.push(rule("てしまう", "て", V5, TE, Completion));
+    rules.push(rule("でしまう", "で", V5, TE, Completion));
+    rules.push(rule("ちゃう", "て", V5, TE, Completion));
+    rules.push(rule("じゃう", "で", V5, TE, Completion));
+    rules.push(rule("ておく", "て", V5, TE, Preparation));
+    rules.push(rule("でおく", "で", V5, TE, Preparation));
+    rules.push(rule("てある", "て", V5, TE, Preparation));
+    rules.push(rule("たら", "た", END, END, Conditional));
+    rules.push(rule("だら", "だ", END, END, Conditional));
+    rules
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    RULES.get_or_init(build_rules)
+}
+
+// Every form `word` could have been conjugated from, itself included first.
+// Most are not real words; the dictionary lookup weeds them out.
+pub fn deinflect(word: &str) -> Vec<Candidate> {
+    let mut candidates = vec![Candidate {
+        term: word.to_string(),
+        types: ANY,
+        reasons: Vec::new(),
+    }];
+    let mut index = 0;
+    while index < candidates.len() {
+        let current = candidates[index].clone();
+        index += 1;
+        if current.reasons.len() >= MAX_STEPS {
+            continue;
+        }
+        for rule in rules() {
+            if current.types & rule.types_in == 0 || !current.term.ends_with(&rule.from) {
+                continue;
+            }
+            let stem = &current.term[..current.term.len() - rule.from.len()];
+            if stem.is_empty() && rule.to.is_empty() {
+                continue;
+            }
+            let candidate = Candidate {
+                term: format!("{}{}", stem, rule.to),
+                types: rule.types_out,
+                reasons: [vec![rule.reason], current.reasons.clone()].concat(),
+            };
+            if !candidates
+                .iter()
+                .any(|existing| existing.term == candidate.term && existing.types == candidate.types)
+            {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+// Classes of a JMdict part-of-speech code; 0 for ones that don't conjugate
+pub fn word_types(pos: &str) -> u16 {
+    match pos {
+        "v1" | "v1-s" => V1,
+        "vk" => VK,
+        "adj-i" | "adj-ix" => ADJ_I,
+        pos if pos.starts_with("v5") => V5,
+        pos if pos.starts_with("vs") => VS,
+        _ => 0,
+    }
+}
+
+// The selected word matches anything; a deinflected form only entries of
+// the class it was deinflected to
+pub fn matches(candidate: &Candidate, types: u16) -> bool {
+    candidate.types == ANY || candidate.types & types != 0
+}
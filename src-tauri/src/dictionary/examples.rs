@@ -0,0 +1,176 @@
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+use std::io::BufRead;
+use tauri::AppHandle;
+
+use super::{with_db, DictionaryKind, MAX_LIMIT};
+use crate::error::AppError;
+use crate::japanese::kana::{normalize_width, to_hiragana};
+
+// Tatoeba's Japanese-English pairs in the EDRDG's examples.utf, where each
+// "A:" line (sentence, tab, translation, #ID=ja_en) is followed by a "B:"
+// line indexing its words
+pub const FORMAT_VERSION: i64 = 1;
+
+pub const SCHEMA: &str = "
+    CREATE TABLE sentences (
+        id INTEGER PRIMARY KEY,
+        japanese TEXT NOT NULL,
+        english TEXT NOT NULL,
+        japanese_id INTEGER,
+        english_id INTEGER
+    );
+    CREATE TABLE words (
+        term TEXT NOT NULL,
+        reading TEXT,
+        -- The word as it appears in the sentence, e.g. 食べた for 食べる
+        form TEXT NOT NULL,
+        -- Marked ~ in the index: a checked, good example of the word
+        checked INTEGER NOT NULL,
+        sentence_id INTEGER NOT NULL REFERENCES sentences (id)
+    );
+";
+pub const INDEXES: &str = "
+    CREATE INDEX words_term ON words (term);
+    CREATE INDEX words_reading ON words (reading);
+";
+const DEFAULT_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExampleSentence {
+    pub japanese: String,
+    pub english: String,
+    // Tatoeba sentence numbers, for linking to the originals
+    pub japanese_id: Option<u64>,
+    pub english_id: Option<u64>,
+    pub form: String,
+    // Character offsets of `form` in the Japanese sentence
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub checked: bool,
+}
+
+struct IndexedWord {
+    term: String,
+    reading: Option<String>,
+    form: Option<String>,
+    checked: bool,
+}
+
+// e.g. 彼(かれ)[01]{彼の}~: headword, reading, sense number, form in the
+// sentence and the checked mark, all but the headword optional
+fn parse_word(word: &str) -> Option<IndexedWord> {
+    let term_end = word.find(['(', '[', '{', '~']).unwrap_or(word.len());
+    let term = &word[..term_end];
+    if term.is_empty() {
+        return None;
+    }
+    let between = |open: char, close: char| {
+        let start = word[term_end..].find(open)? + term_end + open.len_utf8();
+        let end = word[start..].find(close)? + start;
+        Some(word[start..end].to_string())
+    };
+    Some(IndexedWord {
+        term: term.to_string(),
+        reading: between('(', ')').map(|reading| to_hiragana(&reading)),
+        form: between('{', '}'),
+        checked: word.ends_with('~'),
+    })
+}
+
+fn parse_ids(ids: &str) -> (Option<u64>, Option<u64>) {
+    let mut ids = ids.split('_').map(|id| id.trim().parse().ok());
+    (ids.next().flatten(), ids.next().flatten())
+}
+
+pub fn fill(transaction: &Transaction, source: impl BufRead) -> Result<usize, String> {
+    let write_error = |e: rusqlite::Error| format!("Failed to write dictionary: {}", e);
+    let mut count = 0;
+    let mut sentence: Option<i64> = None;
+    let mut insert_sentence = transaction
+        .prepare("INSERT INTO sentences (japanese, english, japanese_id, english_id) VALUES (?1, ?2, ?3, ?4)")
+        .map_err(write_error)?;
+    let mut insert_word = transaction
+        .prepare("INSERT INTO words (term, reading, form, checked, sentence_id) VALUES (?1, ?2, ?3, ?4, ?5)")
+        .map_err(write_error)?;
+    for line in source.lines() {
+        let line = line.map_err(|e| format!("Failed to read example sentences: {}", e))?;
+        if let Some(pair) = line.strip_prefix("A: ") {
+            sentence = None;
+            let Some((japanese, rest)) = pair.split_once('\t') else {
+                continue;
+            };
+            let (english, ids) = rest.split_once("#ID=").unwrap_or((rest, ""));
+            let (japanese_id, english_id) = parse_ids(ids);
+            insert_sentence
+                .execute(params![japanese.trim(), english.trim(), japanese_id, english_id])
+                .map_err(write_error)?;
+            sentence = Some(transaction.last_insert_rowid());
+            count += 1;
+        } else if let (Some(words), Some(sentence_id)) = (line.strip_prefix("B: "), sentence) {
+            for word in words.split_whitespace().filter_map(parse_word) {
+                let form = word.form.unwrap_or_else(|| word.term.clone());
+                insert_word
+                    .execute(params![word.term, word.reading, form, word.checked, sentence_id])
+                    .map_err(write_error)?;
+            }
+        }
+    }
+    Ok(count)
+}
+
+// Checked examples first, then shorter sentences, which make better cards
+pub fn lookup(conn: &Connection, word: &str, limit: usize) -> rusqlite::Result<Vec<ExampleSentence>> {
+    let mut statement = conn.prepare_cached(
+        "SELECT s.japanese, s.english, s.japanese_id, s.english_id, w.form, max(w.checked) AS checked \
+         FROM words w JOIN sentences s ON s.id = w.sentence_id \
+         WHERE w.term = ?1 OR w.reading = ?2 \
+         GROUP BY s.id ORDER BY checked DESC, length(s.japanese) LIMIT ?3",
+    )?;
+    let rows = statement.query_map(params![word, to_hiragana(word), limit as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<u64>>(2)?,
+            row.get::<_, Option<u64>>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, bool>(5)?,
+        ))
+    })?;
+    rows.map(|row| {
+        let (japanese, english, japanese_id, english_id, form, checked) = row?;
+        let start = japanese.find(&form).map(|byte| japanese[..byte].chars().count());
+        Ok(ExampleSentence {
+            end: start.map(|start| start + form.chars().count()),
+            start,
+            japanese,
+            english,
+            japanese_id,
+            english_id,
+            form,
+            checked,
+        })
+    })
+    .collect()
+}
+
+pub fn find(app: &AppHandle, word: &str, limit: usize) -> Result<Vec<ExampleSentence>, String> {
+    let word = normalize_width(word.trim());
+    if word.is_empty() {
+        return Ok(Vec::new());
+    }
+    with_db(app, DictionaryKind::Examples, |conn| lookup(conn, &word, limit.min(MAX_LIMIT)))
+}
+
+// Sentences using `word`, given in its dictionary form (食べる) or kana
+#[tauri::command]
+pub async fn find_example_sentences(
+    app: AppHandle,
+    word: String,
+    limit: Option<usize>,
+) -> Result<Vec<ExampleSentence>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || find(&app, &word, limit.unwrap_or(DEFAULT_LIMIT)))
+        .await
+        .map_err(|e| format!("Example lookup failed: {}", e))??)
+}
@@ -0,0 +1,185 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use tauri::AppHandle;
+
+use super::{with_db, DictionaryKind, WordMatch};
+use crate::error::AppError;
+use crate::japanese::tokenizer::{self, Token};
+use crate::settings;
+
+pub const FORMAT_VERSION: i64 = 1;
+
+pub const SCHEMA: &str = "
+    CREATE TABLE frequency (
+        term TEXT PRIMARY KEY,
+        rank INTEGER NOT NULL
+    );
+";
+
+// Parts of speech worth flagging as rare; particles and symbols aren't
+const CONTENT_WORDS: [&str; 5] = ["名詞", "動詞", "形容詞", "副詞", "連体詞"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FrequencyBucket {
+    // Top 1,500: the core vocabulary of most text
+    VeryCommon,
+    // Up to 5,000
+    Common,
+    // Up to 15,000
+    Uncommon,
+    // Up to 30,000
+    Rare,
+    // Further down, or not in the list at all
+    VeryRare,
+}
+
+impl FrequencyBucket {
+    fn of(rank: Option<u32>) -> Self {
+        match rank {
+            Some(0..=1_500) => FrequencyBucket::VeryCommon,
+            Some(1_501..=5_000) => FrequencyBucket::Common,
+            Some(5_001..=15_000) => FrequencyBucket::Uncommon,
+            Some(15_001..=30_000) => FrequencyBucket::Rare,
+            _ => FrequencyBucket::VeryRare,
+        }
+    }
+
+    pub fn is_rare(self) -> bool {
+        self >= FrequencyBucket::Rare
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Frequency {
+    // 1 is the most frequent word; None when the word isn't listed
+    pub rank: Option<u32>,
+    pub bucket: FrequencyBucket,
+}
+
+impl Frequency {
+    pub fn new(rank: Option<u32>) -> Self {
+        Frequency {
+            rank,
+            bucket: FrequencyBucket::of(rank),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordFrequency {
+    pub surface: String,
+    pub base_form: String,
+    pub start: usize,
+    pub end: usize,
+    // None for particles, punctuation and the like
+    pub frequency: Option<Frequency>,
+    // Set for rare content words while highlighting is on
+    pub highlight: bool,
+}
+
+// A ranked word list, most frequent first: one word per line, optionally
+// followed by its rank after a tab or comma. Lines starting with # are
+// skipped.
+pub fn fill(transaction: &Transaction, source: impl BufRead) -> Result<usize, String> {
+    let mut count = 0;
+    let mut line_rank = 0;
+    for line in source.lines() {
+        let line = line.map_err(|e| format!("Failed to read frequency list: {}", e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        line_rank += 1;
+        let columns: Vec<&str> = line.split(['\t', ',']).map(str::trim).collect();
+        let rank = columns
+            .iter()
+            .skip(1)
+            .rev()
+            .find_map(|column| column.parse::<u32>().ok())
+            .unwrap_or(line_rank);
+        transaction
+            .execute(
+                "INSERT INTO frequency (term, rank) VALUES (?1, ?2) \
+                 ON CONFLICT (term) DO UPDATE SET rank = min(rank, excluded.rank)",
+                params![columns[0], rank],
+            )
+            .map_err(|e| format!("Failed to write dictionary: {}", e))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+pub fn rank(conn: &Connection, term: &str) -> rusqlite::Result<Option<u32>> {
+    conn.prepare_cached("SELECT rank FROM frequency WHERE term = ?1")?
+        .query_row([term], |row| row.get(0))
+        .optional()
+}
+
+// Ranks dictionary results by the form they were found under, falling back
+// to the entry's other spellings, when a list is installed
+pub fn annotate(app: &AppHandle, matches: &mut [WordMatch]) {
+    let _ = with_db(app, DictionaryKind::Frequency, |conn| {
+        for found in matches.iter_mut() {
+            let forms = std::iter::once(&found.dictionary_form)
+                .chain(found.entry.kanji.iter().map(|kanji| &kanji.text))
+                .chain(found.entry.readings.iter().map(|reading| &reading.text));
+            let mut best = None;
+            for form in forms {
+                if let Some(rank) = rank(conn, form)? {
+                    best = Some(best.map_or(rank, |best: u32| best.min(rank)));
+                    if form == &found.dictionary_form {
+                        break;
+                    }
+                }
+            }
+            found.frequency = Some(Frequency::new(best));
+        }
+        Ok(())
+    });
+}
+
+pub fn is_content_word(token: &Token) -> bool {
+    token
+        .pos
+        .first()
+        .is_some_and(|pos| CONTENT_WORDS.contains(&pos.as_str()))
+}
+
+fn words(app: &AppHandle, text: &str) -> Result<Vec<WordFrequency>, String> {
+    let highlight = settings::load(app).highlight_rare_words;
+    let tokens = tokenizer::tokenize(app, text)?;
+    with_db(app, DictionaryKind::Frequency, |conn| {
+        tokens
+            .into_iter()
+            .map(|token| {
+                let frequency = if is_content_word(&token) {
+                    Some(Frequency::new(rank(conn, &token.base_form)?))
+                } else {
+                    None
+                };
+                Ok(WordFrequency {
+                    highlight: highlight
+                        && token.known
+                        && frequency.as_ref().is_some_and(|frequency| frequency.bucket.is_rare()),
+                    frequency,
+                    surface: token.surface,
+                    base_form: token.base_form,
+                    start: token.start,
+                    end: token.end,
+                })
+            })
+            .collect()
+    })
+}
+
+// Frequency of each word in `text`, for marking the rare ones in a panel
+#[tauri::command]
+pub async fn get_word_frequencies(app: AppHandle, text: String) -> Result<Vec<WordFrequency>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || words(&app, &text))
+        .await
+        .map_err(|e| format!("Frequency lookup failed: {}", e))??)
+}
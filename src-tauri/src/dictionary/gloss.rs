@@ -0,0 +1,157 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::frequency::{self, Frequency};
+use super::{lookup, Inflection, WordMatch};
+use crate::error::AppError;
+use crate::japanese::kana::to_hiragana;
+use crate::japanese::tokenizer::{self, Token};
+use crate::settings;
+use crate::translation::{self, TranslationResult};
+
+// Entries kept per word; the panel shows the first and folds the rest
+const ENTRIES_PER_WORD: usize = 3;
+
+// A word as a reader would see it: tokens of one inflected form joined back
+// together, e.g. 食べ + られ + なかっ + た
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossedWord {
+    pub surface: String,
+    // Character offsets into the text
+    pub start: usize,
+    pub end: usize,
+    // Hiragana; None when the analyzer didn't know a token
+    pub reading: Option<String>,
+    pub pos: Vec<String>,
+    // From the best entry, e.g. 食べる with [passive, negative, past]
+    pub dictionary_form: Option<String>,
+    pub inflections: Vec<Inflection>,
+    pub entries: Vec<WordMatch>,
+    pub frequency: Option<Frequency>,
+    // Set for rare content words while highlighting is on
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentenceGloss {
+    pub words: Vec<GlossedWord>,
+    pub translation: Option<TranslationResult>,
+    // The breakdown doesn't depend on the translation, so it's returned
+    // even when that fails
+    pub translation_error: Option<String>,
+}
+
+fn is_inflecting(token: &Token) -> bool {
+    matches!(token.pos.first().map(String::as_str), Some("動詞" | "形容詞"))
+}
+
+// Auxiliaries and the like that belong to the verb or adjective before them
+fn continues(token: &Token) -> bool {
+    let pos: Vec<&str> = token.pos.iter().map(String::as_str).collect();
+    match pos.as_slice() {
+        ["助動詞", ..] | ["動詞" | "形容詞", "接尾" | "非自立", ..] => true,
+        ["助詞", "接続助詞", ..] => matches!(token.surface.as_str(), "て" | "で" | "ば"),
+        _ => false,
+    }
+}
+
+fn group(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut groups = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let mut length = 1;
+        if is_inflecting(&tokens[i]) {
+            while tokens
+                .get(i + length)
+                .is_some_and(|next| next.start == tokens[i + length - 1].end && continues(next))
+            {
+                length += 1;
+            }
+        }
+        groups.push(&tokens[i..i + length]);
+        i += length;
+    }
+    groups
+}
+
+fn gloss_word(app: &AppHandle, tokens: &[Token], highlight: bool) -> Result<GlossedWord, String> {
+    let head = &tokens[0];
+    let surface: String = tokens.iter().map(|token| token.surface.as_str()).collect();
+    let reading = tokens
+        .iter()
+        .map(|token| token.reading.as_deref())
+        .collect::<Option<String>>()
+        .map(|reading| to_hiragana(&reading));
+
+    let entries = if head.pos.first().is_some_and(|pos| pos == "記号") {
+        Vec::new()
+    } else {
+        // The analyzer's base form catches what deinflection misses
+        let mut entries = lookup(app, &surface, ENTRIES_PER_WORD)?;
+        if entries.is_empty() && head.base_form != surface {
+            entries = lookup(app, &head.base_form, ENTRIES_PER_WORD)?;
+        }
+        entries
+    };
+    let best = entries.first();
+    let frequency = best.and_then(|found| found.frequency.clone());
+
+    Ok(GlossedWord {
+        highlight: highlight
+            && head.known
+            && frequency::is_content_word(head)
+            && frequency.as_ref().is_some_and(|frequency| frequency.bucket.is_rare()),
+        dictionary_form: best.map(|found| found.dictionary_form.clone()),
+        inflections: best.map(|found| found.inflections.clone()).unwrap_or_default(),
+        frequency,
+        start: head.start,
+        end: tokens[tokens.len() - 1].end,
+        pos: head.pos.clone(),
+        surface,
+        reading,
+        entries,
+    })
+}
+
+pub fn gloss(app: &AppHandle, text: &str) -> Result<Vec<GlossedWord>, String> {
+    let highlight = settings::load(app).highlight_rare_words;
+    let tokens = tokenizer::tokenize(app, text)?;
+    group(&tokens)
+        .into_iter()
+        .map(|tokens| gloss_word(app, tokens, highlight))
+        .collect()
+}
+
+// Word-by-word breakdown for a reading panel, with the machine translation
+// of the whole text run alongside unless `translate` is false. Not recorded
+// in history.
+#[tauri::command]
+pub async fn gloss_sentence(app: AppHandle, text: String, translate: Option<bool>) -> Result<SentenceGloss, AppError> {
+    let words = {
+        let app = app.clone();
+        let text = text.clone();
+        tauri::async_runtime::spawn_blocking(move || gloss(&app, &text))
+    };
+    let translation = async {
+        if translate.unwrap_or(true) {
+            Some(translation::translate(&app, &text, None, None).await)
+        } else {
+            None
+        }
+    };
+    let (words, translation) = tokio::join!(words, translation);
+    let words = words.map_err(|e| format!("Glossing failed: {}", e))??;
+
+    let (translation, translation_error) = match translation {
+        Some(Ok(result)) => (Some(result), None),
+        Some(Err(e)) => (None, Some(e.to_string())),
+        None => (None, None),
+    };
+    Ok(SentenceGloss {
+        words,
+        translation,
+        translation_error,
+    })
+}
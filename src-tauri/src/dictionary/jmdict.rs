@@ -0,0 +1,244 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+
+use super::pitch::PitchAccent;
+use super::text;
+
+// JMnedict, the names dictionary, has the same layout and is read and
+// indexed by the same code
+
+// Bumped when the tables below change; older files are rebuilt
+pub const FORMAT_VERSION: i64 = 1;
+
+pub const SCHEMA: &str = "
+    CREATE TABLE entries (
+        id INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    -- Every written and kana form, ranked so common words come first
+    CREATE TABLE terms (
+        term TEXT NOT NULL,
+        entry_id INTEGER NOT NULL REFERENCES entries (id),
+        rank INTEGER NOT NULL
+    );
+";
+pub const INDEXES: &str = "CREATE INDEX terms_term ON terms (term, rank);";
+
+// Priority markers JMdict uses for its "common word" flag
+const COMMON_MARKERS: [&str; 5] = ["news1", "ichi1", "spec1", "spec2", "gai1"];
+// Frequency bands run nf01 (most frequent) to nf48; unbanded words sort after
+const UNBANDED: i64 = 49;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiForm {
+    pub text: String,
+    pub common: bool,
+    // e.g. "ateji", "iK"
+    pub info: Vec<String>,
+    #[serde(skip)]
+    pub(super) priority: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingForm {
+    pub text: String,
+    pub common: bool,
+    pub info: Vec<String>,
+    // Not a true reading of any kanji form, e.g. for loanwords
+    pub no_kanji: bool,
+    // Kanji forms this reading belongs to; empty means all of them
+    pub applies_to: Vec<String>,
+    // Filled in at lookup time from the pitch-accent dataset
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pitch: Vec<PitchAccent>,
+    #[serde(skip)]
+    pub(super) priority: Vec<String>,
+}
+
+// POS, misc, field and dialect are JMdict's entity codes, e.g. "v5r" or "uk"
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sense {
+    pub glosses: Vec<String>,
+    pub pos: Vec<String>,
+    pub misc: Vec<String>,
+    pub field: Vec<String>,
+    pub dialect: Vec<String>,
+    pub info: Vec<String>,
+    pub applies_to_kanji: Vec<String>,
+    pub applies_to_reading: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryEntry {
+    pub id: u64,
+    pub kanji: Vec<KanjiForm>,
+    pub readings: Vec<ReadingForm>,
+    pub senses: Vec<Sense>,
+}
+
+fn is_common(priority: &[String]) -> bool {
+    priority.iter().any(|marker| COMMON_MARKERS.contains(&marker.as_str()))
+}
+
+// Lower ranks sort first: common words, then by frequency band
+fn rank(priority: &[String]) -> i64 {
+    let band = priority
+        .iter()
+        .filter_map(|marker| marker.strip_prefix("nf")?.parse::<i64>().ok())
+        .min()
+        .unwrap_or(UNBANDED);
+    if is_common(priority) {
+        band
+    } else {
+        100 + band
+    }
+}
+
+fn is_english(start: &BytesStart) -> bool {
+    start
+        .try_get_attribute("xml:lang")
+        .ok()
+        .flatten()
+        .map_or(true, |lang| lang.value.as_ref() == b"eng")
+}
+
+// Streams <entry> elements to `each`; returns how many there were
+fn parse<R: BufRead>(
+    source: R,
+    mut each: impl FnMut(DictionaryEntry) -> Result<(), String>,
+) -> Result<usize, String> {
+    let mut reader = Reader::from_reader(source);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut entry = DictionaryEntry::default();
+    let mut element = Vec::new();
+    let mut skip_gloss = false;
+    let mut count = 0;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("Invalid JMdict file at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(start) => {
+                element = start.name().as_ref().to_vec();
+                match element.as_slice() {
+                    b"entry" => entry = DictionaryEntry::default(),
+                    b"k_ele" => entry.kanji.push(KanjiForm::default()),
+                    b"r_ele" => entry.readings.push(ReadingForm::default()),
+                    // JMnedict's <trans> is read as a sense, its name types
+                    // as misc codes and its transcriptions as glosses
+                    b"sense" | b"trans" => entry.senses.push(Sense::default()),
+                    b"gloss" | b"trans_det" => skip_gloss = !is_english(&start),
+                    _ => {}
+                }
+            }
+            Event::Empty(start) if start.name().as_ref() == b"re_nokanji" => {
+                if let Some(reading) = entry.readings.last_mut() {
+                    reading.no_kanji = true;
+                }
+            }
+            Event::Text(event) => {
+                let value = text(&event)?;
+                let kanji = entry.kanji.last_mut();
+                let reading = entry.readings.last_mut();
+                let sense = entry.senses.last_mut();
+                match (element.as_slice(), kanji, reading, sense) {
+                    (b"ent_seq", ..) => entry.id = value.parse().unwrap_or_default(),
+                    (b"keb", Some(kanji), ..) => kanji.text = value,
+                    (b"ke_inf", Some(kanji), ..) => kanji.info.push(value),
+                    (b"ke_pri", Some(kanji), ..) => kanji.priority.push(value),
+                    (b"reb", _, Some(reading), _) => reading.text = value,
+                    (b"re_inf", _, Some(reading), _) => reading.info.push(value),
+                    (b"re_pri", _, Some(reading), _) => reading.priority.push(value),
+                    (b"re_restr", _, Some(reading), _) => reading.applies_to.push(value),
+                    (b"gloss" | b"trans_det", .., Some(sense)) if !skip_gloss => sense.glosses.push(value),
+                    (b"pos", .., Some(sense)) => sense.pos.push(value),
+                    (b"misc" | b"name_type", .., Some(sense)) => sense.misc.push(value),
+                    (b"field", .., Some(sense)) => sense.field.push(value),
+                    (b"dial", .., Some(sense)) => sense.dialect.push(value),
+                    (b"s_inf", .., Some(sense)) => sense.info.push(value),
+                    (b"stagk", .., Some(sense)) => sense.applies_to_kanji.push(value),
+                    (b"stagr", .., Some(sense)) => sense.applies_to_reading.push(value),
+                    _ => {}
+                }
+            }
+            Event::End(end) if end.name().as_ref() == b"entry" => {
+                finish(&mut entry);
+                each(std::mem::take(&mut entry))?;
+                count += 1;
+            }
+            Event::End(_) => element.clear(),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(count)
+}
+
+fn finish(entry: &mut DictionaryEntry) {
+    for kanji in &mut entry.kanji {
+        kanji.common = is_common(&kanji.priority);
+    }
+    for reading in &mut entry.readings {
+        reading.common = is_common(&reading.priority);
+    }
+    // A sense without part of speech has that of the sense before it
+    let mut pos = Vec::new();
+    for sense in &mut entry.senses {
+        if sense.pos.is_empty() {
+            sense.pos = pos.clone();
+        } else {
+            pos = sense.pos.clone();
+        }
+    }
+    entry.senses.retain(|sense| !sense.glosses.is_empty());
+}
+
+fn insert(transaction: &Transaction, entry: &DictionaryEntry) -> Result<(), String> {
+    let data = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize entry: {}", e))?;
+    let insert_error = |e: rusqlite::Error| format!("Failed to write dictionary: {}", e);
+    transaction
+        .execute("INSERT OR REPLACE INTO entries (id, data) VALUES (?1, ?2)", params![entry.id, data])
+        .map_err(insert_error)?;
+    let forms = entry
+        .kanji
+        .iter()
+        .map(|kanji| (&kanji.text, &kanji.priority))
+        .chain(entry.readings.iter().map(|reading| (&reading.text, &reading.priority)));
+    for (term, priority) in forms {
+        transaction
+            .execute(
+                "INSERT INTO terms (term, entry_id, rank) VALUES (?1, ?2, ?3)",
+                params![term, entry.id, rank(priority)],
+            )
+            .map_err(insert_error)?;
+    }
+    Ok(())
+}
+
+pub fn fill(transaction: &Transaction, source: impl BufRead) -> Result<usize, String> {
+    parse(source, |entry| insert(transaction, &entry))
+}
+
+// Exact matches on any written or kana form, most common first
+pub fn lookup(conn: &Connection, term: &str, limit: usize) -> rusqlite::Result<Vec<DictionaryEntry>> {
+    let mut statement = conn.prepare(
+        "SELECT e.data FROM terms t JOIN entries e ON e.id = t.entry_id WHERE t.term = ?1 \
+         GROUP BY e.id ORDER BY min(t.rank), e.id LIMIT ?2",
+    )?;
+    let entries = statement.query_map(params![term, limit], |row| {
+        let data: String = row.get(0)?;
+        serde_json::from_str(&data)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+    })?;
+    entries.collect()
+}
@@ -0,0 +1,138 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+
+use super::text;
+
+pub const FORMAT_VERSION: i64 = 1;
+
+pub const SCHEMA: &str = "
+    CREATE TABLE kanji (
+        literal TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiEntry {
+    pub literal: String,
+    pub strokes: u32,
+    // 1-6 are taught in elementary school, 8 is the rest of the jōyō list
+    // and 9-10 are jinmeiyō (name) kanji
+    pub grade: Option<u32>,
+    // KANJIDIC still uses the four levels of the pre-2010 JLPT, 4 easiest
+    pub jlpt: Option<u32>,
+    // Rank among the 2,500 most used kanji in newspapers
+    pub frequency: Option<u32>,
+    // Classical (Kangxi) radical number
+    pub radical: Option<u32>,
+    pub on_readings: Vec<String>,
+    // A dot separates the stem from okurigana, e.g. た.べる
+    pub kun_readings: Vec<String>,
+    // Readings only found in names
+    pub nanori: Vec<String>,
+    pub meanings: Vec<String>,
+}
+
+fn attribute(start: &BytesStart, name: &str) -> Option<String> {
+    start
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .map(|attribute| String::from_utf8_lossy(&attribute.value).into_owned())
+}
+
+fn parse<R: BufRead>(
+    source: R,
+    mut each: impl FnMut(KanjiEntry) -> Result<(), String>,
+) -> Result<usize, String> {
+    let mut reader = Reader::from_reader(source);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut entry = KanjiEntry::default();
+    let mut element = Vec::new();
+    // r_type, m_lang or rad_type of the element being read
+    let mut kind = None;
+    let mut count = 0;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("Invalid KANJIDIC2 file at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(start) => {
+                element = start.name().as_ref().to_vec();
+                kind = match element.as_slice() {
+                    b"reading" => attribute(&start, "r_type"),
+                    b"meaning" => attribute(&start, "m_lang"),
+                    b"rad_value" => attribute(&start, "rad_type"),
+                    _ => None,
+                };
+                if element == b"character" {
+                    entry = KanjiEntry::default();
+                }
+            }
+            Event::Text(event) => {
+                let value = text(&event)?;
+                let number = || value.parse::<u32>().ok();
+                match (element.as_slice(), kind.as_deref()) {
+                    (b"literal", _) => entry.literal = value,
+                    // Later stroke counts are common miscounts
+                    (b"stroke_count", _) if entry.strokes == 0 => entry.strokes = number().unwrap_or(0),
+                    (b"grade", _) => entry.grade = number(),
+                    (b"jlpt", _) => entry.jlpt = number(),
+                    (b"freq", _) => entry.frequency = number(),
+                    (b"rad_value", Some("classical")) => entry.radical = number(),
+                    (b"reading", Some("ja_on")) => entry.on_readings.push(value),
+                    (b"reading", Some("ja_kun")) => entry.kun_readings.push(value),
+                    (b"nanori", _) => entry.nanori.push(value),
+                    // English meanings carry no m_lang
+                    (b"meaning", None) => entry.meanings.push(value),
+                    _ => {}
+                }
+            }
+            Event::End(end) if end.name().as_ref() == b"character" => {
+                each(std::mem::take(&mut entry))?;
+                count += 1;
+            }
+            Event::End(_) => element.clear(),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(count)
+}
+
+fn insert(transaction: &Transaction, entry: &KanjiEntry) -> Result<(), String> {
+    let data = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize kanji: {}", e))?;
+    transaction
+        .execute(
+            "INSERT OR REPLACE INTO kanji (literal, data) VALUES (?1, ?2)",
+            params![entry.literal, data],
+        )
+        .map_err(|e| format!("Failed to write dictionary: {}", e))?;
+    Ok(())
+}
+
+pub fn fill(transaction: &Transaction, source: impl BufRead) -> Result<usize, String> {
+    parse(source, |entry| insert(transaction, &entry))
+}
+
+pub fn lookup(conn: &Connection, literal: char) -> rusqlite::Result<Option<KanjiEntry>> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM kanji WHERE literal = ?1",
+            [literal.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    data.map(|data| {
+        serde_json::from_str(&data)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+    })
+    .transpose()
+}
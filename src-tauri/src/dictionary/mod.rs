@@ -0,0 +1,454 @@
+use chrono::Utc;
+use quick_xml::events::BytesText;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::japanese::kana;
+use crate::{portable, settings};
+
+pub mod deinflect;
+pub mod examples;
+pub mod frequency;
+pub mod gloss;
+pub mod jmdict;
+pub mod kanjidic;
+pub mod names;
+pub mod pitch;
+pub mod user;
+pub mod yomichan;
+
+pub use deinflect::Inflection;
+pub use frequency::Frequency;
+pub use jmdict::DictionaryEntry;
+pub use kanjidic::KanjiEntry;
+
+// Under the data dir, and also where the app's resources are searched for
+// bundled copies of the source files
+const DICTIONARY_DIR: &str = "dictionaries";
+const META_SCHEMA: &str = "
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+// Each is built from its source file into its own SQLite file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DictionaryKind {
+    Jmdict,
+    // People's and place names
+    Jmnedict,
+    Kanjidic,
+    Pitch,
+    Frequency,
+    // Tatoeba example sentences
+    Examples,
+}
+
+impl DictionaryKind {
+    const ALL: [DictionaryKind; 6] = [
+        DictionaryKind::Jmdict,
+        DictionaryKind::Jmnedict,
+        DictionaryKind::Kanjidic,
+        DictionaryKind::Pitch,
+        DictionaryKind::Frequency,
+        DictionaryKind::Examples,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            DictionaryKind::Jmdict => "jmdict",
+            DictionaryKind::Jmnedict => "jmnedict",
+            DictionaryKind::Kanjidic => "kanjidic2",
+            DictionaryKind::Pitch => "pitch-accents",
+            DictionaryKind::Frequency => "frequency",
+            DictionaryKind::Examples => "examples",
+        }
+    }
+
+    // Names the files are distributed under
+    fn source_files(self) -> &'static [&'static str] {
+        match self {
+            DictionaryKind::Jmdict => &["JMdict_e.gz", "JMdict_e.xml", "JMdict_e"],
+            DictionaryKind::Jmnedict => &["JMnedict.xml.gz", "JMnedict.xml"],
+            DictionaryKind::Kanjidic => &["kanjidic2.xml.gz", "kanjidic2.xml"],
+            DictionaryKind::Pitch => &["accents.txt"],
+            DictionaryKind::Frequency => &["frequency.tsv", "frequency.txt", "frequency.csv"],
+            DictionaryKind::Examples => &["examples.utf.gz", "examples.utf"],
+        }
+    }
+
+    fn install_hint(self) -> &'static str {
+        match self {
+            DictionaryKind::Jmdict => "download JMdict_e.gz from the EDRDG and import it",
+            DictionaryKind::Jmnedict => "download JMnedict.xml.gz from the EDRDG and import it",
+            DictionaryKind::Kanjidic => "download kanjidic2.xml.gz from the EDRDG and import it",
+            DictionaryKind::Pitch => "import accents.txt from the Kanjium project",
+            DictionaryKind::Frequency => "import a word list with the most frequent words first",
+            DictionaryKind::Examples => "download examples.utf.gz (the Tatoeba corpus) from the EDRDG and import it",
+        }
+    }
+
+    fn format_version(self) -> i64 {
+        match self {
+            DictionaryKind::Jmdict | DictionaryKind::Jmnedict => jmdict::FORMAT_VERSION,
+            DictionaryKind::Kanjidic => kanjidic::FORMAT_VERSION,
+            DictionaryKind::Pitch => pitch::FORMAT_VERSION,
+            DictionaryKind::Frequency => frequency::FORMAT_VERSION,
+            DictionaryKind::Examples => examples::FORMAT_VERSION,
+        }
+    }
+
+    fn schema(self) -> &'static str {
+        match self {
+            DictionaryKind::Jmdict | DictionaryKind::Jmnedict => jmdict::SCHEMA,
+            DictionaryKind::Kanjidic => kanjidic::SCHEMA,
+            DictionaryKind::Pitch => pitch::SCHEMA,
+            DictionaryKind::Frequency => frequency::SCHEMA,
+            DictionaryKind::Examples => examples::SCHEMA,
+        }
+    }
+
+    // Created after the bulk insert, which is much faster without them
+    fn indexes(self) -> &'static str {
+        match self {
+            DictionaryKind::Jmdict | DictionaryKind::Jmnedict => jmdict::INDEXES,
+            DictionaryKind::Examples => examples::INDEXES,
+            DictionaryKind::Kanjidic | DictionaryKind::Pitch | DictionaryKind::Frequency => "",
+        }
+    }
+
+    fn fill(self, transaction: &rusqlite::Transaction, source: Box<dyn BufRead>) -> Result<usize, String> {
+        match self {
+            DictionaryKind::Jmdict | DictionaryKind::Jmnedict => jmdict::fill(transaction, source),
+            DictionaryKind::Kanjidic => kanjidic::fill(transaction, source),
+            DictionaryKind::Pitch => pitch::fill(transaction, source),
+            DictionaryKind::Frequency => frequency::fill(transaction, source),
+            DictionaryKind::Examples => examples::fill(transaction, source),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WordSource {
+    // The user's own dictionary, listed before everything else
+    User,
+    Jmdict,
+    // A Yomichan archive the user imported
+    Imported,
+    // Names, after regular results
+    Jmnedict,
+}
+
+// An entry and how the looked-up word was conjugated from it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordMatch {
+    #[serde(flatten)]
+    pub entry: DictionaryEntry,
+    pub source: WordSource,
+    // Title of the imported dictionary it came from
+    pub dictionary: Option<String>,
+    // Form the entry was found under, e.g. 食べる for 食べられなかった
+    pub dictionary_form: String,
+    // Empty when the word was found as given
+    pub inflections: Vec<Inflection>,
+    // Only set while a frequency list is installed
+    pub frequency: Option<Frequency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryStatus {
+    pub kind: DictionaryKind,
+    pub installed: bool,
+    pub entries: usize,
+    pub built_at: Option<String>,
+    // File the index was built from
+    pub source: Option<String>,
+}
+
+// Opened on first lookup. A missing index is built then if a source file is
+// available, which takes a while for the full JMdict.
+#[derive(Default)]
+pub struct DictionaryState(Mutex<HashMap<DictionaryKind, Connection>>);
+
+// Text of an XML element. Codes like <pos>&v5r;</pos> are DTD entities; the
+// code itself is kept rather than its English expansion.
+fn text(event: &BytesText) -> Result<String, String> {
+    let raw = std::str::from_utf8(event).map_err(|e| format!("Invalid dictionary text: {}", e))?;
+    if let Some(code) = raw.strip_prefix('&').and_then(|rest| rest.strip_suffix(';')) {
+        if !code.contains('&') {
+            return Ok(code.to_string());
+        }
+    }
+    event
+        .unescape()
+        .map(|text| text.into_owned())
+        .map_err(|e| format!("Invalid dictionary text: {}", e))
+}
+
+fn dictionary_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = portable::data_dir(app)?.join(DICTIONARY_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn database_path(app: &AppHandle, kind: DictionaryKind) -> Result<PathBuf, String> {
+    Ok(dictionary_dir(app)?.join(format!("{}.sqlite3", kind.name())))
+}
+
+// A source file shipped with the app or dropped into the data dir
+fn find_source(app: &AppHandle, kind: DictionaryKind) -> Option<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(dir) = app.path().resource_dir() {
+        dirs.push(dir.join(DICTIONARY_DIR));
+    }
+    if let Ok(dir) = portable::data_dir(app) {
+        dirs.push(dir.join(DICTIONARY_DIR));
+    }
+    dirs.iter()
+        .flat_map(|dir| kind.source_files().iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+fn source_reader(source: &Path) -> Result<Box<dyn BufRead>, String> {
+    let file = File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let gzipped = source
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+    Ok(if gzipped {
+        Box::new(BufReader::new(flate2::read::GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+// Builds the index from a source file (plain or gzipped XML) next to `dest`
+// and then moves it into place
+fn build(kind: DictionaryKind, source: &Path, dest: &Path) -> Result<usize, String> {
+    let reader = source_reader(source)?;
+    let building = dest.with_extension("building");
+    let _ = std::fs::remove_file(&building);
+    let db_error = |e: rusqlite::Error| format!("Failed to build dictionary: {}", e);
+    let count = {
+        let mut conn = Connection::open(&building).map_err(db_error)?;
+        conn.execute_batch(&format!("{}{}", META_SCHEMA, kind.schema()))
+            .map_err(db_error)?;
+        let transaction = conn.transaction().map_err(db_error)?;
+        let count = kind.fill(&transaction, reader)?;
+        transaction
+            .execute(
+                "INSERT INTO meta (key, value) VALUES ('entries', ?1), ('builtAt', ?2), ('source', ?3)",
+                params![count, Utc::now().to_rfc3339(), source.to_string_lossy()],
+            )
+            .map_err(db_error)?;
+        transaction.commit().map_err(db_error)?;
+        conn.execute_batch(&format!("{} PRAGMA user_version = {};", kind.indexes(), kind.format_version()))
+            .map_err(db_error)?;
+        count
+    };
+    if count == 0 {
+        let _ = std::fs::remove_file(&building);
+        return Err(format!("{} has no dictionary entries", source.display()));
+    }
+    std::fs::rename(&building, dest).map_err(|e| format!("Failed to install dictionary: {}", e))?;
+    Ok(count)
+}
+
+fn open_existing(path: &Path, kind: DictionaryKind) -> Result<Option<Connection>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open dictionary: {}", e))?;
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to open dictionary: {}", e))?;
+    Ok((version == kind.format_version()).then_some(conn))
+}
+
+fn open(app: &AppHandle, kind: DictionaryKind) -> Result<Connection, String> {
+    let path = database_path(app, kind)?;
+    if let Some(conn) = open_existing(&path, kind)? {
+        return Ok(conn);
+    }
+    let source = find_source(app, kind).ok_or_else(|| {
+        format!("No {} dictionary installed; {}", kind.name(), kind.install_hint())
+    })?;
+    build(kind, &source, &path)?;
+    open_existing(&path, kind)?.ok_or_else(|| "Failed to open dictionary".to_string())
+}
+
+fn with_db<T>(
+    app: &AppHandle,
+    kind: DictionaryKind,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let state = app.state::<DictionaryState>();
+    let mut dictionaries = state.0.lock().unwrap();
+    let conn = match dictionaries.entry(kind) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(open(app, kind)?),
+    };
+    f(conn).map_err(|e| format!("Dictionary error: {}", e))
+}
+
+// Conjugated words are looked up by every form they could come from, so
+// 食べられなかった finds 食べる. Entries for the word as given come first,
+// and names from JMnedict after all of them.
+pub fn lookup(app: &AppHandle, term: &str, limit: usize) -> Result<Vec<WordMatch>, String> {
+    // OCR and web text bring half-width kana and full-width letters
+    let term = kana::normalize_width(term.trim());
+    let term = term.as_str();
+    if term.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.min(MAX_LIMIT);
+    let mut candidates = deinflect::deinflect(term);
+    // JMdict has 時々 but not every spelling with an iteration mark
+    let expanded = kana::expand_iteration_marks(term);
+    if expanded != term {
+        candidates.extend(deinflect::deinflect(&expanded));
+    }
+    let jmdict = with_db(app, DictionaryKind::Jmdict, |conn| {
+        let mut matches: Vec<WordMatch> = Vec::new();
+        for candidate in &candidates {
+            for entry in jmdict::lookup(conn, &candidate.term, limit)? {
+                let types = entry
+                    .senses
+                    .iter()
+                    .flat_map(|sense| &sense.pos)
+                    .fold(0, |types, pos| types | deinflect::word_types(pos));
+                if !deinflect::matches(candidate, types) || matches.iter().any(|found| found.entry.id == entry.id) {
+                    continue;
+                }
+                matches.push(WordMatch {
+                    entry,
+                    source: WordSource::Jmdict,
+                    dictionary: None,
+                    dictionary_form: candidate.term.clone(),
+                    inflections: candidate.reasons.clone(),
+                    frequency: None,
+                });
+            }
+        }
+        Ok(matches)
+    });
+    // User words take precedence, and still work without JMdict installed
+    let mut matches = user::lookup(app, term);
+    match jmdict {
+        Ok(found) => matches.extend(found),
+        Err(e) if matches.is_empty() => return Err(e),
+        Err(_) => {}
+    }
+    if matches.len() < limit {
+        matches.extend(yomichan::lookup(app, &candidates, limit - matches.len()));
+    }
+    matches.truncate(limit);
+    if settings::load(app).lookup_names && matches.len() < limit {
+        matches.extend(names::lookup(app, term, limit - matches.len()));
+    }
+    pitch::annotate(app, matches.iter_mut().map(|found| &mut found.entry));
+    frequency::annotate(app, &mut matches);
+    Ok(matches)
+}
+
+pub fn kanji(app: &AppHandle, literal: char) -> Result<Option<KanjiEntry>, String> {
+    with_db(app, DictionaryKind::Kanjidic, |conn| kanjidic::lookup(conn, literal))
+}
+
+fn meta(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+        .ok()
+}
+
+fn status(app: &AppHandle, kind: DictionaryKind) -> Result<DictionaryStatus, String> {
+    let state = app.state::<DictionaryState>();
+    let mut dictionaries = state.0.lock().unwrap();
+    if let Entry::Vacant(entry) = dictionaries.entry(kind) {
+        if let Some(conn) = open_existing(&database_path(app, kind)?, kind)? {
+            entry.insert(conn);
+        }
+    }
+    Ok(match dictionaries.get(&kind) {
+        Some(conn) => DictionaryStatus {
+            kind,
+            installed: true,
+            entries: meta(conn, "entries").and_then(|count| count.parse().ok()).unwrap_or(0),
+            built_at: meta(conn, "builtAt"),
+            source: meta(conn, "source"),
+        },
+        None => DictionaryStatus {
+            kind,
+            installed: false,
+            entries: 0,
+            built_at: None,
+            source: None,
+        },
+    })
+}
+
+// Matches a written or kana form, e.g. "食べる" or "たべる", or a conjugation
+// of one; common words are listed first
+#[tauri::command]
+pub async fn lookup_word(app: AppHandle, term: String, limit: Option<usize>) -> Result<Vec<WordMatch>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || lookup(&app, &term, limit.unwrap_or(DEFAULT_LIMIT)))
+        .await
+        .map_err(|e| format!("Dictionary lookup failed: {}", e))??)
+}
+
+// Only the first character of `character` is looked up
+#[tauri::command]
+pub async fn lookup_kanji(app: AppHandle, character: String) -> Result<Option<KanjiEntry>, AppError> {
+    let Some(literal) = character.trim().chars().next() else {
+        return Ok(None);
+    };
+    Ok(tauri::async_runtime::spawn_blocking(move || kanji(&app, literal))
+        .await
+        .map_err(|e| format!("Kanji lookup failed: {}", e))??)
+}
+
+// Rebuilds an index from its source file (plain or .gz), replacing the current
+// one once the new one is complete. Without `kind` the file is taken to be
+// JMdict.
+#[tauri::command]
+pub async fn import_dictionary(
+    app: AppHandle,
+    path: String,
+    kind: Option<DictionaryKind>,
+) -> Result<DictionaryStatus, AppError> {
+    let kind = kind.unwrap_or(DictionaryKind::Jmdict);
+    Ok(tauri::async_runtime::spawn_blocking(move || {
+        let dest = database_path(&app, kind)?;
+        // Built aside so lookups keep working meanwhile
+        let building = dest.with_file_name(format!("{}-import.sqlite3", kind.name()));
+        build(kind, Path::new(&path), &building)?;
+        {
+            let state = app.state::<DictionaryState>();
+            let mut dictionaries = state.0.lock().unwrap();
+            dictionaries.remove(&kind);
+            std::fs::rename(&building, &dest).map_err(|e| format!("Failed to install dictionary: {}", e))?;
+        }
+        status(&app, kind)
+    })
+    .await
+    .map_err(|e| format!("Dictionary import failed: {}", e))??)
+}
+
+#[tauri::command]
+pub async fn get_dictionary_status(app: AppHandle) -> Result<Vec<DictionaryStatus>, AppError> {
+    Ok(DictionaryKind::ALL.iter().map(|kind| status(&app, *kind)).collect::<Result<_, String>>()?)
+}
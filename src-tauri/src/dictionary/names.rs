@@ -0,0 +1,94 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::{jmdict, with_db, DictionaryEntry, DictionaryKind, WordMatch, WordSource};
+use crate::error::AppError;
+use crate::japanese::tokenizer::{self, Token};
+
+// Tokens a name can span, e.g. 田中 + 太郎 or 東京 + 都
+const MAX_SPAN: usize = 4;
+const MAX_NAMES: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameMatch {
+    pub surface: String,
+    // Character offsets into the text
+    pub start: usize,
+    pub end: usize,
+    // JMnedict's own romanization of the likeliest reading, e.g. "Tanaka";
+    // translations can be checked against it
+    pub transliteration: Option<String>,
+    pub names: Vec<DictionaryEntry>,
+}
+
+// JMnedict results for a word, as a secondary source next to JMdict. Nothing
+// when the names dictionary isn't installed.
+pub fn lookup(app: &AppHandle, term: &str, limit: usize) -> Vec<WordMatch> {
+    with_db(app, DictionaryKind::Jmnedict, |conn| jmdict::lookup(conn, term, limit))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| WordMatch {
+            entry,
+            source: WordSource::Jmnedict,
+            dictionary_form: term.to_string(),
+            inflections: Vec::new(),
+            dictionary: None,
+            frequency: None,
+        })
+        .collect()
+}
+
+fn is_proper_noun(token: &Token) -> bool {
+    matches!(token.pos.as_slice(), [first, second, ..] if first == "名詞" && second == "固有名詞")
+}
+
+fn find(app: &AppHandle, text: &str) -> Result<Vec<NameMatch>, String> {
+    let tokens = tokenizer::tokenize(app, text)?;
+    with_db(app, DictionaryKind::Jmnedict, |conn| {
+        let mut found = Vec::new();
+        let mut i = 0;
+        'tokens: while i < tokens.len() {
+            // The analyzer's proper nouns, joined while they run on without a gap
+            let mut run = 0;
+            while tokens.get(i + run).is_some_and(is_proper_noun)
+                && (run == 0 || tokens[i + run].start == tokens[i + run - 1].end)
+            {
+                run += 1;
+            }
+            // Longest span first, so a full name wins over its parts
+            for length in (1..=run.min(MAX_SPAN)).rev() {
+                let span = &tokens[i..i + length];
+                let surface: String = span.iter().map(|token| token.surface.as_str()).collect();
+                let names = jmdict::lookup(conn, &surface, MAX_NAMES)?;
+                if names.is_empty() {
+                    continue;
+                }
+                found.push(NameMatch {
+                    transliteration: names
+                        .first()
+                        .and_then(|name| name.senses.first())
+                        .and_then(|sense| sense.glosses.first())
+                        .cloned(),
+                    surface,
+                    start: span[0].start,
+                    end: span[length - 1].end,
+                    names,
+                });
+                i += length;
+                continue 'tokens;
+            }
+            i += 1;
+        }
+        Ok(found)
+    })
+}
+
+// Person and place names in `text`, found where the analyzer sees proper
+// nouns and JMnedict lists them
+#[tauri::command]
+pub async fn find_names(app: AppHandle, text: String) -> Result<Vec<NameMatch>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || find(&app, &text))
+        .await
+        .map_err(|e| format!("Name lookup failed: {}", e))??)
+}
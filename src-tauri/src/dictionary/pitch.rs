@@ -0,0 +1,221 @@
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use tauri::AppHandle;
+
+use super::{with_db, DictionaryEntry, DictionaryKind};
+use crate::error::AppError;
+use crate::japanese::kana::to_hiragana;
+use crate::japanese::tokenizer;
+
+pub const FORMAT_VERSION: i64 = 1;
+
+// Downsteps are kept as written, e.g. "0,2" for a word with two accepted
+// patterns
+pub const SCHEMA: &str = "
+    CREATE TABLE accents (
+        term TEXT NOT NULL,
+        reading TEXT NOT NULL,
+        downsteps TEXT NOT NULL,
+        PRIMARY KEY (term, reading)
+    );
+";
+
+// Kana that share a mora with the one before them
+const SMALL_KANA: &str = "ゃゅょぁぃぅぇぉゎャュョァィゥェォヮ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PitchPattern {
+    // Low then high, staying high on a following particle
+    Heiban,
+    // High on the first mora only
+    Atamadaka,
+    Nakadaka,
+    // High to the end, dropping on a following particle
+    Odaka,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitchAccent {
+    // Mora after which the pitch falls; 0 means it never does
+    pub downstep: usize,
+    pub pattern: PitchPattern,
+    pub morae: Vec<String>,
+    // "H"/"L" per mora plus one for a following particle, e.g. "LHHL"
+    pub levels: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordPitch {
+    pub surface: String,
+    pub base_form: String,
+    pub start: usize,
+    pub end: usize,
+    // Empty when the word isn't in the dataset
+    pub accents: Vec<PitchAccent>,
+}
+
+fn morae(reading: &str) -> Vec<String> {
+    let mut morae: Vec<String> = Vec::new();
+    for c in reading.chars() {
+        match morae.last_mut() {
+            Some(mora) if SMALL_KANA.contains(c) => mora.push(c),
+            _ => morae.push(c.to_string()),
+        }
+    }
+    morae
+}
+
+pub fn accent(reading: &str, downstep: usize) -> PitchAccent {
+    let morae = morae(reading);
+    let count = morae.len();
+    let pattern = match downstep {
+        0 => PitchPattern::Heiban,
+        1 => PitchPattern::Atamadaka,
+        n if n >= count => PitchPattern::Odaka,
+        _ => PitchPattern::Nakadaka,
+    };
+    let levels = (0..=count)
+        .map(|mora| {
+            let high = match downstep {
+                0 => mora > 0,
+                1 => mora == 0,
+                n => mora > 0 && mora < n,
+            };
+            if high {
+                'H'
+            } else {
+                'L'
+            }
+        })
+        .collect();
+    PitchAccent {
+        downstep,
+        pattern,
+        morae,
+        levels,
+    }
+}
+
+// Numbers from a Kanjium accents.txt column; part-of-speech notes such as
+// "(副)0,(名)3" are dropped
+fn downsteps(column: &str) -> Vec<usize> {
+    let mut plain = String::new();
+    let mut depth = 0;
+    for c in column.chars() {
+        match c {
+            '(' | '（' => depth += 1,
+            ')' | '）' => depth = (depth - 1).max(0),
+            c if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    let mut downsteps: Vec<usize> = Vec::new();
+    for downstep in plain.split([',', '，']).filter_map(|part| part.trim().parse().ok()) {
+        if !downsteps.contains(&downstep) {
+            downsteps.push(downstep);
+        }
+    }
+    downsteps
+}
+
+// Kanjium's accents.txt: word, reading (empty for kana words) and
+// downsteps, separated by tabs
+pub fn fill(transaction: &Transaction, source: impl BufRead) -> Result<usize, String> {
+    let mut count = 0;
+    for line in source.lines() {
+        let line = line.map_err(|e| format!("Failed to read pitch accents: {}", e))?;
+        let columns: Vec<&str> = line.split('\t').collect();
+        let [term, reading, column, ..] = columns.as_slice() else {
+            continue;
+        };
+        let downsteps = downsteps(column);
+        if term.is_empty() || downsteps.is_empty() {
+            continue;
+        }
+        let reading = to_hiragana(if reading.is_empty() { term } else { reading });
+        let downsteps: Vec<String> = downsteps.iter().map(|downstep| downstep.to_string()).collect();
+        count += transaction
+            .execute(
+                "INSERT OR IGNORE INTO accents (term, reading, downsteps) VALUES (?1, ?2, ?3)",
+                params![term, reading, downsteps.join(",")],
+            )
+            .map_err(|e| format!("Failed to write dictionary: {}", e))?;
+    }
+    Ok(count)
+}
+
+// Accents for `term` read as `reading`, or for every reading of it when that
+// one isn't listed
+pub fn lookup(conn: &Connection, term: &str, reading: Option<&str>) -> rusqlite::Result<Vec<PitchAccent>> {
+    let mut statement = conn.prepare_cached("SELECT reading, downsteps FROM accents WHERE term = ?1")?;
+    let rows = statement
+        .query_map([term], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let reading = reading.map(to_hiragana);
+    let matching = rows
+        .iter()
+        .any(|(candidate, _)| Some(candidate) == reading.as_ref());
+    Ok(rows
+        .iter()
+        .filter(|(candidate, _)| !matching || Some(candidate) == reading.as_ref())
+        .flat_map(|(reading, downsteps)| {
+            downsteps
+                .split(',')
+                .filter_map(|downstep| downstep.parse().ok())
+                .map(|downstep| accent(reading, downstep))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+// Adds pitch to each reading of dictionary results, when the dataset is
+// installed
+pub fn annotate<'a>(app: &AppHandle, entries: impl Iterator<Item = &'a mut DictionaryEntry>) {
+    let _ = with_db(app, DictionaryKind::Pitch, |conn| {
+        for entry in entries {
+            let kanji: Vec<String> = entry.kanji.iter().map(|kanji| kanji.text.clone()).collect();
+            for reading in &mut entry.readings {
+                let term = match reading.applies_to.first() {
+                    Some(kanji) => kanji.clone(),
+                    None if reading.no_kanji => reading.text.clone(),
+                    None => kanji.first().cloned().unwrap_or_else(|| reading.text.clone()),
+                };
+                reading.pitch = lookup(conn, &term, Some(&reading.text))?;
+            }
+        }
+        Ok(())
+    });
+}
+
+fn words(app: &AppHandle, text: &str) -> Result<Vec<WordPitch>, String> {
+    let tokens = tokenizer::tokenize(app, text)?;
+    with_db(app, DictionaryKind::Pitch, |conn| {
+        tokens
+            .into_iter()
+            .map(|token| {
+                // The token's reading is only the base form's when it isn't inflected
+                let reading = (token.surface == token.base_form)
+                    .then_some(token.reading.as_deref())
+                    .flatten();
+                Ok(WordPitch {
+                    accents: lookup(conn, &token.base_form, reading)?,
+                    surface: token.surface,
+                    base_form: token.base_form,
+                    start: token.start,
+                    end: token.end,
+                })
+            })
+            .collect()
+    })
+}
+
+#[tauri::command]
+pub async fn get_pitch_accent(app: AppHandle, text: String) -> Result<Vec<WordPitch>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || words(&app, &text))
+        .await
+        .map_err(|e| format!("Pitch accent lookup failed: {}", e))??)
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::jmdict::{KanjiForm, ReadingForm, Sense};
+use super::{DictionaryEntry, WordMatch, WordSource};
+use crate::error::AppError;
+use crate::japanese::kana::{is_kana, to_hiragana, to_katakana};
+use crate::settings;
+
+// IPADIC context ids for each kind of word, so the analyzer treats user words
+// like its own nouns
+const NOUN_CONTEXT: u16 = 1285;
+const PROPER_NOUN_CONTEXT: u16 = 1288;
+const PERSON_CONTEXT: u16 = 1289;
+const ORGANIZATION_CONTEXT: u16 = 1292;
+const PLACE_CONTEXT: u16 = 1293;
+// Well below the system dictionary's nouns, so a user word wins over splitting
+// it into shorter ones
+const WORD_COST: i32 = -3000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserWordKind {
+    #[default]
+    Noun,
+    ProperNoun,
+    Person,
+    Place,
+    Organization,
+}
+
+impl UserWordKind {
+    // JMdict part-of-speech code and JMnedict name type for the entry
+    fn codes(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            UserWordKind::Noun => ("n", None),
+            UserWordKind::ProperNoun => ("n-pr", None),
+            UserWordKind::Person => ("n-pr", Some("person")),
+            UserWordKind::Place => ("n-pr", Some("place")),
+            UserWordKind::Organization => ("n-pr", Some("organization")),
+        }
+    }
+
+    fn context_id(self) -> u16 {
+        match self {
+            UserWordKind::Noun => NOUN_CONTEXT,
+            UserWordKind::ProperNoun => PROPER_NOUN_CONTEXT,
+            UserWordKind::Person => PERSON_CONTEXT,
+            UserWordKind::Place => PLACE_CONTEXT,
+            UserWordKind::Organization => ORGANIZATION_CONTEXT,
+        }
+    }
+
+    // IPADIC's part-of-speech columns
+    fn pos(self) -> &'static str {
+        match self {
+            UserWordKind::Noun => "名詞,一般,*,*",
+            UserWordKind::ProperNoun => "名詞,固有名詞,一般,*",
+            UserWordKind::Person => "名詞,固有名詞,人名,一般",
+            UserWordKind::Place => "名詞,固有名詞,地域,一般",
+            UserWordKind::Organization => "名詞,固有名詞,組織,*",
+        }
+    }
+}
+
+// Jargon or a character name, looked up before the bundled dictionaries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserWord {
+    // Assigned on first save
+    #[serde(default)]
+    pub id: u64,
+    pub term: String,
+    // In kana
+    pub reading: String,
+    pub glosses: Vec<String>,
+    #[serde(default)]
+    pub kind: UserWordKind,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl UserWord {
+    fn matches(&self, term: &str) -> bool {
+        self.term == term || to_hiragana(&self.reading) == to_hiragana(term)
+    }
+
+    fn is_same_word(&self, other: &UserWord) -> bool {
+        self.term == other.term && to_hiragana(&self.reading) == to_hiragana(&other.reading)
+    }
+
+    fn to_entry(&self) -> DictionaryEntry {
+        let (pos, name_type) = self.kind.codes();
+        let kanji = if self.term.chars().all(is_kana) {
+            Vec::new()
+        } else {
+            vec![KanjiForm {
+                text: self.term.clone(),
+                common: true,
+                ..Default::default()
+            }]
+        };
+        DictionaryEntry {
+            id: self.id,
+            kanji,
+            readings: vec![ReadingForm {
+                text: self.reading.clone(),
+                common: true,
+                ..Default::default()
+            }],
+            senses: vec![Sense {
+                glosses: self.glosses.clone(),
+                pos: vec![pos.to_string()],
+                misc: name_type.iter().map(|name| name.to_string()).collect(),
+                info: self.notes.iter().cloned().collect(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    // A row of a MeCab lexicon CSV, with the term as its own base form
+    fn lexicon_row(&self) -> String {
+        let reading = to_katakana(&self.reading);
+        let context = self.kind.context_id();
+        format!(
+            "{},{},{},{},{},*,*,{},{},{}\n",
+            self.term,
+            context,
+            context,
+            WORD_COST,
+            self.kind.pos(),
+            self.term,
+            reading,
+            reading
+        )
+    }
+}
+
+pub fn validate(words: &[UserWord]) -> Result<(), String> {
+    for (index, word) in words.iter().enumerate() {
+        if word.term.trim().is_empty() {
+            return Err("User dictionary terms must not be empty".to_string());
+        }
+        if word.reading.is_empty() || !word.reading.chars().all(is_kana) {
+            return Err(format!("Reading of '{}' must be written in kana", word.term));
+        }
+        // The analyzer reads its lexicon as CSV
+        if word.term.contains([',', '"', '\n', '\r']) {
+            return Err(format!("User dictionary term '{}' contains a comma, quote or line break", word.term));
+        }
+        if words[..index].iter().any(|w| w.id == word.id) {
+            return Err(format!("User dictionary id {} is used twice", word.id));
+        }
+        if words[..index].iter().any(|w| w.is_same_word(word)) {
+            return Err(format!("'{}' ({}) is in the user dictionary twice", word.term, word.reading));
+        }
+    }
+    Ok(())
+}
+
+pub fn load_words(app: &AppHandle) -> Vec<UserWord> {
+    settings::load(app).user_dictionary
+}
+
+// Settings apply the change, which also reloads the analyzer
+fn save_words(app: &AppHandle, words: Vec<UserWord>) -> Result<(), String> {
+    validate(&words)?;
+    settings::update(app, |settings| settings.user_dictionary = words).map(|_| ())
+}
+
+fn next_id(words: &[UserWord]) -> u64 {
+    words.iter().map(|word| word.id).max().unwrap_or(0) + 1
+}
+
+pub fn lookup(app: &AppHandle, term: &str) -> Vec<WordMatch> {
+    load_words(app)
+        .into_iter()
+        .filter(|word| word.matches(term))
+        .map(|word| WordMatch {
+            entry: word.to_entry(),
+            source: WordSource::User,
+            dictionary_form: word.term,
+            inflections: Vec::new(),
+            dictionary: None,
+            frequency: None,
+        })
+        .collect()
+}
+
+// User terms as a MeCab lexicon CSV for the analyzer; empty when there are none
+pub fn lexicon(app: &AppHandle) -> String {
+    load_words(app).iter().map(UserWord::lexicon_row).collect()
+}
+
+#[tauri::command]
+pub async fn list_user_words(app: AppHandle) -> Result<Vec<UserWord>, AppError> {
+    Ok(load_words(&app))
+}
+
+// An id of 0 adds the word; any other replaces the word with that id
+#[tauri::command]
+pub async fn save_user_word(app: AppHandle, word: UserWord) -> Result<UserWord, AppError> {
+    let mut words = load_words(&app);
+    let mut word = UserWord {
+        term: word.term.trim().to_string(),
+        reading: word.reading.trim().to_string(),
+        ..word
+    };
+    if word.id == 0 {
+        word.id = next_id(&words);
+        words.push(word.clone());
+    } else {
+        let existing = words
+            .iter_mut()
+            .find(|w| w.id == word.id)
+            .ok_or_else(|| AppError::NotFound(format!("User word {} not found", word.id)))?;
+        *existing = word.clone();
+    }
+    save_words(&app, words)?;
+    Ok(word)
+}
+
+#[tauri::command]
+pub async fn delete_user_word(app: AppHandle, id: u64) -> Result<(), AppError> {
+    let mut words = load_words(&app);
+    let before = words.len();
+    words.retain(|w| w.id != id);
+    if words.len() == before {
+        return Err(AppError::NotFound(format!("User word {} not found", id)));
+    }
+    Ok(save_words(&app, words)?)
+}
+
+#[tauri::command]
+pub async fn export_user_dictionary(app: AppHandle, path: String) -> Result<usize, AppError> {
+    let words = load_words(&app);
+    let json =
+        serde_json::to_string_pretty(&words).map_err(|e| format!("Failed to serialize user dictionary: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(words.len())
+}
+
+// Merges an export into the current words; ones already there (same term and
+// reading) are replaced. Returns how many were read.
+#[tauri::command]
+pub async fn import_user_dictionary(app: AppHandle, path: String) -> Result<usize, AppError> {
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let imported: Vec<UserWord> =
+        serde_json::from_str(&json).map_err(|e| format!("Not a user dictionary export: {}", e))?;
+    let count = imported.len();
+
+    let mut words = load_words(&app);
+    for word in imported {
+        match words.iter_mut().find(|w| w.is_same_word(&word)) {
+            Some(existing) => {
+                *existing = UserWord {
+                    id: existing.id,
+                    ..word
+                }
+            }
+            None => {
+                let id = next_id(&words);
+                words.push(UserWord { id, ..word });
+            }
+        }
+    }
+    save_words(&app, words)?;
+    Ok(count)
+}
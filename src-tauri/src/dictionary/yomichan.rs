@@ -0,0 +1,485 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use zip::ZipArchive;
+
+use super::deinflect::{self, Candidate};
+use super::jmdict::{KanjiForm, ReadingForm, Sense};
+use super::{dictionary_dir, DictionaryEntry, WordMatch, WordSource};
+use crate::error::AppError;
+use crate::japanese::kana::is_kana;
+
+// Every imported dictionary shares one database, next to the built-in ones
+const DATABASE: &str = "imported.sqlite3";
+const FORMAT_VERSION: i64 = 1;
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS dictionaries (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL UNIQUE,
+        revision TEXT NOT NULL,
+        author TEXT,
+        -- Lower comes first in lookups
+        priority INTEGER NOT NULL,
+        terms INTEGER NOT NULL,
+        imported_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY,
+        dictionary_id INTEGER NOT NULL REFERENCES dictionaries (id),
+        expression TEXT NOT NULL,
+        reading TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS entries_expression ON entries (expression);
+    CREATE INDEX IF NOT EXISTS entries_reading ON entries (reading);
+";
+// Archive formats this reads; 1 lists glosses inline, 2 and 3 as an array
+const MAX_ARCHIVE_FORMAT: u64 = 3;
+
+#[derive(Default)]
+pub struct ImportedState(Mutex<Option<Connection>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedDictionary {
+    pub id: i64,
+    pub title: String,
+    pub revision: String,
+    pub author: Option<String>,
+    pub priority: i64,
+    pub terms: usize,
+    pub imported_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveIndex {
+    title: String,
+    revision: String,
+    #[serde(default)]
+    author: Option<String>,
+    // Older archives call it "version"
+    #[serde(default)]
+    format: Option<u64>,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+fn dictionary_error(e: rusqlite::Error) -> String {
+    format!("Dictionary error: {}", e)
+}
+
+// The database only exists once something has been imported, so lookups
+// don't create it
+fn with_imported<T>(
+    app: &AppHandle,
+    create: bool,
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    let state = app.state::<ImportedState>();
+    let mut imported = state.0.lock().unwrap();
+    if imported.is_none() {
+        let path = dictionary_dir(app)?.join(DATABASE);
+        if !create && !path.exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(dictionary_error)?;
+        if version > FORMAT_VERSION {
+            return Err("Imported dictionaries were written by a newer version of Shunyaku".to_string());
+        }
+        conn.execute_batch(SCHEMA).map_err(dictionary_error)?;
+        conn.pragma_update(None, "user_version", FORMAT_VERSION)
+            .map_err(dictionary_error)?;
+        *imported = Some(conn);
+    }
+    f(imported.as_mut().unwrap()).map(Some)
+}
+
+fn block_tag(tag: &str) -> bool {
+    matches!(tag, "div" | "p" | "li" | "ol" | "ul" | "tr" | "table" | "details" | "summary")
+}
+
+// Structured-content glosses as plain text, with a line per block element
+fn flatten(content: &Value, out: &mut String) {
+    match content {
+        Value::String(text) => out.push_str(text),
+        Value::Array(items) => items.iter().for_each(|item| flatten(item, out)),
+        Value::Object(node) => {
+            let tag = node.get("tag").and_then(Value::as_str).unwrap_or("");
+            if tag == "br" || (block_tag(tag) && !out.is_empty() && !out.ends_with('\n')) {
+                out.push('\n');
+            }
+            if let Some(content) = node.get("content") {
+                flatten(content, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn glosses(items: &[Value]) -> Vec<String> {
+    let mut glosses = Vec::new();
+    for item in items {
+        let text = match item {
+            Value::String(text) => text.clone(),
+            Value::Object(gloss) => match gloss.get("type").and_then(Value::as_str) {
+                Some("text") => gloss.get("text").and_then(Value::as_str).unwrap_or("").to_string(),
+                Some("structured-content") => {
+                    let mut text = String::new();
+                    if let Some(content) = gloss.get("content") {
+                        flatten(content, &mut text);
+                    }
+                    text
+                }
+                // Images, and deinflection pointers at other terms
+                _ => continue,
+            },
+            _ => continue,
+        };
+        glosses.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+    glosses
+}
+
+fn words(field: Option<&Value>) -> Vec<String> {
+    field
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+struct TermRow {
+    expression: String,
+    reading: String,
+    sequence: i64,
+    score: i64,
+}
+
+// First row of a word, and the senses of all its rows
+type Word = (TermRow, Vec<Sense>);
+
+// [expression, reading, definition tags, rules, score, glossary, sequence,
+// term tags], where format 1 has the glosses inline from index 5 on
+fn parse_row(row: &Value, format: u64) -> Option<(TermRow, Sense)> {
+    let row = row.as_array()?;
+    let expression = row.first()?.as_str()?.to_string();
+    let reading = row.get(1).and_then(Value::as_str).unwrap_or("");
+    let glossary = if format == 1 {
+        row.get(5..).unwrap_or_default()
+    } else {
+        row.get(5).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default()
+    };
+    let sense = Sense {
+        glosses: glosses(glossary),
+        // Rules are the deinflection classes, written like JMdict's POS codes
+        pos: words(row.get(3)),
+        misc: words(row.get(2)),
+        ..Default::default()
+    };
+    if sense.glosses.is_empty() {
+        return None;
+    }
+    let term = TermRow {
+        reading: if reading.is_empty() {
+            expression.clone()
+        } else {
+            reading.to_string()
+        },
+        expression,
+        score: row.get(4).and_then(Value::as_i64).unwrap_or(0),
+        sequence: if format == 1 {
+            0
+        } else {
+            row.get(6).and_then(Value::as_i64).unwrap_or(0)
+        },
+    };
+    Some((term, sense))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(archive: &mut ZipArchive<File>, name: &str) -> Result<T, String> {
+    let file = archive
+        .by_name(name)
+        .map_err(|e| format!("Failed to read {} from archive: {}", name, e))?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("Invalid {} in archive: {}", name, e))
+}
+
+fn is_term_bank(name: &str) -> bool {
+    name.starts_with("term_bank_") && name.ends_with(".json")
+}
+
+// Rows of one word (same sequence, or same forms when unsequenced) become
+// the senses of one entry. Kanji, tag and frequency banks are not read.
+fn read_archive(path: &Path) -> Result<(ArchiveIndex, Vec<Word>), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a dictionary archive: {}", e))?;
+    let index: ArchiveIndex = read_json(&mut archive, "index.json")?;
+    let format = index.format.or(index.version).unwrap_or(1);
+    if format > MAX_ARCHIVE_FORMAT {
+        return Err(format!("Unsupported dictionary format {} in '{}'", format, index.title));
+    }
+
+    let mut banks: Vec<String> = archive.file_names().filter(|name| is_term_bank(name)).map(str::to_string).collect();
+    banks.sort_by_key(|name| {
+        name.trim_start_matches("term_bank_")
+            .trim_end_matches(".json")
+            .parse::<u32>()
+            .unwrap_or(u32::MAX)
+    });
+    if banks.is_empty() {
+        return Err(format!("'{}' has no term banks", index.title));
+    }
+
+    let mut words: Vec<Word> = Vec::new();
+    let mut positions: HashMap<(String, String, i64), usize> = HashMap::new();
+    for bank in &banks {
+        let rows: Vec<Value> = read_json(&mut archive, bank)?;
+        for (term, sense) in rows.iter().filter_map(|row| parse_row(row, format)) {
+            let key = (term.expression.clone(), term.reading.clone(), term.sequence);
+            match positions.get(&key) {
+                Some(&position) => {
+                    let (first, senses) = &mut words[position];
+                    first.score = first.score.max(term.score);
+                    senses.push(sense);
+                }
+                None => {
+                    positions.insert(key, words.len());
+                    words.push((term, vec![sense]));
+                }
+            }
+        }
+    }
+    Ok((index, words))
+}
+
+fn to_entry(row: &TermRow, senses: Vec<Sense>) -> DictionaryEntry {
+    let kanji = if row.expression == row.reading && row.expression.chars().all(is_kana) {
+        Vec::new()
+    } else {
+        vec![KanjiForm {
+            text: row.expression.clone(),
+            ..Default::default()
+        }]
+    };
+    DictionaryEntry {
+        id: 0,
+        kanji,
+        readings: vec![ReadingForm {
+            text: row.reading.clone(),
+            ..Default::default()
+        }],
+        senses,
+    }
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<ImportedDictionary>> {
+    conn.prepare(
+        "SELECT id, title, revision, author, priority, terms, imported_at FROM dictionaries ORDER BY priority, id",
+    )?
+    .query_map([], |row| {
+        Ok(ImportedDictionary {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            revision: row.get(2)?,
+            author: row.get(3)?,
+            priority: row.get(4)?,
+            terms: row.get(5)?,
+            imported_at: row.get(6)?,
+        })
+    })?
+    .collect()
+}
+
+// A dictionary imported again under the same title replaces the old copy
+// and keeps its place in the order
+fn import(app: &AppHandle, path: &Path) -> Result<ImportedDictionary, String> {
+    let (index, words) = read_archive(path)?;
+    with_imported(app, true, |conn| {
+        let transaction = conn.transaction().map_err(dictionary_error)?;
+        let previous: Option<(i64, i64)> = transaction
+            .query_row(
+                "SELECT id, priority FROM dictionaries WHERE title = ?1",
+                [&index.title],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(dictionary_error)?;
+        let priority = match previous {
+            Some((id, priority)) => {
+                remove(&transaction, id).map_err(dictionary_error)?;
+                priority
+            }
+            None => transaction
+                .query_row("SELECT coalesce(max(priority) + 1, 0) FROM dictionaries", [], |row| row.get(0))
+                .map_err(dictionary_error)?,
+        };
+        transaction
+            .execute(
+                "INSERT INTO dictionaries (title, revision, author, priority, terms, imported_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![index.title, index.revision, index.author, priority, words.len(), Utc::now().to_rfc3339()],
+            )
+            .map_err(dictionary_error)?;
+        let dictionary_id = transaction.last_insert_rowid();
+        {
+            let mut insert = transaction
+                .prepare(
+                    "INSERT INTO entries (dictionary_id, expression, reading, score, data) \
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(dictionary_error)?;
+            for (row, senses) in words {
+                let data = serde_json::to_string(&to_entry(&row, senses))
+                    .map_err(|e| format!("Failed to serialize entry: {}", e))?;
+                insert
+                    .execute(params![dictionary_id, row.expression, row.reading, row.score, data])
+                    .map_err(dictionary_error)?;
+            }
+        }
+        transaction.commit().map_err(dictionary_error)?;
+        list(conn)
+            .map_err(dictionary_error)?
+            .into_iter()
+            .find(|dictionary| dictionary.id == dictionary_id)
+            .ok_or_else(|| "Failed to import dictionary".to_string())
+    })?
+    .ok_or_else(|| "Failed to import dictionary".to_string())
+}
+
+fn remove(conn: &Connection, id: i64) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM entries WHERE dictionary_id = ?1", [id])?;
+    conn.execute("DELETE FROM dictionaries WHERE id = ?1", [id])
+}
+
+// Entries for any of the deinflection candidates, dictionary by dictionary in
+// priority order. Nothing when no dictionary has been imported.
+pub fn lookup(app: &AppHandle, candidates: &[Candidate], limit: usize) -> Vec<WordMatch> {
+    let found = with_imported(app, false, |conn| {
+        let mut statement = conn
+            .prepare_cached(
+                "SELECT e.id, e.data, d.title, d.priority FROM entries e JOIN dictionaries d ON d.id = e.dictionary_id \
+                 WHERE e.expression = ?1 OR e.reading = ?1 ORDER BY d.priority, e.score DESC LIMIT ?2",
+            )
+            .map_err(dictionary_error)?;
+        let mut matches: Vec<(i64, i64, WordMatch)> = Vec::new();
+        for candidate in candidates {
+            let rows = statement
+                .query_map(params![candidate.term, limit as i64], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                })
+                .map_err(dictionary_error)?;
+            for row in rows {
+                let (id, data, title, priority) = row.map_err(dictionary_error)?;
+                let Ok(mut entry) = serde_json::from_str::<DictionaryEntry>(&data) else {
+                    continue;
+                };
+                entry.id = id as u64;
+                let types = entry
+                    .senses
+                    .iter()
+                    .flat_map(|sense| &sense.pos)
+                    .fold(0, |types, pos| types | deinflect::word_types(pos));
+                if !deinflect::matches(candidate, types) || matches.iter().any(|(found, _, _)| *found == id) {
+                    continue;
+                }
+                matches.push((
+                    id,
+                    priority,
+                    WordMatch {
+                        entry,
+                        source: WordSource::Imported,
+                        dictionary: Some(title),
+                        dictionary_form: candidate.term.clone(),
+                        inflections: candidate.reasons.clone(),
+                        frequency: None,
+                    },
+                ));
+            }
+        }
+        Ok(matches)
+    });
+    let mut matches = match found {
+        Ok(found) => found.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Imported dictionary lookup failed: {}", e);
+            Vec::new()
+        }
+    };
+    // Each candidate's rows come in priority order; this keeps it across them
+    matches.sort_by_key(|(_, priority, _)| *priority);
+    matches.into_iter().take(limit).map(|(_, _, found)| found).collect()
+}
+
+#[tauri::command]
+pub async fn import_yomichan_dictionary(app: AppHandle, path: String) -> Result<ImportedDictionary, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || import(&app, Path::new(&path)))
+        .await
+        .map_err(|e| format!("Dictionary import failed: {}", e))??)
+}
+
+#[tauri::command]
+pub async fn list_imported_dictionaries(app: AppHandle) -> Result<Vec<ImportedDictionary>, AppError> {
+    Ok(with_imported(&app, false, |conn| list(conn).map_err(dictionary_error))?.unwrap_or_default())
+}
+
+// `ids` is every imported dictionary, highest priority first
+#[tauri::command]
+pub async fn reorder_imported_dictionaries(app: AppHandle, ids: Vec<i64>) -> Result<Vec<ImportedDictionary>, AppError> {
+    Ok(with_imported(&app, false, |conn| {
+        let mut current: Vec<i64> = list(conn).map_err(dictionary_error)?.iter().map(|d| d.id).collect();
+        let mut requested = ids.clone();
+        current.sort_unstable();
+        requested.sort_unstable();
+        if current != requested {
+            return Err("The new order must list every imported dictionary once".to_string());
+        }
+        let transaction = conn.transaction().map_err(dictionary_error)?;
+        for (priority, id) in ids.iter().enumerate() {
+            transaction
+                .execute("UPDATE dictionaries SET priority = ?1 WHERE id = ?2", params![priority as i64, id])
+                .map_err(dictionary_error)?;
+        }
+        transaction.commit().map_err(dictionary_error)?;
+        list(conn).map_err(dictionary_error)
+    })?
+    .ok_or_else(|| "No dictionaries have been imported".to_string())?)
+}
+
+#[tauri::command]
+pub async fn remove_imported_dictionary(app: AppHandle, id: i64) -> Result<(), AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || {
+        let removed = with_imported(&app, false, |conn| {
+            let transaction = conn.transaction().map_err(dictionary_error)?;
+            let removed = remove(&transaction, id).map_err(dictionary_error)?;
+            transaction.commit().map_err(dictionary_error)?;
+            Ok(removed)
+        })?;
+        match removed {
+            Some(0) | None => Err(format!("Imported dictionary {} not found", id)),
+            Some(_) => Ok(()),
+        }
+    })
+    .await
+    .map_err(|e| format!("Dictionary removal failed: {}", e))??)
+}
@@ -0,0 +1,100 @@
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::settings;
+
+// Quiet hours during which notifications and popups nobody asked for are held
+// back. Translations still run and land in history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DndSchedule {
+    pub enabled: bool,
+    // Local wall-clock times as "HH:MM". An end before the start runs past
+    // midnight into the next day.
+    pub start: String,
+    pub end: String,
+    // Days the quiet period starts on
+    pub days: Vec<Weekday>,
+}
+
+impl Default for DndSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+            days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DndStatus {
+    pub active: bool,
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time \"{}\", expected HH:MM", value))
+}
+
+impl DndSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        parse_time(&self.start)?;
+        parse_time(&self.end)?;
+        Ok(())
+    }
+
+    pub fn is_active_at(&self, now: NaiveDateTime) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        if !self.enabled || start == end {
+            return false;
+        }
+
+        let time = now.time();
+        let starts_today = |date: NaiveDateTime| self.days.contains(&date.weekday());
+        if start < end {
+            starts_today(now) && time >= start && time < end
+        } else if time >= start {
+            starts_today(now)
+        } else {
+            // Early-morning tail of a period that started yesterday
+            time < end && starts_today(now - Duration::days(1))
+        }
+    }
+}
+
+pub fn load_schedule(app: &AppHandle) -> DndSchedule {
+    settings::load(app).do_not_disturb
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    load_schedule(app).is_active_at(Local::now().naive_local())
+}
+
+#[tauri::command]
+pub async fn get_dnd_schedule(app: AppHandle) -> Result<DndSchedule, AppError> {
+    Ok(load_schedule(&app))
+}
+
+#[tauri::command]
+pub async fn set_dnd_schedule(app: AppHandle, schedule: DndSchedule) -> Result<DndStatus, AppError> {
+    schedule.validate()?;
+    settings::update(&app, |settings| settings.do_not_disturb = schedule.clone())?;
+
+    Ok(DndStatus {
+        active: schedule.is_active_at(Local::now().naive_local()),
+    })
+}
+
+#[tauri::command]
+pub async fn get_dnd_status(app: AppHandle) -> Result<DndStatus, AppError> {
+    Ok(DndStatus {
+        active: is_active(&app),
+    })
+}
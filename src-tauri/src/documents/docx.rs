@@ -0,0 +1,155 @@
+use quick_xml::events::{BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::Piece;
+
+fn zip_error(e: zip::result::ZipError) -> String {
+    format!("Failed to read document: {}", e)
+}
+
+fn xml_error(e: impl std::fmt::Display) -> String {
+    format!("Invalid document XML: {}", e)
+}
+
+// Parts holding the body, notes, headers and footers
+fn is_text_part(name: &str) -> bool {
+    let header_or_footer = name.starts_with("word/header") || name.starts_with("word/footer");
+    matches!(name, "word/document.xml" | "word/footnotes.xml" | "word/endnotes.xml")
+        || (header_or_footer && name.ends_with(".xml"))
+}
+
+fn open(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    ZipArchive::new(file).map_err(zip_error)
+}
+
+fn read_part(archive: &mut ZipArchive<File>, index: usize) -> Result<(String, Vec<u8>), String> {
+    let mut part = archive.by_index(index).map_err(zip_error)?;
+    let mut xml = Vec::new();
+    part.read_to_end(&mut xml).map_err(|e| format!("Failed to read document: {}", e))?;
+    Ok((part.name().to_string(), xml))
+}
+
+// Text of each w:p in a part, numbered in the order they open; a text box
+// inside a paragraph is a paragraph of its own
+fn paragraphs(xml: &[u8]) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut paragraphs = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) if e.name().as_ref() == b"w:p" => {
+                open.push(paragraphs.len());
+                paragraphs.push(String::new());
+            }
+            Event::End(e) if e.name().as_ref() == b"w:p" => {
+                open.pop();
+            }
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) if e.name().as_ref() == b"w:t" => in_text = false,
+            Event::Text(e) if in_text => {
+                if let Some(&paragraph) = open.last() {
+                    paragraphs[paragraph].push_str(&e.unescape().map_err(xml_error)?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+// The part with each paragraph's text replaced. The translation goes into
+// the paragraph's first run and the other runs are emptied, so paragraph
+// styles, lists and tables stay and the text takes the first run's look.
+fn rewrite(xml: &[u8], translations: &mut impl Iterator<Item = String>) -> Result<Vec<u8>, String> {
+    let texts = paragraphs(xml)?;
+    let mut reader = Reader::from_reader(xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut next = 0;
+    let mut open: Vec<(usize, Option<String>)> = Vec::new();
+    let mut in_text = false;
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        match &event {
+            Event::Start(e) if e.name().as_ref() == b"w:p" => {
+                let translation = match texts[next].trim().is_empty() {
+                    true => None,
+                    false => translations.next(),
+                };
+                open.push((next, translation));
+                next += 1;
+            }
+            Event::End(e) if e.name().as_ref() == b"w:p" => {
+                open.pop();
+            }
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) if e.name().as_ref() == b"w:t" => in_text = false,
+            Event::Text(_) if in_text => {
+                if let Some((paragraph, translation)) = open.last_mut() {
+                    if !texts[*paragraph].trim().is_empty() {
+                        let text = translation.take().unwrap_or_default();
+                        writer.write_event(Event::Text(BytesText::new(&text))).map_err(xml_error)?;
+                        continue;
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        writer.write_event(event).map_err(xml_error)?;
+    }
+    Ok(writer.into_inner())
+}
+
+// A segment per non-empty paragraph of each text part
+pub(super) fn pieces(path: &Path) -> Result<Vec<Piece>, String> {
+    let mut archive = open(path)?;
+    let mut pieces = Vec::new();
+    for index in 0..archive.len() {
+        if !is_text_part(archive.name_for_index(index).unwrap_or("")) {
+            continue;
+        }
+        let (_, xml) = read_part(&mut archive, index)?;
+        for paragraph in paragraphs(&xml)? {
+            if !paragraph.trim().is_empty() {
+                pieces.push(Piece::Translate(paragraph.trim().to_string()));
+                pieces.push(Piece::Keep("\n\n".to_string()));
+            }
+        }
+    }
+    if pieces.is_empty() {
+        return Err(format!("{} has no text", path.display()));
+    }
+    Ok(pieces)
+}
+
+// The original package with translated text parts; everything else (styles,
+// images, relationships) is copied as it was
+pub(super) fn rebuild(path: &Path, original: &[Piece], translated: &[Piece]) -> Result<Vec<u8>, String> {
+    let mut translations = original.iter().zip(translated).filter_map(|pieces| match pieces {
+        (Piece::Translate(_), Piece::Keep(text) | Piece::Translate(text)) => Some(text.clone()),
+        _ => None,
+    });
+    let mut archive = open(path)?;
+    let mut output = ZipWriter::new(Cursor::new(Vec::new()));
+    for index in 0..archive.len() {
+        if is_text_part(archive.name_for_index(index).unwrap_or("")) {
+            let (name, xml) = read_part(&mut archive, index)?;
+            let xml = rewrite(&xml, &mut translations)?;
+            let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            output.start_file(name, options).map_err(zip_error)?;
+            output.write_all(&xml).map_err(|e| format!("Failed to write document: {}", e))?;
+        } else {
+            let part = archive.by_index_raw(index).map_err(zip_error)?;
+            output.raw_copy_file(part).map_err(zip_error)?;
+        }
+    }
+    output.finish().map(Cursor::into_inner).map_err(zip_error)
+}
@@ -0,0 +1,248 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+use super::{start as start_job, DocumentFinished, DocumentOutput, Format, JobOptions};
+use crate::error::AppError;
+use crate::settings;
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+// Oldest entries are dropped past this
+const LOG_LIMIT: usize = 500;
+
+// New files in `path` are translated into `output_path`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub output_path: String,
+    pub source_language: String,
+    // None follows the target language setting
+    pub target_language: Option<String>,
+    pub enabled: bool,
+}
+
+impl Default for WatchedFolder {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            output_path: String::new(),
+            source_language: "auto".to_string(),
+            target_language: None,
+            enabled: true,
+        }
+    }
+}
+
+fn check_language(code: &str, allow_auto: bool) -> Result<(), String> {
+    let valid = !code.is_empty() && code.chars().all(|c| c.is_ascii_alphabetic() || c == '-');
+    if !valid || (code == "auto" && !allow_auto) {
+        return Err(format!("'{}' is not a language code", code));
+    }
+    Ok(())
+}
+
+// Output inside a watched folder would be picked up and translated again
+pub fn validate(folders: &[WatchedFolder]) -> Result<(), String> {
+    for (index, folder) in folders.iter().enumerate() {
+        if !Path::new(&folder.path).is_absolute() || !Path::new(&folder.output_path).is_absolute() {
+            return Err(format!("Folder {}: paths must be absolute", index + 1));
+        }
+    }
+    for (index, folder) in folders.iter().enumerate() {
+        let (path, output) = (Path::new(&folder.path), Path::new(&folder.output_path));
+        if folders.iter().any(|other| output.starts_with(&other.path)) {
+            return Err(format!("Folder {}: output must be outside every watched folder", index + 1));
+        }
+        if folders[..index].iter().any(|other| Path::new(&other.path) == path) {
+            return Err(format!("{} is watched twice", folder.path));
+        }
+        check_language(&folder.source_language, true).map_err(|e| format!("Folder {}: {}", index + 1, e))?;
+        if let Some(target) = &folder.target_language {
+            check_language(target, false).map_err(|e| format!("Folder {}: {}", index + 1, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FolderJobStatus {
+    Running,
+    Translated,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderLogEntry {
+    // 0 when the file was turned down before a job started
+    pub job: u64,
+    pub folder: String,
+    pub path: String,
+    pub status: FolderJobStatus,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+pub struct FolderState(Mutex<VecDeque<FolderLogEntry>>);
+
+fn push(log: &mut VecDeque<FolderLogEntry>, entry: FolderLogEntry) {
+    log.push_back(entry);
+    while log.len() > LOG_LIMIT {
+        log.pop_front();
+    }
+}
+
+// Called when a job started from a watched folder ends
+pub(super) fn finished(app: &AppHandle, finished: &DocumentFinished) {
+    let state = app.state::<FolderState>();
+    let mut log = state.0.lock().unwrap();
+    let Some(entry) = log.iter_mut().rev().find(|entry| entry.job == finished.job) else {
+        return;
+    };
+    entry.status = match (&finished.error, finished.cancelled) {
+        (Some(_), _) => FolderJobStatus::Failed,
+        (None, true) => FolderJobStatus::Cancelled,
+        (None, false) => FolderJobStatus::Translated,
+    };
+    entry.output_path = finished.output_path.clone();
+    entry.error = finished.error.clone();
+    entry.finished_at = Some(Utc::now());
+}
+
+fn translate(app: &AppHandle, folder: &WatchedFolder, path: PathBuf) {
+    let options = JobOptions {
+        output: Some(DocumentOutput::File),
+        source: Some(folder.source_language.clone()),
+        target: folder.target_language.clone(),
+        output_dir: Some(PathBuf::from(&folder.output_path)),
+        from_folder: true,
+        ..JobOptions::default()
+    };
+    let mut entry = FolderLogEntry {
+        job: 0,
+        folder: folder.path.clone(),
+        path: path.to_string_lossy().into_owned(),
+        status: FolderJobStatus::Running,
+        output_path: None,
+        error: None,
+        started_at: Utc::now(),
+        finished_at: None,
+    };
+    // Held across the start so the job can't end before its entry exists
+    let state = app.state::<FolderState>();
+    let mut log = state.0.lock().unwrap();
+    match start_job(app, path, options) {
+        Ok(job) => entry.job = job,
+        Err(e) => {
+            entry.status = FolderJobStatus::Failed;
+            entry.error = Some(e);
+            entry.finished_at = Some(entry.started_at);
+        }
+    }
+    push(&mut log, entry);
+}
+
+type Stamp = (SystemTime, u64);
+
+// What a scan has seen of one folder. A file is translated once two scans
+// in a row find it the same size and age, so it isn't read mid-copy.
+struct Scan {
+    seen: HashMap<PathBuf, Stamp>,
+    pending: HashMap<PathBuf, Stamp>,
+}
+
+fn files(folder: &Path) -> HashMap<PathBuf, Stamp> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter(|entry| Format::of(&entry.path()).is_some())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+            Some((entry.path(), (meta.modified().ok()?, meta.len())))
+        })
+        .collect()
+}
+
+impl Scan {
+    // Files already there when watching starts are left alone
+    fn new(folder: &Path) -> Self {
+        Self {
+            seen: files(folder),
+            pending: HashMap::new(),
+        }
+    }
+
+    // Files ready to translate; a changed file counts as new
+    fn ready(&mut self, folder: &Path) -> Vec<PathBuf> {
+        let current = files(folder);
+        let mut ready = Vec::new();
+        for (path, stamp) in &current {
+            if self.seen.get(path) == Some(stamp) {
+                continue;
+            }
+            if self.pending.get(path) == Some(stamp) {
+                self.pending.remove(path);
+                self.seen.insert(path.clone(), *stamp);
+                ready.push(path.clone());
+            } else {
+                self.pending.insert(path.clone(), *stamp);
+            }
+        }
+        self.seen.retain(|path, _| current.contains_key(path));
+        self.pending.retain(|path, _| current.contains_key(path));
+        ready.sort();
+        ready
+    }
+}
+
+// Polls rather than subscribing to file events, as settings hot reload does.
+// Folders are re-read from settings every scan, so edits apply without a
+// restart.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut scans: HashMap<String, Scan> = HashMap::new();
+        loop {
+            tokio::time::sleep(SCAN_INTERVAL).await;
+            let folders: Vec<WatchedFolder> =
+                settings::load(&app).watched_folders.into_iter().filter(|folder| folder.enabled).collect();
+            scans.retain(|path, _| folders.iter().any(|folder| &folder.path == path));
+            for folder in &folders {
+                let path = Path::new(&folder.path);
+                let Some(scan) = scans.get_mut(&folder.path) else {
+                    scans.insert(folder.path.clone(), Scan::new(path));
+                    continue;
+                };
+                for file in scan.ready(path) {
+                    translate(&app, folder, file);
+                }
+            }
+        }
+    });
+}
+
+// Newest first, optionally for one folder
+#[tauri::command]
+pub async fn get_watched_folder_log(app: AppHandle, folder: Option<String>) -> Result<Vec<FolderLogEntry>, AppError> {
+    let state = app.state::<FolderState>();
+    let log = state.0.lock().unwrap();
+    Ok(log
+        .iter()
+        .rev()
+        .filter(|entry| folder.as_ref().map_or(true, |folder| &entry.folder == folder))
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,563 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::tasks::{self, TaskHandle, TaskKind};
+use crate::translation::queue::Priority;
+use crate::transfer::{self, Payload};
+use crate::{settings, translation, window_ready};
+
+mod docx;
+pub mod folders;
+mod pdf;
+mod subtitles;
+
+pub use pdf::{PdfLayout, PdfOptions};
+
+const EXTENSIONS: [&str; 8] = ["txt", "md", "markdown", "srt", "ass", "ssa", "pdf", "docx"];
+const MAX_BYTES: u64 = 5 * 1024 * 1024;
+// PDFs and DOCX files are mostly images and fonts; the text in them is a
+// small part
+const MAX_PACKAGE_BYTES: u64 = 100 * 1024 * 1024;
+// DeepL takes up to 50 texts a request; the character cap keeps each request
+// well under its body size limit
+const BATCH_TEXTS: usize = 50;
+const BATCH_CHARS: usize = 20_000;
+
+// Where a translated document goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocumentOutput {
+    // notes.md becomes notes.ja.md beside it
+    #[default]
+    File,
+    // Shown in a new floating panel, nothing written
+    Panel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentResult {
+    pub path: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentProgress {
+    pub job: u64,
+    pub path: String,
+    // Counted in segments (paragraphs, headings, list items)
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentFinished {
+    pub job: u64,
+    pub path: String,
+    pub output_path: Option<String>,
+    pub window_id: Option<String>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    // Panel results until the panel has loaded and taken them
+    results: HashMap<String, DocumentResult>,
+}
+
+#[derive(Default)]
+pub struct DocumentState(Mutex<Jobs>);
+
+#[derive(Clone)]
+enum Piece {
+    Keep(String),
+    Translate(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Markdown,
+    // SubRip
+    Srt,
+    // Advanced SubStation Alpha, and the older SSA it extends
+    Ass,
+    Pdf,
+    // Word; paragraphs are translated in place
+    Docx,
+}
+
+// What a job was asked for beyond the file itself
+#[derive(Debug, Clone, Default)]
+struct JobOptions {
+    output: Option<DocumentOutput>,
+    source: Option<String>,
+    target: Option<String>,
+    pdf: PdfOptions,
+    // Files are written here rather than beside the original
+    output_dir: Option<PathBuf>,
+    // Reported to the watched folder log when done
+    from_folder: bool,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" => Some(Format::Text),
+            "md" | "markdown" => Some(Format::Markdown),
+            "srt" => Some(Format::Srt),
+            "ass" | "ssa" => Some(Format::Ass),
+            "pdf" => Some(Format::Pdf),
+            "docx" => Some(Format::Docx),
+            _ => None,
+        }
+    }
+
+    fn is_subtitle(self) -> bool {
+        matches!(self, Format::Srt | Format::Ass)
+    }
+
+    // The file as segments, and whether it uses CRLF line endings
+    fn read(self, path: &Path, pdf_options: &PdfOptions) -> Result<(Vec<Piece>, bool), String> {
+        match self {
+            Format::Pdf => return Ok((pdf::pieces(path, pdf_options)?, false)),
+            Format::Docx => return Ok((docx::pieces(path)?, false)),
+            _ => {}
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+        let pieces = match self {
+            Format::Markdown => segment(text, true),
+            Format::Srt => subtitles::segment_srt(text),
+            Format::Ass => subtitles::segment_ass(text),
+            Format::Text | Format::Pdf | Format::Docx => segment(text, false),
+        };
+        Ok((pieces, text.contains("\r\n")))
+    }
+
+    // Line breaks inside a segment go out as \n and come back in the file's
+    // own notation
+    fn restore(self, translated: &str, crlf: bool) -> String {
+        match self {
+            Format::Ass => translated.replace("\r\n", "\n").replace('\n', "\\N"),
+            _ if crlf => translated.replace("\r\n", "\n").replace('\n', "\r\n"),
+            _ => translated.to_string(),
+        }
+    }
+}
+
+// Surrounding whitespace stays out of what is sent, so line breaks and
+// indentation survive the round trip
+fn push_text(pieces: &mut Vec<Piece>, text: &str) {
+    let core = text.trim();
+    if core.is_empty() {
+        pieces.push(Piece::Keep(text.to_string()));
+        return;
+    }
+    let start = text.len() - text.trim_start().len();
+    pieces.push(Piece::Keep(text[..start].to_string()));
+    pieces.push(Piece::Translate(core.to_string()));
+    pieces.push(Piece::Keep(text[start + core.len()..].to_string()));
+}
+
+// Length of the heading, list or quote marker that starts a Markdown line
+fn markdown_prefix(line: &str) -> usize {
+    let mut rest = line.trim_start();
+    loop {
+        let before = rest.len();
+        if let Some(after) = rest.strip_prefix('>') {
+            rest = after.trim_start();
+        } else if let Some(after) = ["- ", "* ", "+ "].iter().find_map(|marker| rest.strip_prefix(marker)) {
+            rest = after.trim_start();
+            if let Some(after) = ["[ ] ", "[x] ", "[X] "].iter().find_map(|task| rest.strip_prefix(task)) {
+                rest = after;
+            }
+        } else if rest.starts_with('#') {
+            let after = rest.trim_start_matches('#');
+            if !after.starts_with(' ') {
+                break;
+            }
+            rest = after.trim_start();
+        } else {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let after = &rest[digits..];
+            if digits == 0 || !(after.starts_with(". ") || after.starts_with(") ")) {
+                break;
+            }
+            rest = after[2..].trim_start();
+        }
+        if rest.len() == before {
+            break;
+        }
+    }
+    line.len() - rest.len()
+}
+
+// Paragraphs, and in Markdown each heading and list item, become segments
+// to translate; blank lines, markers and fenced code are kept as they are
+fn segment(text: &str, markdown: bool) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut paragraph = String::new();
+    let mut fence: Option<&str> = None;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+        if markdown {
+            let trimmed = content.trim_start();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                pieces.push(Piece::Keep(line.to_string()));
+                continue;
+            }
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+                push_text(&mut pieces, &std::mem::take(&mut paragraph));
+                fence = Some(marker);
+                pieces.push(Piece::Keep(line.to_string()));
+                continue;
+            }
+            let prefix = markdown_prefix(content);
+            if prefix > 0 {
+                push_text(&mut pieces, &std::mem::take(&mut paragraph));
+                pieces.push(Piece::Keep(content[..prefix].to_string()));
+                push_text(&mut pieces, &content[prefix..]);
+                pieces.push(Piece::Keep(ending.to_string()));
+                continue;
+            }
+        }
+        if content.trim().is_empty() {
+            push_text(&mut pieces, &std::mem::take(&mut paragraph));
+            pieces.push(Piece::Keep(line.to_string()));
+            continue;
+        }
+        paragraph.push_str(line);
+    }
+    push_text(&mut pieces, &paragraph);
+    pieces
+}
+
+// Consecutive segments up to the request limits
+fn batches(pieces: &[Piece]) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut chars = 0;
+    for (index, piece) in pieces.iter().enumerate() {
+        let Piece::Translate(text) = piece else {
+            continue;
+        };
+        let length = text.chars().count();
+        match batches.last_mut() {
+            Some(batch) if batch.len() < BATCH_TEXTS && chars + length <= BATCH_CHARS => {
+                batch.push(index);
+                chars += length;
+            }
+            _ => {
+                batches.push(vec![index]);
+                chars = length;
+            }
+        }
+    }
+    batches
+}
+
+// notes.md -> notes.ja.md, or notes.ja (2).md when that is taken
+fn output_path(path: &Path, language: &str, extension: &str) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut candidate = path.with_file_name(format!("{}.{}.{}", stem, language, extension));
+    let mut copy = 2;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{}.{} ({}).{}", stem, language, copy, extension));
+        copy += 1;
+    }
+    candidate
+}
+
+// The translated file, or None when cancelled. PDFs come out as originals
+// paired with translations, DOCX files as the same package with the text
+// replaced unless headed for a panel. Documents stay out of history; an entry per
+// paragraph would bury everything else.
+async fn translated(
+    app: &AppHandle,
+    task: &TaskHandle,
+    path: &Path,
+    format: Format,
+    options: &JobOptions,
+) -> Result<Option<(Vec<u8>, String)>, String> {
+    let (mut pieces, crlf) = {
+        let (file, pdf_options) = (path.to_path_buf(), options.pdf);
+        // PDF parsing is slow and can panic on broken files
+        tauri::async_runtime::spawn_blocking(move || format.read(&file, &pdf_options))
+            .await
+            .map_err(|_| format!("Failed to read {}", path.display()))??
+    };
+    let original = matches!(format, Format::Pdf | Format::Docx).then(|| pieces.clone());
+    let target = options.target.as_deref();
+    let batches = batches(&pieces);
+    let total = batches.iter().map(Vec::len).sum();
+    let mut progress = DocumentProgress {
+        job: task.id(),
+        path: path.to_string_lossy().into_owned(),
+        done: 0,
+        total,
+    };
+    events::broadcast(app, AppEvent::DocumentProgress(progress.clone()));
+    task.progress(0, total);
+
+    let mut language = target.map_or_else(|| settings::load(app).target_language, str::to_string);
+    for batch in batches {
+        if !task.proceed().await {
+            return Ok(None);
+        }
+        let texts: Vec<&str> = batch
+            .iter()
+            .filter_map(|index| match &pieces[*index] {
+                Piece::Translate(text) => Some(text.as_str()),
+                Piece::Keep(_) => None,
+            })
+            .collect();
+        let results =
+            translation::translate_batch(app, &texts, options.source.as_deref(), target, Priority::Background).await?;
+        if let Some(result) = results.first() {
+            language = result.target_lang.clone();
+        }
+        for (index, result) in batch.iter().zip(results) {
+            pieces[*index] = Piece::Keep(format.restore(&result.translated_text, crlf));
+        }
+        progress.done += batch.len();
+        events::broadcast(app, AppEvent::DocumentProgress(progress.clone()));
+        task.progress(progress.done, total);
+    }
+
+    let translated = match (format, original) {
+        (Format::Pdf, Some(original)) => {
+            let title = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            pdf::render(&title, &original, &pieces, options.pdf.layout).into_bytes()
+        }
+        (Format::Docx, Some(original)) if options.output != Some(DocumentOutput::Panel) => {
+            docx::rebuild(path, &original, &pieces)?
+        }
+        _ => pieces
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Keep(text) | Piece::Translate(text) => text,
+            })
+            .collect::<String>()
+            .into_bytes(),
+    };
+    Ok(Some((translated, language)))
+}
+
+async fn run(
+    app: &AppHandle,
+    task: &TaskHandle,
+    path: &Path,
+    format: Format,
+    options: &JobOptions,
+) -> DocumentFinished {
+    let mut finished = DocumentFinished {
+        job: task.id(),
+        path: path.to_string_lossy().into_owned(),
+        output_path: None,
+        window_id: None,
+        cancelled: false,
+        error: None,
+    };
+    let (bytes, language) = match translated(app, task, path, format, options).await {
+        Ok(Some(translated)) => translated,
+        Ok(None) => {
+            finished.cancelled = true;
+            return finished;
+        }
+        Err(e) => {
+            finished.error = Some(e);
+            return finished;
+        }
+    };
+
+    match options.output.unwrap_or_default() {
+        DocumentOutput::File => {
+            let extension = match format {
+                Format::Pdf => options.pdf.layout.extension().to_string(),
+                _ => path.extension().map(|extension| extension.to_string_lossy().into_owned()).unwrap_or_default(),
+            };
+            let base = match &options.output_dir {
+                Some(dir) => {
+                    if let Err(e) = std::fs::create_dir_all(dir) {
+                        finished.error = Some(format!("Failed to create {}: {}", dir.display(), e));
+                        return finished;
+                    }
+                    dir.join(path.file_name().unwrap_or_default())
+                }
+                None => path.to_path_buf(),
+            };
+            let target = output_path(&base, &language, &extension);
+            match std::fs::write(&target, bytes) {
+                Ok(()) => finished.output_path = Some(target.to_string_lossy().into_owned()),
+                Err(e) => finished.error = Some(format!("Failed to write {}: {}", target.display(), e)),
+            }
+        }
+        DocumentOutput::Panel => match crate::open_floating_window(app) {
+            Ok(window_id) => {
+                let result = DocumentResult {
+                    path: finished.path.clone(),
+                    text: String::from_utf8_lossy(&bytes).into_owned(),
+                };
+                window_ready::send_when_ready(app, &window_id, AppEvent::DocumentResult(result.clone()));
+                app.state::<DocumentState>().0.lock().unwrap().results.insert(window_id.clone(), result);
+                finished.window_id = Some(window_id);
+            }
+            Err(e) => finished.error = Some(e),
+        },
+    }
+    finished
+}
+
+// Emits document-progress as batches come back and document-finished at the
+// end. Returns the job number, which is also its task id in list_tasks. Subtitles are
+// always written to a file; PDFs shown in a panel use the text layout.
+fn start(app: &AppHandle, path: PathBuf, mut options: JobOptions) -> Result<u64, String> {
+    let Some(format) = Format::of(&path) else {
+        return Err(format!("Only {} files can be translated", EXTENSIONS.join(", ")));
+    };
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let limit = match format {
+        Format::Pdf | Format::Docx => MAX_PACKAGE_BYTES,
+        _ => MAX_BYTES,
+    };
+    if size > limit {
+        return Err(format!("{} is larger than {} MB", path.display(), limit / 1024 / 1024));
+    }
+    let output = match format.is_subtitle() {
+        true => DocumentOutput::File,
+        false => options.output.unwrap_or(settings::load(app).document_output),
+    };
+    if output == DocumentOutput::Panel {
+        options.pdf.layout = PdfLayout::Text;
+    }
+    options.output = Some(output);
+
+    let task = tasks::start(app, TaskKind::Document, path.to_string_lossy().into_owned());
+    let job = task.id();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let finished = run(&app, &task, &path, format, &options).await;
+        task.finish(finished.error.clone().map_or(Ok(()), Err));
+        if options.from_folder {
+            folders::finished(&app, &finished);
+        }
+        events::broadcast(&app, AppEvent::DocumentFinished(finished));
+    });
+    Ok(job)
+}
+
+// Whether a file is one of the formats translate_document takes
+pub fn is_supported(path: &Path) -> bool {
+    Format::of(path).is_some()
+}
+
+// Files dropped on any window; anything but text, Markdown, subtitles, PDFs
+// and DOCX files is left to the page it was dropped on
+pub fn dropped(app: &AppHandle, paths: &[PathBuf]) {
+    for path in paths.iter().filter(|path| Format::of(path).is_some()) {
+        if let Err(e) = start(app, path.clone(), JobOptions::default()) {
+            tracing::error!("Failed to translate {}: {}", path.display(), e);
+            events::broadcast(
+                app,
+                AppEvent::DocumentFinished(DocumentFinished {
+                    job: 0,
+                    path: path.to_string_lossy().into_owned(),
+                    output_path: None,
+                    window_id: None,
+                    cancelled: false,
+                    error: Some(e),
+                }),
+            );
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn translate_document(app: AppHandle, path: String, output: Option<DocumentOutput>) -> Result<u64, AppError> {
+    Ok(start(
+        &app,
+        PathBuf::from(path),
+        JobOptions {
+            output,
+            ..JobOptions::default()
+        },
+    )?)
+}
+
+// Writes movie.<target>.srt (or .ass) beside the original with the same
+// cues, timing and styling. Progress and the result arrive as for documents.
+#[tauri::command]
+pub async fn translate_subtitle(app: AppHandle, path: String, target: String) -> Result<u64, AppError> {
+    let path = PathBuf::from(path);
+    if !Format::of(&path).is_some_and(Format::is_subtitle) {
+        return Err(AppError::InvalidInput("Only .srt, .ass and .ssa subtitles can be translated".to_string()));
+    }
+    if target.is_empty() || target == "auto" || !target.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+        return Err(AppError::InvalidInput(format!("'{}' is not a target language", target)));
+    }
+    Ok(start(
+        &app,
+        path,
+        JobOptions {
+            target: Some(target),
+            ..JobOptions::default()
+        },
+    )?)
+}
+
+// A page range of a PDF, written as report.<target>.html (or .txt) with each
+// paragraph beside its translation
+#[tauri::command]
+pub async fn translate_pdf(
+    app: AppHandle,
+    path: String,
+    options: Option<PdfOptions>,
+    output: Option<DocumentOutput>,
+) -> Result<u64, AppError> {
+    let path = PathBuf::from(path);
+    if Format::of(&path) != Some(Format::Pdf) {
+        return Err(AppError::InvalidInput("Not a PDF file".to_string()));
+    }
+    Ok(start(
+        &app,
+        path,
+        JobOptions {
+            output,
+            pdf: options.unwrap_or_default(),
+            ..JobOptions::default()
+        },
+    )?)
+}
+
+// Stops before the next batch; false when the job already finished. The
+// same as cancel_task with the job number.
+#[tauri::command]
+pub async fn cancel_document_translation(app: AppHandle, job: u64) -> Result<bool, AppError> {
+    Ok(tasks::cancel(&app, job))
+}
+
+// For a panel opened with a document result, once its page is listening.
+// A long document comes back as a transfer for that panel to read.
+#[tauri::command]
+pub async fn take_document_result(
+    app: AppHandle,
+    window_id: String,
+) -> Result<Option<Payload<DocumentResult>>, AppError> {
+    let result = app.state::<DocumentState>().0.lock().unwrap().results.remove(&window_id);
+    result.map(|result| transfer::payload(&app, &window_id, result)).transpose()
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::Piece;
+use crate::ocr::rejoin_lines;
+
+// Kept between pages in the piece list, as pdftotext does
+pub(super) const PAGE_BREAK: &str = "\u{c}";
+// A line this much shorter than the page's longest, ending a sentence, ends
+// its paragraph
+const SHORT_LINE: f64 = 0.7;
+const SENTENCE_ENDS: [char; 8] = ['.', '!', '?', ':', '。', '！', '？', '：'];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PdfLayout {
+    // Each original paragraph followed by its translation
+    Text,
+    // A two-column table per page
+    #[default]
+    Html,
+}
+
+impl PdfLayout {
+    pub(super) fn extension(self) -> &'static str {
+        match self {
+            PdfLayout::Text => "txt",
+            PdfLayout::Html => "html",
+        }
+    }
+}
+
+// Pages are numbered from 1; either end may be left open
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PdfOptions {
+    pub first_page: Option<usize>,
+    pub last_page: Option<usize>,
+    pub layout: PdfLayout,
+}
+
+// Lines as the PDF laid them out, back into paragraphs
+fn paragraphs(page: &str) -> Vec<String> {
+    let lines: Vec<&str> = page.lines().map(str::trim).collect();
+    let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in lines {
+        if !line.is_empty() {
+            current.push(line);
+        }
+        let short = (line.chars().count() as f64) < longest as f64 * SHORT_LINE;
+        if line.is_empty() || (short && line.ends_with(SENTENCE_ENDS)) {
+            let paragraph = rejoin_lines(current.drain(..));
+            if !paragraph.is_empty() {
+                paragraphs.push(paragraph);
+            }
+        }
+    }
+    let paragraph = rejoin_lines(current);
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    paragraphs
+}
+
+// One segment per paragraph, each page introduced by PAGE_BREAK and its
+// number. Scanned pages have no text and come out empty.
+pub(super) fn pieces(path: &Path, options: &PdfOptions) -> Result<Vec<Piece>, String> {
+    let pages = pdf_extract::extract_text_by_pages(path)
+        .map_err(|e| format!("Failed to read text from {}: {}", path.display(), e))?;
+    let first = options.first_page.unwrap_or(1).max(1);
+    let last = options.last_page.unwrap_or(pages.len()).min(pages.len());
+    if first > last {
+        return Err(format!("{} has no pages {}–{}", path.display(), first, last));
+    }
+
+    let mut pieces = Vec::new();
+    for (number, page) in pages.iter().enumerate().take(last).skip(first - 1) {
+        pieces.push(Piece::Keep(PAGE_BREAK.to_string()));
+        pieces.push(Piece::Keep((number + 1).to_string()));
+        for paragraph in paragraphs(page) {
+            pieces.push(Piece::Translate(paragraph));
+        }
+    }
+    Ok(pieces)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Pairs each original paragraph with what took its place in `translated`
+pub(super) fn render(title: &str, original: &[Piece], translated: &[Piece], layout: PdfLayout) -> String {
+    let mut out = String::new();
+    if layout == PdfLayout::Html {
+        out.push_str(&format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\
+             body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;width:100%}}\
+             td{{vertical-align:top;padding:.5em 1em;border-bottom:1px solid #ddd;width:50%}}\
+             h2{{margin-top:2em;color:#666;font-size:1em}}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape(title),
+            escape(title)
+        ));
+    }
+    let mut page_next = false;
+    let mut pages = 0;
+    for (original, translated) in original.iter().zip(translated) {
+        match (original, translated, layout) {
+            (Piece::Keep(mark), _, _) if mark == PAGE_BREAK => page_next = true,
+            (Piece::Keep(number), _, PdfLayout::Html) if page_next => {
+                if pages > 0 {
+                    out.push_str("</table>\n");
+                }
+                out.push_str(&format!("<h2>Page {}</h2>\n<table>\n", escape(number)));
+                page_next = false;
+                pages += 1;
+            }
+            (Piece::Keep(number), _, PdfLayout::Text) if page_next => {
+                out.push_str(&format!("── Page {} ──\n\n", number));
+                page_next = false;
+            }
+            (Piece::Translate(source), Piece::Keep(target) | Piece::Translate(target), PdfLayout::Html) => {
+                out.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", escape(source), escape(target)));
+            }
+            (Piece::Translate(source), Piece::Keep(target) | Piece::Translate(target), PdfLayout::Text) => {
+                out.push_str(&format!("{}\n→ {}\n\n", source, target));
+            }
+            _ => {}
+        }
+    }
+    if layout == PdfLayout::Html {
+        if pages > 0 {
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+    }
+    out
+}
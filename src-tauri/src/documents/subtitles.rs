@@ -0,0 +1,107 @@
+use super::Piece;
+
+// Fields of a Dialogue line when the script has no Format line; Text is last
+const ASS_DEFAULT_FIELDS: usize = 10;
+
+// Splits override blocks ({\i1}, {\an8}) and HTML-style tags (<i>, <font>)
+// off both ends of a cue, so they are written back untouched. Tags in the
+// middle of a line go to the provider with the text around them.
+fn split_tags(text: &str) -> (&str, &str, &str) {
+    let mut start = 0;
+    let mut end = text.len();
+    loop {
+        let rest = &text[start..end];
+        let trimmed = rest.trim_start();
+        let close = match trimmed.chars().next() {
+            Some('<') => '>',
+            Some('{') => '}',
+            _ => break,
+        };
+        match trimmed.find(close) {
+            Some(index) => start += rest.len() - trimmed.len() + index + 1,
+            None => break,
+        }
+    }
+    loop {
+        let trimmed = text[start..end].trim_end();
+        let open = match trimmed.chars().last() {
+            Some('>') => '<',
+            Some('}') => '{',
+            _ => break,
+        };
+        match trimmed.rfind(open) {
+            Some(index) => end = start + index,
+            None => break,
+        }
+    }
+    let core = &text[start..end];
+    let lead = core.len() - core.trim_start().len();
+    let trail = core.len() - core.trim_end().len();
+    (&text[..start + lead], core.trim(), &text[end - trail..])
+}
+
+fn push_cue(pieces: &mut Vec<Piece>, cue: &str, line_break: &str) {
+    let (lead, core, trail) = split_tags(cue);
+    if core.is_empty() {
+        pieces.push(Piece::Keep(cue.to_string()));
+        return;
+    }
+    pieces.push(Piece::Keep(lead.to_string()));
+    pieces.push(Piece::Translate(core.replace(line_break, "\n")));
+    pieces.push(Piece::Keep(trail.to_string()));
+}
+
+// Cue numbers and timings are kept; the text lines after each timing, up
+// to the blank line ending the cue, are one segment
+pub(super) fn segment_srt(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut cue = String::new();
+    let mut in_cue = false;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if in_cue && !content.trim().is_empty() {
+            cue.push_str(line);
+            continue;
+        }
+        if in_cue {
+            push_cue(&mut pieces, &std::mem::take(&mut cue), "\r\n");
+        }
+        pieces.push(Piece::Keep(line.to_string()));
+        in_cue = content.contains("-->");
+    }
+    if in_cue {
+        push_cue(&mut pieces, &cue, "\r\n");
+    }
+    pieces
+}
+
+// Only the Text field of Dialogue lines in [Events] is translated; styles,
+// timing and comments are kept. \N hard breaks travel as line breaks.
+pub(super) fn segment_ass(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut in_events = false;
+    let mut fields = ASS_DEFAULT_FIELDS;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('[') {
+            in_events = trimmed.eq_ignore_ascii_case("[events]");
+        } else if in_events && trimmed.starts_with("Format:") {
+            fields = trimmed.split(',').count().max(2);
+        }
+        let dialogue = in_events
+            .then(|| trimmed.strip_prefix("Dialogue:"))
+            .flatten()
+            .and_then(|rest| rest.match_indices(',').nth(fields - 2).map(|(index, _)| index + 1))
+            .map(|offset| content.len() - trimmed.len() + "Dialogue:".len() + offset);
+        match dialogue {
+            Some(text_start) => {
+                pieces.push(Piece::Keep(content[..text_start].to_string()));
+                push_cue(&mut pieces, &content[text_start..], "\\N");
+                pieces.push(Piece::Keep(line[content.len()..].to_string()));
+            }
+            None => pieces.push(Piece::Keep(line.to_string())),
+        }
+    }
+    pieces
+}
@@ -0,0 +1,114 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+
+use crate::hotkeys::conflict::{HotkeyError, HotkeyErrorKind};
+use crate::settings::SettingsError;
+
+// What commands reject with. It reaches the frontend as
+// { code, message, context } so the UI can tell failures apart without
+// matching on the wording. Helpers inside the backend still return String
+// errors; `?` turns those into Other.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Window not found")]
+    WindowNotFound(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    QuotaExceeded(String),
+    #[error("{0}")]
+    RateLimited(String),
+    #[error("{0}")]
+    Network(String),
+    // The provider answered with an error status not covered above
+    #[error("{message}")]
+    Provider { status: u16, message: String },
+    // Field-by-field validation from update_settings and the import paths
+    #[error("{}", .0.message)]
+    Settings(SettingsError),
+    #[error("{}", .0.message)]
+    Hotkey(HotkeyError),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::WindowNotFound(_) => "windowNotFound",
+            AppError::NotFound(_) => "notFound",
+            AppError::InvalidInput(_) => "invalidInput",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::QuotaExceeded(_) => "quotaExceeded",
+            AppError::RateLimited(_) => "rateLimited",
+            AppError::Network(_) => "network",
+            AppError::Provider { .. } => "provider",
+            AppError::Settings(error) if !error.fields.is_empty() => "invalidInput",
+            AppError::Hotkey(error) => match error.kind {
+                HotkeyErrorKind::Invalid => "invalidInput",
+                HotkeyErrorKind::Conflict => "hotkeyConflict",
+                HotkeyErrorKind::Failed => "other",
+            },
+            AppError::Settings(_) => "other",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    fn context(&self) -> Value {
+        match self {
+            AppError::WindowNotFound(window_id) => json!({ "windowId": window_id }),
+            AppError::Provider { status, .. } => json!({ "status": status }),
+            AppError::Settings(error) if !error.fields.is_empty() => json!({ "fields": error.fields }),
+            AppError::Hotkey(HotkeyError {
+                conflict: Some(conflict),
+                ..
+            }) => json!({ "conflict": conflict }),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("context", &self.context())?;
+        error.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<SettingsError> for AppError {
+    fn from(error: SettingsError) -> Self {
+        AppError::Settings(error)
+    }
+}
+
+impl From<HotkeyError> for AppError {
+    fn from(error: HotkeyError) -> Self {
+        AppError::Hotkey(error)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+// So helpers that still return String can call ones that return AppError
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::documents::{DocumentFinished, DocumentProgress, DocumentResult};
+use crate::history::HistoryEntry;
+use crate::hotkeys::HotkeyAction;
+use crate::instance::ForwardedLaunch;
+use crate::monitoring::MonitoringStatus;
+use crate::mpv::SubtitleLine;
+use crate::overlay::OverlayLabel;
+use crate::popup::PopupContent;
+use crate::reset::ResetSummary;
+use crate::settings::sync::SyncOutcome;
+use crate::settings::Settings;
+use crate::speech::listen::{VoiceTranscript, VoiceTranslation};
+use crate::tasks::TaskInfo;
+use crate::theme::ThemeInfo;
+use crate::transfer::{self, LargePayload};
+use crate::translation::presets::LanguagePreset;
+use crate::translation::usage::ProviderUsage;
+use crate::translation::TranslationActivity;
+use crate::updater::{UpdateInfo, UpdateProgress};
+
+// Everything the backend tells webviews. The tag is the event name pages
+// listen for and the content is its payload, so each name and payload shape
+// is written down once here rather than at every call site.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "payload", rename_all = "kebab-case")]
+pub enum AppEvent {
+    // Windows
+    WindowType(&'static str),
+    SecondInstance(ForwardedLaunch),
+    OpenFile(String),
+    OpenSettings,
+    ShowHistoryEntry(HistoryEntry),
+    AppDataReset(ResetSummary),
+    ThemeChanged(ThemeInfo),
+    PopupContent(PopupContent),
+    OverlayLabels(Vec<OverlayLabel>),
+    SubtitleLine(SubtitleLine),
+    MpvDisconnected,
+
+    // Clipboard watching and other passive triggers
+    MonitoringStatus(MonitoringStatus),
+    HotkeyTriggered(HotkeyAction),
+
+    // Translation
+    TranslationStatus(TranslationActivity),
+    UsageUpdated(ProviderUsage),
+    LanguagePresetChanged(LanguagePreset),
+    AutoTranslateChanged(bool),
+    VoiceCapturePartial(VoiceTranscript),
+    VoiceCaptureResult(VoiceTranscript),
+    VoiceCaptureTranslation(VoiceTranslation),
+    // Why capture ended, when it wasn't asked to
+    #[cfg_attr(not(feature = "voice-input"), allow(dead_code))]
+    VoiceCaptureStopped(Option<String>),
+    // The utterance count that just ended
+    SpeechFinished(u64),
+
+    // Settings; the payload has secrets masked
+    SettingsChanged(Box<Settings>),
+    ProfileChanged(String),
+    SyncConflict(SyncOutcome),
+    SyncCompleted(SyncOutcome),
+
+    // History, with entry ids; history-changed carries 0 for several
+    #[serde(rename = "history:added")]
+    HistoryAdded(Vec<u64>),
+    #[serde(rename = "history:updated")]
+    HistoryUpdated(Vec<u64>),
+    #[serde(rename = "history:deleted")]
+    HistoryDeleted(Vec<u64>),
+    HistoryChanged(u64),
+    HistoryEncryptionFailed(String),
+    // The tag's id, or 0 when several may have changed
+    TagsChanged(u64),
+    ReviewGraded(u64),
+
+    // Tasks and documents
+    TaskUpdated(TaskInfo),
+    DocumentProgress(DocumentProgress),
+    DocumentResult(DocumentResult),
+    DocumentFinished(DocumentFinished),
+
+    // Updates
+    UpdateAvailable(UpdateInfo),
+    UpdateProgress(UpdateProgress),
+    UpdateReady(UpdateInfo),
+
+    // An event too large to emit, to be fetched with read_transfer
+    LargePayload(LargePayload),
+}
+
+impl AppEvent {
+    // The event name and payload as the tagged serialization spells them
+    fn parts(&self) -> Option<(String, Value)> {
+        let Ok(Value::Object(mut event)) = serde_json::to_value(self) else {
+            return None;
+        };
+        let Some(Value::String(name)) = event.remove("event") else {
+            return None;
+        };
+        Some((name, event.remove("payload").unwrap_or(Value::Null)))
+    }
+
+    fn emit(&self, app: &AppHandle, target: Option<&str>) {
+        let Some((name, payload)) = self.parts() else {
+            tracing::error!("Failed to serialize event {:?}", self);
+            return;
+        };
+        let json = payload.to_string();
+        if json.len() > transfer::MAX_INLINE {
+            let (size, json): (usize, Arc<str>) = (json.len(), json.into());
+            // Each window reads its own copy, so one finishing doesn't take
+            // it from the rest
+            let windows = match target {
+                Some(label) => vec![label.to_string()],
+                None => app.webview_windows().into_keys().collect(),
+            };
+            for window in windows {
+                let transfer = transfer::stage(app, &window, json.clone());
+                let event = LargePayload {
+                    event: name.clone(),
+                    transfer,
+                    size,
+                };
+                AppEvent::LargePayload(event).emit(app, Some(&window));
+            }
+            return;
+        }
+        let emitted = match target {
+            Some(label) => app.emit_to(label, &name, payload),
+            None => app.emit(&name, payload),
+        };
+        if let Err(e) = emitted {
+            tracing::warn!("Failed to emit {}: {}", name, e);
+        }
+    }
+}
+
+// To every window
+pub fn broadcast(app: &AppHandle, event: AppEvent) {
+    event.emit(app, None);
+}
+
+// To the window with this label only
+pub fn send(app: &AppHandle, label: &str, event: AppEvent) {
+    event.emit(app, Some(label));
+}
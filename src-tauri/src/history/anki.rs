@@ -0,0 +1,332 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use super::search::{self, HistoryFilters};
+use super::{get, with_db, HistoryEntry};
+use crate::error::AppError;
+use crate::japanese::furigana;
+use crate::{settings, translation};
+
+const ANKI_CONNECT_VERSION: u32 = 6;
+// Upper bound on favorites sent in one export
+const MAX_NOTES: usize = 1000;
+
+// One Anki note field and the template that fills it. Placeholders:
+// {original}, {translation}, {sourceLang}, {targetLang}, {tags}, and
+// {reading}: the Japanese side with furigana in Anki's 漢字[かんじ] form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiFieldMapping {
+    pub field: String,
+    pub template: String,
+}
+
+// Notes go through AnkiConnect, which has to be installed in a running Anki
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnkiSettings {
+    pub url: String,
+    pub deck: String,
+    pub note_type: String,
+    pub fields: Vec<AnkiFieldMapping>,
+    pub tags: Vec<String>,
+}
+
+impl Default for AnkiSettings {
+    fn default() -> Self {
+        let mapping = |field: &str, template: &str| AnkiFieldMapping {
+            field: field.to_string(),
+            template: template.to_string(),
+        };
+        Self {
+            url: "http://127.0.0.1:8765".to_string(),
+            deck: "Shunyaku".to_string(),
+            note_type: "Basic".to_string(),
+            fields: vec![mapping("Front", "{original}"), mapping("Back", "{translation}")],
+            tags: vec!["shunyaku".to_string()],
+        }
+    }
+}
+
+impl AnkiSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err("AnkiConnect URL must start with http:// or https://".to_string());
+        }
+        if self.deck.trim().is_empty() || self.note_type.trim().is_empty() {
+            return Err("Anki deck and note type must not be empty".to_string());
+        }
+        if self.fields.is_empty() || self.fields.iter().any(|f| f.field.trim().is_empty()) {
+            return Err("Every Anki field mapping needs a field name".to_string());
+        }
+        // Anki tags are space-separated
+        if self.tags.iter().any(|tag| tag.is_empty() || tag.contains(char::is_whitespace)) {
+            return Err("Anki tags must be single words".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiExportResult {
+    pub added: usize,
+    // Already in the deck with the same first field
+    pub duplicates: usize,
+    pub failed: Vec<AnkiExportFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiExportFailure {
+    pub entry_id: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnkiNoteStatus {
+    Added,
+    // The deck already has a note with the same first field; nothing was added
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiNoteResult {
+    pub status: AnkiNoteStatus,
+    pub note_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiStatus {
+    pub connected: bool,
+    // AnkiConnect's API version
+    pub version: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AnkiResponse<T> {
+    result: Option<T>,
+    error: Option<String>,
+}
+
+async fn invoke<T: DeserializeOwned>(anki: &AnkiSettings, action: &str, params: Value) -> Result<T, String> {
+    let response = translation::http_client()
+        .post(&anki.url)
+        .json(&json!({ "action": action, "version": ANKI_CONNECT_VERSION, "params": params }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach AnkiConnect (is Anki running?): {}", e))?;
+    let body: AnkiResponse<T> = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid AnkiConnect response: {}", e))?;
+    match (body.result, body.error) {
+        (_, Some(error)) => Err(error),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err(format!("AnkiConnect returned nothing for {}", action)),
+    }
+}
+
+// Anki fields are HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn is_japanese(lang: &str) -> bool {
+    lang.eq_ignore_ascii_case("ja")
+}
+
+// Whichever side of the entry is Japanese, with each reading in brackets
+// after its kanji; empty when neither side is
+fn reading(app: &AppHandle, entry: &HistoryEntry) -> Result<String, String> {
+    let text = if is_japanese(&entry.result.source_lang) {
+        &entry.result.original_text
+    } else if is_japanese(&entry.result.target_lang) {
+        &entry.result.translated_text
+    } else {
+        return Ok(String::new());
+    };
+    let marked: String = furigana::furigana(app, text)?
+        .into_iter()
+        .map(|segment| match segment.reading {
+            // The space tells Anki where the annotated run starts
+            Some(reading) => format!(" {}[{}]", segment.text, reading),
+            None => segment.text,
+        })
+        .collect();
+    Ok(marked.trim_start().to_string())
+}
+
+// One reading per entry. The tokenizer is only loaded when a template asks
+// for {reading}.
+async fn readings(app: &AppHandle, anki: &AnkiSettings, entries: &[HistoryEntry]) -> Result<Vec<String>, String> {
+    if !anki.fields.iter().any(|mapping| mapping.template.contains("{reading}")) {
+        return Ok(vec![String::new(); entries.len()]);
+    }
+    let app = app.clone();
+    let entries = entries.to_vec();
+    tauri::async_runtime::spawn_blocking(move || entries.iter().map(|entry| reading(&app, entry)).collect())
+        .await
+        .map_err(|e| format!("Furigana generation failed: {}", e))?
+}
+
+fn render(template: &str, entry: &HistoryEntry, reading: &str) -> String {
+    let tags = entry.tags.join(", ");
+    let values = [
+        ("{reading}", reading),
+        ("{original}", entry.result.original_text.as_str()),
+        ("{translation}", entry.result.translated_text.as_str()),
+        ("{sourceLang}", entry.result.source_lang.as_str()),
+        ("{targetLang}", entry.result.target_lang.as_str()),
+        ("{tags}", tags.as_str()),
+    ];
+    values
+        .iter()
+        .fold(template.to_string(), |text, (placeholder, value)| text.replace(placeholder, &escape(value)))
+}
+
+fn note(anki: &AnkiSettings, entry: &HistoryEntry, reading: &str) -> Value {
+    let fields: HashMap<&str, String> = anki
+        .fields
+        .iter()
+        .map(|mapping| (mapping.field.as_str(), render(&mapping.template, entry, reading)))
+        .collect();
+    // History tags become Anki tags too, with spaces joined by underscores
+    let mut tags = anki.tags.clone();
+    tags.extend(entry.tags.iter().map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_")));
+    json!({
+        "deckName": anki.deck,
+        "modelName": anki.note_type,
+        "fields": fields,
+        "tags": tags,
+        "options": { "allowDuplicate": false, "duplicateScope": "deck" },
+    })
+}
+
+fn entries(app: &AppHandle, entry_ids: Option<Vec<u64>>) -> Result<Vec<HistoryEntry>, String> {
+    match entry_ids {
+        Some(ids) => ids
+            .into_iter()
+            .map(|id| get(app, id)?.ok_or_else(|| format!("History entry {} not found", id)))
+            .collect(),
+        None => {
+            let filters = HistoryFilters {
+                starred: Some(true),
+                limit: Some(MAX_NOTES),
+                ..HistoryFilters::default()
+            };
+            let hits = with_db(app, |conn| search::search(conn, "", &filters))?;
+            Ok(hits.into_iter().map(|hit| hit.entry).collect())
+        }
+    }
+}
+
+// Sends the given entries, or every favorite when no ids are passed. The deck
+// is created on first use.
+#[tauri::command]
+pub async fn export_to_anki(app: AppHandle, entry_ids: Option<Vec<u64>>) -> Result<AnkiExportResult, AppError> {
+    let anki = settings::load(&app).anki;
+    let entries = entries(&app, entry_ids)?;
+    invoke::<Value>(&anki, "createDeck", json!({ "deck": anki.deck })).await?;
+
+    let readings = readings(&app, &anki, &entries).await?;
+    let notes: Vec<Value> = entries
+        .iter()
+        .zip(&readings)
+        .map(|(entry, reading)| note(&anki, entry, reading))
+        .collect();
+    // Asked up front rather than read out of addNote's error text
+    let addable: Vec<bool> = invoke(&anki, "canAddNotes", json!({ "notes": notes })).await?;
+
+    let mut result = AnkiExportResult::default();
+    for (index, (entry, note)) in entries.iter().zip(notes).enumerate() {
+        if addable.get(index) == Some(&false) {
+            result.duplicates += 1;
+            continue;
+        }
+        match invoke::<Value>(&anki, "addNote", json!({ "note": note })).await {
+            Ok(_) => result.added += 1,
+            Err(error) => result.failed.push(AnkiExportFailure {
+                entry_id: entry.id,
+                error,
+            }),
+        }
+    }
+    Ok(result)
+}
+
+// Adds one entry right away, with the deck, note type and field templates
+// from settings unless given. The deck is created if it doesn't exist; a
+// duplicate is reported rather than treated as an error.
+#[tauri::command]
+pub async fn send_to_anki(
+    app: AppHandle,
+    entry_id: u64,
+    deck: Option<String>,
+    model: Option<String>,
+    field_map: Option<Vec<AnkiFieldMapping>>,
+) -> Result<AnkiNoteResult, AppError> {
+    let mut anki = settings::load(&app).anki;
+    if let Some(deck) = deck {
+        anki.deck = deck;
+    }
+    if let Some(model) = model {
+        anki.note_type = model;
+    }
+    if let Some(fields) = field_map {
+        anki.fields = fields;
+    }
+    anki.validate()?;
+    let entry = get(&app, entry_id)?.ok_or_else(|| AppError::NotFound(format!("History entry {} not found", entry_id)))?;
+    let reading = readings(&app, &anki, std::slice::from_ref(&entry)).await?.pop().unwrap_or_default();
+    let note = note(&anki, &entry, &reading);
+
+    let addable: Vec<bool> = invoke(&anki, "canAddNotes", json!({ "notes": [note.clone()] })).await?;
+    if addable.first() == Some(&false) {
+        return Ok(AnkiNoteResult {
+            status: AnkiNoteStatus::Duplicate,
+            note_id: None,
+        });
+    }
+    invoke::<Value>(&anki, "createDeck", json!({ "deck": anki.deck })).await?;
+    let note_id = invoke::<u64>(&anki, "addNote", json!({ "note": note })).await?;
+    Ok(AnkiNoteResult {
+        status: AnkiNoteStatus::Added,
+        note_id: Some(note_id),
+    })
+}
+
+// Whether AnkiConnect answers at the configured URL, for a status badge
+#[tauri::command]
+pub async fn get_anki_status(app: AppHandle) -> Result<AnkiStatus, AppError> {
+    let anki = settings::load(&app).anki;
+    Ok(match invoke::<u32>(&anki, "version", json!({})).await {
+        Ok(version) => AnkiStatus {
+            connected: true,
+            version: Some(version),
+            error: None,
+        },
+        Err(error) => AnkiStatus {
+            connected: false,
+            version: None,
+            error: Some(error),
+        },
+    })
+}
+
+// For the deck picker; also tells the settings page whether AnkiConnect answers
+#[tauri::command]
+pub async fn list_anki_decks(app: AppHandle) -> Result<Vec<String>, AppError> {
+    let anki = settings::load(&app).anki;
+    Ok(invoke(&anki, "deckNames", json!({})).await?)
+}
@@ -0,0 +1,361 @@
+use chrono::{DateTime, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Params, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+
+use super::{encryption, HistoryEntry, HistoryOrigin};
+use crate::translation::TranslationResult;
+
+// Each step moves the schema one version forward; PRAGMA user_version records
+// how many have run
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE translations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        original_text TEXT NOT NULL,
+        translated_text TEXT NOT NULL,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        processing_time INTEGER NOT NULL,
+        origin TEXT NOT NULL,
+        window_id TEXT,
+        starred INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE INDEX translations_created_at ON translations (created_at DESC);
+", "
+    -- The trigram tokenizer matches any three-character substring, so Japanese
+    -- and Chinese text is searchable without word segmentation
+    CREATE VIRTUAL TABLE translations_fts USING fts5(
+        original_text,
+        translated_text,
+        content = 'translations',
+        content_rowid = 'id',
+        tokenize = 'trigram'
+    );
+    INSERT INTO translations_fts (translations_fts) VALUES ('rebuild');
+    CREATE TRIGGER translations_fts_insert AFTER INSERT ON translations BEGIN
+        INSERT INTO translations_fts (rowid, original_text, translated_text)
+        VALUES (new.id, new.original_text, new.translated_text);
+    END;
+    CREATE TRIGGER translations_fts_delete AFTER DELETE ON translations BEGIN
+        INSERT INTO translations_fts (translations_fts, rowid, original_text, translated_text)
+        VALUES ('delete', old.id, old.original_text, old.translated_text);
+    END;
+    CREATE TRIGGER translations_fts_update AFTER UPDATE OF original_text, translated_text ON translations BEGIN
+        INSERT INTO translations_fts (translations_fts, rowid, original_text, translated_text)
+        VALUES ('delete', old.id, old.original_text, old.translated_text);
+        INSERT INTO translations_fts (rowid, original_text, translated_text)
+        VALUES (new.id, new.original_text, new.translated_text);
+    END;
+", "
+    CREATE INDEX translations_starred ON translations (starred, created_at DESC);
+", "
+    CREATE TABLE tags (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE translation_tags (
+        translation_id INTEGER NOT NULL REFERENCES translations (id) ON DELETE CASCADE,
+        tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+        PRIMARY KEY (translation_id, tag_id)
+    );
+    CREATE INDEX translation_tags_tag ON translation_tags (tag_id);
+", "
+    ALTER TABLE translations ADD COLUMN hit_count INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE translations ADD COLUMN last_seen_at TEXT;
+    UPDATE translations SET last_seen_at = created_at;
+    CREATE INDEX translations_repeat ON translations (original_text, source_lang, target_lang, provider);
+", "
+    ALTER TABLE translations ADD COLUMN deleted_at TEXT;
+    CREATE INDEX translations_deleted_at ON translations (deleted_at);
+", "
+    ALTER TABLE translations ADD COLUMN thumbnail TEXT;
+", "
+    CREATE TABLE reviews (
+        translation_id INTEGER PRIMARY KEY REFERENCES translations (id) ON DELETE CASCADE,
+        due_at TEXT NOT NULL,
+        interval_days INTEGER NOT NULL,
+        ease REAL NOT NULL,
+        repetitions INTEGER NOT NULL,
+        lapses INTEGER NOT NULL,
+        reviewed_at TEXT NOT NULL
+    );
+    CREATE INDEX reviews_due_at ON reviews (due_at);
+"];
+
+// Separates tag names in the aggregated column; tag names can't contain it
+pub const TAG_SEPARATOR: char = '\u{1f}';
+
+// Queries alias the table as `t`, so search can join the index without
+// ambiguous names
+pub const COLUMNS: &str = "t.id, t.original_text, t.translated_text, t.source_lang, t.target_lang, \
+    t.provider, t.processing_time, t.origin, t.window_id, t.starred, t.created_at, t.updated_at, \
+    t.hit_count, t.last_seen_at, t.deleted_at, t.thumbnail, \
+    (SELECT group_concat(g.name, char(31)) FROM translation_tags tt JOIN tags g ON g.id = tt.tag_id \
+     WHERE tt.translation_id = t.id)";
+// Queries selecting more than COLUMNS read the rest from this index on
+pub const COLUMN_COUNT: usize = 17;
+
+pub fn open(path: &Path, key: Option<&str>) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    // Has to come first; SQLCipher reads nothing before it has the key
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", encryption::key_literal(key))?;
+    }
+    // Off by default in SQLite; tag links rely on the cascades
+    conn.pragma_update(None, "foreign_keys", true)?;
+    // Lets a long search read while a new translation is written
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+pub fn schema_version() -> usize {
+    MIGRATIONS.len()
+}
+
+// Unencrypted copy of the whole database, whatever the connection's key
+pub fn snapshot(conn: &Connection, dest: &Path) -> rusqlite::Result<()> {
+    conn.execute(
+        "ATTACH DATABASE ?1 AS snapshot KEY ''",
+        params![dest.to_string_lossy()],
+    )?;
+    let exported = conn.query_row("SELECT sqlcipher_export('snapshot')", [], |_| Ok(()));
+    let versioned = exported.and_then(|_| {
+        conn.execute_batch(&format!("PRAGMA snapshot.user_version = {};", schema_version()))
+    });
+    conn.execute_batch("DETACH DATABASE snapshot;")?;
+    versioned
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!("BEGIN; {} PRAGMA user_version = {}; COMMIT;", sql, index + 1))?;
+    }
+    Ok(())
+}
+
+// Enums are stored under their serde names, the same as in settings.json
+pub fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        _ => String::new(),
+    }
+}
+
+pub fn from_text<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+pub fn entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let tags: Option<String> = row.get(16)?;
+    let mut tags: Vec<String> = tags
+        .map(|tags| tags.split(TAG_SEPARATOR).map(str::to_string).collect())
+        .unwrap_or_default();
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        result: TranslationResult {
+            original_text: row.get(1)?,
+            translated_text: row.get(2)?,
+            source_lang: row.get(3)?,
+            target_lang: row.get(4)?,
+            provider: from_text(row, 5)?,
+            processing_time: row.get(6)?,
+            timestamp: row.get(10)?,
+        },
+        origin: from_text(row, 7)?,
+        window_id: row.get(8)?,
+        starred: row.get(9)?,
+        updated_at: row.get(11)?,
+        hit_count: row.get(12)?,
+        last_seen_at: row.get(13)?,
+        deleted_at: row.get(14)?,
+        thumbnail: row.get(15)?,
+        tags,
+    })
+}
+
+pub fn insert(
+    conn: &Connection,
+    result: &TranslationResult,
+    origin: HistoryOrigin,
+    window_id: Option<&str>,
+    deduplicate: bool,
+) -> rusqlite::Result<HistoryEntry> {
+    let now: DateTime<Utc> = Utc::now();
+    if deduplicate {
+        if let Some(id) = repeat_of(conn, result)? {
+            // Keeps the newest translation, which may differ after a glossary edit
+            conn.execute(
+                "UPDATE translations SET translated_text = ?2, processing_time = ?3, \
+                 hit_count = hit_count + 1, last_seen_at = ?4, updated_at = ?4 WHERE id = ?1",
+                params![id, result.translated_text, result.processing_time, now],
+            )?;
+            return get(conn, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows);
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO translations (original_text, translated_text, source_lang, target_lang, provider, \
+         processing_time, origin, window_id, created_at, updated_at, last_seen_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+        params![
+            result.original_text,
+            result.translated_text,
+            result.source_lang,
+            result.target_lang,
+            to_text(&result.provider),
+            result.processing_time,
+            to_text(&origin),
+            window_id,
+            result.timestamp,
+            now,
+        ],
+    )?;
+    Ok(HistoryEntry {
+        id: conn.last_insert_rowid() as u64,
+        result: result.clone(),
+        origin,
+        window_id: window_id.map(str::to_string),
+        starred: false,
+        updated_at: now,
+        hit_count: 1,
+        last_seen_at: now,
+        deleted_at: None,
+        thumbnail: None,
+        tags: Vec::new(),
+    })
+}
+
+// Same text translated the same way before
+fn repeat_of(conn: &Connection, result: &TranslationResult) -> rusqlite::Result<Option<u64>> {
+    conn.query_row(
+        "SELECT id FROM translations WHERE original_text = ?1 AND source_lang = ?2 \
+         AND target_lang = ?3 AND provider = ?4 AND deleted_at IS NULL ORDER BY id DESC LIMIT 1",
+        params![
+            result.original_text,
+            result.source_lang,
+            result.target_lang,
+            to_text(&result.provider),
+        ],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+// Newest first
+pub fn page(conn: &Connection, limit: usize, offset: usize) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM translations t WHERE t.deleted_at IS NULL \
+         ORDER BY t.created_at DESC, t.id DESC LIMIT ?1 OFFSET ?2",
+        COLUMNS
+    ))?;
+    let entries = statement.query_map(params![limit, offset], entry)?;
+    entries.collect()
+}
+
+pub fn favorites(conn: &Connection, limit: usize, offset: usize) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM translations t WHERE t.starred = 1 AND t.deleted_at IS NULL \
+         ORDER BY t.created_at DESC, t.id DESC LIMIT ?1 OFFSET ?2",
+        COLUMNS
+    ))?;
+    let entries = statement.query_map(params![limit, offset], entry)?;
+    entries.collect()
+}
+
+pub fn get(conn: &Connection, id: u64) -> rusqlite::Result<Option<HistoryEntry>> {
+    conn.query_row(
+        &format!("SELECT {} FROM translations t WHERE t.id = ?1 AND t.deleted_at IS NULL", COLUMNS),
+        params![id],
+        entry,
+    )
+    .optional()
+}
+
+pub fn latest_for_window(conn: &Connection, window_id: &str) -> rusqlite::Result<Option<HistoryEntry>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM translations t WHERE t.window_id = ?1 AND t.deleted_at IS NULL \
+             ORDER BY t.last_seen_at DESC, t.id DESC LIMIT 1",
+            COLUMNS
+        ),
+        params![window_id],
+        entry,
+    )
+    .optional()
+}
+
+pub fn set_starred(conn: &Connection, id: u64, starred: bool) -> rusqlite::Result<Option<HistoryEntry>> {
+    conn.execute(
+        "UPDATE translations SET starred = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+        params![starred, Utc::now(), id],
+    )?;
+    get(conn, id)
+}
+
+pub fn toggle_starred(conn: &Connection, id: u64) -> rusqlite::Result<Option<HistoryEntry>> {
+    conn.execute(
+        "UPDATE translations SET starred = NOT starred, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![Utc::now(), id],
+    )?;
+    get(conn, id)
+}
+
+pub fn set_thumbnail(conn: &Connection, id: u64, thumbnail: &str) -> rusqlite::Result<bool> {
+    Ok(conn.execute(
+        "UPDATE translations SET thumbnail = ?1 WHERE id = ?2",
+        params![thumbnail, id],
+    )? > 0)
+}
+
+// Most recently deleted first
+pub fn trash(conn: &Connection, limit: usize, offset: usize) -> rusqlite::Result<Vec<HistoryEntry>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM translations t WHERE t.deleted_at IS NOT NULL \
+         ORDER BY t.deleted_at DESC, t.id DESC LIMIT ?1 OFFSET ?2",
+        COLUMNS
+    ))?;
+    let entries = statement.query_map(params![limit, offset], entry)?;
+    entries.collect()
+}
+
+// Moves the entry to the trash; retention empties it later
+pub fn delete(conn: &Connection, id: u64) -> rusqlite::Result<bool> {
+    Ok(conn.execute(
+        "UPDATE translations SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![Utc::now(), id],
+    )? > 0)
+}
+
+pub fn restore(conn: &Connection, id: u64) -> rusqlite::Result<Option<HistoryEntry>> {
+    conn.execute(
+        "UPDATE translations SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+        params![id],
+    )?;
+    get(conn, id)
+}
+
+pub fn empty_trash(conn: &Connection) -> rusqlite::Result<Vec<u64>> {
+    ids(conn, "DELETE FROM translations WHERE deleted_at IS NOT NULL RETURNING id", [])
+}
+
+pub fn clear(conn: &Connection) -> rusqlite::Result<Vec<u64>> {
+    ids(conn, "DELETE FROM translations RETURNING id", [])
+}
+
+// For statements that yield entry ids, e.g. deletes with RETURNING id
+pub fn ids(conn: &Connection, sql: &str, params: impl Params) -> rusqlite::Result<Vec<u64>> {
+    let mut statement = conn.prepare(sql)?;
+    let ids = statement.query_map(params, |row| row.get(0))?;
+    ids.collect()
+}
@@ -0,0 +1,105 @@
+use rusqlite::{params, Connection};
+use std::io::Read;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::events::{self, AppEvent};
+use crate::keychain;
+
+// The database key lives in the OS keychain, never next to the database
+const KEY_ACCOUNT: &str = "history-database-key";
+// First bytes of every unencrypted SQLite file; SQLCipher files look random
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+fn is_encrypted(path: &Path) -> Result<bool, String> {
+    let mut header = [0u8; 16];
+    let read = std::fs::File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .map_err(|e| format!("Failed to read history database: {}", e))?;
+    // SQLite leaves a new database empty until the first write
+    Ok(read > 0 && &header != PLAIN_HEADER)
+}
+
+fn stored_key() -> Result<String, String> {
+    keychain::get(KEY_ACCOUNT)?
+        .ok_or_else(|| "History is encrypted but its key is missing from the keychain".to_string())
+}
+
+fn key_or_create() -> Result<String, String> {
+    if let Some(key) = keychain::get(KEY_ACCOUNT)? {
+        return Ok(key);
+    }
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a key: {}", e))?;
+    let key: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    keychain::set(KEY_ACCOUNT, &key)?;
+    Ok(key)
+}
+
+// SQLCipher's syntax for a raw 256-bit key, which skips passphrase derivation
+pub fn key_literal(key: &str) -> String {
+    format!("x'{}'", key)
+}
+
+// Copies the database into a fresh file under the other key (an empty key
+// means plaintext), then swaps it in. The original stays untouched if
+// anything fails on the way.
+fn export(path: &Path, from: Option<&str>, to: Option<&str>) -> rusqlite::Result<()> {
+    let converted = path.with_extension("sqlite3.converting");
+    let _ = std::fs::remove_file(&converted);
+    {
+        let conn = Connection::open(path)?;
+        if let Some(key) = from {
+            conn.pragma_update(None, "key", key_literal(key))?;
+        }
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS converted KEY ?2",
+            params![converted.to_string_lossy(), to.map(key_literal).unwrap_or_default()],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('converted')", [], |_| Ok(()))?;
+        conn.execute_batch(&format!(
+            "PRAGMA converted.user_version = {}; DETACH DATABASE converted;",
+            version
+        ))?;
+    }
+    // Closing the last connection checkpointed the write-ahead log into the
+    // original, and the copy doesn't use one yet
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(sidecar);
+    }
+    std::fs::rename(&converted, path).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn convert(path: &Path, encrypt: bool) -> Result<(), String> {
+    if encrypt {
+        let key = key_or_create()?;
+        export(path, None, Some(&key)).map_err(|e| format!("Failed to encrypt history: {}", e))
+    } else {
+        let key = stored_key()?;
+        export(path, Some(&key), None).map_err(|e| format!("Failed to decrypt history: {}", e))?;
+        keychain::delete(KEY_ACCOUNT)
+    }
+}
+
+// Brings the file in line with the encryption setting before it is opened and
+// returns the key to open it with. A failed conversion leaves the file as it
+// was, so history keeps working while the error is reported.
+pub fn prepare(app: &AppHandle, path: &Path, encrypt: bool) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return if encrypt { key_or_create().map(Some) } else { Ok(None) };
+    }
+    if is_encrypted(path)? != encrypt {
+        if let Err(e) = convert(path, encrypt) {
+            tracing::error!("{}", e);
+            events::broadcast(app, AppEvent::HistoryEncryptionFailed(e));
+        }
+    }
+    if is_encrypted(path)? {
+        stored_key().map(Some)
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Deserialize;
+use std::io::{BufWriter, Write};
+use tauri::AppHandle;
+
+use super::search::{self, HistoryFilters};
+use super::{db, with_db, HistoryEntry};
+use crate::error::AppError;
+
+// Entries are read this many at a time, so a large export doesn't hold the
+// whole history in memory
+const BATCH: usize = 500;
+// Lets Excel recognise the file as UTF-8 instead of the system code page
+const BOM: &str = "\u{feff}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+}
+
+impl ExportFormat {
+    fn delimiter(self) -> char {
+        match self {
+            ExportFormat::Csv => ',',
+            ExportFormat::Tsv => '\t',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportColumn {
+    Id,
+    OriginalText,
+    TranslatedText,
+    SourceLang,
+    TargetLang,
+    Provider,
+    Origin,
+    Starred,
+    Tags,
+    CreatedAt,
+    UpdatedAt,
+    ProcessingTime,
+}
+
+impl ExportColumn {
+    const DEFAULT: [ExportColumn; 6] = [
+        ExportColumn::CreatedAt,
+        ExportColumn::SourceLang,
+        ExportColumn::TargetLang,
+        ExportColumn::OriginalText,
+        ExportColumn::TranslatedText,
+        ExportColumn::Tags,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            ExportColumn::Id => "id",
+            ExportColumn::OriginalText => "original_text",
+            ExportColumn::TranslatedText => "translated_text",
+            ExportColumn::SourceLang => "source_lang",
+            ExportColumn::TargetLang => "target_lang",
+            ExportColumn::Provider => "provider",
+            ExportColumn::Origin => "origin",
+            ExportColumn::Starred => "starred",
+            ExportColumn::Tags => "tags",
+            ExportColumn::CreatedAt => "created_at",
+            ExportColumn::UpdatedAt => "updated_at",
+            ExportColumn::ProcessingTime => "processing_time_ms",
+        }
+    }
+
+    fn value(self, entry: &HistoryEntry) -> String {
+        match self {
+            ExportColumn::Id => entry.id.to_string(),
+            ExportColumn::OriginalText => entry.result.original_text.clone(),
+            ExportColumn::TranslatedText => entry.result.translated_text.clone(),
+            ExportColumn::SourceLang => entry.result.source_lang.clone(),
+            ExportColumn::TargetLang => entry.result.target_lang.clone(),
+            ExportColumn::Provider => db::to_text(&entry.result.provider),
+            ExportColumn::Origin => db::to_text(&entry.origin),
+            ExportColumn::Starred => entry.starred.to_string(),
+            ExportColumn::Tags => entry.tags.join("; "),
+            ExportColumn::CreatedAt => entry.result.timestamp.to_rfc3339(),
+            ExportColumn::UpdatedAt => entry.updated_at.to_rfc3339(),
+            ExportColumn::ProcessingTime => entry.result.processing_time.to_string(),
+        }
+    }
+}
+
+// Same shape as search_history's arguments; limit and offset are ignored
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportFilter {
+    pub query: String,
+    #[serde(flatten)]
+    pub filters: HistoryFilters,
+}
+
+// RFC 4180 quoting, applied with tabs too so multi-line translations survive
+// a TSV round trip through a spreadsheet. Text a spreadsheet would take for a
+// formula gets a leading apostrophe so opening the file can't run it.
+fn field(value: &str, delimiter: char) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn row(values: impl Iterator<Item = String>, delimiter: char) -> String {
+    let mut line = values
+        .map(|value| field(&value, delimiter))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string());
+    line.push_str("\r\n");
+    line
+}
+
+// Writes every entry matching the filter and returns how many that was
+#[tauri::command]
+pub async fn export_history(
+    app: AppHandle,
+    path: String,
+    format: ExportFormat,
+    filter: Option<ExportFilter>,
+    columns: Option<Vec<ExportColumn>>,
+) -> Result<usize, AppError> {
+    let filter = filter.unwrap_or_default();
+    let columns = columns
+        .filter(|columns| !columns.is_empty())
+        .unwrap_or_else(|| ExportColumn::DEFAULT.to_vec());
+    let delimiter = format.delimiter();
+
+    let file = std::fs::File::create(&path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut out = BufWriter::new(file);
+    let write_error = |e: std::io::Error| format!("Failed to write {}: {}", path, e);
+    out.write_all(BOM.as_bytes()).map_err(write_error)?;
+    out.write_all(row(columns.iter().map(|column| column.header().to_string()), delimiter).as_bytes())
+        .map_err(write_error)?;
+
+    let filters = HistoryFilters {
+        limit: Some(BATCH),
+        ..filter.filters.clone()
+    };
+    let mut written = 0;
+    let mut after = None;
+    loop {
+        let hits = with_db(&app, |conn| search::search_after(conn, &filter.query, &filters, after))?;
+        for hit in &hits {
+            let values = columns.iter().map(|column| column.value(&hit.entry));
+            out.write_all(row(values, delimiter).as_bytes()).map_err(write_error)?;
+        }
+        written += hits.len();
+        match hits.last() {
+            Some(last) if hits.len() == BATCH => after = Some((last.entry.result.timestamp, last.entry.id)),
+            _ => break,
+        }
+    }
+    out.flush().map_err(write_error)?;
+    Ok(written)
+}
@@ -0,0 +1,310 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::{db, notify, tags, with_committed, with_db, HistoryChange, HistoryOrigin};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::settings;
+use crate::translation::{ProviderKind, TranslationResult};
+
+// Rows reported as skipped are capped so a wrong mapping on a huge file
+// doesn't produce a huge report
+const MAX_REPORTED: usize = 100;
+
+// Header names of the columns to read. Unset columns fall back to the headers
+// export_history writes, so its files import without a mapping.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportMapping {
+    pub original_text: Option<String>,
+    pub translated_text: Option<String>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    pub provider: Option<String>,
+    pub created_at: Option<String>,
+    // Several tags in one cell are separated by semicolons
+    pub tags: Option<String>,
+    // For files without language columns; otherwise the configured languages
+    pub default_source_lang: Option<String>,
+    pub default_target_lang: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedRow {
+    // Line the record starts on, counting the header as line 1
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub rows: usize,
+    // Imported, or that would be for a dry run
+    pub imported: usize,
+    // Already in history or repeated earlier in the file
+    pub duplicates: usize,
+    pub skipped: Vec<SkippedRow>,
+    pub skipped_count: usize,
+    // False for a dry run
+    pub applied: bool,
+}
+
+struct Columns {
+    original_text: usize,
+    translated_text: usize,
+    source_lang: Option<usize>,
+    target_lang: Option<usize>,
+    provider: Option<usize>,
+    created_at: Option<usize>,
+    tags: Option<usize>,
+}
+
+struct Record {
+    result: TranslationResult,
+    tags: Vec<String>,
+}
+
+// RFC 4180, as export_history writes it: quoted fields may hold delimiters,
+// line breaks and doubled quotes. Each record comes with its starting line.
+fn parse(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push(c);
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.is_empty()) {
+                    records.push((start, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                start = line;
+            }
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|value| !value.is_empty()) {
+        records.push((start, record));
+    }
+    records
+}
+
+fn delimiter(path: &Path, text: &str) -> char {
+    let is_tsv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"));
+    let header = text.lines().next().unwrap_or_default();
+    if is_tsv || header.matches('\t').count() > header.matches(',').count() {
+        '\t'
+    } else {
+        ','
+    }
+}
+
+fn find(headers: &[String], mapped: &Option<String>, default: &str) -> Result<Option<usize>, String> {
+    let name = mapped.as_deref().unwrap_or(default);
+    let index = headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(name.trim()));
+    match (index, mapped) {
+        (None, Some(name)) => Err(format!("The file has no column named '{}'", name)),
+        (index, _) => Ok(index),
+    }
+}
+
+fn required(headers: &[String], mapped: &Option<String>, default: &str) -> Result<usize, String> {
+    find(headers, mapped, default)?
+        .ok_or_else(|| format!("The file has no '{}' column; map one to import", default))
+}
+
+fn columns(headers: &[String], mapping: &ImportMapping) -> Result<Columns, String> {
+    Ok(Columns {
+        original_text: required(headers, &mapping.original_text, "original_text")?,
+        translated_text: required(headers, &mapping.translated_text, "translated_text")?,
+        source_lang: find(headers, &mapping.source_lang, "source_lang")?,
+        target_lang: find(headers, &mapping.target_lang, "target_lang")?,
+        provider: find(headers, &mapping.provider, "provider")?,
+        created_at: find(headers, &mapping.created_at, "created_at")?,
+        tags: find(headers, &mapping.tags, "tags")?,
+    })
+}
+
+// RFC 3339 as exported, or a plain local date and time as most tools write it
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y/%m/%d %H:%M:%S", "%Y/%m/%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn record(
+    values: &[String],
+    columns: &Columns,
+    defaults: &(String, String, ProviderKind),
+) -> Result<Record, String> {
+    let cell = |index: Option<usize>| {
+        index
+            .and_then(|index| values.get(index))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let original_text = cell(Some(columns.original_text)).ok_or("No original text")?;
+    let translated_text = cell(Some(columns.translated_text)).ok_or("No translation")?;
+    let timestamp = match cell(columns.created_at) {
+        Some(value) => timestamp(value).ok_or_else(|| format!("Unrecognised date '{}'", value))?,
+        None => Utc::now(),
+    };
+    // Other tools name providers this app doesn't have; those count as the
+    // configured one
+    let provider = cell(columns.provider)
+        .and_then(|value| serde_json::from_value(serde_json::Value::String(value.to_lowercase())).ok())
+        .unwrap_or(defaults.2);
+
+    Ok(Record {
+        result: TranslationResult {
+            original_text: original_text.to_string(),
+            translated_text: translated_text.to_string(),
+            source_lang: cell(columns.source_lang).unwrap_or(&defaults.0).to_string(),
+            target_lang: cell(columns.target_lang).unwrap_or(&defaults.1).to_string(),
+            provider,
+            processing_time: 0,
+            timestamp,
+        },
+        tags: cell(columns.tags)
+            .map(|value| value.split(';').filter_map(|tag| tags::validate_name(tag).ok()).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn exists(conn: &Connection, result: &TranslationResult) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM translations WHERE original_text = ?1 AND source_lang = ?2 \
+         AND target_lang = ?3 AND translated_text = ?4 AND deleted_at IS NULL LIMIT 1",
+        params![result.original_text, result.source_lang, result.target_lang, result.translated_text],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+}
+
+fn store(conn: &Connection, records: &[Record]) -> rusqlite::Result<Vec<u64>> {
+    let transaction = conn.unchecked_transaction()?;
+    let mut ids = Vec::with_capacity(records.len());
+    for record in records {
+        let entry = db::insert(&transaction, &record.result, HistoryOrigin::Import, None, false)?;
+        for tag in &record.tags {
+            tags::tag_entry(&transaction, entry.id, tag)?;
+        }
+        ids.push(entry.id);
+    }
+    transaction.commit()?;
+    Ok(ids)
+}
+
+// Reads a CSV or TSV file into history. With `dry_run` nothing is written and
+// the report shows what an import would do.
+#[tauri::command]
+pub async fn import_history(
+    app: AppHandle,
+    path: String,
+    mapping: Option<ImportMapping>,
+    dry_run: Option<bool>,
+) -> Result<ImportReport, AppError> {
+    let mapping = mapping.unwrap_or_default();
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut rows = parse(&text, delimiter(Path::new(&path), &text)).into_iter();
+    let (_, headers) = rows.next().ok_or_else(|| format!("{} is empty", path))?;
+    let columns = columns(&headers, &mapping)?;
+
+    let settings = settings::load(&app);
+    let defaults = (
+        mapping.default_source_lang.clone().unwrap_or(settings.source_language),
+        mapping.default_target_lang.clone().unwrap_or(settings.target_language),
+        settings.translation_provider,
+    );
+
+    let mut report = ImportReport {
+        rows: 0,
+        imported: 0,
+        duplicates: 0,
+        skipped: Vec::new(),
+        skipped_count: 0,
+        applied: !dry_run.unwrap_or(false),
+    };
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    with_db(&app, |conn| {
+        for (line, values) in rows {
+            report.rows += 1;
+            let record = match record(&values, &columns, &defaults) {
+                Ok(record) => record,
+                Err(reason) => {
+                    report.skipped_count += 1;
+                    if report.skipped.len() < MAX_REPORTED {
+                        report.skipped.push(SkippedRow { line, reason });
+                    }
+                    continue;
+                }
+            };
+            let result = &record.result;
+            let key = (
+                result.original_text.clone(),
+                result.translated_text.clone(),
+                result.source_lang.clone(),
+                result.target_lang.clone(),
+            );
+            if !seen.insert(key) || exists(conn, result)? {
+                report.duplicates += 1;
+                continue;
+            }
+            records.push(record);
+        }
+        Ok(())
+    })?;
+    report.imported = records.len();
+
+    if report.applied && !records.is_empty() {
+        // Its own transaction can't start inside an open batch
+        let ids = with_committed(&app, |conn| store(conn, &records))?;
+        notify(&app, HistoryChange::Added, &ids);
+        events::broadcast(&app, AppEvent::TagsChanged(0));
+    }
+    Ok(report)
+}
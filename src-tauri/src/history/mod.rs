@@ -0,0 +1,409 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::speech::autoplay;
+use crate::{metrics, portable, settings, stream, webhooks};
+use crate::translation::TranslationResult;
+
+pub mod anki;
+pub mod db;
+mod encryption;
+pub mod export;
+pub mod import;
+pub mod query;
+pub mod retention;
+pub mod review;
+pub mod search;
+pub mod stats;
+pub mod tags;
+pub mod thumbnails;
+
+// Kept in SQLite under the app data dir; the store plugin rewrites its whole
+// file on every change, which doesn't scale to thousands of entries
+const DATABASE_FILE: &str = "history.sqlite3";
+pub(crate) const DEFAULT_PAGE: usize = 50;
+// Translations recorded in a burst share one transaction, committed once
+// they've stopped for this long, so a run of them costs one disk sync
+const BATCH_DELAY: Duration = Duration::from_secs(1);
+
+// What produced the text that was translated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryOrigin {
+    Clipboard,
+    Ocr,
+    Selection,
+    Manual,
+    // Spoken into the microphone and transcribed
+    Voice,
+    // Sent by another app through a shunyaku:// link
+    Link,
+    // Posted by a script or editor plugin to the local HTTP API
+    Api,
+    // Selected on a web page and sent by the browser extension
+    Browser,
+    // Brought in from a CSV file rather than translated here
+    Import,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub result: TranslationResult,
+    pub origin: HistoryOrigin,
+    // Floating panel the translation was shown in, if any
+    pub window_id: Option<String>,
+    pub starred: bool,
+    pub updated_at: DateTime<Utc>,
+    // Times this exact translation was requested; repeats bump it instead of
+    // adding rows unless deduplication is off
+    pub hit_count: u64,
+    pub last_seen_at: DateTime<Utc>,
+    // Set while the entry sits in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+    // File name of the captured region for OCR translations; see
+    // get_history_thumbnail
+    pub thumbnail: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryChange {
+    Added,
+    Updated,
+    Deleted,
+}
+
+impl HistoryChange {
+    fn event(self, ids: Vec<u64>) -> AppEvent {
+        match self {
+            HistoryChange::Added => AppEvent::HistoryAdded(ids),
+            HistoryChange::Updated => AppEvent::HistoryUpdated(ids),
+            HistoryChange::Deleted => AppEvent::HistoryDeleted(ids),
+        }
+    }
+}
+
+// Tells open history windows which entries changed. "history-changed" is
+// also sent, with the id or 0 for several, for listeners that just rebuild
+// (the tray's recent menu).
+pub fn notify(app: &AppHandle, change: HistoryChange, ids: &[u64]) {
+    if ids.is_empty() {
+        return;
+    }
+    let ids = ids.to_vec();
+    let id = if ids.len() == 1 { ids[0] } else { 0 };
+    events::broadcast(app, change.event(ids));
+    events::broadcast(app, AppEvent::HistoryChanged(id));
+}
+
+// Opened on first use, once the data dir is known. `batch` counts recorded
+// translations so only the last of a burst commits.
+#[derive(Default)]
+pub struct HistoryState {
+    db: Mutex<Option<Connection>>,
+    batch: AtomicU64,
+}
+
+fn database_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(portable::data_dir(app)?.join(DATABASE_FILE))
+}
+
+fn open(app: &AppHandle) -> Result<Connection, String> {
+    let dir = portable::data_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = database_path(app)?;
+    let key = encryption::prepare(app, &path, settings::load(app).encrypt_history)?;
+    db::open(&path, key.as_deref()).map_err(|e| format!("Failed to open history database: {}", e))
+}
+
+// Reads and writes on the one connection see an open batch's inserts, so
+// only closing it, attaching another database or starting a transaction
+// needs it committed first; that's what with_committed is for
+fn with_db<T>(app: &AppHandle, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let state = app.state::<HistoryState>();
+    let mut db = state.db.lock().unwrap();
+    let conn = match db.take() {
+        Some(conn) => conn,
+        None => open(app)?,
+    };
+    f(db.insert(conn)).map_err(|e| format!("History database error: {}", e))
+}
+
+fn commit(conn: &Connection) -> Result<(), String> {
+    if !conn.is_autocommit() {
+        conn.execute_batch("COMMIT").map_err(|e| format!("Failed to commit history: {}", e))?;
+    }
+    Ok(())
+}
+
+// Like with_db, but commits the open batch first under the same lock, so a
+// translation recorded in between can't open another before `f` runs its
+// own transaction
+fn with_committed<T>(app: &AppHandle, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    with_db(app, |conn| {
+        if !conn.is_autocommit() {
+            conn.execute_batch("COMMIT")?;
+        }
+        f(conn)
+    })
+}
+
+// Like with_db, but leaves the write in the open batch and commits it after
+// BATCH_DELAY without another
+fn with_batch<T>(app: &AppHandle, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let result = with_db(app, |conn| {
+        if conn.is_autocommit() {
+            conn.execute_batch("BEGIN")?;
+        }
+        f(conn)
+    })?;
+    let batch = app.state::<HistoryState>().batch.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(BATCH_DELAY).await;
+        if app.state::<HistoryState>().batch.load(Ordering::SeqCst) == batch {
+            flush(&app);
+        }
+    });
+    Ok(result)
+}
+
+// Commits the open batch, if any; also run on exit
+pub fn flush(app: &AppHandle) {
+    let state = app.state::<HistoryState>();
+    let db = state.db.lock().unwrap();
+    if let Some(Err(e)) = db.as_ref().map(commit) {
+        tracing::error!("{}", e);
+    }
+}
+
+// What SQLite's quick_check finds wrong with the database; empty when it's
+// sound
+pub fn integrity_problems(app: &AppHandle) -> Result<Vec<String>, String> {
+    let rows = with_db(app, |conn| {
+        let mut stmt = conn.prepare("PRAGMA quick_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+    })?;
+    Ok(rows.into_iter().filter(|row| row != "ok").collect())
+}
+
+// Closes and reopens the database, e.g. to encrypt or decrypt it after the
+// setting changed
+pub fn reopen(app: &AppHandle) {
+    {
+        let state = app.state::<HistoryState>();
+        let mut db = state.db.lock().unwrap();
+        if let Some(Err(e)) = db.as_ref().map(commit) {
+            tracing::error!("{}", e);
+        }
+        *db = None;
+    }
+    if let Err(e) = with_db(app, |_| Ok(())) {
+        tracing::error!("{}", e);
+    }
+}
+
+pub fn schema_version() -> usize {
+    db::schema_version()
+}
+
+// Unencrypted copy of the database as it is right now, for backups
+pub fn snapshot(app: &AppHandle, dest: &Path) -> Result<(), String> {
+    let _ = std::fs::remove_file(dest);
+    with_committed(app, |conn| db::snapshot(conn, dest))
+}
+
+// Swaps in another database file, e.g. from a backup; `source` should be on
+// the same volume as the data dir. Reopening migrates it and encrypts it if
+// the setting asks for that.
+pub fn replace(app: &AppHandle, source: &Path) -> Result<usize, String> {
+    let path = database_path(app)?;
+    let previous = with_db(app, |conn| db::ids(conn, "SELECT id FROM translations", []))?;
+    {
+        let state = app.state::<HistoryState>();
+        let mut db = state.db.lock().unwrap();
+        if let Some(Err(e)) = db.as_ref().map(commit) {
+            tracing::error!("{}", e);
+        }
+        *db = None;
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = path.as_os_str().to_owned();
+            sidecar.push(suffix);
+            let _ = std::fs::remove_file(sidecar);
+        }
+        std::fs::rename(source, &path).map_err(|e| format!("Failed to replace history database: {}", e))?;
+    }
+    notify(app, HistoryChange::Deleted, &previous);
+    let entries = with_db(app, |conn| db::ids(conn, "SELECT id FROM translations WHERE deleted_at IS NULL", []))?;
+    notify(app, HistoryChange::Added, &entries);
+    Ok(entries.len())
+}
+
+pub fn add(
+    app: &AppHandle,
+    result: &TranslationResult,
+    origin: HistoryOrigin,
+    window_id: Option<&str>,
+) -> Result<HistoryEntry, String> {
+    let deduplicate = settings::load(app).deduplicate_history;
+    let entry = with_batch(app, |conn| db::insert(conn, result, origin, window_id, deduplicate))?;
+
+    // A repeat bumps the existing entry instead of adding one
+    let change = if entry.hit_count > 1 { HistoryChange::Updated } else { HistoryChange::Added };
+    notify(app, change, &[entry.id]);
+    Ok(entry)
+}
+
+// A translation still goes through when history can't be written. Every
+// completed translation passes through here, so it's also where auto-speak
+// picks them up.
+pub fn record(
+    app: &AppHandle,
+    result: &TranslationResult,
+    origin: HistoryOrigin,
+    window_id: Option<&str>,
+) -> Option<HistoryEntry> {
+    autoplay::translated(app, result, origin);
+    webhooks::translated(app, result, origin);
+    stream::translated(app, result, origin);
+    metrics::translated(app, origin);
+    add(app, result, origin, window_id)
+        .map_err(|e| tracing::error!("Failed to record translation: {}", e))
+        .ok()
+}
+
+pub fn recent(app: &AppHandle, limit: usize) -> Result<Vec<HistoryEntry>, String> {
+    with_db(app, |conn| db::page(conn, limit, 0))
+}
+
+pub fn get(app: &AppHandle, id: u64) -> Result<Option<HistoryEntry>, String> {
+    with_db(app, |conn| db::get(conn, id))
+}
+
+// Most recent translation shown in a floating panel
+pub fn latest_for_window(app: &AppHandle, window_id: &str) -> Result<Option<HistoryEntry>, String> {
+    with_db(app, |conn| db::latest_for_window(conn, window_id))
+}
+
+pub fn set_starred(app: &AppHandle, id: u64, starred: bool) -> Result<Option<HistoryEntry>, String> {
+    let entry = with_db(app, |conn| db::set_starred(conn, id, starred))?;
+    if entry.is_some() {
+        notify(app, HistoryChange::Updated, &[id]);
+    }
+    Ok(entry)
+}
+
+pub fn toggle_starred(app: &AppHandle, id: u64) -> Result<HistoryEntry, String> {
+    let entry = with_db(app, |conn| db::toggle_starred(conn, id))?
+        .ok_or_else(|| format!("History entry {} not found", id))?;
+    notify(app, HistoryChange::Updated, &[id]);
+    Ok(entry)
+}
+
+pub fn delete(app: &AppHandle, id: u64) -> Result<bool, String> {
+    let deleted = with_db(app, |conn| db::delete(conn, id))?;
+    if deleted {
+        notify(app, HistoryChange::Deleted, &[id]);
+    }
+    Ok(deleted)
+}
+
+pub fn restore(app: &AppHandle, id: u64) -> Result<HistoryEntry, String> {
+    let entry = with_db(app, |conn| db::restore(conn, id))?
+        .ok_or_else(|| format!("History entry {} not found", id))?;
+    // Back in the list as far as history windows are concerned
+    notify(app, HistoryChange::Added, &[id]);
+    Ok(entry)
+}
+
+// Drops every entry, trash included; returns how many there were
+pub fn clear(app: &AppHandle) -> Result<usize, String> {
+    let removed = with_db(app, db::clear)?;
+    notify(app, HistoryChange::Deleted, &removed);
+    Ok(removed.len())
+}
+
+// Whitespace-collapsed text cut to `limit` characters, for menus and notifications
+pub fn preview(text: &str, limit: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(limit) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+#[tauri::command]
+pub async fn get_recent_translations(app: AppHandle, limit: Option<usize>) -> Result<Vec<HistoryEntry>, AppError> {
+    Ok(recent(&app, limit.unwrap_or(DEFAULT_PAGE))?)
+}
+
+#[tauri::command]
+pub async fn get_history(
+    app: AppHandle,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let (limit, offset) = (limit.unwrap_or(DEFAULT_PAGE), offset.unwrap_or(0));
+    Ok(with_db(&app, |conn| db::page(conn, limit, offset))?)
+}
+
+#[tauri::command]
+pub async fn get_history_entry(app: AppHandle, id: u64) -> Result<Option<HistoryEntry>, AppError> {
+    Ok(get(&app, id)?)
+}
+
+// Favorites are starred entries, kept for study or reuse
+#[tauri::command]
+pub async fn toggle_favorite(app: AppHandle, id: u64) -> Result<HistoryEntry, AppError> {
+    Ok(toggle_starred(&app, id)?)
+}
+
+#[tauri::command]
+pub async fn list_favorites(
+    app: AppHandle,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let (limit, offset) = (limit.unwrap_or(DEFAULT_PAGE), offset.unwrap_or(0));
+    Ok(with_db(&app, |conn| db::favorites(conn, limit, offset))?)
+}
+
+// Deleted entries go to the trash, so undo is restore_entry
+#[tauri::command]
+pub async fn delete_history_entry(app: AppHandle, id: u64) -> Result<bool, AppError> {
+    Ok(delete(&app, id)?)
+}
+
+#[tauri::command]
+pub async fn restore_entry(app: AppHandle, id: u64) -> Result<HistoryEntry, AppError> {
+    Ok(restore(&app, id)?)
+}
+
+#[tauri::command]
+pub async fn list_trash(
+    app: AppHandle,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let (limit, offset) = (limit.unwrap_or(DEFAULT_PAGE), offset.unwrap_or(0));
+    Ok(with_db(&app, |conn| db::trash(conn, limit, offset))?)
+}
+
+// Deletes everything in the trash for good; returns how many entries that was
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle) -> Result<usize, AppError> {
+    let removed = with_db(&app, db::empty_trash)?;
+    notify(&app, HistoryChange::Deleted, &removed);
+    Ok(removed.len())
+}
@@ -0,0 +1,101 @@
+use rusqlite::types::ToSql;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::search::{filter_conditions, HistoryFilters};
+use super::{db, with_db, HistoryEntry, DEFAULT_PAGE};
+use crate::error::AppError;
+
+// A page of the history window never needs more than this at once
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistorySort {
+    #[default]
+    Newest,
+    Oldest,
+    // Recently repeated translations first
+    LastSeen,
+    MostUsed,
+}
+
+impl HistorySort {
+    // Ends on the id so pages don't overlap between equal timestamps
+    fn order(self) -> &'static str {
+        match self {
+            HistorySort::Newest => "t.created_at DESC, t.id DESC",
+            HistorySort::Oldest => "t.created_at ASC, t.id ASC",
+            HistorySort::LastSeen => "t.last_seen_at DESC, t.id DESC",
+            HistorySort::MostUsed => "t.hit_count DESC, t.last_seen_at DESC, t.id DESC",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub page: usize,
+    pub page_size: usize,
+    // Entries passing the filters across all pages
+    pub total: usize,
+    pub has_more: bool,
+}
+
+fn query(
+    conn: &Connection,
+    page: usize,
+    page_size: usize,
+    filters: &HistoryFilters,
+    sort: HistorySort,
+) -> rusqlite::Result<HistoryPage> {
+    let mut conditions = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+    filter_conditions(filters, &mut conditions, &mut values);
+    let where_clause = format!("WHERE {}", conditions.join(" AND "));
+
+    let total: usize = conn.query_row(
+        &format!("SELECT count(*) FROM translations t {}", where_clause),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    )?;
+
+    values.push(Box::new(page_size));
+    values.push(Box::new(page * page_size));
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM translations t {} ORDER BY {} LIMIT ? OFFSET ?",
+        db::COLUMNS,
+        where_clause,
+        sort.order()
+    ))?;
+    let entries = statement
+        .query_map(params_from_iter(values.iter()), db::entry)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(HistoryPage {
+        has_more: (page + 1) * page_size < total,
+        entries,
+        page,
+        page_size,
+        total,
+    })
+}
+
+// Pages start at 0. The filters' own limit and offset are ignored in favour
+// of the page arguments.
+#[tauri::command]
+pub async fn query_history(
+    app: AppHandle,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    filters: Option<HistoryFilters>,
+    sort: Option<HistorySort>,
+) -> Result<HistoryPage, AppError> {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE_SIZE);
+    let filters = filters.unwrap_or_default();
+    Ok(with_db(&app, |conn| {
+        query(conn, page.unwrap_or(0), page_size, &filters, sort.unwrap_or_default())
+    })?)
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use super::{db, notify, thumbnails, with_db, HistoryChange};
+use crate::error::AppError;
+use crate::settings;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Applied by the hourly purge. Heavy auto-translate use adds thousands of
+// entries a day, hence a cap by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<u32>,
+    // Favorites are never purged and don't count towards max_entries
+    pub keep_favorites: bool,
+    // Deleted entries stay restorable this long
+    pub trash_days: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(10_000),
+            max_age_days: None,
+            keep_favorites: true,
+            trash_days: 30,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_entries == Some(0) || self.max_age_days == Some(0) || self.trash_days == 0 {
+            return Err("Retention limits must be at least 1; leave them empty for no limit".to_string());
+        }
+        Ok(())
+    }
+
+    fn criteria(&self) -> PurgeCriteria {
+        PurgeCriteria {
+            older_than: self
+                .max_age_days
+                .map(|days| Utc::now() - chrono::Duration::days(i64::from(days))),
+            keep_newest: self.max_entries,
+            keep_favorites: self.keep_favorites,
+            deleted_before: Some(Utc::now() - chrono::Duration::days(i64::from(self.trash_days))),
+        }
+    }
+}
+
+// What a one-off purge removes: entries created before `older_than`,
+// everything beyond the newest `keep_newest`, and entries moved to the trash
+// before `deleted_before`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PurgeCriteria {
+    pub older_than: Option<DateTime<Utc>>,
+    pub keep_newest: Option<usize>,
+    // On unless turned off, so a purge that doesn't mention favorites keeps them
+    pub keep_favorites: bool,
+    pub deleted_before: Option<DateTime<Utc>>,
+}
+
+impl Default for PurgeCriteria {
+    fn default() -> Self {
+        Self {
+            older_than: None,
+            keep_newest: None,
+            keep_favorites: true,
+            deleted_before: None,
+        }
+    }
+}
+
+fn purge(conn: &Connection, criteria: &PurgeCriteria) -> rusqlite::Result<Vec<u64>> {
+    let unstarred = if criteria.keep_favorites { "AND starred = 0" } else { "" };
+    let mut removed = Vec::new();
+    if let Some(older_than) = criteria.older_than {
+        removed.extend(db::ids(
+            conn,
+            &format!("DELETE FROM translations WHERE created_at < ?1 {} RETURNING id", unstarred),
+            params![older_than],
+        )?);
+    }
+    if let Some(keep) = criteria.keep_newest {
+        removed.extend(db::ids(
+            conn,
+            &format!(
+                "DELETE FROM translations WHERE id IN (SELECT id FROM translations WHERE deleted_at IS NULL {} \
+                 ORDER BY created_at DESC, id DESC LIMIT -1 OFFSET ?1) RETURNING id",
+                unstarred
+            ),
+            params![keep],
+        )?);
+    }
+    // The trash goes whether or not its entries were favorites
+    if let Some(deleted_before) = criteria.deleted_before {
+        removed.extend(db::ids(
+            conn,
+            "DELETE FROM translations WHERE deleted_at < ?1 RETURNING id",
+            params![deleted_before],
+        )?);
+    }
+    Ok(removed)
+}
+
+fn run(app: &AppHandle, criteria: &PurgeCriteria) -> Result<usize, String> {
+    let removed = with_db(app, |conn| purge(conn, criteria))?;
+    notify(app, HistoryChange::Deleted, &removed);
+    Ok(removed.len())
+}
+
+// Enforces the retention setting now and then once an hour, dropping
+// thumbnails of the entries it removed
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let criteria = settings::load(&app).history_retention.criteria();
+            if let Err(e) = run(&app, &criteria) {
+                tracing::error!("Failed to purge history: {}", e);
+            }
+            if let Err(e) = thumbnails::prune(&app) {
+                tracing::error!("Failed to prune thumbnails: {}", e);
+            }
+            tokio::time::sleep(PURGE_INTERVAL).await;
+        }
+    });
+}
+
+// Returns how many entries were removed
+#[tauri::command]
+pub async fn purge_history(app: AppHandle, criteria: PurgeCriteria) -> Result<usize, AppError> {
+    Ok(run(&app, &criteria)?)
+}
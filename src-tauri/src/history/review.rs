@@ -0,0 +1,169 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::{db, with_db, HistoryEntry};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+const DEFAULT_BATCH: usize = 20;
+// SM-2's starting ease and its floor
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+// Grades run 0-5 as in SM-2; below this the entry counts as forgotten
+const PASSING_SCORE: u8 = 3;
+const MAX_SCORE: u8 = 5;
+
+// Where an entry stands in the schedule. Favorites that were never reviewed
+// have none and are due straight away.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewState {
+    pub due_at: DateTime<Utc>,
+    pub interval_days: i64,
+    pub ease: f64,
+    pub repetitions: u32,
+    pub lapses: u32,
+}
+
+impl ReviewState {
+    // SM-2: a pass lengthens the interval by the ease, a fail starts over
+    // tomorrow, and the ease follows how hard recall was
+    fn graded(previous: Option<ReviewState>, score: u8, now: DateTime<Utc>) -> ReviewState {
+        let previous = previous.unwrap_or(ReviewState {
+            due_at: now,
+            interval_days: 0,
+            ease: INITIAL_EASE,
+            repetitions: 0,
+            lapses: 0,
+        });
+        let miss = f64::from(MAX_SCORE - score);
+        let ease = (previous.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        let (interval_days, repetitions, lapses) = if score < PASSING_SCORE {
+            (1, 0, previous.lapses + 1)
+        } else {
+            let interval = match previous.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (previous.interval_days as f64 * previous.ease).round() as i64,
+            };
+            (interval, previous.repetitions + 1, previous.lapses)
+        };
+        ReviewState {
+            due_at: now + Duration::days(interval_days),
+            interval_days,
+            ease,
+            repetitions,
+            lapses,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewItem {
+    pub entry: HistoryEntry,
+    // None for a favorite that hasn't been reviewed yet
+    pub review: Option<ReviewState>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewQueue {
+    pub items: Vec<ReviewItem>,
+    // Across the whole queue, not just this batch
+    pub due: usize,
+    pub new: usize,
+}
+
+fn state(row: &rusqlite::Row, first: usize) -> rusqlite::Result<Option<ReviewState>> {
+    let due_at: Option<DateTime<Utc>> = row.get(first)?;
+    let Some(due_at) = due_at else {
+        return Ok(None);
+    };
+    Ok(Some(ReviewState {
+        due_at,
+        interval_days: row.get(first + 1)?,
+        ease: row.get(first + 2)?,
+        repetitions: row.get(first + 3)?,
+        lapses: row.get(first + 4)?,
+    }))
+}
+
+// Overdue entries first, oldest due date leading, then new favorites
+fn queue(conn: &Connection, limit: usize, now: DateTime<Utc>) -> rusqlite::Result<ReviewQueue> {
+    const DUE: &str = "FROM translations t LEFT JOIN reviews r ON r.translation_id = t.id \
+        WHERE t.starred = 1 AND t.deleted_at IS NULL AND (r.due_at IS NULL OR r.due_at <= ?1)";
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT {}, r.due_at, r.interval_days, r.ease, r.repetitions, r.lapses {} \
+         ORDER BY r.due_at IS NULL, r.due_at, t.created_at LIMIT ?2",
+        db::COLUMNS,
+        DUE
+    ))?;
+    let items = statement
+        .query_map(params![now, limit], |row| {
+            Ok(ReviewItem {
+                entry: db::entry(row)?,
+                review: state(row, db::COLUMN_COUNT)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let (due, new) = conn.query_row(
+        &format!("SELECT count(r.due_at), count(*) - count(r.due_at) {}", DUE),
+        params![now],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(ReviewQueue { items, due, new })
+}
+
+fn grade(conn: &Connection, id: u64, score: u8, now: DateTime<Utc>) -> rusqlite::Result<Option<ReviewState>> {
+    let starred: Option<bool> = conn
+        .query_row(
+            "SELECT starred FROM translations WHERE id = ?1 AND deleted_at IS NULL",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if starred != Some(true) {
+        return Ok(None);
+    }
+    let previous = conn
+        .query_row(
+            "SELECT due_at, interval_days, ease, repetitions, lapses FROM reviews WHERE translation_id = ?1",
+            params![id],
+            |row| state(row, 0),
+        )
+        .optional()?
+        .flatten();
+
+    let next = ReviewState::graded(previous, score, now);
+    conn.execute(
+        "INSERT INTO reviews (translation_id, due_at, interval_days, ease, repetitions, lapses, reviewed_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) ON CONFLICT (translation_id) DO UPDATE SET \
+         due_at = excluded.due_at, interval_days = excluded.interval_days, ease = excluded.ease, \
+         repetitions = excluded.repetitions, lapses = excluded.lapses, reviewed_at = excluded.reviewed_at",
+        params![id, next.due_at, next.interval_days, next.ease, next.repetitions, next.lapses, now],
+    )?;
+    Ok(Some(next))
+}
+
+// Favorites due for review now
+#[tauri::command]
+pub async fn get_due_reviews(app: AppHandle, limit: Option<usize>) -> Result<ReviewQueue, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_BATCH);
+    Ok(with_db(&app, |conn| queue(conn, limit, Utc::now()))?)
+}
+
+// `score` is 0 (blank) to 5 (perfect recall); returns when the entry is due next
+#[tauri::command]
+pub async fn grade_review(app: AppHandle, id: u64, score: u8) -> Result<ReviewState, AppError> {
+    if score > MAX_SCORE {
+        return Err(AppError::InvalidInput(format!("Scores run from 0 to {}", MAX_SCORE)));
+    }
+    let state = with_db(&app, |conn| grade(conn, id, score, Utc::now()))?
+        .ok_or_else(|| format!("History entry {} is not a favorite", id))?;
+    events::broadcast(&app, AppEvent::ReviewGraded(id));
+    Ok(state)
+}
@@ -0,0 +1,297 @@
+use chrono::{DateTime, Utc};
+use rusqlite::types::ToSql;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::{db, with_db, HistoryEntry, HistoryOrigin, DEFAULT_PAGE};
+use crate::error::AppError;
+use crate::translation::ProviderKind;
+
+// snippet() wraps matches in these; they never occur in translated text and
+// are split out before anything reaches a webview
+const MARK_START: char = '\u{2}';
+const MARK_END: char = '\u{3}';
+const ELLIPSIS: &str = "…";
+// Tokens per snippet; with trigrams that's roughly one character each
+const SNIPPET_TOKENS: usize = 24;
+// Trigram indexes can't look up anything shorter; such terms fall back to LIKE
+const MIN_INDEXED_CHARS: usize = 3;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryFilters {
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    pub provider: Option<ProviderKind>,
+    pub origin: Option<HistoryOrigin>,
+    pub starred: Option<bool>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    // Entries must carry every one of these tags
+    pub tags: Vec<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub entry: HistoryEntry,
+    pub original_snippet: Vec<SnippetPart>,
+    pub translated_snippet: Vec<SnippetPart>,
+    // bm25 score, lower is better; 0 when the query had no indexed terms
+    pub rank: f64,
+}
+
+// Each whitespace-separated term must match; quoting keeps FTS5 operators in
+// user input literal
+fn fts_query(terms: &[&str]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn parts(marked: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut highlighted = false;
+    for piece in marked.split([MARK_START, MARK_END]) {
+        if !piece.is_empty() {
+            parts.push(SnippetPart {
+                text: piece.to_string(),
+                highlighted,
+            });
+        }
+        highlighted = !highlighted;
+    }
+    parts
+}
+
+// Highlighting for terms too short for the index: the first match with some
+// context on either side, matched ASCII-case-insensitively like LIKE
+fn mark_short_terms(text: &str, terms: &[&str]) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut ranges: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            let term = term.to_ascii_lowercase();
+            lower
+                .match_indices(&term)
+                .map(|(start, found)| (start, start + found.len()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    ranges.sort();
+    let Some(&(first, _)) = ranges.first() else {
+        return text.chars().take(SNIPPET_TOKENS * 2).collect();
+    };
+
+    let context = SNIPPET_TOKENS / 2;
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(context.saturating_sub(1))
+        .map_or(0, |(index, _)| index);
+    let end = text[first..]
+        .char_indices()
+        .nth(SNIPPET_TOKENS)
+        .map_or(text.len(), |(index, _)| first + index);
+
+    let mut marked = String::new();
+    if start > 0 {
+        marked.push_str(ELLIPSIS);
+    }
+    let mut cursor = start;
+    for (from, to) in ranges {
+        if from < cursor || to > end {
+            continue;
+        }
+        marked.push_str(&text[cursor..from]);
+        marked.push(MARK_START);
+        marked.push_str(&text[from..to]);
+        marked.push(MARK_END);
+        cursor = to;
+    }
+    marked.push_str(&text[cursor..end]);
+    if end < text.len() {
+        marked.push_str(ELLIPSIS);
+    }
+    marked
+}
+
+// Conditions on table alias `t` for everything in the filters except paging.
+// Entries in the trash never match.
+pub(super) fn filter_conditions(
+    filters: &HistoryFilters,
+    conditions: &mut Vec<&'static str>,
+    values: &mut Vec<Box<dyn ToSql>>,
+) {
+    conditions.push("t.deleted_at IS NULL");
+    if let Some(source_lang) = &filters.source_lang {
+        conditions.push("t.source_lang = ?");
+        values.push(Box::new(source_lang.clone()));
+    }
+    if let Some(target_lang) = &filters.target_lang {
+        conditions.push("t.target_lang = ?");
+        values.push(Box::new(target_lang.clone()));
+    }
+    if let Some(provider) = filters.provider {
+        conditions.push("t.provider = ?");
+        values.push(Box::new(db::to_text(&provider)));
+    }
+    if let Some(origin) = filters.origin {
+        conditions.push("t.origin = ?");
+        values.push(Box::new(db::to_text(&origin)));
+    }
+    if let Some(starred) = filters.starred {
+        conditions.push("t.starred = ?");
+        values.push(Box::new(starred));
+    }
+    if let Some(from) = filters.from {
+        conditions.push("t.created_at >= ?");
+        values.push(Box::new(from));
+    }
+    if let Some(to) = filters.to {
+        conditions.push("t.created_at < ?");
+        values.push(Box::new(to));
+    }
+    for tag in &filters.tags {
+        conditions.push(
+            "EXISTS (SELECT 1 FROM translation_tags tt JOIN tags g ON g.id = tt.tag_id \
+             WHERE tt.translation_id = t.id AND g.name = ?)",
+        );
+        values.push(Box::new(tag.clone()));
+    }
+}
+
+// Where the next page of a newest-first listing starts: after the entry
+// with this creation time and id
+pub(super) type Cursor = (DateTime<Utc>, u64);
+
+// Best matches first, paged by the filters' limit and offset
+pub(super) fn search(conn: &Connection, query: &str, filters: &HistoryFilters) -> rusqlite::Result<Vec<SearchHit>> {
+    hits(conn, query, filters, None)
+}
+
+// Newest first whatever the query, paged by keyset so entries added or
+// removed between pages don't shift the rest; the offset is ignored
+pub(super) fn search_after(
+    conn: &Connection,
+    query: &str,
+    filters: &HistoryFilters,
+    after: Option<Cursor>,
+) -> rusqlite::Result<Vec<SearchHit>> {
+    hits(conn, query, filters, Some(after))
+}
+
+// `keyset` is None for ranked, offset paging
+fn hits(
+    conn: &Connection,
+    query: &str,
+    filters: &HistoryFilters,
+    keyset: Option<Option<Cursor>>,
+) -> rusqlite::Result<Vec<SearchHit>> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    let (indexed, short): (Vec<&str>, Vec<&str>) = terms
+        .into_iter()
+        .partition(|term| term.chars().count() >= MIN_INDEXED_CHARS);
+    let use_index = !indexed.is_empty();
+
+    // Placeholders are anonymous, so values go in the order their conditions do
+    let mut conditions: Vec<&str> = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+    if use_index {
+        conditions.push("translations_fts MATCH ?");
+        values.push(Box::new(fts_query(&indexed)));
+    }
+    for term in &short {
+        conditions.push("(t.original_text LIKE ? ESCAPE '\\' OR t.translated_text LIKE ? ESCAPE '\\')");
+        values.push(Box::new(like_pattern(term)));
+        values.push(Box::new(like_pattern(term)));
+    }
+    filter_conditions(filters, &mut conditions, &mut values);
+    if let Some(Some((created_at, id))) = keyset {
+        conditions.push("(t.created_at, t.id) < (?, ?)");
+        values.push(Box::new(created_at));
+        values.push(Box::new(id));
+    }
+    let where_clause = format!("WHERE {}", conditions.join(" AND "));
+
+    let (source, extra, order) = if use_index {
+        (
+            "translations t JOIN translations_fts ON translations_fts.rowid = t.id",
+            format!(
+                "snippet(translations_fts, 0, '{s}', '{e}', '{ellipsis}', {n}), \
+                 snippet(translations_fts, 1, '{s}', '{e}', '{ellipsis}', {n}), \
+                 bm25(translations_fts)",
+                s = MARK_START,
+                e = MARK_END,
+                ellipsis = ELLIPSIS,
+                n = SNIPPET_TOKENS
+            ),
+            "bm25(translations_fts), t.created_at DESC, t.id DESC",
+        )
+    } else {
+        ("translations t", "NULL, NULL, 0.0".to_string(), "t.created_at DESC, t.id DESC")
+    };
+    let order = match keyset {
+        Some(_) => "t.created_at DESC, t.id DESC",
+        None => order,
+    };
+
+    values.push(Box::new(filters.limit.unwrap_or(DEFAULT_PAGE)));
+    values.push(Box::new(match keyset {
+        Some(_) => 0,
+        None => filters.offset.unwrap_or(0),
+    }));
+    let sql = format!(
+        "SELECT {}, {} FROM {} {} ORDER BY {} LIMIT ? OFFSET ?",
+        db::COLUMNS,
+        extra,
+        source,
+        where_clause,
+        order
+    );
+
+    let mut statement = conn.prepare(&sql)?;
+    let hits = statement.query_map(params_from_iter(values.iter()), |row| {
+        let entry = db::entry(row)?;
+        let snippet = |index: usize, text: &str| -> rusqlite::Result<Vec<SnippetPart>> {
+            let marked: Option<String> = row.get(index)?;
+            Ok(parts(&marked.unwrap_or_else(|| mark_short_terms(text, &short))))
+        };
+        Ok(SearchHit {
+            original_snippet: snippet(db::COLUMN_COUNT, &entry.result.original_text)?,
+            translated_snippet: snippet(db::COLUMN_COUNT + 1, &entry.result.translated_text)?,
+            rank: row.get(db::COLUMN_COUNT + 2)?,
+            entry,
+        })
+    })?;
+    hits.collect()
+}
+
+// Best matches first. An empty query just lists entries passing the filters,
+// newest first.
+#[tauri::command]
+pub async fn search_history(
+    app: AppHandle,
+    query: String,
+    filters: Option<HistoryFilters>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let filters = filters.unwrap_or_default();
+    Ok(with_db(&app, |conn| search(conn, &query, &filters))?)
+}
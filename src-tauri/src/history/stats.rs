@@ -0,0 +1,166 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::{db, with_db};
+use crate::error::AppError;
+use crate::ocr::OcrCacheState;
+use crate::translation::usage::{ProviderUsage, UsageState};
+use crate::translation::ProviderKind;
+
+const TOP_LANGUAGE_PAIRS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsRange {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl StatsRange {
+    fn since(self) -> Option<DateTime<Utc>> {
+        let days = match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::Year => 365,
+            StatsRange::All => return None,
+        };
+        Some(Utc::now() - Duration::days(days))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyCount {
+    // Local calendar day, e.g. "2024-05-01"
+    pub date: String,
+    pub translations: u64,
+    pub characters: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCount {
+    pub provider: ProviderKind,
+    pub translations: u64,
+    pub characters: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePairCount {
+    pub source_lang: String,
+    pub target_lang: String,
+    pub translations: u64,
+}
+
+// Lookups in the in-memory OCR cache since the app started. Nothing about it
+// is stored, so it ignores the range and starts over at every launch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    // None until the cache has been asked at all
+    pub hit_ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub range: StatsRange,
+    pub translations: u64,
+    pub characters: u64,
+    // Oldest first, only days with translations
+    pub per_day: Vec<DailyCount>,
+    pub per_provider: Vec<ProviderCount>,
+    pub top_language_pairs: Vec<LanguagePairCount>,
+    pub session_ocr_cache: CacheStats,
+    // Last quota reading for the current provider
+    pub quota: Option<ProviderUsage>,
+}
+
+// Characters are counted on the original text, which is what providers bill
+fn per_day(conn: &Connection, since: Option<DateTime<Utc>>) -> rusqlite::Result<Vec<DailyCount>> {
+    let mut statement = conn.prepare(
+        "SELECT date(created_at, 'localtime') AS day, count(*), sum(length(original_text)) \
+         FROM translations WHERE deleted_at IS NULL AND (?1 IS NULL OR created_at >= ?1) GROUP BY day ORDER BY day",
+    )?;
+    let days = statement.query_map(params![since], |row| {
+        Ok(DailyCount {
+            date: row.get(0)?,
+            translations: row.get(1)?,
+            characters: row.get(2)?,
+        })
+    })?;
+    days.collect()
+}
+
+fn per_provider(conn: &Connection, since: Option<DateTime<Utc>>) -> rusqlite::Result<Vec<ProviderCount>> {
+    let mut statement = conn.prepare(
+        "SELECT provider, count(*), sum(length(original_text)) AS characters \
+         FROM translations WHERE deleted_at IS NULL AND (?1 IS NULL OR created_at >= ?1) \
+         GROUP BY provider ORDER BY characters DESC",
+    )?;
+    let providers = statement.query_map(params![since], |row| {
+        Ok(ProviderCount {
+            provider: db::from_text(row, 0)?,
+            translations: row.get(1)?,
+            characters: row.get(2)?,
+        })
+    })?;
+    providers.collect()
+}
+
+fn top_language_pairs(conn: &Connection, since: Option<DateTime<Utc>>) -> rusqlite::Result<Vec<LanguagePairCount>> {
+    let mut statement = conn.prepare(
+        "SELECT source_lang, target_lang, count(*) AS translations \
+         FROM translations WHERE deleted_at IS NULL AND (?1 IS NULL OR created_at >= ?1) \
+         GROUP BY source_lang, target_lang ORDER BY translations DESC, source_lang, target_lang LIMIT ?2",
+    )?;
+    let pairs = statement.query_map(params![since, TOP_LANGUAGE_PAIRS], |row| {
+        Ok(LanguagePairCount {
+            source_lang: row.get(0)?,
+            target_lang: row.get(1)?,
+            translations: row.get(2)?,
+        })
+    })?;
+    pairs.collect()
+}
+
+fn session_cache_stats(app: &AppHandle) -> CacheStats {
+    let (_, hits, misses) = app.state::<OcrCacheState>().lock().unwrap().stats();
+    let lookups = hits + misses;
+    CacheStats {
+        hits,
+        misses,
+        hit_ratio: (lookups > 0).then(|| hits as f64 / lookups as f64),
+    }
+}
+
+#[tauri::command]
+pub async fn get_statistics(app: AppHandle, range: Option<StatsRange>) -> Result<Statistics, AppError> {
+    let range = range.unwrap_or(StatsRange::Month);
+    let since = range.since();
+    let (per_day, per_provider, top_language_pairs) = with_db(&app, |conn| {
+        Ok((
+            per_day(conn, since)?,
+            per_provider(conn, since)?,
+            top_language_pairs(conn, since)?,
+        ))
+    })?;
+
+    Ok(Statistics {
+        range,
+        translations: per_day.iter().map(|day| day.translations).sum(),
+        characters: per_day.iter().map(|day| day.characters).sum(),
+        per_day,
+        per_provider,
+        top_language_pairs,
+        session_ocr_cache: session_cache_stats(&app),
+        quota: app.state::<UsageState>().lock().unwrap().clone(),
+    })
+}
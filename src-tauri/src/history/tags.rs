@@ -0,0 +1,190 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::search::{self, HistoryFilters, SearchHit};
+use super::{db, get, notify, with_db, HistoryChange, HistoryEntry};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+const MAX_NAME_CHARS: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: u64,
+    pub name: String,
+    pub entry_count: u64,
+}
+
+// Names are matched case-insensitively, so "Persona 5" and "persona 5" are
+// the same tag
+pub(super) fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("Tag names are limited to {} characters", MAX_NAME_CHARS));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Tag names can't contain control characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn tag_by(conn: &Connection, condition: &str, value: &dyn rusqlite::ToSql) -> rusqlite::Result<Option<Tag>> {
+    conn.query_row(
+        &format!(
+            "SELECT g.id, g.name, (SELECT count(*) FROM translation_tags tt \
+             JOIN translations t ON t.id = tt.translation_id WHERE tt.tag_id = g.id AND t.deleted_at IS NULL) \
+             FROM tags g WHERE {}",
+            condition
+        ),
+        [value],
+        |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                entry_count: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<Tag>> {
+    let mut statement = conn.prepare(
+        "SELECT g.id, g.name, count(t.id) FROM tags g \
+         LEFT JOIN translation_tags tt ON tt.tag_id = g.id \
+         LEFT JOIN translations t ON t.id = tt.translation_id AND t.deleted_at IS NULL \
+         GROUP BY g.id ORDER BY g.name COLLATE NOCASE",
+    )?;
+    let tags = statement.query_map([], |row| {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            entry_count: row.get(2)?,
+        })
+    })?;
+    tags.collect()
+}
+
+// Returns the existing tag when one with this name is already there
+fn ensure(conn: &Connection, name: &str) -> rusqlite::Result<Tag> {
+    conn.execute(
+        "INSERT INTO tags (name, created_at) VALUES (?1, ?2) ON CONFLICT (name) DO NOTHING",
+        params![name, Utc::now()],
+    )?;
+    tag_by(conn, "g.name = ?1", &name)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+pub(super) fn tag_entry(conn: &Connection, entry_id: u64, name: &str) -> rusqlite::Result<()> {
+    let tag = ensure(conn, name)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO translation_tags (translation_id, tag_id) VALUES (?1, ?2)",
+        params![entry_id, tag.id],
+    )?;
+    Ok(())
+}
+
+fn tagged(conn: &Connection, tag_id: u64) -> rusqlite::Result<Vec<u64>> {
+    db::ids(conn, "SELECT translation_id FROM translation_tags WHERE tag_id = ?1", [tag_id])
+}
+
+fn entry(app: &AppHandle, id: u64) -> Result<HistoryEntry, String> {
+    get(app, id)?.ok_or_else(|| format!("History entry {} not found", id))
+}
+
+#[tauri::command]
+pub async fn list_tags(app: AppHandle) -> Result<Vec<Tag>, AppError> {
+    Ok(with_db(&app, list)?)
+}
+
+#[tauri::command]
+pub async fn create_tag(app: AppHandle, name: String) -> Result<Tag, AppError> {
+    let name = validate_name(&name)?;
+    let tag = with_db(&app, |conn| ensure(conn, &name))?;
+    events::broadcast(&app, AppEvent::TagsChanged(tag.id));
+    Ok(tag)
+}
+
+#[tauri::command]
+pub async fn rename_tag(app: AppHandle, id: u64, name: String) -> Result<Tag, AppError> {
+    let name = validate_name(&name)?;
+    let taken = with_db(&app, |conn| tag_by(conn, "g.name = ?1", &name))?;
+    if taken.is_some_and(|tag| tag.id != id) {
+        return Err(AppError::InvalidInput(format!("A tag named '{}' already exists", name)));
+    }
+    let (tag, entries) = with_db(&app, |conn| {
+        conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![name, id])?;
+        Ok((tag_by(conn, "g.id = ?1", &id)?, tagged(conn, id)?))
+    })?;
+    let tag = tag.ok_or_else(|| AppError::NotFound(format!("Tag {} not found", id)))?;
+
+    // Entries show tag names, so lists that include them are stale too
+    events::broadcast(&app, AppEvent::TagsChanged(tag.id));
+    notify(&app, HistoryChange::Updated, &entries);
+    Ok(tag)
+}
+
+// Removes the tag from every entry; the entries themselves stay
+#[tauri::command]
+pub async fn delete_tag(app: AppHandle, id: u64) -> Result<bool, AppError> {
+    let (deleted, entries) = with_db(&app, |conn| {
+        let entries = tagged(conn, id)?;
+        Ok((conn.execute("DELETE FROM tags WHERE id = ?1", params![id])? > 0, entries))
+    })?;
+    if deleted {
+        events::broadcast(&app, AppEvent::TagsChanged(id));
+        notify(&app, HistoryChange::Updated, &entries);
+    }
+    Ok(deleted)
+}
+
+// Tags the entry, creating the tag on first use
+#[tauri::command]
+pub async fn assign_tag(app: AppHandle, entry_id: u64, tag: String) -> Result<HistoryEntry, AppError> {
+    let name = validate_name(&tag)?;
+    entry(&app, entry_id)?;
+    with_db(&app, |conn| tag_entry(conn, entry_id, &name))?;
+    let entry = entry(&app, entry_id)?;
+
+    events::broadcast(&app, AppEvent::TagsChanged(0));
+    notify(&app, HistoryChange::Updated, &[entry_id]);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn unassign_tag(app: AppHandle, entry_id: u64, tag: String) -> Result<HistoryEntry, AppError> {
+    with_db(&app, |conn| {
+        conn.execute(
+            "DELETE FROM translation_tags WHERE translation_id = ?1 \
+             AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![entry_id, tag.trim()],
+        )
+    })?;
+    let entry = entry(&app, entry_id)?;
+
+    events::broadcast(&app, AppEvent::TagsChanged(0));
+    notify(&app, HistoryChange::Updated, &[entry_id]);
+    Ok(entry)
+}
+
+// Shorthand for an empty search filtered to one tag, newest first
+#[tauri::command]
+pub async fn list_history_by_tag(
+    app: AppHandle,
+    tag: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let filters = HistoryFilters {
+        tags: vec![tag.trim().to_string()],
+        limit,
+        offset,
+        ..HistoryFilters::default()
+    };
+    Ok(with_db(&app, |conn| search::search(conn, "", &filters))?)
+}
@@ -0,0 +1,163 @@
+use image::{ImageFormat, RgbaImage};
+use std::collections::{HashSet, VecDeque};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::{db, get, notify, with_db, HistoryChange};
+use crate::error::AppError;
+use crate::{portable, settings};
+
+const THUMBNAIL_DIR: &str = "thumbnails";
+// Longest side; enough to recognise the context without storing screenshots
+const MAX_SIDE: u32 = 320;
+// Unreferenced files younger than this may belong to a translation in flight
+const PRUNE_GRACE: Duration = Duration::from_secs(60 * 60);
+// Recent captures waiting to be translated, and the pixel bytes they may hold
+const PENDING: usize = 8;
+const PENDING_BYTES: usize = 64 * 1024 * 1024;
+
+// Captures are staged here as read and only downscaled and written out once
+// an entry that uses one is recorded; most live-region frames never are
+#[derive(Default)]
+pub struct ThumbnailState(Mutex<VecDeque<(String, Arc<RgbaImage>)>>);
+
+pub fn dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(portable::data_dir(app)?.join(THUMBNAIL_DIR))
+}
+
+// Names come back from the webview, so they must not be able to leave the dir
+pub fn is_name(name: &str) -> bool {
+    name.strip_suffix(".png")
+        .is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// Stages a capture and returns the name attach writes it under. The name
+// comes from the OCR cache key, so a live region re-read on every tick keeps
+// pointing at the same file. None while history is encrypted: the files would
+// be screen contents in plain sight beside the encrypted database.
+pub fn store(app: &AppHandle, key: u64, image: Arc<RgbaImage>) -> Option<String> {
+    if settings::load(app).encrypt_history {
+        return None;
+    }
+    let name = format!("{:016x}.png", key);
+
+    let state = app.state::<ThumbnailState>();
+    let mut pending = state.0.lock().unwrap();
+    pending.retain(|(pending, _)| *pending != name);
+    pending.push_back((name.clone(), image));
+    let staged = |pending: &VecDeque<(String, Arc<RgbaImage>)>| -> usize {
+        pending.iter().map(|(_, image)| image.as_raw().len()).sum()
+    };
+    while pending.len() > 1 && (pending.len() > PENDING || staged(&pending) > PENDING_BYTES) {
+        pending.pop_front();
+    }
+    Some(name)
+}
+
+fn encode(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let scale = (MAX_SIDE as f64 / image.width().max(image.height()) as f64).min(1.0);
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    let mut png = Vec::new();
+    image::imageops::thumbnail(image, width, height)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+    Ok(png)
+}
+
+pub fn attach(app: &AppHandle, entry_id: u64, name: &str) -> Result<(), String> {
+    if !is_name(name) {
+        return Err(format!("Unknown thumbnail '{}'", name));
+    }
+    let dir = dir(app)?;
+    let path = dir.join(name);
+    if !path.is_file() {
+        let image = {
+            let state = app.state::<ThumbnailState>();
+            let mut pending = state.0.lock().unwrap();
+            let index = pending.iter().position(|(pending, _)| pending == name);
+            index.and_then(|index| pending.remove(index)).map(|(_, image)| image)
+        };
+        let Some(image) = image else {
+            return Err(format!("Unknown thumbnail '{}'", name));
+        };
+        // Encryption may have been turned on since the capture
+        if settings::load(app).encrypt_history {
+            return Ok(());
+        }
+        let png = encode(&image)?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::write(&path, png).map_err(|e| format!("Failed to save thumbnail: {}", e))?;
+    }
+    if with_db(app, |conn| db::set_thumbnail(conn, entry_id, name))? {
+        notify(app, HistoryChange::Updated, &[entry_id]);
+    }
+    Ok(())
+}
+
+// Deletes files no entry points at any more, e.g. after a purge
+pub fn prune(app: &AppHandle) -> Result<usize, String> {
+    let dir = dir(app)?;
+    let Ok(files) = std::fs::read_dir(&dir) else {
+        return Ok(0);
+    };
+    let referenced: HashSet<String> = with_db(app, |conn| {
+        let mut statement = conn.prepare("SELECT DISTINCT thumbnail FROM translations WHERE thumbnail IS NOT NULL")?;
+        let names = statement.query_map([], |row| row.get(0))?;
+        names.collect()
+    })?;
+
+    let mut removed = 0;
+    for file in files.flatten() {
+        let name = file.file_name().to_string_lossy().into_owned();
+        let settled = file
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().unwrap_or_default() >= PRUNE_GRACE);
+        if is_name(&name) && settled && !referenced.contains(&name) && std::fs::remove_file(file.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// Deletes every thumbnail and unlinks them from their entries, for when
+// history encryption is turned on
+pub fn discard_all(app: &AppHandle) -> Result<usize, String> {
+    app.state::<ThumbnailState>().0.lock().unwrap().clear();
+    let entries = with_db(app, |conn| {
+        let entries = db::ids(conn, "SELECT id FROM translations WHERE thumbnail IS NOT NULL", [])?;
+        conn.execute("UPDATE translations SET thumbnail = NULL WHERE thumbnail IS NOT NULL", [])?;
+        Ok(entries)
+    })?;
+    notify(app, HistoryChange::Updated, &entries);
+
+    let mut removed = 0;
+    if let Ok(files) = std::fs::read_dir(dir(app)?) {
+        for file in files.flatten() {
+            let name = file.file_name().to_string_lossy().into_owned();
+            if is_name(&name) && std::fs::remove_file(file.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+// Absolute path for the webview's asset protocol, if the entry has one. The
+// config grants the protocol no paths, so the folder is allowed here.
+#[tauri::command]
+pub async fn get_history_thumbnail(app: AppHandle, id: u64) -> Result<Option<String>, AppError> {
+    let Some(name) = get(&app, id)?.and_then(|entry| entry.thumbnail) else {
+        return Ok(None);
+    };
+    let dir = dir(&app)?;
+    app.asset_protocol_scope()
+        .allow_directory(&dir, false)
+        .map_err(|e| format!("Failed to allow thumbnail folder: {}", e))?;
+    let path = dir.join(name);
+    Ok(path.is_file().then(|| path.to_string_lossy().into_owned()))
+}
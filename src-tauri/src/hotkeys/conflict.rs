@@ -0,0 +1,171 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{parse_accelerator, HotkeyAction};
+use tauri_plugin_global_shortcut::Shortcut;
+
+// Shortcuts the OS or its bundled apps claim. Registering these often "succeeds"
+// but the shortcut never reaches us, so they're rejected up front.
+#[cfg(target_os = "macos")]
+const RESERVED: &[(&str, &str)] = &[
+    ("Command+Space", "Spotlight"),
+    ("Control+Space", "Input source switching"),
+    ("Command+Tab", "App switcher"),
+    ("Command+Q", "Quit application"),
+    ("Command+H", "Hide application"),
+    ("Command+Shift+3", "Screenshot"),
+    ("Command+Shift+4", "Screenshot"),
+    ("Command+Shift+5", "Screenshot"),
+    ("Command+Alt+Escape", "Force Quit"),
+    ("Control+Command+Q", "Lock Screen"),
+    ("Command+Alt+D", "Dock visibility"),
+];
+
+#[cfg(target_os = "windows")]
+const RESERVED: &[(&str, &str)] = &[
+    ("Alt+Tab", "Task switcher"),
+    ("Alt+F4", "Close window"),
+    ("Super+L", "Lock screen"),
+    ("Super+D", "Show desktop"),
+    ("Super+E", "File Explorer"),
+    ("Super+R", "Run dialog"),
+    ("Super+V", "Clipboard history"),
+    ("Super+Tab", "Task view"),
+    ("Super+Shift+S", "Snipping Tool"),
+    ("Control+Shift+Escape", "Task Manager"),
+];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const RESERVED: &[(&str, &str)] = &[
+    ("Alt+Tab", "Window switcher"),
+    ("Alt+F2", "Run command"),
+    ("Alt+F4", "Close window"),
+    ("Super+L", "Lock screen"),
+    ("Super+A", "Application grid"),
+    ("Control+Alt+T", "Terminal"),
+    ("Control+Alt+Delete", "Log out"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSource {
+    // Another action of this app
+    Action,
+    // The operating system
+    System,
+    // Some other application; the OS doesn't say which
+    External,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyConflict {
+    pub source: ConflictSource,
+    pub accelerator: String,
+    pub action: Option<HotkeyAction>,
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyErrorKind {
+    Invalid,
+    Conflict,
+    Failed,
+}
+
+// Returned to the settings UI so it can point at the clashing binding instead
+// of showing a bare message
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyError {
+    pub kind: HotkeyErrorKind,
+    pub message: String,
+    pub conflict: Option<HotkeyConflict>,
+}
+
+impl HotkeyError {
+    pub fn invalid(message: String) -> Self {
+        Self {
+            kind: HotkeyErrorKind::Invalid,
+            message,
+            conflict: None,
+        }
+    }
+
+    pub fn conflict(conflict: HotkeyConflict) -> Self {
+        let message = match (&conflict.source, &conflict.action, &conflict.owner) {
+            (ConflictSource::Action, Some(action), _) => {
+                format!("'{}' is already bound to {:?}", conflict.accelerator, action)
+            }
+            (ConflictSource::System, _, Some(owner)) => {
+                format!("'{}' is reserved by the system ({})", conflict.accelerator, owner)
+            }
+            _ => format!("'{}' is already in use by another application", conflict.accelerator),
+        };
+
+        Self {
+            kind: HotkeyErrorKind::Conflict,
+            message,
+            conflict: Some(conflict),
+        }
+    }
+
+    // The OS refused the registration. Platforms don't report who owns the
+    // shortcut, so this is as specific as it gets.
+    pub fn registration(accelerator: &str, error: impl std::fmt::Display) -> Self {
+        let mut conflict = Self::conflict(HotkeyConflict {
+            source: ConflictSource::External,
+            accelerator: accelerator.to_string(),
+            action: None,
+            owner: None,
+        });
+        conflict.message = format!("{}: {}", conflict.message, error);
+        conflict
+    }
+}
+
+impl From<String> for HotkeyError {
+    fn from(message: String) -> Self {
+        Self {
+            kind: HotkeyErrorKind::Failed,
+            message,
+            conflict: None,
+        }
+    }
+}
+
+// Conflicts that can be found without touching the OS: another action of ours
+// holding the same keys, or a known system shortcut
+pub fn find_known_conflict(
+    accelerators: &HashMap<HotkeyAction, String>,
+    action: Option<HotkeyAction>,
+    shortcut: &Shortcut,
+) -> Option<HotkeyConflict> {
+    let bound = accelerators
+        .iter()
+        .filter(|(other, accelerator)| Some(**other) != action && !accelerator.is_empty())
+        .find(|(_, accelerator)| {
+            parse_accelerator(accelerator).is_ok_and(|other| other.id() == shortcut.id())
+        });
+    if let Some((other, accelerator)) = bound {
+        return Some(HotkeyConflict {
+            source: ConflictSource::Action,
+            accelerator: accelerator.clone(),
+            action: Some(*other),
+            owner: None,
+        });
+    }
+
+    RESERVED
+        .iter()
+        .find(|(accelerator, _)| {
+            parse_accelerator(accelerator).is_ok_and(|reserved| reserved.id() == shortcut.id())
+        })
+        .map(|(accelerator, owner)| HotkeyConflict {
+            source: ConflictSource::System,
+            accelerator: accelerator.to_string(),
+            action: None,
+            owner: Some(owner.to_string()),
+        })
+}
@@ -0,0 +1,151 @@
+use rdev::{EventType, Key};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use super::{dispatch, listener, HotkeyAction};
+use crate::error::AppError;
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Modifier {
+    Meta,
+    Control,
+    Alt,
+    Shift,
+}
+
+impl Modifier {
+    pub fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::MetaLeft | Key::MetaRight => Some(Modifier::Meta),
+            Key::ControlLeft | Key::ControlRight => Some(Modifier::Control),
+            Key::Alt | Key::AltGr => Some(Modifier::Alt),
+            Key::ShiftLeft | Key::ShiftRight => Some(Modifier::Shift),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DoubleTapConfig {
+    pub enabled: bool,
+    pub modifier: Modifier,
+    pub action: HotkeyAction,
+    // Maximum time between the first release and the second press, and the
+    // longest a single tap may be held
+    pub interval_ms: u64,
+}
+
+impl Default for DoubleTapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            modifier: if cfg!(target_os = "macos") {
+                Modifier::Meta
+            } else {
+                Modifier::Control
+            },
+            action: HotkeyAction::TranslateSelection,
+            interval_ms: 300,
+        }
+    }
+}
+
+pub type DoubleTapState = Mutex<DoubleTapConfig>;
+
+// Tracks taps of a bare modifier. Any other key or a mouse click in between
+// means the modifier was part of a chord and resets the sequence.
+#[derive(Default)]
+pub struct DoubleTapDetector {
+    pressed: Option<(Modifier, Instant)>,
+    released: Option<(Modifier, Instant)>,
+}
+
+impl DoubleTapDetector {
+    pub fn handle(&mut self, app: &AppHandle, event: &EventType) {
+        let config = app.state::<DoubleTapState>().lock().unwrap().clone();
+        if !config.enabled {
+            return;
+        }
+        let interval = Duration::from_millis(config.interval_ms);
+
+        match event {
+            EventType::KeyPress(key) => match Modifier::from_key(*key) {
+                // Auto-repeat while the modifier is held
+                Some(modifier) if self.pressed.is_some_and(|(held, _)| held == modifier) => {}
+                Some(modifier) => {
+                    let second_tap = self.released.take().is_some_and(|(tapped, at)| {
+                        tapped == modifier && at.elapsed() <= interval
+                    });
+                    if second_tap && modifier == config.modifier {
+                        // Don't let the second tap start a new sequence
+                        self.pressed = None;
+                        dispatch(app, config.action);
+                    } else {
+                        self.pressed = Some((modifier, Instant::now()));
+                    }
+                }
+                None => self.reset(),
+            },
+            EventType::KeyRelease(key) => {
+                if let (Some(modifier), Some((held, at))) = (Modifier::from_key(*key), self.pressed) {
+                    if modifier == held && at.elapsed() <= interval {
+                        self.released = Some((modifier, Instant::now()));
+                    }
+                }
+                self.pressed = None;
+            }
+            EventType::ButtonPress(_) | EventType::Wheel { .. } => self.reset(),
+            _ => {}
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pressed = None;
+        self.released = None;
+    }
+}
+
+pub fn load_config(app: &AppHandle) -> DoubleTapConfig {
+    settings::load(app).double_tap
+}
+
+impl DoubleTapConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_ms < 100 || self.interval_ms > 1000 {
+            return Err("Double-tap interval must be between 100 and 1000 ms".to_string());
+        }
+        if self.action.needs_release() {
+            return Err(format!("{:?} needs a shortcut that can be held", self.action));
+        }
+        Ok(())
+    }
+}
+
+// Makes a saved config live
+pub fn apply(app: &AppHandle, config: DoubleTapConfig) {
+    let enabled = config.enabled;
+    *app.state::<DoubleTapState>().lock().unwrap() = config;
+    if enabled {
+        listener::ensure_started(app);
+    }
+}
+
+#[tauri::command]
+pub async fn get_double_tap(
+    double_tap_state: State<'_, DoubleTapState>,
+) -> Result<DoubleTapConfig, AppError> {
+    Ok(double_tap_state.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_double_tap(app: AppHandle, config: DoubleTapConfig) -> Result<DoubleTapConfig, AppError> {
+    config.validate()?;
+    settings::update(&app, |settings| settings.double_tap = config.clone())?;
+    apply(&app, config.clone());
+    Ok(config)
+}
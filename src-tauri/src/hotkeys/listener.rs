@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::AppHandle;
+
+use super::double_tap::DoubleTapDetector;
+use super::mouse::MouseTriggerDetector;
+use super::recorder::ChordRecorder;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// Starts the OS-level input hook the first time a feature needs it. The hook
+// sees every key and mouse event, so it isn't installed unless something uses it.
+pub fn ensure_started(app: &AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut double_tap = DoubleTapDetector::default();
+        let mut mouse = MouseTriggerDetector::default();
+        let mut recorder = ChordRecorder::default();
+
+        // listen() blocks for the lifetime of the hook
+        let result = rdev::listen(move |event| {
+            double_tap.handle(&app, &event.event_type);
+            mouse.handle(&app, &event.event_type);
+            recorder.handle(&app, &event.event_type);
+        });
+
+        if let Err(e) = result {
+            // Usually a missing accessibility / input monitoring permission
+            tracing::error!("Failed to start input listener: {:?}", e);
+            STARTED.store(false, Ordering::SeqCst);
+        }
+    });
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use super::conflict::{find_known_conflict, HotkeyError};
+use super::{
+    action_name, current_bindings, load_accelerators, parse_accelerator, reregister_all,
+    save_accelerators, HotkeyAction, HotkeyBinding,
+};
+use crate::error::AppError;
+
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyActionInfo {
+    pub action: HotkeyAction,
+    pub default_accelerator: String,
+    // Push-to-translate style actions react to key-up too
+    pub needs_release: bool,
+}
+
+// On-disk export format. Actions are kept as plain strings so a file naming
+// actions this build doesn't know about still imports.
+#[derive(Debug, Serialize, Deserialize)]
+struct HotkeyExport {
+    version: u32,
+    hotkeys: HashMap<String, String>,
+}
+
+// Swaps the whole map at once. Validating and unregistering everything first
+// lets two actions trade shortcuts, which one-at-a-time rebinding would refuse.
+fn replace_all(
+    app: &AppHandle,
+    accelerators: HashMap<HotkeyAction, String>,
+) -> Result<Vec<HotkeyBinding>, HotkeyError> {
+    for (action, accelerator) in accelerators.iter().filter(|(_, a)| !a.is_empty()) {
+        let shortcut = parse_accelerator(accelerator).map_err(HotkeyError::invalid)?;
+        if let Some(conflict) = find_known_conflict(&accelerators, Some(*action), &shortcut) {
+            return Err(HotkeyError::conflict(conflict));
+        }
+    }
+
+    save_accelerators(app, &accelerators)?;
+    reregister_all(app);
+    Ok(current_bindings(app))
+}
+
+#[tauri::command]
+pub async fn list_hotkey_actions() -> Result<Vec<HotkeyActionInfo>, AppError> {
+    Ok(HotkeyAction::ALL
+        .iter()
+        .map(|action| HotkeyActionInfo {
+            action: *action,
+            default_accelerator: action.default_accelerator().to_string(),
+            needs_release: action.needs_release(),
+        })
+        .collect())
+}
+
+// Restores the default shortcut for one action, or for all of them
+#[tauri::command]
+pub async fn reset_hotkeys(
+    app: AppHandle,
+    action: Option<HotkeyAction>,
+) -> Result<Vec<HotkeyBinding>, AppError> {
+    let mut accelerators = load_accelerators(&app);
+    for (current, accelerator) in accelerators.iter_mut() {
+        if action.map_or(true, |action| action == *current) {
+            *accelerator = current.default_accelerator().to_string();
+        }
+    }
+    Ok(replace_all(&app, accelerators)?)
+}
+
+#[tauri::command]
+pub async fn export_hotkeys(app: AppHandle, path: String) -> Result<(), AppError> {
+    let export = HotkeyExport {
+        version: EXPORT_VERSION,
+        hotkeys: load_accelerators(&app)
+            .into_iter()
+            .map(|(action, accelerator)| (action_name(action), accelerator))
+            .collect(),
+    };
+
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize hotkeys: {}", e))?;
+    Ok(std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?)
+}
+
+// Actions missing from the file keep their default shortcut; unknown ones are ignored
+#[tauri::command]
+pub async fn import_hotkeys(
+    app: AppHandle,
+    path: String,
+) -> Result<Vec<HotkeyBinding>, AppError> {
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let export: HotkeyExport = serde_json::from_str(&json)
+        .map_err(|e| HotkeyError::invalid(format!("Not a hotkey export file: {}", e)))?;
+    if export.version > EXPORT_VERSION {
+        return Err(HotkeyError::invalid(format!(
+            "Hotkey file version {} is newer than supported",
+            export.version
+        )).into());
+    }
+
+    let accelerators = HotkeyAction::ALL
+        .iter()
+        .map(|action| {
+            let accelerator = export
+                .hotkeys
+                .get(&action_name(*action))
+                .map(|accelerator| accelerator.trim().to_string())
+                .unwrap_or_else(|| action.default_accelerator().to_string());
+            (*action, accelerator)
+        })
+        .collect();
+
+    Ok(replace_all(&app, accelerators)?)
+}
@@ -0,0 +1,421 @@
+pub mod conflict;
+pub mod double_tap;
+mod listener;
+pub mod mapping;
+pub mod mouse;
+pub mod recorder;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::{metrics, monitoring};
+use crate::nudge::{self, NudgeAction};
+use crate::popup::{self, PopupTrigger};
+use crate::settings;
+use crate::translation::presets;
+use conflict::{find_known_conflict, HotkeyError};
+use double_tap::DoubleTapState;
+use mouse::MouseTriggerState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyAction {
+    TranslateSelection,
+    PushToTranslate,
+    TranslateClipboard,
+    CaptureRegion,
+    ToggleWatcher,
+    ToggleMonitoring,
+    ShowLastResult,
+    CycleLanguagePreset,
+    MoveWindowUp,
+    MoveWindowDown,
+    MoveWindowLeft,
+    MoveWindowRight,
+    GrowWindowWidth,
+    ShrinkWindowWidth,
+    GrowWindowHeight,
+    ShrinkWindowHeight,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 16] = [
+        HotkeyAction::TranslateSelection,
+        HotkeyAction::PushToTranslate,
+        HotkeyAction::TranslateClipboard,
+        HotkeyAction::CaptureRegion,
+        HotkeyAction::ToggleWatcher,
+        HotkeyAction::ToggleMonitoring,
+        HotkeyAction::ShowLastResult,
+        HotkeyAction::CycleLanguagePreset,
+        HotkeyAction::MoveWindowUp,
+        HotkeyAction::MoveWindowDown,
+        HotkeyAction::MoveWindowLeft,
+        HotkeyAction::MoveWindowRight,
+        HotkeyAction::GrowWindowWidth,
+        HotkeyAction::ShrinkWindowWidth,
+        HotkeyAction::GrowWindowHeight,
+        HotkeyAction::ShrinkWindowHeight,
+    ];
+
+    pub fn default_accelerator(&self) -> &'static str {
+        match self {
+            HotkeyAction::TranslateSelection => "CommandOrControl+Alt+E",
+            HotkeyAction::PushToTranslate => "CommandOrControl+Alt+H",
+            // Control+Alt+T opens a terminal on most Linux desktops
+            #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+            HotkeyAction::TranslateClipboard => "CommandOrControl+Alt+V",
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            HotkeyAction::TranslateClipboard => "CommandOrControl+Alt+T",
+            HotkeyAction::CaptureRegion => "CommandOrControl+Alt+S",
+            HotkeyAction::ToggleWatcher => "CommandOrControl+Alt+W",
+            HotkeyAction::ToggleMonitoring => "CommandOrControl+Alt+P",
+            HotkeyAction::ShowLastResult => "CommandOrControl+Alt+L",
+            HotkeyAction::CycleLanguagePreset => "CommandOrControl+Alt+J",
+            // Arrow chords clash with too many editors to claim by default
+            _ => "",
+        }
+    }
+
+    pub fn nudge(&self) -> Option<NudgeAction> {
+        match self {
+            HotkeyAction::MoveWindowUp => Some(NudgeAction::MoveUp),
+            HotkeyAction::MoveWindowDown => Some(NudgeAction::MoveDown),
+            HotkeyAction::MoveWindowLeft => Some(NudgeAction::MoveLeft),
+            HotkeyAction::MoveWindowRight => Some(NudgeAction::MoveRight),
+            HotkeyAction::GrowWindowWidth => Some(NudgeAction::GrowWidth),
+            HotkeyAction::ShrinkWindowWidth => Some(NudgeAction::ShrinkWidth),
+            HotkeyAction::GrowWindowHeight => Some(NudgeAction::GrowHeight),
+            HotkeyAction::ShrinkWindowHeight => Some(NudgeAction::ShrinkHeight),
+            _ => None,
+        }
+    }
+
+    // Actions that act on key-up as well as key-down, so they only make sense
+    // bound to a real shortcut
+    pub fn needs_release(&self) -> bool {
+        matches!(self, HotkeyAction::PushToTranslate)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    // Empty when the action is unbound
+    pub accelerator: String,
+    pub registered: bool,
+}
+
+// Shortcuts currently registered with the OS, keyed by the action they trigger
+pub type HotkeyRegistry = Mutex<HashMap<HotkeyAction, Shortcut>>;
+
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    // While the settings UI records a new chord, existing bindings stay inert
+    if recorder::is_capturing(app) {
+        return;
+    }
+
+    let action = app
+        .state::<HotkeyRegistry>()
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(_, registered)| registered.id() == shortcut.id())
+        .map(|(action, _)| *action);
+
+    match (action, event.state) {
+        (Some(action), ShortcutState::Pressed) => dispatch(app, action),
+        (Some(HotkeyAction::PushToTranslate), ShortcutState::Released) => popup::end_hold(app),
+        _ => {}
+    }
+}
+
+// Selection translation, monitoring, presets and window nudging run in the
+// backend so they work while every window is hidden; other actions go to the
+// frontend
+fn dispatch(app: &AppHandle, action: HotkeyAction) {
+    trigger(app, action, PopupTrigger::Press);
+}
+
+// For triggers that fire as a side effect of ordinary input (e.g. a middle click)
+fn dispatch_passive(app: &AppHandle, action: HotkeyAction) {
+    trigger(app, action, PopupTrigger::Passive);
+}
+
+fn trigger(app: &AppHandle, action: HotkeyAction, popup_trigger: PopupTrigger) {
+    // Paused means nothing fires on its own, whatever the action; only a
+    // trigger bound to the pause switch itself still works, to resume
+    let paused = popup_trigger == PopupTrigger::Passive && monitoring::is_paused(app);
+    if paused && action != HotkeyAction::ToggleMonitoring {
+        return;
+    }
+    metrics::hotkey(app, action);
+
+    if let Some(nudge) = action.nudge() {
+        if let Err(e) = nudge::nudge_focused(app, nudge) {
+            tracing::error!("Failed to nudge window: {}", e);
+        }
+        return;
+    }
+
+    let popup_trigger = match action {
+        HotkeyAction::ToggleMonitoring => {
+            monitoring::toggle(app);
+            return;
+        }
+        HotkeyAction::CycleLanguagePreset => {
+            if let Err(e) = presets::cycle(app) {
+                tracing::error!("Failed to switch language preset: {}", e);
+            }
+            return;
+        }
+        HotkeyAction::TranslateSelection => popup_trigger,
+        HotkeyAction::PushToTranslate if popup::begin_hold() => PopupTrigger::Hold,
+        HotkeyAction::PushToTranslate => return,
+        _ => {
+            events::broadcast(app, AppEvent::HotkeyTriggered(action));
+            return;
+        }
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = popup::translate_selection(app, popup_trigger).await {
+            if popup_trigger != PopupTrigger::Passive {
+                tracing::error!("Selection translation failed: {}", e);
+            }
+        }
+    });
+}
+
+fn load_accelerators(app: &AppHandle) -> HashMap<HotkeyAction, String> {
+    let stored = settings::load(app).hotkeys;
+
+    HotkeyAction::ALL
+        .iter()
+        .map(|action| {
+            let accelerator = stored
+                .get(action)
+                .cloned()
+                .unwrap_or_else(|| action.default_accelerator().to_string());
+            (*action, accelerator)
+        })
+        .collect()
+}
+
+fn save_accelerators(app: &AppHandle, accelerators: &HashMap<HotkeyAction, String>) -> Result<(), String> {
+    settings::update(app, |settings| settings.hotkeys = accelerators.clone()).map(|_| ())
+}
+
+// Name the frontend and export files use for an action
+pub fn action_name(action: HotkeyAction) -> String {
+    serde_json::to_value(action)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+// Problems with a whole action→shortcut map: accelerators that don't parse
+// and shortcuts that clash with each other or with the system
+pub fn find_map_errors(accelerators: &HashMap<HotkeyAction, String>) -> Vec<(HotkeyAction, String)> {
+    accelerators
+        .iter()
+        .filter(|(_, accelerator)| !accelerator.trim().is_empty())
+        .filter_map(|(action, accelerator)| {
+            let shortcut = match parse_accelerator(accelerator.trim()) {
+                Ok(shortcut) => shortcut,
+                Err(e) => return Some((*action, e)),
+            };
+            find_known_conflict(accelerators, Some(*action), &shortcut)
+                .map(|conflict| (*action, HotkeyError::conflict(conflict).message))
+        })
+        .collect()
+}
+
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(accelerator).map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+}
+
+// Registers every non-empty binding in `accelerators`; bindings the OS refuses
+// are skipped and returned with the reason so the remaining shortcuts still work
+fn register_map(app: &AppHandle, accelerators: &HashMap<HotkeyAction, String>) -> Vec<(HotkeyAction, String)> {
+    let mut failures = Vec::new();
+    for (action, accelerator) in accelerators {
+        if accelerator.is_empty() {
+            continue;
+        }
+
+        let result = parse_accelerator(accelerator).and_then(|shortcut| {
+            app.global_shortcut()
+                .register(shortcut)
+                .map_err(|e| e.to_string())?;
+            Ok(shortcut)
+        });
+
+        match result {
+            Ok(shortcut) => {
+                app.state::<HotkeyRegistry>()
+                    .lock()
+                    .unwrap()
+                    .insert(*action, shortcut);
+            }
+            Err(e) => failures.push((*action, e)),
+        }
+    }
+    failures
+}
+
+pub fn current_bindings(app: &AppHandle) -> Vec<HotkeyBinding> {
+    let accelerators = load_accelerators(app);
+    let registry = app.state::<HotkeyRegistry>();
+    let registry = registry.lock().unwrap();
+
+    HotkeyAction::ALL
+        .iter()
+        .map(|action| HotkeyBinding {
+            action: *action,
+            accelerator: accelerators.get(action).cloned().unwrap_or_default(),
+            registered: registry.contains_key(action),
+        })
+        .collect()
+}
+
+// True when something is bound and every bound action made it into the OS
+pub fn all_registered(app: &AppHandle) -> bool {
+    let bindings = current_bindings(app);
+    let mut bound = bindings.iter().filter(|binding| !binding.accelerator.is_empty()).peekable();
+    bound.peek().is_some() && bound.all(|binding| binding.registered)
+}
+
+// Hands every shortcut back to the OS, e.g. on quit
+pub fn unregister_all(app: &AppHandle) {
+    // Drain before unregistering; the lock must not be held across plugin calls
+    let previous: Vec<_> = app.state::<HotkeyRegistry>().lock().unwrap().drain().collect();
+    for (_, shortcut) in previous {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+}
+
+// Drops every registration and registers the persisted bindings afresh
+pub fn reregister_all(app: &AppHandle) {
+    unregister_all(app);
+    for (action, e) in register_map(app, &load_accelerators(app)) {
+        tracing::error!("Failed to register hotkey for {:?}: {}", action, e);
+    }
+}
+
+// Registers the persisted bindings at startup
+pub fn register_all(app: &AppHandle) {
+    for (action, e) in register_map(app, &load_accelerators(app)) {
+        tracing::error!("Failed to register hotkey for {:?}: {}", action, e);
+    }
+
+    let double_tap = app.state::<DoubleTapState>().lock().unwrap().enabled;
+    let mouse = app.state::<MouseTriggerState>().lock().unwrap().is_enabled();
+    if double_tap || mouse {
+        listener::ensure_started(app);
+    }
+}
+
+#[tauri::command]
+pub async fn get_hotkeys(app: AppHandle) -> Result<Vec<HotkeyBinding>, AppError> {
+    Ok(current_bindings(&app))
+}
+
+fn parse_for_binding(accelerator: &str) -> Result<Shortcut, HotkeyError> {
+    parse_accelerator(accelerator).map_err(HotkeyError::invalid)
+}
+
+// Dry run for the settings UI: reports whether `accelerator` could be bound to
+// `action` without changing any registration
+#[tauri::command]
+pub async fn validate_hotkey(
+    app: AppHandle,
+    hotkey_registry: State<'_, HotkeyRegistry>,
+    action: Option<HotkeyAction>,
+    accelerator: String,
+) -> Result<(), AppError> {
+    let accelerator = accelerator.trim().to_string();
+    if accelerator.is_empty() {
+        return Ok(());
+    }
+
+    let shortcut = parse_for_binding(&accelerator)?;
+    if let Some(conflict) = find_known_conflict(&load_accelerators(&app), action, &shortcut) {
+        return Err(HotkeyError::conflict(conflict).into());
+    }
+
+    // Already ours for this action, so the OS has nothing to object to
+    let current = action.and_then(|action| hotkey_registry.lock().unwrap().get(&action).copied());
+    if current.is_some_and(|current| current.id() == shortcut.id()) {
+        return Ok(());
+    }
+
+    // Probe the OS by registering and immediately releasing the shortcut
+    app.global_shortcut()
+        .register(shortcut)
+        .map_err(|e| HotkeyError::registration(&accelerator, e))?;
+    app.global_shortcut()
+        .unregister(shortcut)
+        .map_err(|e| format!("Failed to release probed shortcut: {}", e))?;
+    Ok(())
+}
+
+// Rebinds an action at runtime. An empty accelerator clears the binding.
+#[tauri::command]
+pub async fn set_hotkey(
+    app: AppHandle,
+    hotkey_registry: State<'_, HotkeyRegistry>,
+    action: HotkeyAction,
+    accelerator: String,
+) -> Result<HotkeyBinding, AppError> {
+    let accelerator = accelerator.trim().to_string();
+    let mut accelerators = load_accelerators(&app);
+    let shortcut = if accelerator.is_empty() {
+        None
+    } else {
+        let shortcut = parse_for_binding(&accelerator)?;
+        if let Some(conflict) = find_known_conflict(&accelerators, Some(action), &shortcut) {
+            return Err(HotkeyError::conflict(conflict).into());
+        }
+        Some(shortcut)
+    };
+
+    // The registry lock must not be held while talking to the plugin: registration
+    // runs on the main thread, which also runs the shortcut handler
+    let previous = hotkey_registry.lock().unwrap().remove(&action);
+    if let Some(previous) = previous {
+        app.global_shortcut()
+            .unregister(previous)
+            .map_err(|e| format!("Failed to unregister previous shortcut: {}", e))?;
+    }
+
+    if let Some(shortcut) = shortcut {
+        if let Err(e) = app.global_shortcut().register(shortcut) {
+            // Put the old binding back so the action keeps working
+            if let Some(previous) = previous {
+                if app.global_shortcut().register(previous).is_ok() {
+                    hotkey_registry.lock().unwrap().insert(action, previous);
+                }
+            }
+            return Err(HotkeyError::registration(&accelerator, e).into());
+        }
+        hotkey_registry.lock().unwrap().insert(action, shortcut);
+    }
+
+    accelerators.insert(action, accelerator.clone());
+    save_accelerators(&app, &accelerators)?;
+
+    Ok(HotkeyBinding {
+        action,
+        accelerator,
+        registered: shortcut.is_some(),
+    })
+}
@@ -0,0 +1,195 @@
+use rdev::{Button, EventType};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use super::double_tap::Modifier;
+use super::{dispatch_passive, listener, HotkeyAction};
+use crate::error::AppError;
+use crate::{cursor, settings};
+
+// How close to the corner counts as "in" it, and how far the cursor has to
+// move back out before the corner fires again
+const CORNER_SIZE: f64 = 3.0;
+const CORNER_RELEASE: f64 = 40.0;
+const MONITOR_REFRESH: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotCorner {
+    pub corner: Corner,
+    pub action: HotkeyAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MouseTriggerConfig {
+    pub middle_click: Option<HotkeyAction>,
+    // Held with the middle click. A bare middle click is paste on Linux and
+    // the simulated copy that follows is an interrupt in a terminal.
+    pub middle_click_modifier: Modifier,
+    pub hot_corner: Option<HotCorner>,
+}
+
+impl Default for MouseTriggerConfig {
+    fn default() -> Self {
+        Self {
+            middle_click: None,
+            middle_click_modifier: Modifier::Alt,
+            hot_corner: None,
+        }
+    }
+}
+
+impl MouseTriggerConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.middle_click.is_some() || self.hot_corner.is_some()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let actions = [self.middle_click, self.hot_corner.map(|corner| corner.action)];
+        match actions.into_iter().flatten().find(|action| action.needs_release()) {
+            Some(action) => Err(format!("{:?} needs a shortcut that can be held", action)),
+            None => Ok(()),
+        }
+    }
+}
+
+pub type MouseTriggerState = Mutex<MouseTriggerConfig>;
+
+// Monitor rectangle (left, top, right, bottom) in the listener's coordinate space
+type Rect = (f64, f64, f64, f64);
+
+#[derive(Default)]
+pub struct MouseTriggerDetector {
+    monitors: Vec<Rect>,
+    refreshed: Option<Instant>,
+    in_corner: bool,
+    held: Vec<Modifier>,
+}
+
+impl MouseTriggerDetector {
+    pub fn handle(&mut self, app: &AppHandle, event: &EventType) {
+        let config = *app.state::<MouseTriggerState>().lock().unwrap();
+        match event {
+            EventType::KeyPress(key) => {
+                if let Some(modifier) = Modifier::from_key(*key) {
+                    if !self.held.contains(&modifier) {
+                        self.held.push(modifier);
+                    }
+                }
+            }
+            EventType::KeyRelease(key) => {
+                if let Some(modifier) = Modifier::from_key(*key) {
+                    self.held.retain(|held| *held != modifier);
+                }
+            }
+            EventType::ButtonPress(Button::Middle) => {
+                if let Some(action) = config.middle_click {
+                    if self.held.contains(&config.middle_click_modifier) {
+                        dispatch_passive(app, action);
+                    }
+                }
+            }
+            EventType::MouseMove { x, y } => {
+                if let Some(hot_corner) = config.hot_corner {
+                    self.track_corner(app, hot_corner, *x, *y);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn track_corner(&mut self, app: &AppHandle, hot_corner: HotCorner, x: f64, y: f64) {
+        if self.refreshed.map_or(true, |at| at.elapsed() >= MONITOR_REFRESH) {
+            self.monitors = monitor_rects(app);
+            self.refreshed = Some(Instant::now());
+        }
+
+        let distance = self
+            .monitors
+            .iter()
+            .map(|rect| corner_distance(rect, hot_corner.corner, x, y))
+            .fold(f64::INFINITY, f64::min);
+
+        if self.in_corner {
+            if distance > CORNER_RELEASE {
+                self.in_corner = false;
+            }
+        } else if distance <= CORNER_SIZE {
+            self.in_corner = true;
+            dispatch_passive(app, hot_corner.action);
+        }
+    }
+}
+
+fn corner_distance(rect: &Rect, corner: Corner, x: f64, y: f64) -> f64 {
+    let (left, top, right, bottom) = *rect;
+    let (cx, cy) = match corner {
+        Corner::TopLeft => (left, top),
+        Corner::TopRight => (right - 1.0, top),
+        Corner::BottomLeft => (left, bottom - 1.0),
+        Corner::BottomRight => (right - 1.0, bottom - 1.0),
+    };
+    (x - cx).abs().max((y - cy).abs())
+}
+
+fn monitor_rects(app: &AppHandle) -> Vec<Rect> {
+    cursor::monitors(app)
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| {
+            // The macOS event tap reports points, everything else physical pixels
+            let scale = if cfg!(target_os = "macos") {
+                monitor.scale_factor
+            } else {
+                1.0
+            };
+            let left = monitor.x as f64 / scale;
+            let top = monitor.y as f64 / scale;
+            (
+                left,
+                top,
+                left + monitor.width as f64 / scale,
+                top + monitor.height as f64 / scale,
+            )
+        })
+        .collect()
+}
+
+pub fn load_config(app: &AppHandle) -> MouseTriggerConfig {
+    settings::load(app).mouse_triggers
+}
+
+// Makes a saved config live
+pub fn apply(app: &AppHandle, config: MouseTriggerConfig) {
+    *app.state::<MouseTriggerState>().lock().unwrap() = config;
+    if config.is_enabled() {
+        listener::ensure_started(app);
+    }
+}
+
+#[tauri::command]
+pub async fn get_mouse_triggers(
+    mouse_trigger_state: State<'_, MouseTriggerState>,
+) -> Result<MouseTriggerConfig, AppError> {
+    Ok(*mouse_trigger_state.lock().unwrap())
+}
+
+#[tauri::command]
+pub async fn set_mouse_triggers(app: AppHandle, config: MouseTriggerConfig) -> Result<MouseTriggerConfig, AppError> {
+    config.validate()?;
+    settings::update(&app, |settings| settings.mouse_triggers = config)?;
+    apply(&app, config);
+    Ok(config)
+}
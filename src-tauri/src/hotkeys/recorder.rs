@@ -0,0 +1,201 @@
+use rdev::{EventType, Key};
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::oneshot;
+
+use super::double_tap::Modifier;
+use super::listener;
+use crate::error::AppError;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Sender half of the capture in progress, if any
+pub type HotkeyCaptureState = Mutex<Option<oneshot::Sender<Option<String>>>>;
+
+pub fn is_capturing(app: &AppHandle) -> bool {
+    app.state::<HotkeyCaptureState>().lock().unwrap().is_some()
+}
+
+fn finish(app: &AppHandle, accelerator: Option<String>) {
+    if let Some(sender) = app.state::<HotkeyCaptureState>().lock().unwrap().take() {
+        let _ = sender.send(accelerator);
+    }
+}
+
+// Turns raw key events into an accelerator string in the format Shortcut parses
+#[derive(Default)]
+pub struct ChordRecorder {
+    held: BTreeSet<u8>,
+}
+
+impl ChordRecorder {
+    pub fn handle(&mut self, app: &AppHandle, event: &EventType) {
+        match event {
+            EventType::KeyPress(key) => {
+                if let Some(modifier) = Modifier::from_key(*key) {
+                    self.held.insert(modifier_rank(modifier));
+                    return;
+                }
+                if !is_capturing(app) {
+                    return;
+                }
+
+                if *key == Key::Escape && self.held.is_empty() {
+                    finish(app, None);
+                    return;
+                }
+
+                // Bare letters and the like would hijack normal typing, so only
+                // function keys are accepted without a modifier
+                let Some(name) = key_name(*key) else { return };
+                if self.held.is_empty() && !is_function_key(*key) {
+                    return;
+                }
+
+                let mut parts: Vec<&str> = self.held.iter().map(|rank| modifier_name(*rank)).collect();
+                parts.push(&name);
+                finish(app, Some(parts.join("+")));
+            }
+            EventType::KeyRelease(key) => {
+                if let Some(modifier) = Modifier::from_key(*key) {
+                    self.held.remove(&modifier_rank(modifier));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Position in the accelerator, which also keeps the set ordered
+fn modifier_rank(modifier: Modifier) -> u8 {
+    match modifier {
+        Modifier::Meta => 0,
+        Modifier::Control => 1,
+        Modifier::Alt => 2,
+        Modifier::Shift => 3,
+    }
+}
+
+fn modifier_name(rank: u8) -> &'static str {
+    match rank {
+        0 if cfg!(target_os = "macos") => "Command",
+        0 => "Super",
+        1 => "Control",
+        2 => "Alt",
+        _ => "Shift",
+    }
+}
+
+fn is_function_key(key: Key) -> bool {
+    matches!(
+        key,
+        Key::F1
+            | Key::F2
+            | Key::F3
+            | Key::F4
+            | Key::F5
+            | Key::F6
+            | Key::F7
+            | Key::F8
+            | Key::F9
+            | Key::F10
+            | Key::F11
+            | Key::F12
+    )
+}
+
+fn key_name(key: Key) -> Option<String> {
+    let name = match key {
+        Key::Backspace => "Backspace",
+        Key::CapsLock => "CapsLock",
+        Key::Delete => "Delete",
+        Key::Insert => "Insert",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "PageUp",
+        Key::PageDown => "PageDown",
+        Key::UpArrow => "ArrowUp",
+        Key::DownArrow => "ArrowDown",
+        Key::LeftArrow => "ArrowLeft",
+        Key::RightArrow => "ArrowRight",
+        Key::Return => "Enter",
+        Key::Space => "Space",
+        Key::Tab => "Tab",
+        Key::Escape => "Escape",
+        Key::PrintScreen => "PrintScreen",
+        Key::ScrollLock => "ScrollLock",
+        Key::Pause => "Pause",
+        Key::NumLock => "NumLock",
+        Key::BackQuote => "Backquote",
+        Key::Minus => "Minus",
+        Key::Equal => "Equal",
+        Key::LeftBracket => "BracketLeft",
+        Key::RightBracket => "BracketRight",
+        Key::SemiColon => "Semicolon",
+        Key::Quote => "Quote",
+        Key::BackSlash => "Backslash",
+        Key::Comma => "Comma",
+        Key::Dot => "Period",
+        Key::Slash => "Slash",
+        Key::KpReturn => "NumpadEnter",
+        Key::KpMinus => "NumpadSubtract",
+        Key::KpPlus => "NumpadAdd",
+        Key::KpMultiply => "NumpadMultiply",
+        Key::KpDivide => "NumpadDivide",
+        Key::KpDelete => "NumpadDecimal",
+        _ => {
+            // Letters, digits, keypad digits and F-keys follow a pattern
+            let debug = format!("{:?}", key);
+            return if let Some(letter) = debug.strip_prefix("Key") {
+                Some(letter.to_string())
+            } else if let Some(digit) = debug.strip_prefix("Num") {
+                Some(digit.to_string())
+            } else if let Some(digit) = debug.strip_prefix("Kp") {
+                Some(format!("Numpad{}", digit))
+            } else if is_function_key(key) {
+                Some(debug)
+            } else {
+                None
+            };
+        }
+    };
+    Some(name.to_string())
+}
+
+// Waits for the next chord pressed anywhere. Resolves to None on Escape, on
+// timeout, or when cancel_hotkey_capture is called.
+#[tauri::command]
+pub async fn begin_hotkey_capture(
+    app: AppHandle,
+    hotkey_capture_state: State<'_, HotkeyCaptureState>,
+    timeout_ms: Option<u64>,
+) -> Result<Option<String>, AppError> {
+    listener::ensure_started(&app);
+
+    let (sender, receiver) = oneshot::channel();
+    // Starting a new capture cancels one that is still waiting
+    if let Some(previous) = hotkey_capture_state.lock().unwrap().replace(sender) {
+        let _ = previous.send(None);
+    }
+
+    let timeout = timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TIMEOUT);
+    match tokio::time::timeout(timeout, receiver).await {
+        Ok(accelerator) => Ok(accelerator.unwrap_or(None)),
+        Err(_) => {
+            // Only clear the slot if it still holds this capture's sender
+            let mut slot = hotkey_capture_state.lock().unwrap();
+            if slot.as_ref().is_some_and(|sender| sender.is_closed()) {
+                slot.take();
+            }
+            Ok(None)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_hotkey_capture(app: AppHandle) -> Result<(), AppError> {
+    finish(&app, None);
+    Ok(())
+}
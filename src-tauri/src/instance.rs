@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, ErrorKind, IsTerminal, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::api::{new_token, same_token};
+use crate::cli::{self, CliArgs};
+use crate::events::{self, AppEvent};
+use crate::{deeplink, main_window};
+use crate::history::HistoryOrigin;
+use crate::popup::{self, PopupAnchor};
+use crate::share;
+
+const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+// How long a forwarded --translate waits for the running app's result
+const TRANSLATION_TIMEOUT: Duration = Duration::from_secs(30);
+// Longest launch message read, arguments and all
+const MAX_MESSAGE: u64 = 64 * 1024;
+// How long a launch that lost the race for the instance file gives the
+// winner to start listening
+const CLAIM_GRACE: Duration = Duration::from_millis(300);
+
+// Command line of a second launch, handed to the instance already running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardedLaunch {
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    // Send the --translate result back so this launch can print it
+    #[serde(default)]
+    pub reply: bool,
+}
+
+impl ForwardedLaunch {
+    fn current() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self {
+            reply: cli::parse(&args).translate.is_some() && std::io::stdout().is_terminal(),
+            args,
+            cwd: std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+// What a second launch needs to reach the running instance. Only this
+// user can read the file, so only this user's launches know the tokens:
+// `client` proves a message comes from one of them, and `server` is the
+// reply that proves the listener is really Shunyaku and not some process
+// that took over a stale port.
+struct InstanceFile {
+    port: u16,
+    client: String,
+    server: String,
+}
+
+impl InstanceFile {
+    fn read() -> Option<Self> {
+        let contents = std::fs::read_to_string(instance_file()?).ok()?;
+        let mut lines = contents.lines().map(str::trim);
+        Some(Self {
+            port: lines.next()?.parse().ok()?,
+            client: lines.next()?.to_string(),
+            server: lines.next()?.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    token: String,
+    launch: ForwardedLaunch,
+}
+
+// A directory only this user can get into: the session's runtime dir on
+// Linux, else the temp dir, which macOS and Windows already keep per user.
+// Linux without a runtime dir falls back to a private folder of its own in
+// the shared /tmp.
+fn private_dir() -> std::io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        let user = std::env::var("USER").unwrap_or_default();
+        let dir = std::env::temp_dir().join(format!("shunyaku-{}", user));
+        match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        // Someone else may have made it first, to read or plant the file. One
+        // they own with no access for others is harmless: this launch can't
+        // write in it either, and simply goes without forwarding.
+        let metadata = std::fs::symlink_metadata(&dir)?;
+        if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+            return Err(std::io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} is not private to this user", dir.display()),
+            ));
+        }
+        Ok(dir)
+    }
+    #[cfg(not(unix))]
+    Ok(std::env::temp_dir())
+}
+
+fn instance_file() -> Option<PathBuf> {
+    match private_dir() {
+        Ok(dir) => Some(dir.join("shunyaku.instance")),
+        Err(e) => {
+            eprintln!("No private place for the single-instance file: {}", e);
+            None
+        }
+    }
+}
+
+// Readable and writable by this user only. `exclusive` fails with
+// AlreadyExists when another launch holds the file.
+fn write_private(path: &Path, contents: &str, exclusive: bool) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if exclusive {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn send(instance: &InstanceFile, launch: ForwardedLaunch) -> std::io::Result<bool> {
+    let mut stream = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, instance.port).into(), REPLY_TIMEOUT)?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+
+    let reply_wanted = launch.reply;
+    let message = Message {
+        token: instance.client.clone(),
+        launch,
+    };
+    let mut message = serde_json::to_string(&message)?;
+    message.push('\n');
+    stream.write_all(message.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    if !same_token(reply.trim(), &instance.server) {
+        return Ok(false);
+    }
+
+    if reply_wanted {
+        reader.get_ref().set_read_timeout(Some(TRANSLATION_TIMEOUT))?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        match serde_json::from_str::<Result<String, String>>(&line) {
+            Ok(Ok(text)) => println!("{}", text),
+            Ok(Err(e)) => eprintln!("Translation failed: {}", e),
+            Err(_) => eprintln!("No translation received from the running app"),
+        }
+    }
+    Ok(true)
+}
+
+// Hands this launch's arguments to an instance that is already running.
+// True means this process should exit.
+fn forward_to_running() -> bool {
+    let Some(instance) = InstanceFile::read() else {
+        return false;
+    };
+    send(&instance, ForwardedLaunch::current()).unwrap_or(false)
+}
+
+pub struct InstanceLock {
+    listener: TcpListener,
+    client: String,
+    server: String,
+}
+
+pub enum Claim {
+    // Another instance took this launch's arguments; exit
+    Forwarded,
+    Owned(InstanceLock),
+    // No forwarding this run, but the app still starts
+    Unavailable,
+}
+
+fn bind(path: &Path) -> Result<Claim, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|e| format!("Failed to open single-instance port: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let (client, server) = (new_token()?, new_token()?);
+    let contents = format!("{}\n{}\n{}\n", port, client, server);
+
+    // Exclusive, so two launches at once can't both become the instance
+    match write_private(path, &contents, true) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            std::thread::sleep(CLAIM_GRACE);
+            if forward_to_running() {
+                return Ok(Claim::Forwarded);
+            }
+            // Left behind by an instance that didn't exit cleanly
+            write_private(path, &contents, false)
+                .map_err(|e| format!("Failed to record single-instance port: {}", e))?;
+        }
+        Err(e) => return Err(format!("Failed to record single-instance port: {}", e)),
+    }
+    let _ = OWNED_PORT.set(port);
+    Ok(Claim::Owned(InstanceLock { listener, client, server }))
+}
+
+// Port this process listens on, once it holds the instance file
+static OWNED_PORT: OnceLock<u16> = OnceLock::new();
+
+// Removes the instance file on the way out, so the next launch claims it at
+// once instead of probing a dead port first
+pub fn release() {
+    let Some(port) = OWNED_PORT.get() else {
+        return;
+    };
+    if InstanceFile::read().is_some_and(|instance| instance.port == *port) {
+        if let Some(path) = instance_file() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Forwards this launch to the running instance, or makes this process the
+// one later launches forward to
+pub fn claim() -> Claim {
+    if forward_to_running() {
+        return Claim::Forwarded;
+    }
+    let Some(path) = instance_file() else {
+        return Claim::Unavailable;
+    };
+    bind(&path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Claim::Unavailable
+    })
+}
+
+fn receive(lock: &InstanceLock, mut stream: TcpStream) -> std::io::Result<(ForwardedLaunch, TcpStream)> {
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    let mut line = String::new();
+    // Bounded so a stray client can't make it buffer without end
+    BufReader::new(&stream).take(MAX_MESSAGE).read_line(&mut line)?;
+    let message: Message = serde_json::from_str(&line)?;
+    if !same_token(&message.token, &lock.client) {
+        return Err(std::io::Error::new(ErrorKind::PermissionDenied, "wrong token"));
+    }
+    stream.write_all(format!("{}\n", lock.server).as_bytes())?;
+    Ok((message.launch, stream))
+}
+
+fn handle(app: &AppHandle, launch: ForwardedLaunch, mut stream: TcpStream) {
+    let args = cli::parse(&launch.args);
+    let link = args.target.as_deref().is_some_and(deeplink::is_link);
+    // Links only bring up the popup
+    if !args.minimized && !link {
+        main_window::show(app);
+    }
+    events::broadcast(app, AppEvent::SecondInstance(launch.clone()));
+    cli::apply(app, &args, false);
+
+    if let Some(text) = args.translate {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = cli::translate(&app, &text).await;
+            if launch.reply {
+                if let Ok(line) = serde_json::to_string(&result) {
+                    let _ = stream.write_all(format!("{}\n", line).as_bytes());
+                }
+            } else if let Err(e) = result {
+                tracing::error!("Failed to translate forwarded text: {}", e);
+            }
+        });
+        return;
+    }
+    open_target(app, &args, launch.cwd.as_deref());
+}
+
+// A bare argument names a file to open, or is itself the text to translate
+fn open_target(app: &AppHandle, args: &CliArgs, cwd: Option<&str>) {
+    let Some(target) = args.target.clone() else {
+        return;
+    };
+    if deeplink::is_link(&target) {
+        deeplink::open(app, &target);
+        return;
+    }
+    let path = match cwd {
+        Some(cwd) => Path::new(cwd).join(&target),
+        None => PathBuf::from(&target),
+    };
+    if path.is_file() {
+        share::open(app, path);
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let translated =
+            popup::show_translation(&app, &target, PopupAnchor::Cursor, HistoryOrigin::Manual, None, None).await;
+        if let Err(e) = translated {
+            tracing::error!("Failed to translate forwarded text: {}", e);
+        }
+    });
+}
+
+pub fn listen(app: &AppHandle, lock: InstanceLock) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in lock.listener.incoming().flatten() {
+            match receive(&lock, stream) {
+                Ok((launch, stream)) => handle(&app, launch, stream),
+                Err(e) => tracing::warn!("Ignoring single-instance message: {}", e),
+            }
+        }
+    });
+}
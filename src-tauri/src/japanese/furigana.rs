@@ -0,0 +1,110 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::kana::{is_kanji, to_hiragana};
+use super::tokenizer::{self, Token};
+use crate::error::AppError;
+
+// One run of the input; concatenating every `text` gives the input back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RubySegment {
+    pub text: String,
+    // Hiragana to show above `text`; None for kana, punctuation and words
+    // the analyzer doesn't know
+    pub reading: Option<String>,
+}
+
+fn push(segments: &mut Vec<RubySegment>, text: &str, reading: Option<String>) {
+    if text.is_empty() {
+        return;
+    }
+    // Unannotated runs are merged so the frontend gets fewer spans
+    if reading.is_none() {
+        if let Some(last) = segments.last_mut().filter(|last| last.reading.is_none()) {
+            last.text.push_str(text);
+            return;
+        }
+    }
+    segments.push(RubySegment {
+        text: text.to_string(),
+        reading,
+    });
+}
+
+// Splits a word into kanji and kana runs, e.g. 取り扱い → 取, り, 扱, い
+fn runs(surface: &str) -> Vec<(bool, String)> {
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for c in surface.chars() {
+        let kanji = is_kanji(c);
+        match runs.last_mut() {
+            Some((last_kanji, run)) if *last_kanji == kanji => run.push(c),
+            _ => runs.push((kanji, c.to_string())),
+        }
+    }
+    runs
+}
+
+// Gives each kanji run its share of the reading, matching the kana runs
+// between them against the reading (okurigana); None when they don't line up
+fn align(runs: &[(bool, String)], reading: &[char]) -> Option<Vec<String>> {
+    let Some(((kanji, run), rest)) = runs.split_first() else {
+        return reading.is_empty().then(Vec::new);
+    };
+    if !kanji {
+        let kana: Vec<char> = to_hiragana(run).chars().collect();
+        return reading.strip_prefix(kana.as_slice()).and_then(|reading| align(rest, reading));
+    }
+    if rest.is_empty() {
+        return (!reading.is_empty()).then(|| vec![reading.iter().collect()]);
+    }
+    (1..reading.len()).find_map(|length| {
+        let mut readings = align(rest, &reading[length..])?;
+        readings.insert(0, reading[..length].iter().collect());
+        Some(readings)
+    })
+}
+
+fn annotate(segments: &mut Vec<RubySegment>, token: &Token) {
+    let reading = token.reading.as_deref().map(to_hiragana);
+    let Some(reading) = reading.filter(|_| token.surface.chars().any(is_kanji)) else {
+        push(segments, &token.surface, None);
+        return;
+    };
+    let runs = runs(&token.surface);
+    let chars: Vec<char> = reading.chars().collect();
+    match align(&runs, &chars) {
+        Some(readings) => {
+            let mut readings = readings.into_iter();
+            for (kanji, run) in &runs {
+                push(segments, run, if *kanji { readings.next() } else { None });
+            }
+        }
+        // Kana in the word that the reading spells differently; the whole
+        // word gets the reading
+        None => push(segments, &token.surface, Some(reading)),
+    }
+}
+
+pub fn furigana(app: &AppHandle, text: &str) -> Result<Vec<RubySegment>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = Vec::new();
+    let mut cursor = 0;
+    for token in tokenizer::tokenize(app, text)? {
+        // Whitespace the tokenizer skipped
+        if token.start > cursor {
+            push(&mut segments, &chars[cursor..token.start].iter().collect::<String>(), None);
+        }
+        annotate(&mut segments, &token);
+        cursor = token.end;
+    }
+    push(&mut segments, &chars[cursor.min(chars.len())..].iter().collect::<String>(), None);
+    Ok(segments)
+}
+
+#[tauri::command]
+pub async fn generate_furigana(app: AppHandle, text: String) -> Result<Vec<RubySegment>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || furigana(&app, &text))
+        .await
+        .map_err(|e| format!("Furigana generation failed: {}", e))??)
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+// Katakana that have a hiragana counterpart sit exactly this far above it
+const KATAKANA_OFFSET: u32 = 0x60;
+// Half-width katakana and punctuation, U+FF61 to U+FF9F, in full width
+const HALF_WIDTH: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+// Voiced kana are the code point after their plain kana; semi-voiced the one after that
+const VOICEABLE: &str = "かきくけこさしすせそたちつてとはひふへほカキクケコサシスセソタチツテトハヒフヘホ";
+const SEMI_VOICEABLE: &str = "はひふへほハヒフヘホ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KanaScript {
+    Hiragana,
+    Katakana,
+}
+
+pub fn is_katakana(c: char) -> bool {
+    ('\u{30A1}'..='\u{30F6}').contains(&c)
+}
+
+pub fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{3096}').contains(&c)
+}
+
+// Either script, plus the long-vowel mark
+pub fn is_kana(c: char) -> bool {
+    is_hiragana(c) || is_katakana(c) || c == 'ー'
+}
+
+// Includes 々 and the small ヶ/ヵ of counters (一ヶ月), which are read like kanji
+pub fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FFFF}'
+        | '々' | '〆' | 'ヶ' | 'ヵ')
+}
+
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ヵ' | 'ヶ' => c,
+            c if is_katakana(c) || c == 'ヽ' || c == 'ヾ' => char::from_u32(c as u32 - KATAKANA_OFFSET).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            c if is_hiragana(c) || c == 'ゝ' || c == 'ゞ' => char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+pub fn voiced(c: char) -> Option<char> {
+    match c {
+        'う' => Some('ゔ'),
+        'ウ' => Some('ヴ'),
+        c if VOICEABLE.contains(c) => char::from_u32(c as u32 + 1),
+        _ => None,
+    }
+}
+
+pub fn semi_voiced(c: char) -> Option<char> {
+    SEMI_VOICEABLE
+        .contains(c)
+        .then(|| char::from_u32(c as u32 + 2))
+        .flatten()
+}
+
+fn unvoiced(c: char) -> char {
+    ['\u{1}', '\u{2}']
+        .iter()
+        .filter_map(|step| char::from_u32(c as u32 - *step as u32))
+        .find(|base| voiced(*base) == Some(c) || semi_voiced(*base) == Some(c))
+        .unwrap_or(c)
+}
+
+// Full-width ASCII and the ideographic space to ASCII, half-width katakana
+// to full width with their sound marks folded in (ｶﾞ to ガ)
+pub fn normalize_width(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{FF01}'..='\u{FF5E}' => normalized.extend(char::from_u32(c as u32 - 0xFEE0)),
+            '\u{3000}' => normalized.push(' '),
+            '\u{FF61}'..='\u{FF9F}' => {
+                let full = HALF_WIDTH.chars().nth(c as usize - 0xFF61).unwrap_or(c);
+                let previous = normalized.chars().last();
+                let combined = match full {
+                    '゛' => previous.and_then(voiced),
+                    '゜' => previous.and_then(semi_voiced),
+                    _ => None,
+                };
+                match combined {
+                    Some(combined) => {
+                        normalized.pop();
+                        normalized.push(combined);
+                    }
+                    None => normalized.push(full),
+                }
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+// Writes out what 々, ゝ/ゞ and ヽ/ヾ repeat: 時々 to 時時, いすゞ to いすず
+pub fn expand_iteration_marks(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        let previous = expanded.chars().last();
+        let repeated = match (c, previous) {
+            ('々', Some(previous)) if is_kanji(previous) => Some(previous),
+            ('ゝ', Some(previous)) if is_hiragana(previous) => Some(unvoiced(previous)),
+            ('ヽ', Some(previous)) if is_katakana(previous) => Some(unvoiced(previous)),
+            ('ゞ', Some(previous)) if is_hiragana(previous) => voiced(unvoiced(previous)),
+            ('ヾ', Some(previous)) if is_katakana(previous) => voiced(unvoiced(previous)),
+            _ => None,
+        };
+        expanded.push(repeated.unwrap_or(c));
+    }
+    expanded
+}
+
+#[tauri::command]
+pub async fn convert_kana(text: String, script: KanaScript) -> Result<String, AppError> {
+    Ok(match script {
+        KanaScript::Hiragana => to_hiragana(&text),
+        KanaScript::Katakana => to_katakana(&text),
+    })
+}
+
+#[tauri::command]
+pub async fn normalize_japanese_width(text: String) -> Result<String, AppError> {
+    Ok(normalize_width(&text))
+}
+
+#[tauri::command]
+pub async fn expand_japanese_iteration_marks(text: String) -> Result<String, AppError> {
+    Ok(expand_iteration_marks(&text))
+}
@@ -0,0 +1,5 @@
+pub mod furigana;
+pub mod kana;
+pub mod numbers;
+pub mod romaji;
+pub mod tokenizer;
@@ -0,0 +1,529 @@
+use serde::Serialize;
+
+use super::kana::{semi_voiced, voiced};
+use super::romaji::{kana_to_romaji, RomanizationSystem};
+use crate::error::AppError;
+
+const DIGITS: [&str; 10] = ["ぜろ", "いち", "に", "さん", "よん", "ご", "ろく", "なな", "はち", "きゅう"];
+// Units above 千, largest first, with how the counter-like sound change
+// applies to them (いっちょう)
+const MYRIADS: [(u64, &str, Sound); 3] = [
+    (1_000_000_000_000, "ちょう", Sound::T),
+    (100_000_000, "おく", Sound::Plain),
+    (10_000, "まん", Sound::Plain),
+];
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+// How a counter's first sound changes after the number, by its consonant:
+// いっこ, いっさつ, いっとう, いっぽん and さんぼん, いっぷん and さんぷん
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sound {
+    Plain,
+    K,
+    S,
+    T,
+    H,
+    // Like H, but p after every ん too
+    P,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum English {
+    Count(&'static str, &'static str),
+    Ordinal(&'static str),
+    Clock,
+    // A span of years, or a year by the calendar (2024年)
+    Year,
+    Month,
+    DayOfMonth,
+}
+
+struct Counter {
+    written: &'static [&'static str],
+    reading: &'static str,
+    sound: Sound,
+    // 階 and 軒 voice after さん (さんがい) though most k-counters don't
+    voiced_after_san: bool,
+    // Replacements for a final ones digit, e.g. よ in よじ
+    ones: &'static [(u64, &'static str)],
+    // Whole readings that follow no rule, e.g. ふつか
+    irregular: &'static [(u64, &'static str)],
+    alternatives: &'static [(u64, &'static str)],
+    // Numbers the counter is used with, e.g. 1 to 12 for months
+    range: (u64, u64),
+    english: English,
+}
+
+const fn counter(written: &'static [&'static str], reading: &'static str, sound: Sound, english: English) -> Counter {
+    Counter {
+        written,
+        reading,
+        sound,
+        voiced_after_san: false,
+        ones: &[],
+        irregular: &[],
+        alternatives: &[],
+        range: (0, u64::MAX),
+        english,
+    }
+}
+
+const DAYS: [(u64, &str); 13] = [
+    (1, "ついたち"), (2, "ふつか"), (3, "みっか"), (4, "よっか"), (5, "いつか"), (6, "むいか"), (7, "なのか"),
+    (8, "ようか"), (9, "ここのか"), (10, "とおか"), (14, "じゅうよっか"), (20, "はつか"), (24, "にじゅうよっか"),
+];
+
+// Longer spellings first, so 時間 wins over 時
+const COUNTERS: [Counter; 30] = [
+    counter(&["時間"], "じかん", Sound::Plain, English::Count("hour", "hours")),
+    counter(&["週間"], "しゅうかん", Sound::S, English::Count("week", "weeks")),
+    counter(&["ヶ月", "か月", "カ月", "ヵ月", "ケ月"], "かげつ", Sound::K, English::Count("month", "months")),
+    counter(&["メートル"], "めーとる", Sound::Plain, English::Count("metre", "metres")),
+    counter(&["ドル"], "どる", Sound::Plain, English::Count("dollar", "dollars")),
+    counter(&["本"], "ほん", Sound::H, English::Count("long object", "long objects")),
+    counter(&["枚"], "まい", Sound::Plain, English::Count("flat object", "flat objects")),
+    counter(&["匹"], "ひき", Sound::H, English::Count("small animal", "small animals")),
+    counter(&["頭"], "とう", Sound::T, English::Count("large animal", "large animals")),
+    counter(&["羽"], "わ", Sound::Plain, English::Count("bird", "birds")),
+    counter(&["個"], "こ", Sound::K, English::Count("item", "items")),
+    counter(&["台"], "だい", Sound::Plain, English::Count("machine", "machines")),
+    counter(&["冊"], "さつ", Sound::S, English::Count("book", "books")),
+    counter(&["杯"], "はい", Sound::H, English::Count("cupful", "cupfuls")),
+    counter(&["回"], "かい", Sound::K, English::Count("time", "times")),
+    Counter {
+        voiced_after_san: true,
+        ..counter(&["階"], "かい", Sound::K, English::Ordinal("floor"))
+    },
+    Counter {
+        voiced_after_san: true,
+        ..counter(&["軒"], "けん", Sound::K, English::Count("house", "houses"))
+    },
+    Counter {
+        voiced_after_san: true,
+        ..counter(&["足"], "そく", Sound::S, English::Count("pair", "pairs"))
+    },
+    counter(&["件"], "けん", Sound::K, English::Count("case", "cases")),
+    counter(&["点"], "てん", Sound::T, English::Count("point", "points")),
+    Counter {
+        ones: &[(4, "よ")],
+        irregular: &[(1, "ひとり"), (2, "ふたり")],
+        alternatives: &[(7, "しちにん")],
+        ..counter(&["人"], "にん", Sound::Plain, English::Count("person", "people"))
+    },
+    Counter {
+        irregular: &[(20, "はたち")],
+        ..counter(&["歳", "才"], "さい", Sound::S, English::Count("year old", "years old"))
+    },
+    Counter {
+        ones: &[(4, "よ")],
+        ..counter(&["円"], "えん", Sound::Plain, English::Count("yen", "yen"))
+    },
+    counter(&["分"], "ふん", Sound::P, English::Count("minute", "minutes")),
+    counter(&["秒"], "びょう", Sound::Plain, English::Count("second", "seconds")),
+    Counter {
+        ones: &[(4, "よ"), (7, "しち"), (9, "く")],
+        range: (0, 24),
+        ..counter(&["時"], "じ", Sound::Plain, English::Clock)
+    },
+    Counter {
+        ones: &[(7, "しち"), (9, "く")],
+        irregular: &DAYS,
+        alternatives: &[(1, "いちにち")],
+        range: (1, 31),
+        ..counter(&["日"], "にち", Sound::Plain, English::DayOfMonth)
+    },
+    Counter {
+        ones: &[(4, "し"), (7, "しち"), (9, "く")],
+        range: (1, 12),
+        ..counter(&["月"], "がつ", Sound::Plain, English::Month)
+    },
+    Counter {
+        ones: &[(4, "よ")],
+        ..counter(&["年"], "ねん", Sound::Plain, English::Year)
+    },
+    // The native counter, only used up to ten
+    Counter {
+        irregular: &[
+            (1, "ひとつ"), (2, "ふたつ"), (3, "みっつ"), (4, "よっつ"), (5, "いつつ"),
+            (6, "むっつ"), (7, "ななつ"), (8, "やっつ"), (9, "ここのつ"), (10, "とお"),
+        ],
+        range: (1, 10),
+        ..counter(&["つ"], "つ", Sound::Plain, English::Count("thing", "things"))
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberReading {
+    pub surface: String,
+    // Character offsets into the text
+    pub start: usize,
+    pub end: usize,
+    pub value: f64,
+    // As written, e.g. 本
+    pub counter: Option<String>,
+    // In hiragana, with the counter's sound changes (さんぼん)
+    pub reading: String,
+    // Another common reading, e.g. いちにち beside ついたち
+    pub alternative: Option<String>,
+    pub romaji: String,
+    pub english: String,
+}
+
+struct Number {
+    integer: u64,
+    // Digits after the decimal point, as written
+    fraction: Option<String>,
+    // Kanji numerals are only taken with a counter; 一 alone is usually part
+    // of a word (一緒)
+    kanji: bool,
+}
+
+fn digit(c: char) -> Option<u64> {
+    match c {
+        '0'..='9' => Some(c as u64 - '0' as u64),
+        '０'..='９' => Some(c as u64 - '０' as u64),
+        _ => None,
+    }
+}
+
+fn kanji_digit(c: char) -> Option<u64> {
+    "〇一二三四五六七八九".chars().position(|d| d == c).map(|d| d as u64).or((c == '零').then_some(0))
+}
+
+fn small_unit(c: char) -> Option<u64> {
+    match c {
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1000),
+        _ => None,
+    }
+}
+
+fn myriad(c: char) -> Option<u64> {
+    match c {
+        '万' => Some(10_000),
+        '億' => Some(100_000_000),
+        '兆' => Some(1_000_000_000_000),
+        _ => None,
+    }
+}
+
+// A number written with digits, kanji or both (2万, 二千二十四, 二〇二四),
+// and where it ends
+fn parse_number(chars: &[char], start: usize) -> Option<(Number, usize)> {
+    let mut total: u64 = 0;
+    let mut section: u64 = 0;
+    let mut current: Option<u64> = None;
+    let mut fraction: Option<String> = None;
+    let mut kanji = false;
+    let mut i = start;
+    while i < chars.len() {
+        let c = chars[i];
+        if digit(c).is_some() {
+            if current.is_some() {
+                break;
+            }
+            let mut value: u64 = 0;
+            while i < chars.len() {
+                if let Some(d) = digit(chars[i]) {
+                    value = value.checked_mul(10)?.checked_add(d)?;
+                    i += 1;
+                } else if matches!(chars[i], ',' | '，')
+                    && chars.get(i + 1..i + 4).is_some_and(|next| next.iter().all(|c| digit(*c).is_some()))
+                {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            if matches!(chars.get(i), Some('.' | '．')) && chars.get(i + 1).and_then(|c| digit(*c)).is_some() {
+                i += 1;
+                let mut digits = String::new();
+                while let Some(d) = chars.get(i).and_then(|c| digit(*c)) {
+                    digits.push_str(&d.to_string());
+                    i += 1;
+                }
+                fraction = Some(digits);
+            }
+            current = Some(value);
+            continue;
+        }
+        if let Some(d) = kanji_digit(c) {
+            // Digit by digit, as in years (二〇二四)
+            current = Some(current.unwrap_or(0).checked_mul(10)?.checked_add(d)?);
+            kanji = true;
+        } else if let Some(unit) = small_unit(c) {
+            section = section.checked_add(current.take().unwrap_or(1).checked_mul(unit)?)?;
+            fraction = None;
+            kanji = true;
+        } else if let Some(unit) = myriad(c) {
+            let mut value = section.checked_add(current.take().unwrap_or(0))?;
+            if value == 0 && fraction.is_none() {
+                break;
+            }
+            value = value.checked_mul(unit)?;
+            // 1.5万 is 15000
+            if let Some(digits) = fraction.take() {
+                let scale = 10u64.checked_pow(digits.len() as u32)?;
+                value = value.checked_add(digits.parse::<u64>().ok()?.checked_mul(unit)? / scale)?;
+            }
+            total = total.checked_add(value)?;
+            section = 0;
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    if i == start {
+        return None;
+    }
+    let integer = total.checked_add(section)?.checked_add(current.unwrap_or(0))?;
+    Some((Number { integer, fraction, kanji }, i))
+}
+
+// Counter sounds after the number, e.g. いち + ほん = いっぽん
+fn join(number: &str, suffix: &str, sound: Sound, voiced_after_san: bool) -> String {
+    let mut rest = suffix.chars();
+    let Some(first) = rest.next() else {
+        return number.to_string();
+    };
+    let rest = rest.as_str();
+    let geminating: &[&str] = match sound {
+        // ゃく covers びゃく and ぴゃく too (さんびゃっぽん)
+        Sound::K | Sound::H | Sound::P => &["いち", "ろく", "はち", "じゅう", "ゃく"],
+        Sound::S | Sound::T => &["いち", "はち", "じゅう"],
+        Sound::Plain => &[],
+    };
+    if geminating.iter().any(|ending| number.ends_with(ending)) {
+        let stem: String = number.chars().take(number.chars().count() - 1).collect();
+        let first = match sound {
+            Sound::H | Sound::P => semi_voiced(first).unwrap_or(first),
+            _ => first,
+        };
+        return format!("{}っ{}{}", stem, first, rest);
+    }
+    let first = if number.ends_with('ん') {
+        match sound {
+            Sound::H if !number.ends_with("よん") => voiced(first).unwrap_or(first),
+            Sound::P => semi_voiced(first).unwrap_or(first),
+            _ if voiced_after_san && number.ends_with("さん") => voiced(first).unwrap_or(first),
+            _ => first,
+        }
+    } else {
+        first
+    };
+    format!("{}{}{}", number, first, rest)
+}
+
+// 0 to 9999; `before_myriad` is for the いっせん of 1000万
+fn group_reading(value: u64, before_myriad: bool) -> String {
+    let digit = |d: u64| DIGITS[d as usize];
+    let thousands = match value / 1000 {
+        0 => String::new(),
+        1 if before_myriad => "いっせん".to_string(),
+        1 => "せん".to_string(),
+        3 => "さんぜん".to_string(),
+        8 => "はっせん".to_string(),
+        d => format!("{}せん", digit(d)),
+    };
+    let hundreds = match value / 100 % 10 {
+        0 => String::new(),
+        1 => "ひゃく".to_string(),
+        3 => "さんびゃく".to_string(),
+        6 => "ろっぴゃく".to_string(),
+        8 => "はっぴゃく".to_string(),
+        d => format!("{}ひゃく", digit(d)),
+    };
+    let tens = match value / 10 % 10 {
+        0 => String::new(),
+        1 => "じゅう".to_string(),
+        d => format!("{}じゅう", digit(d)),
+    };
+    let ones = match value % 10 {
+        0 => "",
+        d => digit(d),
+    };
+    format!("{}{}{}{}", thousands, hundreds, tens, ones)
+}
+
+pub fn integer_reading(value: u64) -> String {
+    if value == 0 {
+        return DIGITS[0].to_string();
+    }
+    let mut reading = String::new();
+    let mut rest = value;
+    for (unit, name, sound) in MYRIADS {
+        let group = rest / unit;
+        if group > 0 {
+            // Past 兆 there's no larger unit, so the group can exceed 9999
+            let group_text = if group > 9999 { integer_reading(group) } else { group_reading(group, true) };
+            reading.push_str(&join(&group_text, name, sound, false));
+        }
+        rest %= unit;
+    }
+    reading.push_str(&group_reading(rest, false));
+    reading
+}
+
+fn number_reading(number: &Number) -> String {
+    match &number.fraction {
+        Some(digits) => {
+            let integer = if number.integer == 0 {
+                "れい".to_string()
+            } else {
+                integer_reading(number.integer)
+            };
+            let digits: String = digits
+                .chars()
+                .filter_map(|c| c.to_digit(10))
+                .map(|d| DIGITS[d as usize])
+                .collect();
+            join(&integer, &format!("てん{}", digits), Sound::T, false)
+        }
+        None => integer_reading(number.integer),
+    }
+}
+
+fn with_counter(number: &Number, counter: &Counter) -> String {
+    if number.fraction.is_none() {
+        if let Some((_, reading)) = counter.irregular.iter().find(|(n, _)| *n == number.integer) {
+            return reading.to_string();
+        }
+    }
+    let mut reading = number_reading(number);
+    if number.fraction.is_none() {
+        let ones = number.integer % 10;
+        if let Some((_, replacement)) = counter.ones.iter().find(|(n, _)| *n == ones) {
+            if let Some(stem) = reading.strip_suffix(DIGITS[ones as usize]) {
+                reading = format!("{}{}", stem, replacement);
+            }
+        }
+    }
+    join(&reading, counter.reading, counter.sound, counter.voiced_after_san)
+}
+
+fn ordinal(value: u64) -> String {
+    let suffix = match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", value, suffix)
+}
+
+fn format_number(number: &Number) -> String {
+    let digits = number.integer.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    match &number.fraction {
+        Some(fraction) => format!("{}.{}", grouped, fraction),
+        None => grouped,
+    }
+}
+
+fn english(number: &Number, counter: Option<&Counter>) -> String {
+    let formatted = format_number(number);
+    let Some(counter) = counter else {
+        return formatted;
+    };
+    let one = number.integer == 1 && number.fraction.is_none();
+    match counter.english {
+        English::Count(singular, plural) => format!("{} {}", formatted, if one { singular } else { plural }),
+        English::Ordinal(noun) => format!("{} {}", ordinal(number.integer), noun),
+        English::Clock if number.integer > 12 => format!("{}:00", number.integer),
+        English::Clock => format!("{} o'clock", formatted),
+        English::Year if number.integer >= 1000 => format!("{} (year)", number.integer),
+        English::Year => format!("{} {}", formatted, if one { "year" } else { "years" }),
+        English::Month => MONTHS[(number.integer as usize).clamp(1, 12) - 1].to_string(),
+        English::DayOfMonth => format!("the {} (day of the month)", ordinal(number.integer)),
+    }
+}
+
+fn find_counter(chars: &[char], at: usize, number: &Number) -> Option<(&'static Counter, &'static str)> {
+    COUNTERS
+        .iter()
+        .filter(|counter| {
+            let usable = number.fraction.is_none() || matches!(counter.english, English::Count(..));
+            usable && (counter.range.0..=counter.range.1).contains(&number.integer)
+        })
+        .flat_map(|counter| counter.written.iter().map(move |written| (counter, *written)))
+        .find(|(_, written)| {
+            let length = written.chars().count();
+            chars.get(at..at + length).is_some_and(|next| next.iter().copied().eq(written.chars()))
+        })
+}
+
+fn starts_number(c: char) -> bool {
+    digit(c).is_some() || kanji_digit(c).is_some() || small_unit(c).is_some()
+}
+
+pub fn read_numbers(text: &str) -> Vec<NumberReading> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut readings = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        // Only at the start of a number, not inside one
+        let inside = i > 0 && (starts_number(chars[i - 1]) || myriad(chars[i - 1]).is_some());
+        let parsed = if inside || !starts_number(chars[i]) {
+            None
+        } else {
+            parse_number(&chars, i)
+        };
+        let Some((number, end)) = parsed else {
+            i += 1;
+            continue;
+        };
+        let counter = find_counter(&chars, end, &number);
+        if number.kanji && counter.is_none() {
+            i = end;
+            continue;
+        }
+        let end = end + counter.map_or(0, |(_, written)| written.chars().count());
+        let reading = match counter {
+            Some((counter, _)) => with_counter(&number, counter),
+            None => number_reading(&number),
+        };
+        let alternative = counter.and_then(|(counter, _)| {
+            counter
+                .alternatives
+                .iter()
+                .find(|(n, _)| *n == number.integer && number.fraction.is_none())
+                .map(|(_, reading)| reading.to_string())
+        });
+        let value = match &number.fraction {
+            Some(digits) => format!("{}.{}", number.integer, digits).parse().unwrap_or(number.integer as f64),
+            None => number.integer as f64,
+        };
+        readings.push(NumberReading {
+            surface: chars[i..end].iter().collect(),
+            start: i,
+            end,
+            value,
+            counter: counter.map(|(_, written)| written.to_string()),
+            romaji: kana_to_romaji(&reading, RomanizationSystem::Hepburn, true),
+            english: english(&number, counter.map(|(counter, _)| counter)),
+            alternative,
+            reading,
+        });
+        i = end;
+    }
+    readings
+}
+
+// Numbers in `text` with the counter after them, if any: 三本 is さんぼん,
+// "3 long objects"
+#[tauri::command]
+pub async fn read_japanese_numbers(text: String) -> Result<Vec<NumberReading>, AppError> {
+    Ok(read_numbers(&text))
+}
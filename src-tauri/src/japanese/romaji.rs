@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::kana::{is_katakana, to_hiragana};
+use super::tokenizer::{self, Token};
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RomanizationSystem {
+    // Modified Hepburn: shi, chi, tsu, long vowels with macrons (Tōkyō)
+    #[default]
+    Hepburn,
+    // Kunrei-shiki: si, ti, tu, long vowels with circumflexes (Tôkyô)
+    Kunrei,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RomanizedWord {
+    pub surface: String,
+    pub romaji: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Romanization {
+    pub text: String,
+    pub words: Vec<RomanizedWord>,
+}
+
+const SYLLABLES: [(&str, &str); 71] = [
+    ("あ", "a"), ("い", "i"), ("う", "u"), ("え", "e"), ("お", "o"),
+    ("か", "ka"), ("き", "ki"), ("く", "ku"), ("け", "ke"), ("こ", "ko"),
+    ("が", "ga"), ("ぎ", "gi"), ("ぐ", "gu"), ("げ", "ge"), ("ご", "go"),
+    ("さ", "sa"), ("し", "shi"), ("す", "su"), ("せ", "se"), ("そ", "so"),
+    ("ざ", "za"), ("じ", "ji"), ("ず", "zu"), ("ぜ", "ze"), ("ぞ", "zo"),
+    ("た", "ta"), ("ち", "chi"), ("つ", "tsu"), ("て", "te"), ("と", "to"),
+    ("だ", "da"), ("ぢ", "ji"), ("づ", "zu"), ("で", "de"), ("ど", "do"),
+    ("な", "na"), ("に", "ni"), ("ぬ", "nu"), ("ね", "ne"), ("の", "no"),
+    ("は", "ha"), ("ひ", "hi"), ("ふ", "fu"), ("へ", "he"), ("ほ", "ho"),
+    ("ば", "ba"), ("び", "bi"), ("ぶ", "bu"), ("べ", "be"), ("ぼ", "bo"),
+    ("ぱ", "pa"), ("ぴ", "pi"), ("ぷ", "pu"), ("ぺ", "pe"), ("ぽ", "po"),
+    ("ま", "ma"), ("み", "mi"), ("む", "mu"), ("め", "me"), ("も", "mo"),
+    ("や", "ya"), ("ゆ", "yu"), ("よ", "yo"),
+    ("ら", "ra"), ("り", "ri"), ("る", "ru"), ("れ", "re"), ("ろ", "ro"),
+    ("わ", "wa"), ("を", "o"), ("ゔ", "vu"),
+];
+
+// Small kana written on their own, and obsolete ones
+const OTHER_SYLLABLES: [(&str, &str); 10] = [
+    ("ぁ", "a"), ("ぃ", "i"), ("ぅ", "u"), ("ぇ", "e"), ("ぉ", "o"),
+    ("ゃ", "ya"), ("ゅ", "yu"), ("ょ", "yo"), ("ゐ", "i"), ("ゑ", "e"),
+];
+
+const DIGRAPHS: [(&str, &str); 57] = [
+    ("きゃ", "kya"), ("きゅ", "kyu"), ("きょ", "kyo"),
+    ("ぎゃ", "gya"), ("ぎゅ", "gyu"), ("ぎょ", "gyo"),
+    ("しゃ", "sha"), ("しゅ", "shu"), ("しょ", "sho"), ("しぇ", "she"),
+    ("じゃ", "ja"), ("じゅ", "ju"), ("じょ", "jo"), ("じぇ", "je"),
+    ("ちゃ", "cha"), ("ちゅ", "chu"), ("ちょ", "cho"), ("ちぇ", "che"),
+    ("ぢゃ", "ja"), ("ぢゅ", "ju"), ("ぢょ", "jo"),
+    ("にゃ", "nya"), ("にゅ", "nyu"), ("にょ", "nyo"),
+    ("ひゃ", "hya"), ("ひゅ", "hyu"), ("ひょ", "hyo"),
+    ("びゃ", "bya"), ("びゅ", "byu"), ("びょ", "byo"),
+    ("ぴゃ", "pya"), ("ぴゅ", "pyu"), ("ぴょ", "pyo"),
+    ("みゃ", "mya"), ("みゅ", "myu"), ("みょ", "myo"),
+    ("りゃ", "rya"), ("りゅ", "ryu"), ("りょ", "ryo"),
+    // Loanword spellings
+    ("ふぁ", "fa"), ("ふぃ", "fi"), ("ふぇ", "fe"), ("ふぉ", "fo"),
+    ("てぃ", "ti"), ("でぃ", "di"), ("とぅ", "tu"), ("どぅ", "du"),
+    ("うぃ", "wi"), ("うぇ", "we"), ("うぉ", "wo"), ("いぇ", "ye"),
+    ("ゔぁ", "va"), ("ゔぃ", "vi"), ("ゔぇ", "ve"), ("ゔぉ", "vo"),
+    ("つぁ", "tsa"), ("つぇ", "tse"),
+];
+
+// Where Kunrei-shiki spells a syllable differently from Hepburn
+const KUNREI: [(&str, &str); 19] = [
+    ("し", "si"), ("じ", "zi"), ("ち", "ti"), ("ぢ", "zi"), ("つ", "tu"),
+    ("づ", "zu"), ("ふ", "hu"),
+    ("しゃ", "sya"), ("しゅ", "syu"), ("しょ", "syo"),
+    ("じゃ", "zya"), ("じゅ", "zyu"), ("じょ", "zyo"),
+    ("ちゃ", "tya"), ("ちゅ", "tyu"), ("ちょ", "tyo"),
+    ("ぢゃ", "zya"), ("ぢゅ", "zyu"), ("ぢょ", "zyo"),
+];
+
+// Vowel pairs written as one long vowel: おう/おお, うう, ああ, ええ.
+// いい and えい stay spelled out, as in modified Hepburn.
+const LONG_VOWELS: [(char, char); 5] = [('o', 'u'), ('o', 'o'), ('u', 'u'), ('a', 'a'), ('e', 'e')];
+
+fn find(table: &[(&'static str, &'static str)], kana: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == kana).map(|(_, romaji)| *romaji)
+}
+
+fn syllable(kana: &str, system: RomanizationSystem) -> Option<&'static str> {
+    let kunrei = match system {
+        RomanizationSystem::Kunrei => find(&KUNREI, kana),
+        RomanizationSystem::Hepburn => None,
+    };
+    kunrei
+        .or_else(|| find(&DIGRAPHS, kana))
+        .or_else(|| find(&SYLLABLES, kana))
+        .or_else(|| find(&OTHER_SYLLABLES, kana))
+}
+
+fn lengthen(vowel: char, system: RomanizationSystem) -> Option<char> {
+    let (macron, circumflex) = match vowel {
+        'a' => ('ā', 'â'),
+        'i' => ('ī', 'î'),
+        'u' => ('ū', 'û'),
+        'e' => ('ē', 'ê'),
+        'o' => ('ō', 'ô'),
+        _ => return None,
+    };
+    Some(match system {
+        RomanizationSystem::Hepburn => macron,
+        RomanizationSystem::Kunrei => circumflex,
+    })
+}
+
+// Romaji for a run of kana; anything else is copied as is. `final_long`
+// allows the last two kana to merge into a long vowel, which verbs like
+// 思う (omou) must not do.
+pub fn kana_to_romaji(kana: &str, system: RomanizationSystem, final_long: bool) -> String {
+    let chars: Vec<char> = to_hiragana(kana).chars().collect();
+    let mut syllables: Vec<String> = Vec::new();
+    let mut doubled = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == 'っ' {
+            doubled = true;
+            i += 1;
+            continue;
+        }
+        if c == 'ー' {
+            if let Some(last) = syllables.last_mut() {
+                if let Some(vowel) = last.pop() {
+                    last.push(lengthen(vowel, system).unwrap_or(vowel));
+                }
+            }
+            i += 1;
+            continue;
+        }
+        if c == 'ん' {
+            // n' keeps ん + vowel apart from な行, e.g. kin'en (禁煙) vs kinen
+            let next = chars.get(i + 1).map(|next| next.to_string());
+            let before_vowel = next
+                .as_deref()
+                .and_then(|next| syllable(next, system))
+                .is_some_and(|romaji| romaji.starts_with(['a', 'i', 'u', 'e', 'o', 'y']));
+            syllables.push(if before_vowel { "n'" } else { "n" }.to_string());
+            doubled = false;
+            i += 1;
+            continue;
+        }
+
+        let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let (romaji, length) = match syllable(&pair, system).filter(|_| pair.chars().count() == 2) {
+            Some(romaji) => (romaji.to_string(), 2),
+            None => match syllable(&c.to_string(), system) {
+                Some(romaji) => (romaji.to_string(), 1),
+                None => (c.to_string(), 1),
+            },
+        };
+        let mut romaji = romaji;
+        if doubled {
+            // Hepburn writes っち as tchi
+            let consonant = if romaji.starts_with("ch") { Some('t') } else { romaji.chars().next() };
+            if let Some(consonant) = consonant.filter(|c| c.is_ascii_alphabetic() && !"aiueo".contains(*c)) {
+                romaji.insert(0, consonant);
+            }
+            doubled = false;
+        }
+        syllables.push(romaji);
+        i += length;
+    }
+
+    let count = syllables.len();
+    let mut text = String::new();
+    for (index, syllable) in syllables.into_iter().enumerate() {
+        let last = text.chars().last();
+        let merge = syllable.len() == 1
+            && (final_long || index + 1 < count)
+            && LONG_VOWELS
+                .iter()
+                .any(|(first, second)| Some(*first) == last && syllable.starts_with(*second));
+        match last.filter(|_| merge).and_then(|vowel| lengthen(vowel, system)) {
+            Some(long) => {
+                text.pop();
+                text.push(long);
+            }
+            None => text.push_str(&syllable),
+        }
+    }
+    text
+}
+
+fn is_kana(text: &str) -> bool {
+    text.chars()
+        .all(|c| is_katakana(c) || ('\u{3041}'..='\u{3096}').contains(&c) || c == 'ー')
+}
+
+fn punctuation(c: char) -> Option<&'static str> {
+    match c {
+        '。' => Some("."),
+        '、' => Some(","),
+        '！' => Some("!"),
+        '？' => Some("?"),
+        '「' | '」' | '『' | '』' => Some("\""),
+        '（' => Some("("),
+        '）' => Some(")"),
+        '・' => Some(" "),
+        '〜' | '～' => Some("~"),
+        _ => None,
+    }
+}
+
+fn romanize_token(token: &Token, system: RomanizationSystem) -> String {
+    // Particles are read by their sound, not their kana
+    if token.pos.first().map(String::as_str) == Some("助詞") {
+        match token.surface.as_str() {
+            "は" => return "wa".to_string(),
+            "へ" => return "e".to_string(),
+            "を" => return "o".to_string(),
+            _ => {}
+        }
+    }
+    if token.surface.chars().all(|c| punctuation(c).is_some()) {
+        return token.surface.chars().filter_map(punctuation).collect();
+    }
+    let reading = match token.reading.as_deref() {
+        Some(reading) => reading,
+        None if is_kana(&token.surface) => &token.surface,
+        None => return token.surface.clone(),
+    };
+    let verb = token.pos.first().map(String::as_str) == Some("動詞");
+    kana_to_romaji(reading, system, !verb)
+}
+
+// Auxiliaries, suffixes and conjunctive particles are written onto the
+// word before them (tabenakatta, not tabe nakatta)
+fn attaches(token: &Token) -> bool {
+    let pos: Vec<&str> = token.pos.iter().map(String::as_str).collect();
+    matches!(pos.as_slice(), ["助動詞", ..] | ["動詞" | "形容詞", "接尾", ..] | ["助詞", "接続助詞", ..])
+        || token
+            .surface
+            .chars()
+            .all(|c| matches!(c, '。' | '、' | '！' | '？' | '」' | '』' | '）'))
+}
+
+fn opens(token: &Token) -> bool {
+    matches!(token.surface.as_str(), "「" | "『" | "（")
+}
+
+pub fn romanize_text(app: &AppHandle, text: &str, system: RomanizationSystem) -> Result<Romanization, String> {
+    let tokens = tokenizer::tokenize(app, text)?;
+    let mut romanized = String::new();
+    let mut words = Vec::new();
+    let mut previous: Option<&Token> = None;
+    for token in &tokens {
+        let romaji = romanize_token(token, system);
+        if !romanized.is_empty() && !attaches(token) && !previous.is_some_and(opens) {
+            romanized.push(' ');
+        }
+        romanized.push_str(&romaji);
+        words.push(RomanizedWord {
+            surface: token.surface.clone(),
+            romaji,
+        });
+        previous = Some(token);
+    }
+    Ok(Romanization { text: romanized, words })
+}
+
+#[tauri::command]
+pub async fn romanize(
+    app: AppHandle,
+    text: String,
+    system: Option<RomanizationSystem>,
+) -> Result<Romanization, AppError> {
+    let system = system.unwrap_or_default();
+    Ok(tauri::async_runtime::spawn_blocking(move || romanize_text(&app, &text, system))
+        .await
+        .map_err(|e| format!("Romanization failed: {}", e))??)
+}
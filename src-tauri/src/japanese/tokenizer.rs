@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use vibrato::dictionary::LexType;
+use vibrato::{Dictionary, Tokenizer};
+
+use crate::dictionary::user;
+use crate::error::AppError;
+use crate::portable;
+
+// A Vibrato build of MeCab's IPADIC (system.dic.zst from the Vibrato
+// releases), in the data dir's models folder or bundled with the app
+const MODEL_DIR: &str = "vibrato";
+const DICTIONARY_FILES: [&str; 2] = ["system.dic.zst", "system.dic"];
+// As MeCab does, so results match what users see elsewhere
+const MAX_UNKNOWN_LENGTH: usize = 24;
+
+// IPADIC feature columns
+const POS_LEVELS: usize = 4;
+const CONJUGATION_TYPE: usize = 4;
+const CONJUGATION_FORM: usize = 5;
+const BASE_FORM: usize = 6;
+const READING: usize = 7;
+const PRONUNCIATION: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    pub surface: String,
+    // e.g. 食べる for 食べ; the surface itself when the dictionary has none
+    pub base_form: String,
+    // Katakana as the dictionary gives it; None for unknown words
+    pub reading: Option<String>,
+    // Differs from the reading for long vowels and particles (ワ for は)
+    pub pronunciation: Option<String>,
+    // IPADIC's part of speech, coarsest first, e.g. ["動詞", "自立"]
+    pub pos: Vec<String>,
+    pub conjugation_type: Option<String>,
+    pub conjugation_form: Option<String>,
+    // Character offsets into the input
+    pub start: usize,
+    pub end: usize,
+    pub known: bool,
+}
+
+// Loaded on first use; the dictionary is tens of megabytes
+#[derive(Default)]
+pub struct TokenizerState(Mutex<Option<Arc<Tokenizer>>>);
+
+fn find_dictionary(app: &AppHandle) -> Result<PathBuf, String> {
+    let mut dirs = vec![portable::data_dir(app)?.join("models").join(MODEL_DIR)];
+    if let Ok(dir) = app.path().resource_dir() {
+        dirs.push(dir.join("models").join(MODEL_DIR));
+    }
+    dirs.iter()
+        .flat_map(|dir| DICTIONARY_FILES.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("No tokenizer dictionary found in {}", dirs[0].display()))
+}
+
+fn read_dictionary(path: &Path) -> Result<Dictionary, String> {
+    let load_error = |e: String| format!("Failed to load {}: {}", path.display(), e);
+    let file = BufReader::new(File::open(path).map_err(|e| load_error(e.to_string()))?);
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|extension| extension == "zst") {
+        Box::new(ruzstd::decoding::StreamingDecoder::new(file).map_err(|e| load_error(e.to_string()))?)
+    } else {
+        Box::new(file)
+    };
+    Dictionary::read(reader).map_err(|e| load_error(e.to_string()))
+}
+
+fn load(app: &AppHandle) -> Result<Tokenizer, String> {
+    let path = find_dictionary(app)?;
+    let mut dictionary = read_dictionary(&path)?;
+    // The user's words go in as a user lexicon. One the analyzer rejects
+    // costs a second read, but tokenizing still works.
+    let lexicon = user::lexicon(app);
+    if !lexicon.is_empty() {
+        dictionary = match dictionary.reset_user_lexicon_from_reader(Some(lexicon.as_bytes())) {
+            Ok(dictionary) => dictionary,
+            Err(e) => {
+                tracing::error!("Failed to load user dictionary into the tokenizer: {}", e);
+                read_dictionary(&path)?
+            }
+        };
+    }
+    Tokenizer::new(dictionary)
+        .ignore_space(true)
+        .map(|tokenizer| tokenizer.max_grouping_len(MAX_UNKNOWN_LENGTH))
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+fn tokenizer(app: &AppHandle) -> Result<Arc<Tokenizer>, String> {
+    let state = app.state::<TokenizerState>();
+    let mut tokenizer = state.0.lock().unwrap();
+    if tokenizer.is_none() {
+        *tokenizer = Some(Arc::new(load(app)?));
+    }
+    Ok(tokenizer.as_ref().unwrap().clone())
+}
+
+// Reloaded on next use, e.g. after the user dictionary changes
+pub fn reset(app: &AppHandle) {
+    *app.state::<TokenizerState>().0.lock().unwrap() = None;
+}
+
+// "*" marks an empty column
+fn column(features: &[&str], index: usize) -> Option<String> {
+    features
+        .get(index)
+        .filter(|value| **value != "*" && !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+pub fn tokenize(app: &AppHandle, text: &str) -> Result<Vec<Token>, String> {
+    let tokenizer = tokenizer(app)?;
+    let mut worker = tokenizer.new_worker();
+    worker.reset_sentence(text);
+    worker.tokenize();
+
+    Ok((0..worker.num_tokens())
+        .map(|i| {
+            let token = worker.token(i);
+            let features: Vec<&str> = token.feature().split(',').collect();
+            let range = token.range_char();
+            let reading = column(&features, READING);
+            Token {
+                surface: token.surface().to_string(),
+                base_form: column(&features, BASE_FORM).unwrap_or_else(|| token.surface().to_string()),
+                pronunciation: column(&features, PRONUNCIATION),
+                pos: (0..POS_LEVELS).filter_map(|level| column(&features, level)).collect(),
+                conjugation_type: column(&features, CONJUGATION_TYPE),
+                conjugation_form: column(&features, CONJUGATION_FORM),
+                start: range.start,
+                end: range.end,
+                known: token.lex_type() != LexType::Unknown,
+                reading,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn tokenize_japanese(app: AppHandle, text: String) -> Result<Vec<Token>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || tokenize(&app, &text))
+        .await
+        .map_err(|e| format!("Tokenization failed: {}", e))??)
+}
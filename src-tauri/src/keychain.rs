@@ -0,0 +1,158 @@
+// Secrets kept in the OS credential store (Keychain, Credential Manager,
+// Secret Service) instead of in our own files
+const SERVICE: &str = "Shunyaku";
+
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    platform::get(SERVICE, account)
+}
+
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    platform::set(SERVICE, account, secret)
+}
+
+pub fn delete(account: &str) -> Result<(), String> {
+    platform::delete(SERVICE, account)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use security_framework::passwords::{delete_generic_password, get_generic_password, set_generic_password};
+
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        match get_generic_password(service, account) {
+            Ok(secret) => String::from_utf8(secret)
+                .map(Some)
+                .map_err(|_| "Keychain item is not valid UTF-8".to_string()),
+            Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(format!("Failed to read from the keychain: {}", e)),
+        }
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        set_generic_password(service, account, secret.as_bytes())
+            .map_err(|e| format!("Failed to write to the keychain: {}", e))
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<(), String> {
+        match delete_generic_password(service, account) {
+            Err(e) if e.code() != ERR_SEC_ITEM_NOT_FOUND => {
+                Err(format!("Failed to remove from the keychain: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::{HSTRING, PWSTR};
+    use windows::Win32::Foundation::ERROR_NOT_FOUND;
+    use windows::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    fn target(service: &str, account: &str) -> HSTRING {
+        HSTRING::from(format!("{}:{}", service, account))
+    }
+
+    fn not_found(e: &windows::core::Error) -> bool {
+        e.code() == ERROR_NOT_FOUND.to_hresult()
+    }
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        match unsafe { CredReadW(&target(service, account), CRED_TYPE_GENERIC, None, &mut credential) } {
+            Ok(()) => {
+                let secret = unsafe {
+                    let credential = &*credential;
+                    std::slice::from_raw_parts(credential.CredentialBlob, credential.CredentialBlobSize as usize).to_vec()
+                };
+                unsafe { CredFree(credential.cast()) };
+                String::from_utf8(secret)
+                    .map(Some)
+                    .map_err(|_| "Stored credential is not valid UTF-8".to_string())
+            }
+            Err(e) if not_found(&e) => Ok(None),
+            Err(e) => Err(format!("Failed to read from Credential Manager: {}", e)),
+        }
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        let target = target(service, account);
+        let mut blob = secret.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: PWSTR(target.as_ptr() as *mut u16),
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            ..Default::default()
+        };
+        unsafe { CredWriteW(&credential, 0) }.map_err(|e| format!("Failed to write to Credential Manager: {}", e))
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<(), String> {
+        match unsafe { CredDeleteW(&target(service, account), CRED_TYPE_GENERIC, None) } {
+            Err(e) if !not_found(&e) => Err(format!("Failed to remove from Credential Manager: {}", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Secret Service through libsecret's secret-tool, which GNOME and KDE both ship
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    fn secret_tool(args: &[&str]) -> Command {
+        let mut command = Command::new("secret-tool");
+        command.args(args);
+        command
+    }
+
+    fn missing(e: std::io::Error) -> String {
+        format!("Failed to run secret-tool (install libsecret-tools): {}", e)
+    }
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        let output = secret_tool(&["lookup", "service", service, "account", account])
+            .output()
+            .map_err(missing)?;
+        // secret-tool exits with 1 and prints nothing when there is no match
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|_| "Stored secret is not valid UTF-8".to_string())
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        let label = format!("{} {}", service, account);
+        // The secret goes through stdin so it never shows up in the process list
+        let mut child = secret_tool(&["store", "--label", &label, "service", service, "account", account])
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(missing)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(secret.as_bytes())
+                .map_err(|e| format!("Failed to pass secret to secret-tool: {}", e))?;
+        }
+        let status = child.wait().map_err(missing)?;
+        if !status.success() {
+            return Err("secret-tool could not store the secret; is a keyring unlocked?".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<(), String> {
+        secret_tool(&["clear", "service", service, "account", account])
+            .status()
+            .map_err(missing)?;
+        Ok(())
+    }
+}
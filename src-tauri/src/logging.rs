@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, State};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::error::AppError;
+use crate::portable;
+
+// Ours at info, dependencies only when something is wrong
+const DEFAULT_LEVEL: &str = "warn";
+const APP_TARGET: &str = "shunyaku";
+const APP_LEVEL: &str = "info";
+// Days of log files kept, one file a day
+const MAX_LOG_FILES: usize = 7;
+// Entries held in memory for the log viewer
+const RECENT_LIMIT: usize = 1000;
+const LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    // Module the event came from, e.g. shunyaku::translation
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevels {
+    pub default: String,
+    // Module path to level
+    pub modules: BTreeMap<String, String>,
+    // Where the rotating files are written; None when the folder couldn't be made
+    pub directory: Option<String>,
+}
+
+type Recent = Arc<Mutex<VecDeque<LogEntry>>>;
+
+pub struct LogState {
+    filter: reload::Handle<EnvFilter, Registry>,
+    levels: Mutex<LogLevels>,
+    recent: Recent,
+    // Flushes the file writer when the app exits
+    _guard: Option<WorkerGuard>,
+}
+
+impl LogState {
+    // The latest `count` entries, oldest first. Gives up rather than waits
+    // when the buffer is locked, since the panic hook may be running on the
+    // thread that holds it.
+    pub fn tail(&self, count: usize) -> Vec<LogEntry> {
+        let Ok(recent) = self.recent.try_lock() else {
+            return Vec::new();
+        };
+        recent.iter().skip(recent.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.push_str(value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+// Keeps the latest events for get_recent_logs
+struct RecentLayer(Recent);
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let entry = LogEntry {
+            timestamp: Utc::now(),
+            level: event.metadata().level().to_string().to_lowercase(),
+            target: event.metadata().target().to_string(),
+            message: message.0.trim_start().to_string(),
+        };
+        let mut recent = self.0.lock().unwrap();
+        if recent.len() >= RECENT_LIMIT {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+}
+
+fn directives(levels: &LogLevels) -> String {
+    let mut directives = vec![levels.default.clone()];
+    directives.extend(levels.modules.iter().map(|(module, level)| format!("{}={}", module, level)));
+    directives.join(",")
+}
+
+fn check_level(level: &str) -> Result<String, AppError> {
+    let level = level.trim().to_lowercase();
+    if !LEVELS.contains(&level.as_str()) {
+        return Err(AppError::InvalidInput(format!("'{}' is not a log level", level)));
+    }
+    Ok(level)
+}
+
+fn is_module_path(module: &str) -> bool {
+    !module.is_empty() && module.chars().all(|c| c.is_ascii_alphanumeric() || "_:".contains(c))
+}
+
+// Installs the global subscriber: daily files under <data dir>/logs, the
+// in-memory buffer, and a filter that set_log_level swaps at runtime.
+// RUST_LOG, when set, replaces the default levels.
+pub fn init(app: &AppHandle) {
+    let mut levels = LogLevels {
+        default: DEFAULT_LEVEL.to_string(),
+        modules: BTreeMap::from([(APP_TARGET.to_string(), APP_LEVEL.to_string())]),
+        directory: None,
+    };
+    let filter = match std::env::var("RUST_LOG").ok().and_then(|env| EnvFilter::try_new(env).ok()) {
+        Some(filter) => filter,
+        None => EnvFilter::new(directives(&levels)),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let appender = portable::data_dir(app).and_then(|dir| {
+        let dir = dir.join("logs");
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(APP_TARGET)
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(&dir)
+            .map_err(|e| format!("Failed to open log folder {}: {}", dir.display(), e))?;
+        Ok((appender, dir))
+    });
+    let (file, guard) = match appender {
+        Ok((appender, dir)) => {
+            levels.directory = Some(dir.to_string_lossy().into_owned());
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(false);
+            (Some(layer), Some(guard))
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            (None, None)
+        }
+    };
+
+    let recent = Recent::default();
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(file)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(RecentLayer(recent.clone()))
+        .try_init();
+    if let Err(e) = installed {
+        eprintln!("Failed to start logging: {}", e);
+    }
+    app.manage(LogState {
+        filter: handle,
+        levels: Mutex::new(levels),
+        recent,
+        _guard: guard,
+    });
+    tracing::info!("Shunyaku {} starting", env!("CARGO_PKG_VERSION"));
+}
+
+// Sets the level of one module, e.g. "shunyaku::translation", or the default
+// for every module without its own when `module` is None. Passing no level
+// for a module drops its override.
+#[tauri::command]
+pub async fn set_log_level(
+    log_state: State<'_, LogState>,
+    module: Option<String>,
+    level: Option<String>,
+) -> Result<LogLevels, AppError> {
+    let mut levels = log_state.levels.lock().unwrap();
+    let mut changed = levels.clone();
+    match (module.map(|module| module.trim().to_string()), level) {
+        (None, Some(level)) => changed.default = check_level(&level)?,
+        (None, None) => return Err(AppError::InvalidInput("Give a level for the default".to_string())),
+        (Some(module), _) if !is_module_path(&module) => {
+            return Err(AppError::InvalidInput(format!("'{}' is not a module path", module)));
+        }
+        (Some(module), Some(level)) => {
+            changed.modules.insert(module, check_level(&level)?);
+        }
+        (Some(module), None) => {
+            changed.modules.remove(&module);
+        }
+    }
+    let filter = EnvFilter::try_new(directives(&changed))
+        .map_err(|e| AppError::InvalidInput(format!("Invalid log filter: {}", e)))?;
+    log_state
+        .filter
+        .reload(filter)
+        .map_err(|e| format!("Failed to change log level: {}", e))?;
+    *levels = changed;
+    tracing::info!("Log levels set to {}", directives(&levels));
+    Ok(levels.clone())
+}
+
+#[tauri::command]
+pub async fn get_log_levels(log_state: State<'_, LogState>) -> Result<LogLevels, AppError> {
+    Ok(log_state.levels.lock().unwrap().clone())
+}
+
+// Oldest first, at most `limit` of the latest, optionally only at `level` or
+// more severe
+#[tauri::command]
+pub async fn get_recent_logs(
+    log_state: State<'_, LogState>,
+    limit: Option<usize>,
+    level: Option<String>,
+) -> Result<Vec<LogEntry>, AppError> {
+    let level = match level {
+        Some(level) => Some(
+            check_level(&level)?
+                .parse::<Level>()
+                .map_err(|_| AppError::InvalidInput(format!("'{}' is not a log level", level)))?,
+        ),
+        None => None,
+    };
+    let recent = log_state.recent.lock().unwrap();
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|entry| {
+            level.map_or(true, |level| entry.level.parse::<Level>().is_ok_and(|entry| entry <= level))
+        })
+        .take(limit.unwrap_or(RECENT_LIMIT))
+        .cloned()
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
@@ -2,76 +2,172 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use tauri::{Manager, WebviewWindowBuilder, LogicalSize, LogicalPosition};
-use tauri::State;
-use std::sync::Mutex;
+use tauri::{DragDropEvent, RunEvent, State, WindowEvent};
+use tauri::webview::PageLoadEvent;
 
-// Store window references for management
-type WindowStore = Mutex<Vec<String>>;
+mod api;
+mod autostart;
+mod backup;
+mod benchmark;
+mod bridge;
+mod capture;
+mod cli;
+mod crash;
+mod cursor;
+mod deeplink;
+mod diagnostics;
+mod dictionary;
+mod dnd;
+mod documents;
+mod error;
+mod events;
+mod history;
+mod hotkeys;
+mod instance;
+mod japanese;
+mod keychain;
+mod logging;
+mod main_window;
+mod metrics;
+mod monitoring;
+mod mpv;
+mod native_host;
+mod notifications;
+mod nudge;
+mod ocr;
+mod onboarding;
+mod overlay;
+mod panel_pool;
+mod permissions;
+mod popup;
+mod portable;
+mod reset;
+mod resources;
+mod selection;
+mod session;
+mod settings;
+mod share;
+mod shutdown;
+mod speech;
+mod stream;
+mod tasks;
+mod theme;
+mod transfer;
+mod tray;
+mod translation;
+mod updater;
+mod webhooks;
+mod window_ready;
+mod window_store;
+
+#[cfg(all(test, feature = "mock-provider"))]
+mod tests;
+
+use error::AppError;
+use events::AppEvent;
+use panel_pool::PanelPool;
+use window_store::WindowStore;
+
+// tauri-plugin-store file holding user preferences
+pub const SETTINGS_STORE: &str = "settings.json";
 
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-async fn create_floating_window(
-    app: tauri::AppHandle,
-    window_store: State<'_, WindowStore>,
-) -> Result<String, String> {
-    let window_id = format!("floating-{}", chrono::Utc::now().timestamp_millis());
+// Shared by the command and the tray menu. Takes a pre-built panel from the
+// pool when there is one, so the window shows without waiting for a webview.
+fn open_floating_window(app: &tauri::AppHandle) -> Result<String, String> {
+    if let Some(window) = panel_pool::take(app) {
+        let defaults = settings::load(app).window_defaults;
+        let _ = window.set_size(LogicalSize::new(defaults.width, defaults.height));
+        let _ = window.set_position(LogicalPosition::new(defaults.x, defaults.y));
+        let _ = window.set_always_on_top(defaults.always_on_top);
+        window.show().map_err(|e| format!("Failed to show window: {}", e))?;
+        let _ = window.set_focus();
+        register_panel(app, &window);
+        panel_pool::fill(app);
+        return Ok(window.label().to_string());
+    }
+    let window_id = panel_pool::new_label(app);
+    open_panel(app, &window_id)?;
+    panel_pool::fill(app);
+    Ok(window_id)
+}
 
-    let window = WebviewWindowBuilder::new(
-        &app,
-        &window_id,
+fn panel_builder<'a>(
+    app: &'a tauri::AppHandle,
+    window_id: &str,
+) -> WebviewWindowBuilder<'a, tauri::Wry, tauri::AppHandle> {
+    let defaults = settings::load(app).window_defaults;
+
+    let builder = WebviewWindowBuilder::new(
+        app,
+        window_id,
         tauri::WebviewUrl::App("index.html".into())
     )
     .title("Floating Panel")
-    .inner_size(LogicalSize::new(400.0, 300.0))
-    .position(LogicalPosition::new(100.0, 100.0))
+    .inner_size(defaults.width, defaults.height)
+    .position(defaults.x, defaults.y)
     .resizable(true)
     .decorations(true)
-    .always_on_top(true)
-    .skip_taskbar(false)
-    .build();
+    .always_on_top(defaults.always_on_top)
+    .skip_taskbar(false);
+    theme::styled(builder, app)
+}
 
-    match window {
-        Ok(win) => {
-            // Store window ID for management
-            window_store.lock().unwrap().push(window_id.clone());
+fn register_panel(app: &tauri::AppHandle, window: &tauri::WebviewWindow) {
+    // Store window ID for management
+    app.state::<WindowStore>().insert(window.label());
 
-            // Send initialization message to the new window
-            let _ = win.emit("window-type", "floating-panel");
+    // Send initialization message to the new window once its page is listening
+    window_ready::send_when_ready(app, window.label(), AppEvent::WindowType("floating-panel"));
+    session::changed(app);
+}
 
-            Ok(window_id)
+// Also used to bring back a panel from the last session under its old label
+fn open_panel(app: &tauri::AppHandle, window_id: &str) -> Result<tauri::WebviewWindow, String> {
+    match panel_builder(app, window_id).build() {
+        Ok(win) => {
+            register_panel(app, &win);
+            Ok(win)
         }
         Err(e) => Err(format!("Failed to create window: {}", e))
     }
 }
 
+#[tauri::command]
+async fn create_floating_window(app: tauri::AppHandle) -> Result<String, AppError> {
+    let window_id = open_floating_window(&app)?;
+    window_ready::wait(&app, &window_id).await?;
+    Ok(window_id)
+}
+
 #[tauri::command]
 async fn close_floating_window(
     app: tauri::AppHandle,
     window_store: State<'_, WindowStore>,
     window_id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    // Removed first so a panel that was already gone doesn't linger
+    let stored = window_store.remove(&window_id);
+    session::unpark(&app, &window_id);
     if let Some(window) = app.get_webview_window(&window_id) {
         window.close().map_err(|e| format!("Failed to close window: {}", e))?;
-
-        // Remove from store
-        let mut store = window_store.lock().unwrap();
-        store.retain(|id| id != &window_id);
-
+        Ok(())
+    } else if stored {
         Ok(())
     } else {
-        Err("Window not found".to_string())
+        Err(AppError::WindowNotFound(window_id))
     }
 }
 
 #[tauri::command]
 async fn list_floating_windows(
     window_store: State<'_, WindowStore>,
-) -> Result<Vec<String>, String> {
-    Ok(window_store.lock().unwrap().clone())
+) -> Result<Vec<String>, AppError> {
+    Ok(window_store.ids())
 }
 
 #[tauri::command]
@@ -80,13 +176,13 @@ async fn update_window_position(
     window_id: String,
     x: f64,
     y: f64,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&window_id) {
+) -> Result<(), AppError> {
+    if let Some(window) = session::window(&app, &window_id) {
         window.set_position(LogicalPosition::new(x, y))
             .map_err(|e| format!("Failed to update position: {}", e))?;
         Ok(())
     } else {
-        Err("Window not found".to_string())
+        Err(AppError::WindowNotFound(window_id))
     }
 }
 
@@ -96,37 +192,399 @@ async fn update_window_size(
     window_id: String,
     width: f64,
     height: f64,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&window_id) {
+) -> Result<(), AppError> {
+    if let Some(window) = session::window(&app, &window_id) {
         window.set_size(LogicalSize::new(width, height))
             .map_err(|e| format!("Failed to update size: {}", e))?;
         Ok(())
     } else {
-        Err("Window not found".to_string())
+        Err(AppError::WindowNotFound(window_id))
     }
 }
 
 fn main() {
+    let args = cli::from_env();
+    let context = tauri::generate_context!();
+    if args.native_host {
+        std::process::exit(native_host::run(context));
+    }
+    if args.is_headless() {
+        std::process::exit(cli::run_headless(&args, context));
+    }
+    // A second launch hands its arguments to the running app and exits
+    let instance_lock = match instance::claim() {
+        instance::Claim::Forwarded => return,
+        instance::Claim::Owned(lock) => Some(lock),
+        instance::Claim::Unavailable => None,
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_clipboard_manager::init())
-        .manage(WindowStore::new(Vec::new()))
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::handle_shortcut)
+                .build(),
+        )
+        .on_page_load(|webview, payload| {
+            if let PageLoadEvent::Started = payload.event() {
+                window_ready::loading(webview.app_handle(), webview.label());
+            }
+        })
+        .manage(WindowStore::default())
+        .manage(window_ready::ReadyState::default())
+        .manage(transfer::TransferState::default())
+        .manage(PanelPool::default())
+        .manage(tasks::TaskManager::default())
+        .manage(resources::ResourceState::default())
+        .manage(session::SessionState::default())
+        .manage(shutdown::ShutdownState::default())
+        .manage(updater::UpdateState::default())
+        .manage(metrics::MetricsState::default())
+        .manage(overlay::OverlayStore::new(Vec::new()))
+        .manage(ocr::OcrCacheState::default())
+        .manage(hotkeys::HotkeyRegistry::default())
+        .manage(hotkeys::recorder::HotkeyCaptureState::default())
+        .manage(popup::PopupStore::default())
+        .manage(cursor::LayoutCacheState::default())
+        .manage(monitoring::MonitoringState::default())
+        .manage(history::HistoryState::default())
+        .manage(history::thumbnails::ThumbnailState::default())
+        .manage(dictionary::DictionaryState::default())
+        .manage(dictionary::yomichan::ImportedState::default())
+        .manage(japanese::tokenizer::TokenizerState::default())
+        .manage(translation::usage::UsageState::default())
+        .manage(translation::queue::TranslationQueue::default())
+        .manage(reset::ResetTokenState::default())
+        .manage(theme::ThemeState::default())
+        .manage(speech::SpeechState::default())
+        .manage(speech::listen::VoiceCaptureState::default())
+        .manage(cli::PendingCapture::default())
+        .manage(api::ApiServerState::default())
+        .manage(bridge::BridgeState::default())
+        .manage(documents::DocumentState::default())
+        .manage(documents::folders::FolderState::default())
+        .manage(webhooks::WebhookState::default())
+        .manage(stream::StreamState::default())
+        .manage(mpv::MpvState::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             create_floating_window,
+            window_ready::window_ready,
+            transfer::begin_transfer,
+            transfer::append_transfer,
+            transfer::commit_transfer,
+            transfer::read_transfer,
+            transfer::cancel_transfer,
             close_floating_window,
             list_floating_windows,
             update_window_position,
-            update_window_size
+            update_window_size,
+            ocr::postprocess_ocr,
+            ocr::recognize_region,
+            ocr::get_ocr_cache_stats,
+            ocr::clear_ocr_cache,
+            ocr::profiles::list_capture_profiles,
+            ocr::profiles::save_capture_profile,
+            ocr::profiles::delete_capture_profile,
+            overlay::show_translation_overlay,
+            overlay::hide_translation_overlay,
+            overlay::get_overlay_labels,
+            permissions::check_permissions,
+            permissions::request_permission,
+            hotkeys::get_hotkeys,
+            hotkeys::set_hotkey,
+            hotkeys::validate_hotkey,
+            hotkeys::mapping::list_hotkey_actions,
+            hotkeys::mapping::reset_hotkeys,
+            hotkeys::mapping::export_hotkeys,
+            hotkeys::mapping::import_hotkeys,
+            hotkeys::double_tap::get_double_tap,
+            hotkeys::double_tap::set_double_tap,
+            hotkeys::mouse::get_mouse_triggers,
+            hotkeys::mouse::set_mouse_triggers,
+            hotkeys::recorder::begin_hotkey_capture,
+            hotkeys::recorder::cancel_hotkey_capture,
+            translation::translate_text,
+            translation::set_api_key,
+            translation::usage::get_usage,
+            translation::presets::list_language_presets,
+            translation::presets::save_language_preset,
+            translation::presets::delete_language_preset,
+            translation::presets::apply_language_preset,
+            translation::presets::cycle_language_preset,
+            popup::get_popup_content,
+            popup::hide_translation_popup,
+            cursor::get_cursor_position,
+            history::get_recent_translations,
+            history::get_history,
+            history::get_history_entry,
+            history::delete_history_entry,
+            history::restore_entry,
+            history::list_trash,
+            history::empty_trash,
+            history::toggle_favorite,
+            history::list_favorites,
+            history::search::search_history,
+            history::query::query_history,
+            history::tags::list_tags,
+            history::tags::create_tag,
+            history::tags::rename_tag,
+            history::tags::delete_tag,
+            history::tags::assign_tag,
+            history::tags::unassign_tag,
+            history::tags::list_history_by_tag,
+            history::export::export_history,
+            history::import::import_history,
+            history::thumbnails::get_history_thumbnail,
+            history::anki::export_to_anki,
+            history::anki::list_anki_decks,
+            history::anki::send_to_anki,
+            history::anki::get_anki_status,
+            history::retention::purge_history,
+            history::review::get_due_reviews,
+            history::review::grade_review,
+            history::stats::get_statistics,
+            dictionary::lookup_word,
+            dictionary::lookup_kanji,
+            dictionary::names::find_names,
+            dictionary::pitch::get_pitch_accent,
+            dictionary::frequency::get_word_frequencies,
+            dictionary::gloss::gloss_sentence,
+            dictionary::examples::find_example_sentences,
+            dictionary::import_dictionary,
+            dictionary::get_dictionary_status,
+            dictionary::yomichan::import_yomichan_dictionary,
+            dictionary::yomichan::list_imported_dictionaries,
+            dictionary::yomichan::reorder_imported_dictionaries,
+            dictionary::yomichan::remove_imported_dictionary,
+            dictionary::user::list_user_words,
+            dictionary::user::save_user_word,
+            dictionary::user::delete_user_word,
+            dictionary::user::import_user_dictionary,
+            dictionary::user::export_user_dictionary,
+            japanese::tokenizer::tokenize_japanese,
+            japanese::furigana::generate_furigana,
+            japanese::romaji::romanize,
+            japanese::kana::convert_kana,
+            japanese::kana::normalize_japanese_width,
+            japanese::kana::expand_japanese_iteration_marks,
+            japanese::numbers::read_japanese_numbers,
+            speech::speak,
+            speech::stop_speaking,
+            speech::voices::list_tts_voices,
+            speech::voices::set_tts_voice,
+            speech::cache::clear_speech_cache,
+            speech::pronunciation::play_pronunciation,
+            speech::listen::start_voice_capture,
+            speech::listen::stop_voice_capture,
+            monitoring::get_monitoring_status,
+            monitoring::set_monitoring_paused,
+            notifications::get_notifications_enabled,
+            notifications::set_notifications_enabled,
+            dnd::get_dnd_schedule,
+            dnd::set_dnd_schedule,
+            dnd::get_dnd_status,
+            tray::clicks::get_tray_click_bindings,
+            tray::clicks::set_tray_click_bindings,
+            tray::dock::get_menubar_only,
+            tray::dock::set_menubar_only,
+            nudge::nudge_floating_window,
+            nudge::get_nudge_settings,
+            nudge::set_nudge_settings,
+            session::restore_session,
+            session::get_panel_entry,
+            settings::get_settings,
+            settings::update_settings,
+            settings::transfer::export_settings,
+            settings::transfer::import_settings,
+            settings::profiles::list_profiles,
+            settings::profiles::switch_profile,
+            settings::profiles::duplicate_profile,
+            settings::profiles::delete_profile,
+            settings::sync::get_sync_folder,
+            settings::sync::set_sync_folder,
+            settings::sync::sync_settings_now,
+            settings::sync::resolve_sync_conflict,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            backup::create_backup,
+            backup::restore_backup,
+            onboarding::get_onboarding_state,
+            onboarding::mark_onboarding_step,
+            onboarding::reset_onboarding,
+            portable::get_portable_status,
+            reset::request_reset_token,
+            reset::reset_app_data,
+            theme::get_system_theme,
+            cli::take_pending_capture,
+            api::get_api_server_status,
+            api::get_api_token,
+            api::regenerate_api_token,
+            bridge::get_extension_bridge_status,
+            bridge::get_extension_bridge_token,
+            bridge::regenerate_extension_bridge_token,
+            documents::translate_document,
+            documents::translate_subtitle,
+            documents::translate_pdf,
+            documents::cancel_document_translation,
+            documents::take_document_result,
+            documents::folders::get_watched_folder_log,
+            webhooks::test_webhook,
+            stream::get_stream_output_status,
+            stream::clear_stream_output,
+            mpv::start_mpv_subtitles,
+            mpv::stop_mpv_subtitles,
+            mpv::get_mpv_status,
+            mpv::get_subtitle_line,
+            native_host::install_native_host,
+            native_host::uninstall_native_host,
+            native_host::get_native_host_status,
+            logging::set_log_level,
+            logging::get_log_levels,
+            logging::get_recent_logs,
+            crash::list_crash_reports,
+            crash::reveal_crash_report,
+            crash::delete_crash_reports,
+            tasks::list_tasks,
+            tasks::pause_task,
+            tasks::resume_task,
+            tasks::cancel_task,
+            resources::get_resource_usage,
+            diagnostics::run_diagnostics,
+            benchmark::run_benchmark,
+            updater::check_for_updates,
+            updater::download_update,
+            metrics::get_metrics,
+            metrics::export_metrics,
+            metrics::clear_metrics
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            logging::init(app.handle());
+            crash::install(app.handle());
+            if let Some(lock) = instance_lock {
+                instance::listen(app.handle(), lock);
+            }
+            if let Err(e) = settings::migrations::run(app.handle()) {
+                tracing::error!("{}", e);
+            }
+
+            #[cfg(feature = "onnx-ocr")]
+            app.manage(ocr::MangaOcrState::default());
+            #[cfg(feature = "voice-input")]
+            app.manage(speech::listen::WhisperModelState::default());
+
+            app.manage(hotkeys::double_tap::DoubleTapState::new(
+                hotkeys::double_tap::load_config(app.handle()),
+            ));
+            app.manage(hotkeys::mouse::MouseTriggerState::new(
+                hotkeys::mouse::load_config(app.handle()),
+            ));
+            hotkeys::register_all(app.handle());
+            translation::usage::start(app.handle());
+            settings::reload::start(app.handle());
+            settings::sync::start(app.handle());
+            history::retention::start(app.handle());
+            tray::create(app.handle())?;
+            session::start(app.handle());
+            api::apply(app.handle());
+            bridge::apply(app.handle());
+            stream::apply(app.handle());
+            documents::folders::start(app.handle());
+            popup::prewarm(app.handle());
+            panel_pool::fill(app.handle());
+            resources::start(app.handle());
+            shutdown::listen(app.handle());
+            updater::start(app.handle());
+
+            main_window::launch(app.handle(), &args);
+            cli::apply(app.handle(), &args, true);
+            deeplink::register(app.handle());
+            // A file passed by "Open with" or a file association
+            match args.target.as_deref() {
+                Some(link) if deeplink::is_link(link) => deeplink::open(app.handle(), link),
+                Some(target) if std::path::Path::new(target).is_file() => {
+                    share::open(app.handle(), target.into())
+                }
+                _ => {}
+            }
+            if let Some(text) = args.translate.clone() {
+                let app = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    match cli::translate(&app, &text).await {
+                        Ok(translated) => println!("{}", translated),
+                        Err(e) => eprintln!("Translation failed: {}", e),
+                    }
+                });
+            }
+
             #[cfg(debug_assertions)]
-            {
-                let window = app.get_webview_window("main").unwrap();
+            if let Some(window) = app.get_webview_window(main_window::MAIN_LABEL) {
                 window.open_devtools();
             }
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::WindowEvent { label, event, .. } = &event {
+                resources::window_event(app, label, event);
+                if let WindowEvent::Destroyed = event {
+                    window_ready::forget(app, label);
+                }
+            }
+            match event {
+                // Closing the last window leaves the tray running; only an explicit
+                // exit (tray Quit) ends the process
+                RunEvent::ExitRequested { api, code: None, .. } => api.prevent_exit(),
+                RunEvent::ExitRequested { api, code: Some(code), .. } => shutdown::exit(app, &api, code),
+                RunEvent::Exit => shutdown::finish(app),
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::ThemeChanged(theme),
+                    ..
+                } => theme::window_theme_changed(app, &label, theme),
+                // Any window's scale changing means a display was rescaled,
+                // added or removed under it
+                RunEvent::WindowEvent {
+                    event: WindowEvent::ScaleFactorChanged { .. },
+                    ..
+                } => cursor::invalidate(app),
+                RunEvent::WindowEvent {
+                    event: WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }),
+                    ..
+                } => documents::dropped(app, &paths),
+                // Panels closed from their title bar leave the store here
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } if label.starts_with("floating-") => {
+                    // Parked panels stay listed until they're reopened or closed
+                    if !session::is_parked(app, &label) {
+                        app.state::<WindowStore>().remove(&label);
+                    }
+                    session::changed(app)
+                }
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Moved(_) | WindowEvent::Resized(_),
+                    ..
+                } if label.starts_with("floating-") => session::changed(app),
+                // macOS hands links and opened files to the running app rather
+                // than launching it with them as arguments
+                #[cfg(target_os = "macos")]
+                RunEvent::Opened { urls } => {
+                    for url in urls.iter() {
+                        if url.scheme() == deeplink::SCHEME {
+                            deeplink::open(app, url.as_str());
+                        } else if let Ok(path) = url.to_file_path() {
+                            share::open(app, path);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+}
@@ -0,0 +1,50 @@
+use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder};
+
+use crate::cli::CliArgs;
+use crate::settings;
+
+pub const MAIN_LABEL: &str = "main";
+
+// The main window isn't created with the app (create is false in
+// tauri.conf.json); it's built the first time something asks for it, so a
+// tray-only launch never loads its webview. Closing it later drops it the
+// same way.
+pub fn get_or_create(app: &AppHandle) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        return Ok(window);
+    }
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|window| window.label == MAIN_LABEL)
+        .ok_or("The main window is missing from the app config")?;
+    WebviewWindowBuilder::from_config(app, config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to create the main window: {}", e))
+}
+
+pub fn show(app: &AppHandle) -> Option<WebviewWindow> {
+    match get_or_create(app) {
+        Ok(window) => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+            Some(window)
+        }
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
+
+// Opens the window at startup unless --minimized or the start hidden setting
+// asks to stay in the tray
+pub fn launch(app: &AppHandle, args: &CliArgs) {
+    if args.minimized || settings::load(app).start_hidden {
+        return;
+    }
+    show(app);
+}
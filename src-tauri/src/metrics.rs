@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::history::HistoryOrigin;
+use crate::hotkeys::HotkeyAction;
+use crate::translation::{ProviderKind, TranslationResult};
+use crate::{portable, settings};
+
+const METRICS_STORE: &str = "metrics.json";
+const METRICS_KEY: &str = "metrics";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProviderMetrics {
+    pub requests: u64,
+    pub failures: u64,
+    pub characters: u64,
+    // Summed over successful requests, for an average
+    pub total_milliseconds: u64,
+}
+
+// Counts only: no text, languages or window contents are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageMetrics {
+    // When counting started, or was last cleared
+    pub since: Option<DateTime<Utc>>,
+    pub hotkeys: HashMap<HotkeyAction, u64>,
+    pub ocr_captures: u64,
+    pub ocr_cache_hits: u64,
+    pub providers: HashMap<ProviderKind, ProviderMetrics>,
+    // Translations recorded to history, by what produced the text
+    pub origins: HashMap<HistoryOrigin, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MetricsExport<'a> {
+    version: u32,
+    app_version: String,
+    os: &'static str,
+    exported_at: DateTime<Utc>,
+    metrics: &'a UsageMetrics,
+}
+
+// Loaded from the store on first use
+pub type MetricsState = Mutex<Option<UsageMetrics>>;
+
+fn stored(app: &AppHandle) -> UsageMetrics {
+    portable::store(app, METRICS_STORE)
+        .ok()
+        .and_then(|store| store.get(METRICS_KEY))
+        .and_then(|metrics| serde_json::from_value(metrics).ok())
+        .unwrap_or_default()
+}
+
+fn with_metrics<T>(app: &AppHandle, f: impl FnOnce(&mut UsageMetrics) -> T) -> T {
+    let state = app.state::<MetricsState>();
+    let mut metrics = state.lock().unwrap();
+    f(metrics.get_or_insert_with(|| stored(app)))
+}
+
+// Counts nothing unless the user turned usage metrics on. The store writes
+// itself out a moment later, so a burst of hotkeys is one write. Nothing
+// here ever leaves the machine except through export_metrics.
+fn count(app: &AppHandle, f: impl FnOnce(&mut UsageMetrics)) {
+    if !settings::load(app).usage_metrics {
+        return;
+    }
+    let value = with_metrics(app, |metrics| {
+        metrics.since.get_or_insert_with(Utc::now);
+        f(metrics);
+        serde_json::to_value(&*metrics)
+    });
+    match (portable::store(app, METRICS_STORE), value) {
+        (Ok(store), Ok(value)) => store.set(METRICS_KEY, value),
+        (Err(e), _) => tracing::warn!("{}", e),
+        (_, Err(e)) => tracing::warn!("Failed to serialize usage metrics: {}", e),
+    }
+}
+
+pub fn hotkey(app: &AppHandle, action: HotkeyAction) {
+    count(app, |metrics| *metrics.hotkeys.entry(action).or_default() += 1);
+}
+
+pub fn ocr(app: &AppHandle, cached: bool) {
+    count(app, |metrics| {
+        metrics.ocr_captures += 1;
+        if cached {
+            metrics.ocr_cache_hits += 1;
+        }
+    });
+}
+
+pub fn provider_call(app: &AppHandle, characters: usize, result: &Result<Vec<TranslationResult>, AppError>) {
+    count(app, |metrics| {
+        let provider = match result {
+            Ok(results) => results.first().map_or(ProviderKind::Deepl, |result| result.provider),
+            Err(_) => settings::load(app).translation_provider,
+        };
+        let provider = metrics.providers.entry(provider).or_default();
+        provider.requests += 1;
+        match result {
+            Ok(results) => {
+                provider.characters += characters as u64;
+                provider.total_milliseconds += results.first().map_or(0, |result| result.processing_time);
+            }
+            Err(_) => provider.failures += 1,
+        }
+    });
+}
+
+pub fn translated(app: &AppHandle, origin: HistoryOrigin) {
+    count(app, |metrics| *metrics.origins.entry(origin).or_default() += 1);
+}
+
+pub fn clear(app: &AppHandle) {
+    with_metrics(app, |metrics| *metrics = UsageMetrics::default());
+    match portable::store(app, METRICS_STORE) {
+        Ok(store) => {
+            store.delete(METRICS_KEY);
+        }
+        Err(e) => tracing::warn!("{}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_metrics(app: AppHandle) -> Result<UsageMetrics, AppError> {
+    Ok(with_metrics(&app, |metrics| metrics.clone()))
+}
+
+// Writes the counts to a file the user can attach to a report
+#[tauri::command]
+pub async fn export_metrics(app: AppHandle, path: String) -> Result<(), AppError> {
+    let metrics = with_metrics(&app, |metrics| metrics.clone());
+    let export = MetricsExport {
+        version: EXPORT_VERSION,
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        exported_at: Utc::now(),
+        metrics: &metrics,
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize usage metrics: {}", e))?;
+    Ok(std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?)
+}
+
+#[tauri::command]
+pub async fn clear_metrics(app: AppHandle) -> Result<(), AppError> {
+    clear(&app);
+    Ok(())
+}
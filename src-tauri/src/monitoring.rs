@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+// Global "quiet" switch for everything that reacts without an explicit request:
+// the clipboard watcher, live OCR regions and passive mouse triggers. Not
+// persisted, so a restart always comes back active.
+#[derive(Default)]
+pub struct MonitoringState {
+    paused: AtomicBool,
+}
+
+impl MonitoringState {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitoringStatus {
+    pub paused: bool,
+}
+
+pub fn is_paused(app: &AppHandle) -> bool {
+    app.state::<MonitoringState>().is_paused()
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) -> MonitoringStatus {
+    app.state::<MonitoringState>()
+        .paused
+        .store(paused, Ordering::SeqCst);
+
+    // Panels stop their watchers on this; the tray mirrors it
+    let status = MonitoringStatus { paused };
+    events::broadcast(app, AppEvent::MonitoringStatus(status.clone()));
+    status
+}
+
+pub fn toggle(app: &AppHandle) -> MonitoringStatus {
+    set_paused(app, !is_paused(app))
+}
+
+#[tauri::command]
+pub async fn get_monitoring_status(
+    monitoring_state: State<'_, MonitoringState>,
+) -> Result<MonitoringStatus, AppError> {
+    Ok(MonitoringStatus {
+        paused: monitoring_state.is_paused(),
+    })
+}
+
+#[tauri::command]
+pub async fn set_monitoring_paused(app: AppHandle, paused: bool) -> Result<MonitoringStatus, AppError> {
+    Ok(set_paused(&app, paused))
+}
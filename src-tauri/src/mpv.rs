@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindowBuilder};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::{settings, translation};
+
+pub const SUBTITLE_OVERLAY_LABEL: &str = "subtitle-overlay";
+// Translations kept for lines seen again after a seek
+const CACHE_LIMIT: usize = 1000;
+// Share of the screen height the overlay strip takes, and where it starts
+const STRIP_HEIGHT: f64 = 0.2;
+const STRIP_TOP: f64 = 0.75;
+const OBSERVE_ID: u64 = 1;
+
+#[cfg(unix)]
+const DEFAULT_SOCKET: &str = "/tmp/mpvsocket";
+#[cfg(windows)]
+const DEFAULT_SOCKET: &str = r"\\.\pipe\mpvsocket";
+
+// mpv's JSON IPC: a Unix socket, or a named pipe on Windows
+#[cfg(unix)]
+type Socket = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type Socket = std::fs::File;
+
+#[cfg(unix)]
+fn connect(path: &str) -> std::io::Result<Socket> {
+    Socket::connect(path)
+}
+
+#[cfg(windows)]
+fn connect(path: &str) -> std::io::Result<Socket> {
+    std::fs::OpenOptions::new().read(true).write(true).open(path)
+}
+
+// Start mpv with --input-ipc-server=<socketPath> for it to be found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MpvSettings {
+    pub socket_path: String,
+    // None follows the target language setting
+    pub target_language: Option<String>,
+    // Show the line as mpv has it above its translation
+    pub show_original: bool,
+}
+
+impl Default for MpvSettings {
+    fn default() -> Self {
+        Self {
+            socket_path: DEFAULT_SOCKET.to_string(),
+            target_language: None,
+            show_original: false,
+        }
+    }
+}
+
+impl MpvSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.socket_path.trim().is_empty() {
+            return Err("mpv socket path must not be empty".to_string());
+        }
+        if let Some(target) = &self.target_language {
+            if target.is_empty() || target == "auto" || !target.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+                return Err(format!("'{}' is not a target language", target));
+            }
+        }
+        Ok(())
+    }
+}
+
+// What the overlay shows; `translated` is None while the line is on its way
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleLine {
+    pub original: String,
+    pub translated: Option<String>,
+    pub show_original: bool,
+}
+
+struct Listener {
+    socket_path: String,
+    stop: Arc<AtomicBool>,
+    // Closed to unblock the reader; Windows pipes can't be, so there the
+    // reader ends with the next message or when mpv quits
+    #[cfg_attr(windows, allow(dead_code))]
+    socket: Socket,
+}
+
+#[derive(Default)]
+struct Subtitles {
+    listener: Option<Listener>,
+    error: Option<String>,
+    line: SubtitleLine,
+    cache: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct MpvState {
+    subtitles: Mutex<Subtitles>,
+    // Bumped for every line, so a translation that comes back after mpv
+    // moved on is dropped instead of shown out of sync
+    current: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MpvStatus {
+    pub connected: bool,
+    pub socket_path: Option<String>,
+    // Why the last connection ended, e.g. mpv was closed
+    pub error: Option<String>,
+}
+
+fn show(app: &AppHandle, line: SubtitleLine) {
+    events::send(app, SUBTITLE_OVERLAY_LABEL, AppEvent::SubtitleLine(line.clone()));
+    app.state::<MpvState>().subtitles.lock().unwrap().line = line;
+}
+
+fn subtitle_changed(app: &AppHandle, text: String, settings: &MpvSettings) {
+    let sequence = app.state::<MpvState>().current.fetch_add(1, Ordering::SeqCst) + 1;
+    let original = text.trim().to_string();
+    let cached = app.state::<MpvState>().subtitles.lock().unwrap().cache.get(&original).cloned();
+    let line = SubtitleLine {
+        translated: cached.clone().or_else(|| original.is_empty().then(String::new)),
+        original,
+        show_original: settings.show_original,
+    };
+    let pending = line.translated.is_none();
+    show(app, line.clone());
+    if !pending {
+        return;
+    }
+
+    let app = app.clone();
+    let target = settings.target_language.clone();
+    tauri::async_runtime::spawn(async move {
+        // Subtitles stay out of history, as documents do
+        let translated = match translation::translate(&app, &line.original, None, target.as_deref()).await {
+            Ok(result) => result.translated_text,
+            Err(e) => {
+                tracing::error!("Failed to translate subtitle: {}", e);
+                return;
+            }
+        };
+        let state = app.state::<MpvState>();
+        {
+            let mut subtitles = state.subtitles.lock().unwrap();
+            if subtitles.cache.len() >= CACHE_LIMIT {
+                subtitles.cache.clear();
+            }
+            subtitles.cache.insert(line.original.clone(), translated.clone());
+        }
+        if state.current.load(Ordering::SeqCst) == sequence {
+            show(
+                &app,
+                SubtitleLine {
+                    translated: Some(translated),
+                    ..line
+                },
+            );
+        }
+    });
+}
+
+fn read_events(app: &AppHandle, socket: Socket, stop: &AtomicBool, settings: &MpvSettings) -> Result<(), String> {
+    for line in BufReader::new(socket).lines() {
+        if stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let line = line.map_err(|e| format!("Lost the connection to mpv: {}", e))?;
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let is_subtitle = message["event"] == "property-change" && message["id"] == OBSERVE_ID;
+        if is_subtitle {
+            // Null when no subtitle track is selected
+            subtitle_changed(app, message["data"].as_str().unwrap_or("").to_string(), settings);
+        }
+    }
+    Err("mpv closed the connection".to_string())
+}
+
+fn overlay(app: &AppHandle) -> Result<(), String> {
+    let window = match app.get_webview_window(SUBTITLE_OVERLAY_LABEL) {
+        Some(window) => window,
+        None => WebviewWindowBuilder::new(app, SUBTITLE_OVERLAY_LABEL, tauri::WebviewUrl::App("index.html#subtitles".into()))
+            .title("Subtitles")
+            .decorations(false)
+            .transparent(true)
+            .shadow(false)
+            .resizable(false)
+            .always_on_top(true)
+            .visible_on_all_workspaces(true)
+            .skip_taskbar(true)
+            .focused(false)
+            .focusable(false)
+            .visible(false)
+            .build()
+            .map_err(|e| format!("Failed to create subtitle overlay: {}", e))?,
+    };
+    let monitor = app
+        .primary_monitor()
+        .map_err(|e| format!("Failed to get primary monitor: {}", e))?
+        .ok_or_else(|| "No primary monitor".to_string())?;
+    let (position, size) = (monitor.position(), monitor.size());
+    let top = position.y + (size.height as f64 * STRIP_TOP) as i32;
+    window
+        .set_position(PhysicalPosition::new(position.x, top))
+        .map_err(|e| format!("Failed to position subtitle overlay: {}", e))?;
+    window
+        .set_size(PhysicalSize::new(size.width, (size.height as f64 * STRIP_HEIGHT) as u32))
+        .map_err(|e| format!("Failed to size subtitle overlay: {}", e))?;
+    // Click-through so the player's own controls keep working
+    window
+        .set_ignore_cursor_events(true)
+        .map_err(|e| format!("Failed to make subtitle overlay click-through: {}", e))?;
+    window.show().map_err(|e| format!("Failed to show subtitle overlay: {}", e))
+}
+
+fn disconnect(app: &AppHandle) {
+    let Some(listener) = app.state::<MpvState>().subtitles.lock().unwrap().listener.take() else {
+        return;
+    };
+    listener.stop.store(true, Ordering::SeqCst);
+    #[cfg(unix)]
+    let _ = listener.socket.shutdown(std::net::Shutdown::Both);
+    if let Some(window) = app.get_webview_window(SUBTITLE_OVERLAY_LABEL) {
+        let _ = window.hide();
+    }
+}
+
+// Connects to a running mpv and shows each subtitle line translated in a
+// click-through strip over the bottom of the screen. `socket_path` falls
+// back to the one in settings.
+#[tauri::command]
+pub async fn start_mpv_subtitles(app: AppHandle, socket_path: Option<String>) -> Result<(), AppError> {
+    disconnect(&app);
+    let settings = settings::load(&app).mpv;
+    let path = socket_path.unwrap_or_else(|| settings.socket_path.clone());
+    let mut socket = connect(&path).map_err(|e| format!("Failed to connect to mpv at {}: {}", path, e))?;
+    let observe = json!({ "command": ["observe_property_string", OBSERVE_ID, "sub-text"] });
+    writeln!(socket, "{}", observe).map_err(|e| format!("Failed to talk to mpv: {}", e))?;
+    let reader = socket.try_clone().map_err(|e| format!("Failed to connect to mpv at {}: {}", path, e))?;
+    overlay(&app)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let state = app.state::<MpvState>();
+        let mut subtitles = state.subtitles.lock().unwrap();
+        subtitles.error = None;
+        subtitles.listener = Some(Listener {
+            socket_path: path,
+            stop: stop.clone(),
+            socket,
+        });
+    }
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("mpv-subtitles".to_string())
+        .spawn(move || {
+            let result = read_events(&app, reader, &stop, &settings);
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            disconnect(&app);
+            if let Err(e) = result {
+                app.state::<MpvState>().subtitles.lock().unwrap().error = Some(e);
+            }
+            events::broadcast(&app, AppEvent::MpvDisconnected);
+        })
+        .map_err(|e| format!("Failed to start the mpv listener: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_mpv_subtitles(app: AppHandle) -> Result<(), AppError> {
+    disconnect(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_mpv_status(app: AppHandle) -> Result<MpvStatus, AppError> {
+    let state = app.state::<MpvState>();
+    let subtitles = state.subtitles.lock().unwrap();
+    Ok(MpvStatus {
+        connected: subtitles.listener.is_some(),
+        socket_path: subtitles.listener.as_ref().map(|listener| listener.socket_path.clone()),
+        error: subtitles.error.clone(),
+    })
+}
+
+// For the overlay page once it has loaded
+#[tauri::command]
+pub async fn get_subtitle_line(app: AppHandle) -> Result<SubtitleLine, AppError> {
+    Ok(app.state::<MpvState>().subtitles.lock().unwrap().line.clone())
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Context};
+#[cfg(not(target_os = "windows"))]
+use tauri::Manager;
+
+use crate::error::AppError;
+use crate::{autostart, cli, translation};
+
+// Browsers look the host up by this name; it is also the manifest's file name
+pub const HOST_NAME: &str = "com.shunyaku.translate";
+const MAX_TEXT: usize = 5000;
+// Chrome drops the host for anything it sends over 1 MB
+const MAX_OUTGOING: usize = 1024 * 1024;
+const MAX_INCOMING: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NativeBrowser {
+    Chrome,
+    Chromium,
+    Edge,
+    Firefox,
+}
+
+impl NativeBrowser {
+    const ALL: [NativeBrowser; 4] = [
+        NativeBrowser::Chrome,
+        NativeBrowser::Chromium,
+        NativeBrowser::Edge,
+        NativeBrowser::Firefox,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeHostStatus {
+    pub browser: NativeBrowser,
+    pub installed: bool,
+    pub manifest_path: String,
+}
+
+// Chrome starts the host with the calling extension's origin, Firefox with
+// the path of our manifest
+pub fn is_launch_arg(arg: &str) -> bool {
+    arg.starts_with("chrome-extension://") || arg.ends_with(&format!("{}.json", HOST_NAME))
+}
+
+// Same shapes as the WebSocket bridge, minus the hello: the browser only
+// starts the host for extensions the manifest allows
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum HostMessage {
+    Ping,
+    Translate {
+        id: u64,
+        text: String,
+        #[serde(rename = "sourceLang")]
+        source_lang: Option<String>,
+        #[serde(rename = "targetLang")]
+        target_lang: Option<String>,
+    },
+}
+
+// Each message is a 32-bit length in native byte order, then that much JSON.
+// None once the browser closes stdin.
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>, String> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read from the browser: {}", e)),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_INCOMING {
+        return Err(format!("Message of {} bytes is over the {} byte limit", length, MAX_INCOMING));
+    }
+    let mut message = vec![0; length];
+    input.read_exact(&mut message).map_err(|e| format!("Failed to read from the browser: {}", e))?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<(), String> {
+    let mut body = message.to_string();
+    if body.len() > MAX_OUTGOING {
+        body = json!({ "type": "error", "id": message["id"], "error": "Translation is too long to send back" }).to_string();
+    }
+    output
+        .write_all(&(body.len() as u32).to_ne_bytes())
+        .and_then(|()| output.write_all(body.as_bytes()))
+        .and_then(|()| output.flush())
+        .map_err(|e| format!("Failed to write to the browser: {}", e))
+}
+
+fn respond(app: &AppHandle, message: &[u8]) -> Value {
+    let message = match serde_json::from_slice::<HostMessage>(message) {
+        Ok(message) => message,
+        Err(e) => return json!({ "type": "error", "error": format!("Invalid message: {}", e) }),
+    };
+    match message {
+        HostMessage::Ping => json!({ "type": "ready", "version": env!("CARGO_PKG_VERSION") }),
+        HostMessage::Translate { id, text, .. } if text.chars().count() > MAX_TEXT => {
+            json!({ "type": "error", "id": id, "error": format!("Text is longer than {} characters", MAX_TEXT) })
+        }
+        HostMessage::Translate {
+            id,
+            text,
+            source_lang,
+            target_lang,
+        } => {
+            let translated = tauri::async_runtime::block_on(translation::translate(
+                app,
+                &text,
+                source_lang.as_deref(),
+                target_lang.as_deref(),
+            ));
+            match translated {
+                Ok(result) => json!({ "type": "done", "id": id, "result": result }),
+                Err(e) => json!({ "type": "error", "id": id, "error": e }),
+            }
+        }
+    }
+}
+
+// Answers the browser over stdin/stdout until it closes the pipe, for
+// machines where the bridge can't open a local port. History stays with the
+// app itself; this process only translates.
+pub fn run(context: Context) -> i32 {
+    let app = match cli::headless_app(context) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let (mut input, mut output) = (std::io::stdin().lock(), std::io::stdout().lock());
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return 0,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        };
+        if let Err(e) = write_message(&mut output, &respond(app.handle(), &message)) {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+}
+
+fn manifest(browser: NativeBrowser, executable: &str, extension_ids: &[String]) -> Value {
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Shunyaku translation",
+        "path": executable,
+        "type": "stdio",
+    });
+    match browser {
+        NativeBrowser::Firefox => manifest["allowed_extensions"] = json!(extension_ids),
+        _ => {
+            let origins: Vec<String> = extension_ids.iter().map(|id| format!("chrome-extension://{}/", id)).collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+    }
+    manifest
+}
+
+// Linux and macOS browsers read manifests from fixed per-user folders
+#[cfg(not(target_os = "windows"))]
+fn manifest_path(app: &AppHandle, browser: NativeBrowser) -> Result<PathBuf, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+    let folder = match (cfg!(target_os = "macos"), browser) {
+        (true, NativeBrowser::Chrome) => "Library/Application Support/Google/Chrome/NativeMessagingHosts",
+        (true, NativeBrowser::Chromium) => "Library/Application Support/Chromium/NativeMessagingHosts",
+        (true, NativeBrowser::Edge) => "Library/Application Support/Microsoft Edge/NativeMessagingHosts",
+        (true, NativeBrowser::Firefox) => "Library/Application Support/Mozilla/NativeMessagingHosts",
+        (false, NativeBrowser::Chrome) => ".config/google-chrome/NativeMessagingHosts",
+        (false, NativeBrowser::Chromium) => ".config/chromium/NativeMessagingHosts",
+        (false, NativeBrowser::Edge) => ".config/microsoft-edge/NativeMessagingHosts",
+        (false, NativeBrowser::Firefox) => ".mozilla/native-messaging-hosts",
+    };
+    Ok(home.join(folder).join(format!("{}.json", HOST_NAME)))
+}
+
+// Windows browsers find the manifest through the registry, so it can live
+// with the app's data
+#[cfg(target_os = "windows")]
+fn manifest_path(app: &AppHandle, browser: NativeBrowser) -> Result<PathBuf, String> {
+    let folder = format!("{:?}", browser).to_lowercase();
+    Ok(crate::portable::data_dir(app)?
+        .join("native-messaging")
+        .join(folder)
+        .join(format!("{}.json", HOST_NAME)))
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+    use windows::Win32::System::Registry::{
+        RegDeleteTreeW, RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ,
+    };
+
+    use super::{NativeBrowser, HOST_NAME};
+
+    fn key(browser: NativeBrowser) -> HSTRING {
+        let vendor = match browser {
+            NativeBrowser::Chrome => "Google\\Chrome",
+            NativeBrowser::Chromium => "Chromium",
+            NativeBrowser::Edge => "Microsoft\\Edge",
+            NativeBrowser::Firefox => "Mozilla",
+        };
+        HSTRING::from(format!("Software\\{}\\NativeMessagingHosts\\{}", vendor, HOST_NAME))
+    }
+
+    pub fn is_registered(browser: NativeBrowser) -> bool {
+        let mut size = 0u32;
+        let status = unsafe {
+            RegGetValueW(HKEY_CURRENT_USER, &key(browser), PCWSTR::null(), RRF_RT_REG_SZ, None, None, Some(&mut size))
+        };
+        status.is_ok()
+    }
+
+    // The key's default value points at the manifest
+    pub fn register(browser: NativeBrowser, manifest: &str) -> Result<(), String> {
+        let data: Vec<u16> = manifest.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &key(browser),
+                PCWSTR::null(),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                (data.len() * 2) as u32,
+            )
+        }
+        .ok()
+        .map_err(|e| format!("Failed to register native messaging host: {}", e))
+    }
+
+    pub fn unregister(browser: NativeBrowser) -> Result<(), String> {
+        let status = unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &key(browser)) };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        status.ok().map_err(|e| format!("Failed to unregister native messaging host: {}", e))
+    }
+}
+
+fn status(app: &AppHandle, browser: NativeBrowser) -> Result<NativeHostStatus, String> {
+    let path = manifest_path(app, browser)?;
+    #[cfg(target_os = "windows")]
+    let installed = path.exists() && registry::is_registered(browser);
+    #[cfg(not(target_os = "windows"))]
+    let installed = path.exists();
+    Ok(NativeHostStatus {
+        browser,
+        installed,
+        manifest_path: path.to_string_lossy().into_owned(),
+    })
+}
+
+// Writes the host manifest for `browser` allowing the given extensions:
+// Chrome-family IDs, or Firefox add-on IDs such as name@example.com
+#[tauri::command]
+pub async fn install_native_host(
+    app: AppHandle,
+    browser: NativeBrowser,
+    extension_ids: Vec<String>,
+) -> Result<NativeHostStatus, AppError> {
+    if extension_ids.is_empty() {
+        return Err(AppError::InvalidInput("Name at least one extension".to_string()));
+    }
+    if let Some(id) = extension_ids
+        .iter()
+        .find(|id| id.is_empty() || id.contains(|c: char| c.is_whitespace() || c == '/' || c == '"'))
+    {
+        return Err(AppError::InvalidInput(format!("'{}' is not an extension ID", id)));
+    }
+    let executable = autostart::executable()?;
+    let manifest = manifest(browser, &executable.to_string_lossy(), &extension_ids);
+    let path = manifest_path(&app, browser)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents =
+        serde_json::to_string_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(target_os = "windows")]
+    registry::register(browser, &path.to_string_lossy())?;
+    Ok(status(&app, browser)?)
+}
+
+#[tauri::command]
+pub async fn uninstall_native_host(app: AppHandle, browser: NativeBrowser) -> Result<NativeHostStatus, AppError> {
+    #[cfg(target_os = "windows")]
+    registry::unregister(browser)?;
+    let path = manifest_path(&app, browser)?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Failed to remove {}: {}", path.display(), e).into());
+        }
+        _ => {}
+    }
+    Ok(status(&app, browser)?)
+}
+
+#[tauri::command]
+pub async fn get_native_host_status(app: AppHandle) -> Result<Vec<NativeHostStatus>, AppError> {
+    Ok(NativeBrowser::ALL.iter().map(|browser| status(&app, *browser)).collect::<Result<_, String>>()?)
+}
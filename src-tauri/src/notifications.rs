@@ -0,0 +1,117 @@
+use notify_rust::{Notification, NotificationResponse};
+use std::thread;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::dnd;
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry};
+use crate::session;
+use crate::settings;
+
+// Notification bodies are cut to this many characters
+const BODY_LIMIT: usize = 100;
+
+const COPY_ACTION: &str = "copy";
+const OPEN_ACTION: &str = "open";
+const STAR_ACTION: &str = "star";
+
+pub fn notifications_enabled(app: &AppHandle) -> bool {
+    settings::load(app).notifications_enabled
+}
+
+fn any_window_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+// Raises the panel the result belongs to, or opens a fresh one if it has
+// been closed since
+fn activate(app: &AppHandle, window_id: &str) {
+    match session::window(app, window_id) {
+        Some(window) => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        None => {
+            if let Err(e) = crate::open_floating_window(app) {
+                tracing::error!("{}", e);
+            }
+        }
+    }
+}
+
+fn handle_response(app: &AppHandle, entry: &HistoryEntry, window_id: &str, response: &NotificationResponse) {
+    let action = match response {
+        NotificationResponse::Default => OPEN_ACTION,
+        NotificationResponse::Action(action) => action.as_str(),
+        _ => return,
+    };
+
+    match action {
+        COPY_ACTION => {
+            if let Err(e) = app.clipboard().write_text(entry.result.translated_text.clone()) {
+                tracing::error!("Failed to copy translation: {}", e);
+            }
+        }
+        STAR_ACTION => {
+            if let Err(e) = history::set_starred(app, entry.id, true) {
+                tracing::error!("Failed to star translation: {}", e);
+            }
+        }
+        _ => activate(app, window_id),
+    }
+}
+
+// Posts the result of a translation the user isn't looking at. Does nothing
+// when notifications are switched off, during quiet hours, or while one of
+// our windows has focus.
+pub fn notify_background_result(app: &AppHandle, entry: &HistoryEntry, window_id: &str) {
+    if !notifications_enabled(app) || dnd::is_active(app) || any_window_focused(app) {
+        return;
+    }
+
+    let result = &entry.result;
+    let mut notification = Notification::new();
+    notification
+        .appname("Shunyaku")
+        .summary(&format!(
+            "Translated {} → {}",
+            result.source_lang.to_uppercase(),
+            result.target_lang.to_uppercase()
+        ))
+        .body(&history::preview(&result.translated_text, BODY_LIMIT))
+        .action(COPY_ACTION, "Copy")
+        .action(OPEN_ACTION, "Open Panel")
+        .action(STAR_ACTION, "Star");
+    // XDG servers only report body clicks for a registered "default" action;
+    // elsewhere it would show up as an extra button
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        notification.action("default", "");
+    }
+
+    // Waiting for the response blocks, so each notification gets its own thread
+    let app = app.clone();
+    let entry = entry.clone();
+    let window_id = window_id.to_string();
+    thread::spawn(move || match notification.show() {
+        Ok(handle) => {
+            let _ = handle.wait_for_response(|response: &NotificationResponse| {
+                handle_response(&app, &entry, &window_id, response)
+            });
+        }
+        Err(e) => tracing::error!("Failed to show notification: {}", e),
+    });
+}
+
+#[tauri::command]
+pub async fn get_notifications_enabled(app: AppHandle) -> Result<bool, AppError> {
+    Ok(notifications_enabled(&app))
+}
+
+#[tauri::command]
+pub async fn set_notifications_enabled(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    Ok(settings::update(&app, |settings| settings.notifications_enabled = enabled).map(|_| ())?)
+}
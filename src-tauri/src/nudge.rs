@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, LogicalPosition, LogicalSize, Manager, State, WebviewWindow};
+
+use crate::error::AppError;
+use crate::{settings, WindowStore};
+
+// Smallest floating panel the keyboard or window defaults can produce
+pub const MIN_WIDTH: f64 = 160.0;
+pub const MIN_HEIGHT: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NudgeAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    GrowWidth,
+    ShrinkWidth,
+    GrowHeight,
+    ShrinkHeight,
+}
+
+// Step sizes in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NudgeSettings {
+    pub move_step: f64,
+    pub resize_step: f64,
+}
+
+impl Default for NudgeSettings {
+    fn default() -> Self {
+        Self {
+            move_step: 20.0,
+            resize_step: 20.0,
+        }
+    }
+}
+
+impl NudgeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.move_step <= 0.0 || self.resize_step <= 0.0 {
+            return Err("Nudge steps must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn load_settings(app: &AppHandle) -> NudgeSettings {
+    settings::load(app).nudge
+}
+
+// The focused floating panel, or the most recently opened one when focus is
+// in another app (as it usually is when a global shortcut fires)
+fn target_window(app: &AppHandle, window_store: &WindowStore) -> Option<WebviewWindow> {
+    let windows: Vec<WebviewWindow> = window_store
+        .ids()
+        .iter()
+        .filter_map(|id| app.get_webview_window(id))
+        .collect();
+
+    let focused = windows
+        .iter()
+        .position(|window| window.is_focused().unwrap_or(false));
+    match focused {
+        Some(index) => windows.into_iter().nth(index),
+        None => windows.into_iter().last(),
+    }
+}
+
+fn apply(window: &WebviewWindow, action: NudgeAction, settings: NudgeSettings) -> Result<(), String> {
+    let scale = window
+        .scale_factor()
+        .map_err(|e| format!("Failed to read scale factor: {}", e))?;
+
+    let (m, r) = (settings.move_step, settings.resize_step);
+    let (dx, dy, dw, dh) = match action {
+        NudgeAction::MoveUp => (0.0, -m, 0.0, 0.0),
+        NudgeAction::MoveDown => (0.0, m, 0.0, 0.0),
+        NudgeAction::MoveLeft => (-m, 0.0, 0.0, 0.0),
+        NudgeAction::MoveRight => (m, 0.0, 0.0, 0.0),
+        NudgeAction::GrowWidth => (0.0, 0.0, r, 0.0),
+        NudgeAction::ShrinkWidth => (0.0, 0.0, -r, 0.0),
+        NudgeAction::GrowHeight => (0.0, 0.0, 0.0, r),
+        NudgeAction::ShrinkHeight => (0.0, 0.0, 0.0, -r),
+    };
+
+    if dx != 0.0 || dy != 0.0 {
+        let position = window
+            .outer_position()
+            .map_err(|e| format!("Failed to read position: {}", e))?
+            .to_logical::<f64>(scale);
+        window
+            .set_position(LogicalPosition::new(position.x + dx, position.y + dy))
+            .map_err(|e| format!("Failed to update position: {}", e))?;
+    }
+
+    if dw != 0.0 || dh != 0.0 {
+        let size = window
+            .inner_size()
+            .map_err(|e| format!("Failed to read size: {}", e))?
+            .to_logical::<f64>(scale);
+        window
+            .set_size(LogicalSize::new(
+                (size.width + dw).max(MIN_WIDTH),
+                (size.height + dh).max(MIN_HEIGHT),
+            ))
+            .map_err(|e| format!("Failed to update size: {}", e))?;
+    }
+    Ok(())
+}
+
+pub fn nudge_focused(app: &AppHandle, action: NudgeAction) -> Result<(), String> {
+    let window = target_window(app, &app.state::<WindowStore>())
+        .ok_or_else(|| "No floating window is open".to_string())?;
+    apply(&window, action, load_settings(app))
+}
+
+#[tauri::command]
+pub async fn nudge_floating_window(
+    app: AppHandle,
+    window_store: State<'_, WindowStore>,
+    action: NudgeAction,
+    window_id: Option<String>,
+) -> Result<(), AppError> {
+    let window = match &window_id {
+        Some(id) => app.get_webview_window(id),
+        None => target_window(&app, &window_store),
+    }
+    .ok_or_else(|| AppError::WindowNotFound(window_id.unwrap_or_default()))?;
+
+    Ok(apply(&window, action, load_settings(&app))?)
+}
+
+#[tauri::command]
+pub async fn get_nudge_settings(app: AppHandle) -> Result<NudgeSettings, AppError> {
+    Ok(load_settings(&app))
+}
+
+#[tauri::command]
+pub async fn set_nudge_settings(app: AppHandle, settings: NudgeSettings) -> Result<NudgeSettings, AppError> {
+    settings.validate()?;
+    settings::update(&app, |stored| stored.nudge = settings)?;
+    Ok(settings)
+}
@@ -0,0 +1,92 @@
+use image::RgbaImage;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use super::OcrLine;
+
+const DEFAULT_CAPACITY: usize = 64;
+
+// Recognition results keyed by a hash of the captured pixels, so a live region
+// whose content hasn't changed skips the recognizer entirely
+pub struct OcrCache {
+    entries: HashMap<u64, Vec<OcrLine>>,
+    order: VecDeque<u64>,
+    capacity: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl OcrCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<Vec<OcrLine>> {
+        match self.entries.get(&key) {
+            Some(lines) => {
+                self.hits += 1;
+                let lines = lines.clone();
+                self.touch(key);
+                Some(lines)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, lines: Vec<OcrLine>) {
+        if self.entries.insert(key, lines).is_some() {
+            self.touch(key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    pub fn stats(&self) -> (usize, u64, u64) {
+        (self.entries.len(), self.hits, self.misses)
+    }
+
+    fn touch(&mut self, key: u64) {
+        if let Some(pos) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key);
+    }
+}
+
+impl Default for OcrCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+// The recognizer settings are part of the key: the same pixels read with a
+// different engine or language model can produce different text
+pub fn image_key(image: &RgbaImage, recognizer: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.width().hash(&mut hasher);
+    image.height().hash(&mut hasher);
+    image.as_raw().hash(&mut hasher);
+    recognizer.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,244 @@
+mod cache;
+#[cfg(feature = "onnx-ocr")]
+mod onnx;
+mod postprocess;
+pub mod profiles;
+mod tesseract;
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+use crate::capture::{capture_region, CaptureRegion};
+use crate::error::AppError;
+use crate::history::thumbnails;
+use crate::metrics;
+use crate::transfer::{self, Payload};
+
+pub use cache::OcrCache;
+pub use postprocess::{postprocess_lines, rejoin_lines, PostprocessOptions};
+pub use profiles::OcrEngineKind;
+
+pub type OcrCacheState = Mutex<OcrCache>;
+
+// Loaded on first use; the model is large and most users never select it
+#[cfg(feature = "onnx-ocr")]
+pub type MangaOcrState = Mutex<Option<onnx::MangaOcr>>;
+
+const DEFAULT_LANGUAGE: &str = "jpn+eng";
+
+// Pixel rectangle as reported by Tesseract (top-left / bottom-right corners)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> f64 {
+        (self.x1 - self.x0).max(0.0)
+    }
+
+    pub fn height(&self) -> f64 {
+        (self.y1 - self.y0).max(0.0)
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+}
+
+// A single recognized line; confidence uses Tesseract's 0-100 scale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrLine {
+    pub text: String,
+    pub confidence: f64,
+    pub bbox: BoundingBox,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessResult {
+    pub lines: Vec<OcrLine>,
+    pub text: String,
+    pub dropped: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionRecognition {
+    #[serde(flatten)]
+    pub result: PostprocessResult,
+    pub cached: bool,
+    // Pass to translate_text so history keeps a picture of the region
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[tauri::command]
+pub fn postprocess_ocr(
+    lines: Vec<OcrLine>,
+    options: Option<PostprocessOptions>,
+) -> Result<PostprocessResult, AppError> {
+    let options = options.unwrap_or_default();
+    if !(0.0..=100.0).contains(&options.confidence_threshold) {
+        return Err(AppError::InvalidInput("Confidence threshold must be between 0 and 100".to_string()));
+    }
+
+    Ok(postprocess_lines(lines, &options))
+}
+
+fn run_engine(
+    app: &tauri::AppHandle,
+    engine: OcrEngineKind,
+    image: &RgbaImage,
+    language: &str,
+) -> Result<Vec<OcrLine>, String> {
+    match engine {
+        OcrEngineKind::Tesseract => tesseract::recognize(image, language),
+        #[cfg(feature = "onnx-ocr")]
+        OcrEngineKind::MangaOcr => {
+            use tauri::Manager;
+
+            let state = app.state::<MangaOcrState>();
+            let mut model = state.lock().unwrap();
+            if model.is_none() {
+                let model_dir = crate::portable::data_dir(app)?
+                    .join("models")
+                    .join("manga-ocr");
+                *model = Some(onnx::MangaOcr::load(&model_dir)?);
+            }
+            model.as_mut().unwrap().recognize(image)
+        }
+        #[cfg(not(feature = "onnx-ocr"))]
+        OcrEngineKind::MangaOcr => {
+            let _ = app;
+            Err("This build does not include the ONNX recognizer".to_string())
+        }
+    }
+}
+
+pub fn cache_key(image: &RgbaImage, engine: OcrEngineKind, language: &str) -> u64 {
+    cache::image_key(image, &format!("{}:{}", engine.cache_tag(), language))
+}
+
+// The recognizer alone, without the cache or a thumbnail, e.g. for timing it
+pub async fn recognize_uncached(
+    app: &tauri::AppHandle,
+    image: RgbaImage,
+    engine: OcrEngineKind,
+    language: String,
+) -> Result<Vec<OcrLine>, String> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || run_engine(&handle, engine, &image, &language))
+        .await
+        .map_err(|e| format!("Recognition task failed: {}", e))?
+}
+
+// Recognizes an image, reusing the previous result when the pixels are
+// identical (live regions call this on every tick)
+pub async fn recognize(
+    app: &tauri::AppHandle,
+    image: RgbaImage,
+    profile: Option<String>,
+    language: Option<String>,
+    options: Option<PostprocessOptions>,
+) -> Result<RegionRecognition, String> {
+    use tauri::Manager;
+
+    let (engine, language, options) = match profile {
+        Some(name) => {
+            let profile = profiles::find_profile(app, &name)?;
+            (
+                profile.engine,
+                language.unwrap_or(profile.language),
+                options.unwrap_or(profile.postprocess),
+            )
+        }
+        None => (
+            OcrEngineKind::Tesseract,
+            language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+            options.unwrap_or_default(),
+        ),
+    };
+
+    let ocr_cache = app.state::<OcrCacheState>();
+    let key = cache_key(&image, engine, &language);
+    let image = Arc::new(image);
+    let thumbnail = thumbnails::store(app, key, image.clone());
+    if let Some(lines) = ocr_cache.lock().unwrap().get(key) {
+        metrics::ocr(app, true);
+        return Ok(RegionRecognition {
+            result: postprocess_lines(lines, &options),
+            cached: true,
+            thumbnail,
+        });
+    }
+
+    let handle = app.clone();
+    let lines = tauri::async_runtime::spawn_blocking(move || {
+        run_engine(&handle, engine, &image, &language)
+    })
+    .await
+    .map_err(|e| format!("Recognition task failed: {}", e))??;
+
+    ocr_cache.lock().unwrap().insert(key, lines.clone());
+    metrics::ocr(app, false);
+
+    Ok(RegionRecognition {
+        result: postprocess_lines(lines, &options),
+        cached: false,
+        thumbnail,
+    })
+}
+
+// Captures a screen region and recognizes it
+#[tauri::command]
+pub async fn recognize_region(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    region: CaptureRegion,
+    profile: Option<String>,
+    language: Option<String>,
+    options: Option<PostprocessOptions>,
+) -> Result<Payload<RegionRecognition>, AppError> {
+    let image = tauri::async_runtime::spawn_blocking(move || capture_region(&region))
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??;
+    let recognition = recognize(&app, image, profile, language, options).await?;
+    transfer::payload(&app, window.label(), recognition)
+}
+
+#[tauri::command]
+pub async fn get_ocr_cache_stats(
+    ocr_cache: State<'_, OcrCacheState>,
+) -> Result<OcrCacheStats, AppError> {
+    let (entries, hits, misses) = ocr_cache.lock().unwrap().stats();
+    Ok(OcrCacheStats {
+        entries,
+        hits,
+        misses,
+    })
+}
+
+#[tauri::command]
+pub async fn clear_ocr_cache(ocr_cache: State<'_, OcrCacheState>) -> Result<(), AppError> {
+    ocr_cache.lock().unwrap().clear();
+    Ok(())
+}
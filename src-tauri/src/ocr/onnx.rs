@@ -0,0 +1,170 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider,
+};
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::Path;
+
+use super::{BoundingBox, OcrLine};
+
+// manga-ocr (ViT encoder + BERT character decoder) exported with Hugging Face optimum
+const ENCODER_FILE: &str = "encoder_model.onnx";
+const DECODER_FILE: &str = "decoder_model.onnx";
+const VOCAB_FILE: &str = "vocab.txt";
+const IMAGE_SIZE: usize = 224;
+const MAX_TOKENS: usize = 300;
+
+pub struct MangaOcr {
+    encoder: Session,
+    decoder: Session,
+    vocab: Vec<String>,
+    start_token: i64,
+    end_token: i64,
+}
+
+fn build_session(path: &Path) -> Result<Session, String> {
+    // Providers that aren't available on this machine are skipped and CPU is used
+    Session::builder()
+        .and_then(|builder| {
+            builder.with_execution_providers([
+                CUDAExecutionProvider::default().build(),
+                DirectMLExecutionProvider::default().build(),
+                CoreMLExecutionProvider::default().build(),
+            ])
+        })
+        .and_then(|builder| builder.commit_from_file(path))
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+impl MangaOcr {
+    pub fn load(model_dir: &Path) -> Result<Self, String> {
+        let vocab: Vec<String> = std::fs::read_to_string(model_dir.join(VOCAB_FILE))
+            .map_err(|e| format!("Failed to read manga-ocr vocabulary: {}", e))?
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+
+        let token_id = |token: &str| {
+            vocab
+                .iter()
+                .position(|t| t == token)
+                .map(|i| i as i64)
+                .ok_or_else(|| format!("Vocabulary is missing {}", token))
+        };
+        let start_token = token_id("[CLS]")?;
+        let end_token = token_id("[SEP]")?;
+
+        Ok(Self {
+            encoder: build_session(&model_dir.join(ENCODER_FILE))?,
+            decoder: build_session(&model_dir.join(DECODER_FILE))?,
+            vocab,
+            start_token,
+            end_token,
+        })
+    }
+
+    pub fn recognize(&mut self, image: &RgbaImage) -> Result<Vec<OcrLine>, String> {
+        let pixels = preprocess(image);
+        let pixel_values = Tensor::from_array(([1, 3, IMAGE_SIZE, IMAGE_SIZE], pixels))
+            .map_err(|e| format!("Failed to build image tensor: {}", e))?;
+
+        let (hidden_shape, hidden_states) = {
+            let outputs = self
+                .encoder
+                .run(ort::inputs!["pixel_values" => pixel_values])
+                .map_err(|e| format!("Encoder failed: {}", e))?;
+            let (shape, data) = outputs["last_hidden_state"]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| format!("Unexpected encoder output: {}", e))?;
+            (shape.to_vec(), data.to_vec())
+        };
+
+        // Greedy decoding; the decoder is re-run on the whole prefix each step
+        let mut tokens = vec![self.start_token];
+        let mut probabilities = Vec::new();
+        while tokens.len() < MAX_TOKENS {
+            let input_ids = Tensor::from_array(([1, tokens.len()], tokens.clone()))
+                .map_err(|e| format!("Failed to build token tensor: {}", e))?;
+            let encoder_states =
+                Tensor::from_array((hidden_shape.clone(), hidden_states.clone()))
+                    .map_err(|e| format!("Failed to build encoder tensor: {}", e))?;
+
+            let outputs = self
+                .decoder
+                .run(ort::inputs![
+                    "input_ids" => input_ids,
+                    "encoder_hidden_states" => encoder_states
+                ])
+                .map_err(|e| format!("Decoder failed: {}", e))?;
+            let (shape, logits) = outputs["logits"]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| format!("Unexpected decoder output: {}", e))?;
+
+            let vocab_size = shape[shape.len() - 1] as usize;
+            let last = &logits[logits.len() - vocab_size..];
+            let (next, probability) = argmax_softmax(last);
+            if next as i64 == self.end_token {
+                break;
+            }
+            tokens.push(next as i64);
+            probabilities.push(probability);
+        }
+
+        let text: String = tokens[1..]
+            .iter()
+            .filter_map(|&id| self.vocab.get(id as usize))
+            .filter(|token| !(token.starts_with('[') && token.ends_with(']')))
+            .map(|token| token.trim_start_matches("##"))
+            .collect();
+
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let confidence = if probabilities.is_empty() {
+            0.0
+        } else {
+            probabilities.iter().sum::<f32>() as f64 / probabilities.len() as f64 * 100.0
+        };
+
+        Ok(vec![OcrLine {
+            text,
+            confidence,
+            bbox: BoundingBox {
+                x0: 0.0,
+                y0: 0.0,
+                x1: image.width() as f64,
+                y1: image.height() as f64,
+            },
+        }])
+    }
+}
+
+// Grayscale, resize to the ViT input size and normalize to [-1, 1] in CHW order
+fn preprocess(image: &RgbaImage) -> Vec<f32> {
+    let gray = DynamicImage::ImageRgba8(image.clone())
+        .grayscale()
+        .resize_exact(IMAGE_SIZE as u32, IMAGE_SIZE as u32, FilterType::Triangle)
+        .to_rgb8();
+
+    let plane = IMAGE_SIZE * IMAGE_SIZE;
+    let mut pixels = vec![0.0f32; 3 * plane];
+    for (i, pixel) in gray.pixels().enumerate() {
+        for channel in 0..3 {
+            pixels[channel * plane + i] = pixel[channel] as f32 / 127.5 - 1.0;
+        }
+    }
+    pixels
+}
+
+fn argmax_softmax(logits: &[f32]) -> (usize, f32) {
+    let (index, max) = logits
+        .iter()
+        .copied()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (i, v)| if v > best.1 { (i, v) } else { best });
+    let sum: f32 = logits.iter().map(|v| (v - max).exp()).sum();
+    (index, 1.0 / sum)
+}
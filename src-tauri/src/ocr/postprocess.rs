@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BoundingBox, OcrLine, PostprocessResult};
+
+// Katakana / kanji pairs that Tesseract routinely swaps in Japanese text
+const LOOKALIKE_PAIRS: &[(char, char)] = &[
+    ('ー', '一'),
+    ('カ', '力'),
+    ('ロ', '口'),
+    ('エ', '工'),
+    ('ニ', '二'),
+    ('タ', '夕'),
+    ('ハ', '八'),
+];
+
+// Dash-like glyphs that stand in for the long vowel mark after katakana
+const DASH_CHARS: &[char] = &['-', '‐', '−', '—', '―', '－', 'ｰ'];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PostprocessOptions {
+    pub confidence_threshold: f64,
+    pub merge_lines: bool,
+    pub fix_japanese: bool,
+}
+
+impl Default for PostprocessOptions {
+    fn default() -> Self {
+        Self {
+            confidence_threshold: 60.0,
+            merge_lines: true,
+            fix_japanese: true,
+        }
+    }
+}
+
+pub fn postprocess_lines(lines: Vec<OcrLine>, options: &PostprocessOptions) -> PostprocessResult {
+    let total = lines.len();
+    let mut kept: Vec<OcrLine> = lines
+        .into_iter()
+        .filter(|line| line.confidence >= options.confidence_threshold)
+        .filter(|line| !line.text.trim().is_empty())
+        .map(|mut line| {
+            line.text = line.text.trim().to_string();
+            line
+        })
+        .collect();
+    let dropped = total - kept.len();
+
+    if options.merge_lines {
+        kept = merge_lines(kept);
+    }
+
+    if options.fix_japanese {
+        for line in kept.iter_mut() {
+            line.text = fix_japanese_confusions(&line.text);
+        }
+    }
+
+    let text = kept
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    PostprocessResult {
+        lines: kept,
+        text,
+        dropped,
+    }
+}
+
+// Groups lines that belong to the same paragraph based on their placement.
+// Tall, narrow boxes are treated as vertical (tategaki) columns read right to left.
+fn merge_lines(mut lines: Vec<OcrLine>) -> Vec<OcrLine> {
+    if lines.len() < 2 {
+        return lines;
+    }
+
+    let vertical_count = lines
+        .iter()
+        .filter(|line| line.bbox.height() > line.bbox.width() * 1.5)
+        .count();
+    let vertical = vertical_count * 2 > lines.len();
+
+    if vertical {
+        lines.sort_by(|a, b| b.bbox.x1.total_cmp(&a.bbox.x1).then(a.bbox.y0.total_cmp(&b.bbox.y0)));
+    } else {
+        lines.sort_by(|a, b| a.bbox.y0.total_cmp(&b.bbox.y0).then(a.bbox.x0.total_cmp(&b.bbox.x0)));
+    }
+
+    // Typical glyph size: line height for horizontal text, column width for vertical
+    let mut extents: Vec<f64> = lines
+        .iter()
+        .map(|line| if vertical { line.bbox.width() } else { line.bbox.height() })
+        .collect();
+    extents.sort_by(|a, b| a.total_cmp(b));
+    let typical = extents[extents.len() / 2].max(1.0);
+
+    let mut blocks: Vec<(OcrLine, BoundingBox)> = Vec::new();
+    for line in lines {
+        let joins_previous = blocks.last().is_some_and(|(block, last)| {
+            if vertical {
+                continues_column(&block.bbox, last, &line.bbox, typical)
+            } else {
+                continues_paragraph(&block.bbox, last, &line.bbox, typical)
+            }
+        });
+
+        if joins_previous {
+            let (block, last) = blocks.last_mut().unwrap();
+            let block_chars = block.text.chars().count() as f64;
+            let line_chars = line.text.chars().count() as f64;
+            block.confidence = (block.confidence * block_chars + line.confidence * line_chars)
+                / (block_chars + line_chars);
+            join_fragment(&mut block.text, &line.text);
+            block.bbox = block.bbox.union(&line.bbox);
+            *last = line.bbox;
+        } else {
+            let bbox = line.bbox;
+            blocks.push((line, bbox));
+        }
+    }
+
+    blocks.into_iter().map(|(block, _)| block).collect()
+}
+
+fn continues_paragraph(block: &BoundingBox, last: &BoundingBox, next: &BoundingBox, typical: f64) -> bool {
+    let gap = next.y0 - last.y1;
+    if gap < -0.5 * typical || gap > 0.8 * typical {
+        return false;
+    }
+
+    // Headings and captions usually differ noticeably in size from body text
+    let ratio = next.height() / last.height().max(1.0);
+    if !(0.6..=1.6).contains(&ratio) {
+        return false;
+    }
+
+    let overlap = next.x1.min(block.x1) - next.x0.max(block.x0);
+    overlap > 0.0 || (next.x0 - block.x0).abs() < 1.5 * typical
+}
+
+fn continues_column(block: &BoundingBox, last: &BoundingBox, next: &BoundingBox, typical: f64) -> bool {
+    let gap = last.x0 - next.x1;
+    if gap < -0.5 * typical || gap > 0.8 * typical {
+        return false;
+    }
+
+    let ratio = next.width() / last.width().max(1.0);
+    if !(0.6..=1.6).contains(&ratio) {
+        return false;
+    }
+
+    let overlap = next.y1.min(block.y1) - next.y0.max(block.y0);
+    overlap > 0.0 || (next.y0 - block.y0).abs() < 1.5 * typical
+}
+
+// Joins lines that layout broke apart (PDF text as well as OCR) back into
+// running text, undoing hyphenation and leaving CJK unspaced
+pub fn rejoin_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line = line.trim();
+        if !line.is_empty() {
+            join_fragment(&mut text, line);
+        }
+    }
+    text
+}
+
+fn join_fragment(acc: &mut String, next: &str) {
+    let last = acc.chars().last();
+    let first = next.chars().next();
+
+    match (last, first) {
+        // Re-join words hyphenated across a line break
+        (Some('-'), Some(f)) if f.is_lowercase() && acc.chars().rev().nth(1).is_some_and(char::is_alphabetic) => {
+            acc.pop();
+        }
+        (Some(l), Some(f)) if is_cjk(l) || is_cjk(f) => {}
+        (Some(_), Some(_)) => acc.push(' '),
+        _ => {}
+    }
+
+    acc.push_str(next);
+}
+
+pub fn fix_japanese_confusions(text: &str) -> String {
+    let chars = collapse_cjk_spaces(text);
+    let mut fixed = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        let kana_neighbor = prev.is_some_and(is_katakana) || next.is_some_and(is_katakana);
+        let kanji_neighbor = prev.is_some_and(is_kanji) || next.is_some_and(is_kanji);
+        let digit_neighbor = prev.is_some_and(|p| p.is_numeric()) || next.is_some_and(|n| n.is_numeric());
+
+        if DASH_CHARS.contains(&c)
+            && prev.is_some_and(is_katakana)
+            && next.map_or(true, |n| is_kana(n) || is_cjk_punctuation(n))
+        {
+            fixed.push('ー');
+            continue;
+        }
+
+        let replacement = LOOKALIKE_PAIRS.iter().find_map(|&(kana, kanji)| {
+            if c == kanji && kana_neighbor && !kanji_neighbor {
+                Some(kana)
+            } else if c == kana && kanji_neighbor && !kana_neighbor && !digit_neighbor {
+                Some(kanji)
+            } else {
+                None
+            }
+        });
+
+        fixed.push(replacement.unwrap_or(c));
+    }
+
+    fixed
+}
+
+// Tesseract's jpn models often emit a space between every glyph
+fn collapse_cjk_spaces(text: &str) -> Vec<char> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::with_capacity(chars.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_whitespace() && c != '\n' {
+            let prev = out.last().copied();
+            let next = chars[i + 1..].iter().copied().find(|n: &char| !n.is_whitespace() || *n == '\n');
+            if prev.is_some_and(is_cjk) && next.is_some_and(is_cjk) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々')
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A1}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+fn is_hiragana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}')
+}
+
+fn is_kana(c: char) -> bool {
+    is_katakana(c) || is_hiragana(c)
+}
+
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}')
+}
+
+fn is_cjk(c: char) -> bool {
+    is_kanji(c) || is_kana(c) || is_cjk_punctuation(c) || matches!(c, '\u{FF00}'..='\u{FFEF}')
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use super::PostprocessOptions;
+use crate::error::AppError;
+use crate::settings;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OcrEngineKind {
+    Tesseract,
+    // Transformer model for manga / stylized scene text, run through onnxruntime
+    MangaOcr,
+}
+
+impl OcrEngineKind {
+    pub fn cache_tag(&self) -> &'static str {
+        match self {
+            OcrEngineKind::Tesseract => "tesseract",
+            OcrEngineKind::MangaOcr => "manga-ocr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureProfile {
+    pub name: String,
+    pub engine: OcrEngineKind,
+    pub language: String,
+    #[serde(default)]
+    pub postprocess: PostprocessOptions,
+}
+
+pub fn default_profiles() -> Vec<CaptureProfile> {
+    vec![
+        CaptureProfile {
+            name: DEFAULT_PROFILE.to_string(),
+            engine: OcrEngineKind::Tesseract,
+            language: super::DEFAULT_LANGUAGE.to_string(),
+            postprocess: PostprocessOptions::default(),
+        },
+        CaptureProfile {
+            name: "manga".to_string(),
+            engine: OcrEngineKind::MangaOcr,
+            language: "jpn".to_string(),
+            // The model reads a single speech bubble, so there is nothing to merge
+            postprocess: PostprocessOptions {
+                confidence_threshold: 0.0,
+                merge_lines: false,
+                fix_japanese: false,
+            },
+        },
+    ]
+}
+
+pub fn validate_profiles(profiles: &[CaptureProfile]) -> Result<(), String> {
+    for (index, profile) in profiles.iter().enumerate() {
+        if profile.name.trim().is_empty() {
+            return Err("Capture profile name must not be empty".to_string());
+        }
+        if profiles[..index].iter().any(|p| p.name == profile.name) {
+            return Err(format!("Capture profile '{}' is defined twice", profile.name));
+        }
+    }
+    if !profiles.iter().any(|p| p.name == DEFAULT_PROFILE) {
+        return Err(format!("Capture profile '{}' is required", DEFAULT_PROFILE));
+    }
+    Ok(())
+}
+
+pub fn load_profiles(app: &tauri::AppHandle) -> Result<Vec<CaptureProfile>, String> {
+    Ok(settings::load(app).capture_profiles)
+}
+
+fn save_profiles(app: &tauri::AppHandle, profiles: Vec<CaptureProfile>) -> Result<(), String> {
+    validate_profiles(&profiles)?;
+    settings::update(app, |settings| settings.capture_profiles = profiles).map(|_| ())
+}
+
+pub fn find_profile(app: &tauri::AppHandle, name: &str) -> Result<CaptureProfile, String> {
+    load_profiles(app)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Capture profile '{}' not found", name))
+}
+
+#[tauri::command]
+pub async fn list_capture_profiles(app: tauri::AppHandle) -> Result<Vec<CaptureProfile>, AppError> {
+    Ok(load_profiles(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_capture_profile(
+    app: tauri::AppHandle,
+    profile: CaptureProfile,
+) -> Result<(), AppError> {
+    if profile.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Capture profile name must not be empty".to_string()));
+    }
+
+    let mut profiles = load_profiles(&app)?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    Ok(save_profiles(&app, profiles)?)
+}
+
+#[tauri::command]
+pub async fn delete_capture_profile(app: tauri::AppHandle, name: String) -> Result<(), AppError> {
+    if name == DEFAULT_PROFILE {
+        return Err(AppError::InvalidInput("The default capture profile cannot be deleted".to_string()));
+    }
+
+    let mut profiles = load_profiles(&app)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Err(AppError::NotFound(format!("Capture profile '{}' not found", name)));
+    }
+    Ok(save_profiles(&app, profiles)?)
+}
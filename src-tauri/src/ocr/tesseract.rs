@@ -0,0 +1,98 @@
+use image::{ImageFormat, RgbaImage};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+
+use super::{BoundingBox, OcrLine};
+
+// TSV level for individual words; lines are rebuilt from their words
+const WORD_LEVEL: &str = "5";
+
+type Word = (String, f64, BoundingBox);
+
+pub fn recognize(image: &RgbaImage, language: &str) -> Result<Vec<OcrLine>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode capture: {}", e))?;
+
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "-l", language, "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start tesseract: {}", e))?;
+
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to open tesseract stdin".to_string())?
+        .write_all(&png)
+        .map_err(|e| format!("Failed to send image to tesseract: {}", e))?;
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_tsv(tsv: &str) -> Vec<OcrLine> {
+    // (block, paragraph, line) -> words in reading order
+    let mut lines: BTreeMap<(u32, u32, u32), Vec<Word>> = BTreeMap::new();
+
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != WORD_LEVEL {
+            continue;
+        }
+
+        let text = cols[11].trim();
+        let confidence: f64 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+
+        let num = |i: usize| cols[i].parse::<f64>().unwrap_or(0.0);
+        let key = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let bbox = BoundingBox {
+            x0: num(6),
+            y0: num(7),
+            x1: num(6) + num(8),
+            y1: num(7) + num(9),
+        };
+
+        lines
+            .entry((key(2), key(3), key(4)))
+            .or_default()
+            .push((text.to_string(), confidence, bbox));
+    }
+
+    lines
+        .into_values()
+        .map(|words| {
+            let bbox = words
+                .iter()
+                .skip(1)
+                .fold(words[0].2, |acc, (_, _, b)| acc.union(b));
+            let confidence = words.iter().map(|(_, c, _)| c).sum::<f64>() / words.len() as f64;
+            let text = words
+                .into_iter()
+                .map(|(w, _, _)| w)
+                .collect::<Vec<_>>()
+                .join(" ");
+            OcrLine {
+                text,
+                confidence,
+                bbox,
+            }
+        })
+        .collect()
+}
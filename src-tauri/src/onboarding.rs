@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::permissions::{self, PermissionKind, PermissionState};
+use crate::{hotkeys, settings, translation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStep {
+    ApiKey,
+    Accessibility,
+    ScreenRecording,
+    Hotkeys,
+}
+
+impl OnboardingStep {
+    const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::ApiKey,
+        OnboardingStep::Accessibility,
+        OnboardingStep::ScreenRecording,
+        OnboardingStep::Hotkeys,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepStatus {
+    pub step: OnboardingStep,
+    // What the system reports right now
+    pub detected: bool,
+    // The user finished or skipped the step in the wizard
+    pub marked: bool,
+    pub done: bool,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    pub steps: Vec<StepStatus>,
+    pub complete: bool,
+}
+
+fn permission_step(kind: PermissionKind) -> (bool, Option<String>) {
+    let status = permissions::permission_status(kind);
+    let granted = matches!(status.state, PermissionState::Granted | PermissionState::NotRequired);
+    (granted, status.hint)
+}
+
+fn detect(app: &AppHandle, step: OnboardingStep) -> (bool, Option<String>) {
+    match step {
+        OnboardingStep::ApiKey => match translation::load_config(app) {
+            Ok(config) => match config.api_key(config.provider) {
+                Ok(_) => (true, None),
+                Err(e) => (false, Some(e)),
+            },
+            Err(e) => (false, Some(e)),
+        },
+        OnboardingStep::Accessibility => permission_step(PermissionKind::Accessibility),
+        OnboardingStep::ScreenRecording => permission_step(PermissionKind::ScreenRecording),
+        OnboardingStep::Hotkeys => {
+            let registered = hotkeys::all_registered(app);
+            let hint = (!registered).then(|| "Some hotkeys are unbound or taken by another app".to_string());
+            (registered, hint)
+        }
+    }
+}
+
+fn state(app: &AppHandle) -> OnboardingState {
+    let marked = settings::load(app).onboarding_completed;
+    let steps: Vec<StepStatus> = OnboardingStep::ALL
+        .iter()
+        .map(|step| {
+            let (detected, hint) = detect(app, *step);
+            let marked = marked.contains(step);
+            StepStatus {
+                step: *step,
+                detected,
+                marked,
+                done: detected || marked,
+                hint,
+            }
+        })
+        .collect();
+
+    OnboardingState {
+        complete: steps.iter().all(|status| status.done),
+        steps,
+    }
+}
+
+// Re-checked on every call, so the wizard can poll while the user is off
+// granting a permission in System Settings
+#[tauri::command]
+pub async fn get_onboarding_state(app: AppHandle) -> Result<OnboardingState, AppError> {
+    Ok(state(&app))
+}
+
+#[tauri::command]
+pub async fn mark_onboarding_step(app: AppHandle, step: OnboardingStep) -> Result<OnboardingState, AppError> {
+    settings::update(&app, |settings| {
+        if !settings.onboarding_completed.contains(&step) {
+            settings.onboarding_completed.push(step);
+        }
+    })?;
+    Ok(state(&app))
+}
+
+#[tauri::command]
+pub async fn reset_onboarding(app: AppHandle) -> Result<OnboardingState, AppError> {
+    settings::update(&app, |settings| settings.onboarding_completed.clear())?;
+    Ok(state(&app))
+}
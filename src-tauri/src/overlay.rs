@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{Manager, PhysicalPosition, PhysicalSize, State, WebviewWindowBuilder};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::ocr::BoundingBox;
+
+pub const OVERLAY_LABEL: &str = "translation-overlay";
+
+// Labels currently shown, kept so a freshly loaded overlay page can fetch them
+pub type OverlayStore = Mutex<Vec<OverlayLabel>>;
+
+// A translated string anchored at the box OCR reported for its source text.
+// Coordinates are physical pixels relative to the target monitor's top-left corner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayLabel {
+    pub text: String,
+    pub bbox: BoundingBox,
+}
+
+#[tauri::command]
+pub async fn show_translation_overlay(
+    app: tauri::AppHandle,
+    overlay_store: State<'_, OverlayStore>,
+    labels: Vec<OverlayLabel>,
+    monitor_index: Option<usize>,
+) -> Result<(), AppError> {
+    let monitor = match monitor_index {
+        Some(index) => app
+            .available_monitors()
+            .map_err(|e| format!("Failed to list monitors: {}", e))?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| "Monitor not found".to_string())?,
+        None => app
+            .primary_monitor()
+            .map_err(|e| format!("Failed to get primary monitor: {}", e))?
+            .ok_or_else(|| "No primary monitor".to_string())?,
+    };
+
+    // The webview lays out in CSS pixels, so convert from physical capture coordinates
+    let scale = monitor.scale_factor();
+    let logical_labels: Vec<OverlayLabel> = labels
+        .into_iter()
+        .map(|label| OverlayLabel {
+            text: label.text,
+            bbox: BoundingBox {
+                x0: label.bbox.x0 / scale,
+                y0: label.bbox.y0 / scale,
+                x1: label.bbox.x1 / scale,
+                y1: label.bbox.y1 / scale,
+            },
+        })
+        .collect();
+
+    let window = match app.get_webview_window(OVERLAY_LABEL) {
+        Some(window) => window,
+        None => WebviewWindowBuilder::new(
+            &app,
+            OVERLAY_LABEL,
+            tauri::WebviewUrl::App("index.html#overlay".into())
+        )
+        .title("Translation Overlay")
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .resizable(false)
+        .always_on_top(true)
+        .visible_on_all_workspaces(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .focusable(false)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to create overlay: {}", e))?,
+    };
+
+    let position = monitor.position();
+    let size = monitor.size();
+    window
+        .set_position(PhysicalPosition::new(position.x, position.y))
+        .map_err(|e| format!("Failed to position overlay: {}", e))?;
+    window
+        .set_size(PhysicalSize::new(size.width, size.height))
+        .map_err(|e| format!("Failed to size overlay: {}", e))?;
+
+    // Click-through so the game or app underneath keeps receiving input
+    window
+        .set_ignore_cursor_events(true)
+        .map_err(|e| format!("Failed to make overlay click-through: {}", e))?;
+
+    *overlay_store.lock().unwrap() = logical_labels.clone();
+    events::send(&app, OVERLAY_LABEL, AppEvent::OverlayLabels(logical_labels));
+
+    window.show().map_err(|e| format!("Failed to show overlay: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn hide_translation_overlay(
+    app: tauri::AppHandle,
+    overlay_store: State<'_, OverlayStore>,
+) -> Result<(), AppError> {
+    overlay_store.lock().unwrap().clear();
+
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        window.hide().map_err(|e| format!("Failed to hide overlay: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_overlay_labels(
+    overlay_store: State<'_, OverlayStore>,
+) -> Result<Vec<OverlayLabel>, AppError> {
+    Ok(overlay_store.lock().unwrap().clone())
+}
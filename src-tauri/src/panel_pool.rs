@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+// Hidden panels kept loaded and ready to show
+const POOL_SIZE: usize = 2;
+
+// Floating panels built ahead of time and kept hidden. They stay out of the
+// WindowStore until handed out, so sessions and nudges never see them.
+#[derive(Default)]
+pub struct PanelPool {
+    // Labels, oldest first
+    hidden: Mutex<VecDeque<String>>,
+    // Set while a background fill runs, so only one builds at a time
+    filling: AtomicBool,
+}
+
+// A label no window has yet. Pooled panels are built in quick succession, so
+// the millisecond alone isn't enough.
+pub fn new_label(app: &AppHandle) -> String {
+    let mut millis = chrono::Utc::now().timestamp_millis();
+    while app.get_webview_window(&format!("floating-{}", millis)).is_some() {
+        millis += 1;
+    }
+    format!("floating-{}", millis)
+}
+
+// A hidden panel that is still alive, if the pool has one
+pub fn take(app: &AppHandle) -> Option<WebviewWindow> {
+    let pool = app.state::<PanelPool>();
+    loop {
+        let label = pool.hidden.lock().unwrap().pop_front()?;
+        if let Some(window) = app.get_webview_window(&label) {
+            return Some(window);
+        }
+    }
+}
+
+pub fn contains(app: &AppHandle, label: &str) -> bool {
+    app.state::<PanelPool>().hidden.lock().unwrap().iter().any(|hidden| hidden == label)
+}
+
+// Tops the pool back up in the background
+pub fn fill(app: &AppHandle) {
+    if app.state::<PanelPool>().filling.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<PanelPool>();
+        while pool.hidden.lock().unwrap().len() < POOL_SIZE {
+            let label = new_label(&app);
+            match crate::panel_builder(&app, &label).visible(false).build() {
+                Ok(_) => pool.hidden.lock().unwrap().push_back(label),
+                Err(e) => {
+                    tracing::warn!("Failed to pre-build a floating panel: {}", e);
+                    break;
+                }
+            }
+        }
+        pool.filling.store(false, Ordering::SeqCst);
+    });
+}
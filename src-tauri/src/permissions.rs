@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionKind {
+    ScreenRecording,
+    Accessibility,
+}
+
+// Which variants can occur depends on the platform backend below
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    // The platform does not gate this capability behind a user prompt
+    NotRequired,
+    // The capability cannot be used in the current session (e.g. Wayland)
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionStatus {
+    pub kind: PermissionKind,
+    pub state: PermissionState,
+    // macOS only applies a new Screen Recording grant after the app restarts
+    pub requires_restart: bool,
+    pub hint: Option<String>,
+}
+
+impl PermissionStatus {
+    fn new(kind: PermissionKind, state: PermissionState) -> Self {
+        Self {
+            kind,
+            state,
+            requires_restart: false,
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+pub fn permission_status(kind: PermissionKind) -> PermissionStatus {
+    platform::status(kind)
+}
+
+#[tauri::command]
+pub async fn check_permissions() -> Result<Vec<PermissionStatus>, AppError> {
+    Ok(vec![
+        permission_status(PermissionKind::ScreenRecording),
+        permission_status(PermissionKind::Accessibility),
+    ])
+}
+
+#[tauri::command]
+pub async fn request_permission(kind: PermissionKind) -> Result<PermissionStatus, AppError> {
+    Ok(platform::request(kind)?)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{PermissionKind, PermissionState, PermissionStatus};
+    use core_foundation::base::TCFType;
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::string::{CFString, CFStringRef};
+    use std::process::Command;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+        static kAXTrustedCheckOptionPrompt: CFStringRef;
+    }
+
+    const SCREEN_RECORDING_PANE: &str =
+        "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture";
+    const ACCESSIBILITY_PANE: &str =
+        "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility";
+
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        let granted = match kind {
+            PermissionKind::ScreenRecording => unsafe { CGPreflightScreenCaptureAccess() },
+            PermissionKind::Accessibility => unsafe { AXIsProcessTrusted() },
+        };
+
+        if granted {
+            return PermissionStatus::new(kind, PermissionState::Granted);
+        }
+
+        let status = PermissionStatus::new(kind, PermissionState::Denied);
+        match kind {
+            PermissionKind::ScreenRecording => status.with_hint(
+                "Enable Shunyaku under System Settings > Privacy & Security > Screen Recording",
+            ),
+            PermissionKind::Accessibility => status.with_hint(
+                "Enable Shunyaku under System Settings > Privacy & Security > Accessibility",
+            ),
+        }
+    }
+
+    pub fn request(kind: PermissionKind) -> Result<PermissionStatus, String> {
+        match kind {
+            PermissionKind::ScreenRecording => {
+                // Only prompts the first time; afterwards the user has to use System Settings
+                let granted = unsafe { CGRequestScreenCaptureAccess() };
+                if !granted {
+                    open_settings(SCREEN_RECORDING_PANE)?;
+                }
+            }
+            PermissionKind::Accessibility => {
+                let key = unsafe { CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt) };
+                let options = CFDictionary::from_CFType_pairs(&[(key, CFBoolean::true_value())]);
+                let trusted = unsafe { AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef()) };
+                if !trusted {
+                    open_settings(ACCESSIBILITY_PANE)?;
+                }
+            }
+        }
+
+        let mut status = status(kind);
+        status.requires_restart =
+            kind == PermissionKind::ScreenRecording && status.state != PermissionState::Granted;
+        Ok(status)
+    }
+
+    fn open_settings(url: &str) -> Result<(), String> {
+        Command::new("open")
+            .arg(url)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to open System Settings: {}", e))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{PermissionKind, PermissionState, PermissionStatus};
+
+    fn is_wayland() -> bool {
+        std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+            || std::env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        if !is_wayland() {
+            return PermissionStatus::new(kind, PermissionState::NotRequired);
+        }
+
+        let status = PermissionStatus::new(kind, PermissionState::Unsupported);
+        match kind {
+            PermissionKind::ScreenRecording => status
+                .with_hint("Wayland blocks direct screen capture; run under an X11 session for region OCR"),
+            PermissionKind::Accessibility => status
+                .with_hint("Wayland does not expose other applications' selections or key events"),
+        }
+    }
+
+    pub fn request(kind: PermissionKind) -> Result<PermissionStatus, String> {
+        Ok(status(kind))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+mod platform {
+    use super::{PermissionKind, PermissionState, PermissionStatus};
+
+    // Windows grants screen capture and UI Automation to desktop apps without prompting
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        let status = PermissionStatus::new(kind, PermissionState::NotRequired);
+        match kind {
+            PermissionKind::ScreenRecording => status,
+            PermissionKind::Accessibility => status
+                .with_hint("Selections in apps running as administrator are only readable when Shunyaku is elevated too"),
+        }
+    }
+
+    pub fn request(kind: PermissionKind) -> Result<PermissionStatus, String> {
+        Ok(status(kind))
+    }
+}
@@ -0,0 +1,278 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, PhysicalPosition, State, WebviewWindowBuilder};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::cursor;
+use crate::dnd;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::history::{self, HistoryOrigin};
+use crate::selection;
+use crate::theme;
+use crate::translation::{self, TranslationResult};
+
+pub const POPUP_LABEL: &str = "translation-popup";
+
+const POPUP_WIDTH: f64 = 360.0;
+const POPUP_HEIGHT: f64 = 200.0;
+// Distance from the cursor so the popup doesn't cover the selection itself
+const CURSOR_OFFSET: f64 = 16.0;
+// Space between a rectangle anchor and the popup
+const RECT_GAP: f64 = 4.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum PopupContent {
+    Pending { text: String },
+    Done { result: TranslationResult },
+    Failed { error: String, code: String },
+}
+
+// `code` is AppError's, so the popup can offer e.g. a link to the key settings
+fn failed(error: &AppError) -> PopupContent {
+    PopupContent::Failed {
+        error: error.to_string(),
+        code: error.code().to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupTrigger {
+    // A deliberate key press; failures are shown in the popup
+    Press,
+    // A side effect of ordinary input such as a middle click; nothing is shown
+    // when there turns out to be no selection
+    Passive,
+    // Push-to-translate: the popup only lives while the key is held
+    Hold,
+}
+
+// True between a push-to-translate key going down and coming back up
+static HOLDING: AtomicBool = AtomicBool::new(false);
+
+// Last content pushed to the popup, for a popup page that loads after the event fired
+pub type PopupStore = Mutex<Option<PopupContent>>;
+
+fn set_content(app: &AppHandle, content: PopupContent) {
+    *app.state::<PopupStore>().lock().unwrap() = Some(content.clone());
+    events::send(app, POPUP_LABEL, AppEvent::PopupContent(content));
+}
+
+// Where the popup goes, in global physical pixels
+#[derive(Debug, Clone, Copy)]
+pub enum PopupAnchor {
+    // Beside the mouse cursor
+    Cursor,
+    // Under a screen rectangle such as the tray icon, or above it when the
+    // rectangle is at the bottom of the screen
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+}
+
+// Opens (or moves) the popup next to its anchor, kept inside the monitor the
+// anchor is on
+fn window(app: &AppHandle) -> Result<tauri::WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(POPUP_LABEL) {
+        return Ok(window);
+    }
+    let builder = WebviewWindowBuilder::new(
+        app,
+        POPUP_LABEL,
+        tauri::WebviewUrl::App("index.html#popup".into())
+    )
+    .title("Translation")
+    .inner_size(POPUP_WIDTH, POPUP_HEIGHT)
+    .decorations(false)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .visible(false);
+    theme::styled(builder, app)
+        .build()
+        .map_err(|e| format!("Failed to create popup: {}", e))
+}
+
+// Builds the hidden popup at startup so the first hotkey doesn't wait for a
+// webview to load
+pub fn prewarm(app: &AppHandle) {
+    if let Err(e) = window(app) {
+        tracing::warn!("{}", e);
+    }
+}
+
+pub fn show_near(app: &AppHandle, anchor: PopupAnchor) -> Result<(), String> {
+    let window = window(app)?;
+
+    // Anchor rectangle, then horizontal and vertical gaps in logical pixels
+    let (left, top, right, bottom, gap_x, gap_y) = match anchor {
+        PopupAnchor::Cursor => {
+            let (x, y) = cursor::position(app)?;
+            (x, y, x, y, CURSOR_OFFSET, CURSOR_OFFSET)
+        }
+        PopupAnchor::Rect { x, y, width, height } => (x, y, x + width, y + height, 0.0, RECT_GAP),
+    };
+
+    let (mut x, mut y) = (left, bottom);
+    if let Some(monitor) = cursor::monitor_at(app, (left + right) / 2.0, (top + bottom) / 2.0)? {
+        let scale = monitor.scale_factor;
+        let (width, height) = (POPUP_WIDTH * scale, POPUP_HEIGHT * scale);
+        let (gap_x, gap_y) = (gap_x * scale, gap_y * scale);
+        let screen_left = monitor.x as f64;
+        let screen_top = monitor.y as f64;
+        let screen_right = screen_left + monitor.width as f64;
+        let screen_bottom = screen_top + monitor.height as f64;
+
+        x = left + gap_x;
+        y = bottom + gap_y;
+        // Flip to the other side of the anchor rather than run off the screen
+        if x + width > screen_right {
+            x = right - gap_x - width;
+        }
+        if y + height > screen_bottom {
+            y = top - gap_y - height;
+        }
+        x = x.max(screen_left).min(screen_right - width);
+        y = y.max(screen_top).min(screen_bottom - height);
+    }
+
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| format!("Failed to position popup: {}", e))?;
+    window.show().map_err(|e| format!("Failed to show popup: {}", e))?;
+    Ok(())
+}
+
+// The key may already be up by the time the selection has been read, in
+// which case a hold-triggered popup stays closed
+fn show_for(app: &AppHandle, trigger: PopupTrigger) -> Result<(), String> {
+    if trigger == PopupTrigger::Hold && !HOLDING.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    show_near(app, PopupAnchor::Cursor)
+}
+
+// Hotkey flow: grab the foreground selection, show the popup in its pending
+// state right away, then fill it in once the provider answers
+pub async fn translate_selection(app: AppHandle, trigger: PopupTrigger) -> Result<(), String> {
+    let capture_app = app.clone();
+    let captured = tauri::async_runtime::spawn_blocking(move || selection::capture_selection(&capture_app))
+        .await
+        .map_err(|e| format!("Selection capture failed: {}", e))
+        .and_then(|result| result);
+
+    let text = match captured {
+        Ok(text) => text,
+        Err(error) if trigger == PopupTrigger::Passive => return Err(error),
+        Err(error) => {
+            // Still open the popup so the key press visibly did something
+            set_content(&app, failed(&AppError::Other(error.clone())));
+            show_for(&app, trigger)?;
+            return Err(error);
+        }
+    };
+
+    // During quiet hours passive triggers translate straight into history
+    if trigger == PopupTrigger::Passive && dnd::is_active(&app) {
+        let result = translation::translate(&app, &text, None, None).await?;
+        history::record(&app, &result, HistoryOrigin::Selection, None);
+        return Ok(());
+    }
+
+    set_content(&app, PopupContent::Pending { text: text.clone() });
+    show_for(&app, trigger)?;
+    let _ = finish_translation(&app, &text, HistoryOrigin::Selection, None, None).await;
+    Ok(())
+}
+
+// What's worth translating from the clipboard's text, if it has any
+pub fn clipboard_text(contents: Option<String>) -> Result<String, AppError> {
+    let text = contents.map(|text| text.trim().to_string()).unwrap_or_default();
+    if text.is_empty() {
+        return Err(AppError::InvalidInput("Clipboard has no text".to_string()));
+    }
+    Ok(text)
+}
+
+// Tray quick-translate: the same popup, fed from the clipboard and anchored
+// wherever the caller asks
+pub async fn translate_clipboard(app: AppHandle, anchor: PopupAnchor) -> Result<(), String> {
+    let text = match clipboard_text(app.clipboard().read_text().ok()) {
+        Ok(text) => text,
+        Err(error) => {
+            set_content(&app, failed(&error));
+            show_near(&app, anchor)?;
+            return Err(error.to_string());
+        }
+    };
+
+    show_translation(&app, &text, anchor, HistoryOrigin::Clipboard, None, None).await?;
+    Ok(())
+}
+
+// Popup for text that arrived from outside the app, e.g. a second launch.
+// Languages default to the ones in settings.
+pub async fn show_translation(
+    app: &AppHandle,
+    text: &str,
+    anchor: PopupAnchor,
+    origin: HistoryOrigin,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> Result<TranslationResult, AppError> {
+    set_content(app, PopupContent::Pending { text: text.to_string() });
+    show_near(app, anchor)?;
+    finish_translation(app, text, origin, source_lang, target_lang).await
+}
+
+async fn finish_translation(
+    app: &AppHandle,
+    text: &str,
+    origin: HistoryOrigin,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> Result<TranslationResult, AppError> {
+    let translated = translation::translate(app, text, source_lang, target_lang).await;
+    let content = match &translated {
+        Ok(result) => {
+            history::record(app, result, origin, None);
+            PopupContent::Done { result: result.clone() }
+        }
+        Err(error) => failed(error),
+    };
+    set_content(app, content);
+    translated
+}
+
+// Key-down half of push-to-translate. Returns false for auto-repeated presses
+// of a key that is already held.
+pub fn begin_hold() -> bool {
+    !HOLDING.swap(true, Ordering::SeqCst)
+}
+
+pub fn end_hold(app: &AppHandle) {
+    if HOLDING.swap(false, Ordering::SeqCst) {
+        if let Some(window) = app.get_webview_window(POPUP_LABEL) {
+            let _ = window.hide();
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_popup_content(
+    popup_store: State<'_, PopupStore>,
+) -> Result<Option<PopupContent>, AppError> {
+    Ok(popup_store.lock().unwrap().clone())
+}
+
+pub fn hide(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(POPUP_LABEL) {
+        window.hide().map_err(|e| format!("Failed to hide popup: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn hide_translation_popup(app: AppHandle) -> Result<(), AppError> {
+    Ok(hide(&app)?)
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+
+use crate::error::AppError;
+
+// Either of these turns on portable mode: a file with this name next to the
+// executable, or the command-line flag
+const MARKER_FILE: &str = "portable";
+pub const PORTABLE_FLAG: &str = "--portable";
+// Folder beside the executable that replaces the per-user app data dir
+const DATA_DIR: &str = "data";
+const STORE_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableStatus {
+    pub portable: bool,
+    pub data_dir: String,
+}
+
+fn detect() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let exe_dir = exe.parent()?;
+    let flagged = std::env::args().skip(1).any(|arg| arg == PORTABLE_FLAG);
+    (flagged || exe_dir.join(MARKER_FILE).exists()).then(|| exe_dir.join(DATA_DIR))
+}
+
+// Decided once per run so every subsystem agrees on it
+pub fn portable_dir() -> Option<&'static Path> {
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(detect).as_deref()
+}
+
+// Where settings, caches and models live. The webview's own storage is not
+// covered; it stays wherever the OS webview keeps it.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    match portable_dir() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data dir: {}", e)),
+    }
+}
+
+// Store files are resolved against the app data dir unless absolute
+pub fn store_path(name: &str) -> PathBuf {
+    match portable_dir() {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+// Opens a store whose changes reach disk once it's been left alone for
+// STORE_SAVE_DELAY, so a burst of them (dragging panels, usage counters)
+// ends in one write. The store plugin writes whatever is pending on exit.
+pub fn store(app: &AppHandle, name: &str) -> Result<Arc<Store<Wry>>, String> {
+    app.store_builder(store_path(name))
+        .auto_save(STORE_SAVE_DELAY)
+        .build()
+        .map_err(|e| format!("Failed to open {}: {}", name, e))
+}
+
+// Writes a store's pending changes now instead of after the delay; a store
+// that was never opened has none
+pub fn save_store(app: &AppHandle, name: &str) -> Result<(), String> {
+    match app.get_store(store_path(name)) {
+        Some(store) => store.save().map_err(|e| format!("Failed to save {}: {}", name, e)),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_portable_status(app: AppHandle) -> Result<PortableStatus, AppError> {
+    Ok(PortableStatus {
+        portable: portable_dir().is_some(),
+        data_dir: data_dir(&app)?.to_string_lossy().into_owned(),
+    })
+}
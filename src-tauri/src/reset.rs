@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::ocr::OcrCacheState;
+use crate::settings::{self, migrations, Settings};
+use crate::translation::usage::UsageState;
+use crate::{history, metrics, portable, WindowStore, SETTINGS_STORE};
+
+// The confirmation step has to follow the request within this long
+const TOKEN_LIFETIME: Duration = Duration::from_secs(60);
+
+// Token handed out by request_reset_token and when it was issued
+pub type ResetTokenState = Mutex<Option<(String, Instant)>>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetSummary {
+    pub settings_keys: usize,
+    pub ocr_cache_entries: usize,
+    // None when history was kept
+    pub history_entries: Option<usize>,
+    pub windows_closed: usize,
+}
+
+fn new_token() -> String {
+    // RandomState is seeded from the OS, which is plenty for a confirmation nonce
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos());
+    format!("{:016x}", hasher.finish())
+}
+
+fn take_token(state: &ResetTokenState, token: &str) -> Result<(), String> {
+    // One token, one attempt; a mistyped confirmation needs a new request
+    match state.lock().unwrap().take() {
+        Some((expected, issued)) if expected == token && issued.elapsed() < TOKEN_LIFETIME => Ok(()),
+        Some((expected, _)) if expected == token => Err("Reset confirmation expired, please try again".to_string()),
+        _ => Err("Reset confirmation does not match".to_string()),
+    }
+}
+
+fn clear_settings(app: &AppHandle) -> Result<usize, String> {
+    let store = portable::store(app, SETTINGS_STORE)?;
+    let keys = store.keys().len();
+    store.clear();
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    // Stamps the now empty file with the current schema version
+    migrations::run(app)?;
+    Ok(keys)
+}
+
+fn close_floating_windows(app: &AppHandle) -> usize {
+    let window_ids: Vec<String> = app.state::<WindowStore>().take_all();
+    window_ids
+        .iter()
+        .filter_map(|id| app.get_webview_window(id))
+        .filter(|window| window.close().is_ok())
+        .count()
+}
+
+// First half of the reset; the UI shows a warning and sends the token back
+#[tauri::command]
+pub async fn request_reset_token(token_state: State<'_, ResetTokenState>) -> Result<String, AppError> {
+    let token = new_token();
+    *token_state.lock().unwrap() = Some((token.clone(), Instant::now()));
+    Ok(token)
+}
+
+#[tauri::command]
+pub async fn reset_app_data(
+    app: AppHandle,
+    token_state: State<'_, ResetTokenState>,
+    confirm_token: String,
+    include_history: Option<bool>,
+) -> Result<ResetSummary, AppError> {
+    take_token(&token_state, &confirm_token)?;
+
+    let windows_closed = close_floating_windows(&app);
+    let previous = settings::load(&app);
+    let settings_keys = clear_settings(&app)?;
+    // Re-registers default hotkeys, tray clicks and the rest
+    settings::apply(&app, &previous, &Settings::default());
+
+    let ocr_cache_entries = {
+        let cache = app.state::<OcrCacheState>();
+        let mut cache = cache.lock().unwrap();
+        let (entries, _, _) = cache.stats();
+        cache.clear();
+        entries
+    };
+    *app.state::<UsageState>().lock().unwrap() = None;
+    metrics::clear(&app);
+
+    let history_entries = include_history
+        .unwrap_or(false)
+        .then(|| history::clear(&app))
+        .transpose()?;
+
+    let summary = ResetSummary {
+        settings_keys,
+        ocr_cache_entries,
+        history_entries,
+        windows_closed,
+    };
+    events::broadcast(&app, AppEvent::AppDataReset(summary.clone()));
+    Ok(summary)
+}
@@ -0,0 +1,164 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager, WindowEvent};
+
+use crate::error::AppError;
+use crate::{panel_pool, popup, session, settings, WindowStore};
+
+// How often hidden windows are checked for reclaiming
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    // Resident memory in bytes
+    pub memory: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowUsage {
+    pub label: String,
+    pub visible: bool,
+    // Hidden panels kept loaded for the next one opened
+    pub pooled: bool,
+    pub idle_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    // The app and the processes it started, webview renderers included
+    pub total_memory: u64,
+    pub processes: Vec<ProcessUsage>,
+    pub windows: Vec<WindowUsage>,
+    // Floating panels whose webview was destroyed, waiting to be reopened
+    pub parked_panels: Vec<String>,
+    // Webviews destroyed for being hidden and idle since launch
+    pub reclaimed: u64,
+}
+
+// When each window last saw an event (focus, move, resize and so on)
+#[derive(Default)]
+pub struct ResourceState {
+    active: Mutex<HashMap<String, Instant>>,
+    reclaimed: AtomicU64,
+}
+
+impl ResourceState {
+    fn idle(&self, label: &str) -> Duration {
+        let mut active = self.active.lock().unwrap();
+        active.entry(label.to_string()).or_insert_with(Instant::now).elapsed()
+    }
+}
+
+// Called for every window event
+pub fn window_event(app: &AppHandle, label: &str, event: &WindowEvent) {
+    let state = app.state::<ResourceState>();
+    let mut active = state.active.lock().unwrap();
+    match event {
+        WindowEvent::Destroyed => active.remove(label),
+        _ => active.insert(label.to_string(), Instant::now()),
+    };
+}
+
+// Destroys the webviews of windows that have been hidden and untouched for
+// longer than the setting allows. Floating panels are parked so they come
+// back where they were when next needed; overlays and the main window are
+// rebuilt the next time they're shown anyway. The panel pool and the
+// prewarmed popup are left alone, since keeping those loaded for the next
+// hotkey is their point.
+fn reclaim(app: &AppHandle, limit: Duration) {
+    let state = app.state::<ResourceState>();
+    for (label, window) in app.webview_windows() {
+        let kept_warm = label == popup::POPUP_LABEL || panel_pool::contains(app, &label);
+        if window.is_visible().unwrap_or(true) || kept_warm || state.idle(&label) < limit {
+            continue;
+        }
+        let reclaimed = match app.state::<WindowStore>().contains(&label) {
+            true => session::park(app, &window),
+            false => window.destroy().map_err(|e| format!("Failed to destroy {}: {}", label, e)),
+        };
+        match reclaimed {
+            Ok(()) => {
+                state.reclaimed.fetch_add(1, Ordering::SeqCst);
+                tracing::info!("Reclaimed idle hidden window {}", label);
+            }
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+}
+
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let minutes = settings::load(&app).reclaim_hidden_after;
+            if minutes > 0 {
+                reclaim(&app, Duration::from_secs(minutes * 60));
+            }
+        }
+    });
+}
+
+// This process and everything below it. On macOS the WebKit content
+// processes are started by the system rather than by the app, so only the
+// app's own memory shows there.
+fn processes() -> Vec<ProcessUsage> {
+    let Ok(root) = sysinfo::get_current_pid() else {
+        return Vec::new();
+    };
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new().with_memory());
+    let mut family: Vec<Pid> = vec![root];
+    let mut index = 0;
+    while index < family.len() {
+        let parent = family[index];
+        family.extend(system.processes().values().filter(|p| p.parent() == Some(parent)).map(|p| p.pid()));
+        index += 1;
+    }
+    family
+        .iter()
+        .filter_map(|pid| system.process(*pid))
+        .map(|process| ProcessUsage {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            memory: process.memory(),
+        })
+        .collect()
+}
+
+// Memory of the app and its webview processes, and every window with how
+// long it's been idle, most idle first
+#[tauri::command]
+pub async fn get_resource_usage(app: AppHandle) -> Result<ResourceUsage, AppError> {
+    let processes = tauri::async_runtime::spawn_blocking(processes)
+        .await
+        .map_err(|e| format!("Failed to read process memory: {}", e))?;
+    let state = app.state::<ResourceState>();
+    let mut windows: Vec<WindowUsage> = app
+        .webview_windows()
+        .into_iter()
+        .map(|(label, window)| WindowUsage {
+            visible: window.is_visible().unwrap_or(false),
+            pooled: panel_pool::contains(&app, &label),
+            idle_seconds: state.idle(&label).as_secs(),
+            label,
+        })
+        .collect();
+    windows.sort_by_key(|window| std::cmp::Reverse(window.idle_seconds));
+    Ok(ResourceUsage {
+        total_memory: processes.iter().map(|process| process.memory).sum(),
+        processes,
+        windows,
+        parked_panels: session::parked(&app),
+        reclaimed: state.reclaimed.load(Ordering::SeqCst),
+    })
+}
@@ -0,0 +1,215 @@
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::image::Image;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// How long to wait for the foreground app to answer the simulated copy
+const COPY_TIMEOUT: Duration = Duration::from_millis(300);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[cfg(target_os = "macos")]
+const COPY_MODIFIER: Key = Key::Meta;
+#[cfg(not(target_os = "macos"))]
+const COPY_MODIFIER: Key = Key::Control;
+
+// Reads the selection in the foreground app. The platform accessibility API is
+// tried first since it leaves the clipboard alone; apps that don't expose their
+// selection there fall back to a simulated copy. Blocking; call off the main thread.
+pub fn capture_selection(app: &AppHandle) -> Result<String, String> {
+    match platform::read_selection() {
+        Some(text) if !text.trim().is_empty() => Ok(text),
+        _ => copy_selection(app),
+    }
+}
+
+// What the clipboard held before the simulated copy, to put back afterwards
+enum Saved {
+    Empty,
+    Text(String),
+    Image(Image<'static>),
+}
+
+// Files or HTML without a plain-text copy: formats the clipboard plugin can't
+// read back. Assumed present when the clipboard can't be opened to check.
+fn holds_other_content() -> bool {
+    let Ok(mut clipboard) = arboard::Clipboard::new() else {
+        return true;
+    };
+    clipboard.get().file_list().is_ok_and(|files| !files.is_empty()) || clipboard.get().html().is_ok()
+}
+
+// Simulates the copy shortcut and restores the user's clipboard afterwards.
+// Only text and images can be read back; with files on the clipboard the
+// copy isn't attempted rather than overwriting them. An empty clipboard is
+// cleared again afterwards.
+fn copy_selection(app: &AppHandle) -> Result<String, String> {
+    let clipboard = app.clipboard();
+    let saved = match clipboard.read_text() {
+        Ok(text) => Saved::Text(text),
+        Err(_) => match clipboard.read_image() {
+            Ok(image) => Saved::Image(image.to_owned()),
+            Err(_) if holds_other_content() => {
+                return Err("The clipboard holds content that couldn't be restored; copy the text instead".to_string())
+            }
+            Err(_) => Saved::Empty,
+        },
+    };
+
+    // A sentinel lets us tell "nothing selected" apart from "selection equals clipboard"
+    let sentinel = format!(
+        "shunyaku-selection-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    clipboard
+        .write_text(sentinel.clone())
+        .map_err(|e| format!("Failed to prepare clipboard: {}", e))?;
+
+    let copied = simulate_copy().and_then(|_| {
+        let started = Instant::now();
+        loop {
+            if let Ok(text) = clipboard.read_text() {
+                if text != sentinel {
+                    return Ok(text);
+                }
+            }
+            if started.elapsed() >= COPY_TIMEOUT {
+                return Err("No text is selected".to_string());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+
+    let restored = match saved {
+        Saved::Empty => clipboard.clear(),
+        Saved::Text(text) => clipboard.write_text(text),
+        Saved::Image(image) => clipboard.write_image(&image),
+    };
+    if let Err(e) = restored {
+        tracing::warn!("Failed to restore the clipboard: {}", e);
+    }
+
+    let text = copied?;
+    if text.trim().is_empty() {
+        return Err("No text is selected".to_string());
+    }
+    Ok(text)
+}
+
+fn simulate_copy() -> Result<(), String> {
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to access keyboard input: {}", e))?;
+
+    // The hotkey's own modifiers may still be held down and would turn the copy
+    // into a different shortcut
+    for modifier in [Key::Alt, Key::Shift, Key::Control, Key::Meta] {
+        let _ = enigo.key(modifier, Direction::Release);
+    }
+
+    enigo
+        .key(COPY_MODIFIER, Direction::Press)
+        .and_then(|_| enigo.key(Key::Unicode('c'), Direction::Click))
+        .and_then(|_| enigo.key(COPY_MODIFIER, Direction::Release))
+        .map_err(|e| format!("Failed to simulate copy: {}", e))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use core_foundation::base::{CFGetTypeID, CFRelease, CFTypeRef, TCFType};
+    use core_foundation::string::{CFString, CFStringRef};
+    use std::ffi::c_void;
+    use std::ptr;
+
+    type AXUIElementRef = *const c_void;
+
+    const AX_ERROR_SUCCESS: i32 = 0;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+        fn AXUIElementCopyAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: *mut CFTypeRef,
+        ) -> i32;
+    }
+
+    // Returns an owned reference the caller must release
+    unsafe fn copy_attribute(element: AXUIElementRef, name: &'static str) -> Option<CFTypeRef> {
+        let attribute = CFString::from_static_string(name);
+        let mut value: CFTypeRef = ptr::null();
+        let error = AXUIElementCopyAttributeValue(element, attribute.as_concrete_TypeRef(), &mut value);
+        (error == AX_ERROR_SUCCESS && !value.is_null()).then_some(value)
+    }
+
+    pub fn read_selection() -> Option<String> {
+        if !unsafe { AXIsProcessTrusted() } {
+            return None;
+        }
+
+        unsafe {
+            let system = AXUIElementCreateSystemWide();
+            if system.is_null() {
+                return None;
+            }
+            let focused = copy_attribute(system, "AXFocusedUIElement");
+            CFRelease(system);
+
+            let focused = focused?;
+            let selected = copy_attribute(focused, "AXSelectedText");
+            CFRelease(focused);
+
+            let selected = selected?;
+            if CFGetTypeID(selected) != CFString::type_id() {
+                CFRelease(selected);
+                return None;
+            }
+            Some(CFString::wrap_under_create_rule(selected as CFStringRef).to_string())
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationTextPattern, UIA_TextPatternId,
+    };
+
+    pub fn read_selection() -> Option<String> {
+        // Runs on a blocking worker thread that may or may not have COM set up yet
+        let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+        let text = unsafe { focused_selection() };
+        if initialized {
+            unsafe { CoUninitialize() };
+        }
+        text
+    }
+
+    // Every COM object is dropped before returning, ahead of CoUninitialize
+    unsafe fn focused_selection() -> Option<String> {
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let focused = automation.GetFocusedElement().ok()?;
+        let pattern: IUIAutomationTextPattern = focused.GetCurrentPatternAs(UIA_TextPatternId).ok()?;
+        let ranges = pattern.GetSelection().ok()?;
+
+        let mut text = String::new();
+        for i in 0..ranges.Length().ok()? {
+            let range = ranges.GetElement(i).ok()?;
+            text.push_str(&range.GetText(-1).ok()?.to_string());
+        }
+        Some(text)
+    }
+}
+
+// No accessibility route on Linux; the copy fallback handles it
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub fn read_selection() -> Option<String> {
+        None
+    }
+}
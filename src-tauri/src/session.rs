@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager, WebviewWindow};
+
+use crate::error::AppError;
+use crate::events::AppEvent;
+use crate::history::{self, HistoryEntry};
+use crate::{portable, settings, window_ready, WindowStore};
+
+const SESSION_STORE: &str = "session.json";
+const PANELS_KEY: &str = "panels";
+// Dragging a panel fires a move event per frame; the session is written once
+// things have been still this long
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+// A floating panel as it was left, in logical pixels. Panels come back under
+// the same label, so history entries still point at them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PanelSession {
+    pub window_id: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub always_on_top: bool,
+    // Last translation the panel showed
+    pub entry_id: Option<u64>,
+}
+
+// `generation` lets a burst of changes end in a single write; `closing` stops
+// the windows torn down on quit from being saved as closed. Parked panels had
+// their webview destroyed while hidden and stay in the session until reopened.
+#[derive(Default)]
+pub struct SessionState {
+    generation: AtomicU64,
+    closing: AtomicBool,
+    parked: Mutex<HashMap<String, PanelSession>>,
+}
+
+fn panel(app: &AppHandle, window: &WebviewWindow) -> Result<PanelSession, String> {
+    let scale = window.scale_factor().map_err(|e| format!("Failed to read scale factor: {}", e))?;
+    let position = window
+        .outer_position()
+        .map_err(|e| format!("Failed to read panel position: {}", e))?
+        .to_logical::<f64>(scale);
+    let size = window
+        .inner_size()
+        .map_err(|e| format!("Failed to read panel size: {}", e))?
+        .to_logical::<f64>(scale);
+    Ok(PanelSession {
+        window_id: window.label().to_string(),
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        always_on_top: window.is_always_on_top().unwrap_or(false),
+        entry_id: history::latest_for_window(app, window.label())?.map(|entry| entry.id),
+    })
+}
+
+fn capture(app: &AppHandle) -> Result<Vec<PanelSession>, String> {
+    let labels = app.state::<WindowStore>().ids();
+    let parked = app.state::<SessionState>().parked.lock().unwrap().clone();
+    labels
+        .iter()
+        .filter_map(|label| match app.get_webview_window(label) {
+            Some(window) => Some(panel(app, &window)),
+            None => parked.get(label).cloned().map(Ok),
+        })
+        .collect()
+}
+
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    let panels = capture(app)?;
+    let store = portable::store(app, SESSION_STORE)?;
+    store.set(
+        PANELS_KEY,
+        serde_json::to_value(&panels).map_err(|e| format!("Failed to serialize session: {}", e))?,
+    );
+    Ok(())
+}
+
+fn saved(app: &AppHandle) -> Result<Vec<PanelSession>, String> {
+    let store = portable::store(app, SESSION_STORE)?;
+    Ok(store
+        .get(PANELS_KEY)
+        .and_then(|panels| serde_json::from_value(panels).ok())
+        .unwrap_or_default())
+}
+
+// Called for panel moves, resizes, opens and closes and for new translations
+pub fn changed(app: &AppHandle) {
+    let state = app.state::<SessionState>();
+    if state.closing.load(Ordering::SeqCst) {
+        return;
+    }
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DELAY).await;
+        let state = app.state::<SessionState>();
+        if state.generation.load(Ordering::SeqCst) != generation || state.closing.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = save(&app) {
+            tracing::error!("{}", e);
+        }
+    });
+}
+
+// Writes the final state on quit, before the panels are destroyed
+pub fn finish(app: &AppHandle) {
+    let state = app.state::<SessionState>();
+    if state.closing.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let Err(e) = save(app).and_then(|()| portable::save_store(app, SESSION_STORE)) {
+        tracing::error!("{}", e);
+    }
+}
+
+fn reopen(app: &AppHandle, panel: &PanelSession) -> Result<WebviewWindow, String> {
+    let window = crate::open_panel(app, &panel.window_id)?;
+    let _ = window.set_position(tauri::LogicalPosition::new(panel.x, panel.y));
+    let _ = window.set_size(tauri::LogicalSize::new(panel.width, panel.height));
+    let _ = window.set_always_on_top(panel.always_on_top);
+    // Entries deleted since then leave the panel empty
+    if let Some(entry) = panel.entry_id.and_then(|id| history::get(app, id).ok().flatten()) {
+        window_ready::send_when_ready(app, &panel.window_id, AppEvent::ShowHistoryEntry(entry));
+    }
+    Ok(window)
+}
+
+// Reopens the saved panels that aren't open already and returns their labels
+pub fn restore(app: &AppHandle) -> Result<Vec<String>, String> {
+    let mut restored = Vec::new();
+    for panel in saved(app)? {
+        if app.get_webview_window(&panel.window_id).is_some() {
+            continue;
+        }
+        // A parked panel comes back as it was parked rather than as last saved
+        let panel = unpark(app, &panel.window_id).unwrap_or(panel);
+        reopen(app, &panel)?;
+        restored.push(panel.window_id);
+    }
+    Ok(restored)
+}
+
+// Destroys a hidden panel's webview, keeping its place in the session and in
+// the WindowStore so `window` can bring it back
+pub fn park(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let panel = panel(app, window)?;
+    let label = panel.window_id.clone();
+    app.state::<SessionState>().parked.lock().unwrap().insert(label.clone(), panel);
+    if let Err(e) = window.destroy() {
+        unpark(app, &label);
+        return Err(format!("Failed to destroy {}: {}", label, e));
+    }
+    Ok(())
+}
+
+pub fn is_parked(app: &AppHandle, window_id: &str) -> bool {
+    app.state::<SessionState>().parked.lock().unwrap().contains_key(window_id)
+}
+
+// Drops a parked panel, returning where it was
+pub fn unpark(app: &AppHandle, window_id: &str) -> Option<PanelSession> {
+    app.state::<SessionState>().parked.lock().unwrap().remove(window_id)
+}
+
+pub fn parked(app: &AppHandle) -> Vec<String> {
+    app.state::<SessionState>().parked.lock().unwrap().keys().cloned().collect()
+}
+
+// A floating panel by label, reopening it first if it was parked
+pub fn window(app: &AppHandle, window_id: &str) -> Option<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(window_id) {
+        return Some(window);
+    }
+    let panel = unpark(app, window_id)?;
+    match reopen(app, &panel) {
+        Ok(window) => Some(window),
+        Err(e) => {
+            tracing::error!("Failed to reopen parked panel {}: {}", window_id, e);
+            None
+        }
+    }
+}
+
+// Brings the last session back if the setting allows and keeps it saved
+pub fn start(app: &AppHandle) {
+    if settings::load(app).restore_session {
+        if let Err(e) = restore(app) {
+            tracing::error!("Failed to restore session: {}", e);
+        }
+    }
+    let handle = app.clone();
+    app.listen_any("history-changed", move |_| changed(&handle));
+}
+
+#[tauri::command]
+pub async fn restore_session(app: AppHandle) -> Result<Vec<String>, AppError> {
+    Ok(restore(&app)?)
+}
+
+// What a panel should show when it loads, e.g. after being restored
+#[tauri::command]
+pub async fn get_panel_entry(app: AppHandle, window_id: String) -> Result<Option<HistoryEntry>, AppError> {
+    Ok(history::latest_for_window(&app, &window_id)?)
+}
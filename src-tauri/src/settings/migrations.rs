@@ -0,0 +1,100 @@
+use serde_json::{json, Map, Value};
+use tauri::AppHandle;
+use tauri_plugin_store::resolve_store_path;
+
+use super::store_path;
+use crate::{portable, SETTINGS_STORE};
+
+// Settings files without this key predate versioning and count as version 1
+pub const VERSION_KEY: &str = "schemaVersion";
+
+type Migration = fn(&mut Map<String, Value>);
+
+// MIGRATIONS[n] upgrades a version n + 1 file to version n + 2. Append new
+// steps here; never edit one that has shipped.
+const MIGRATIONS: &[Migration] = &[tray_click_action_to_bindings];
+
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+// v1 → v2: the single left-click "trayClickAction" became the per-button
+// "trayClickBindings"
+fn tray_click_action_to_bindings(settings: &mut Map<String, Value>) {
+    let Some(old) = settings.remove("trayClickAction") else {
+        return;
+    };
+    if settings.contains_key("trayClickBindings") {
+        return;
+    }
+    let left = match old.as_str() {
+        Some("quickTranslate") => "quickTranslate",
+        Some("newPanel") => "newPanel",
+        _ => "showMenu",
+    };
+    settings.insert("trayClickBindings".to_string(), json!({ "left": left }));
+}
+
+// Runs every step after `version` over a settings object
+pub fn upgrade(settings: &mut Map<String, Value>, version: u64) {
+    let start = version.clamp(1, CURRENT_VERSION) - 1;
+    for migration in &MIGRATIONS[start as usize..] {
+        migration(settings);
+    }
+}
+
+// Keeps the pre-migration file next to the original in case a step loses
+// something the user cared about
+fn back_up(app: &AppHandle, version: u64) -> Result<(), String> {
+    let path = resolve_store_path(app, store_path())
+        .map_err(|e| format!("Failed to resolve settings path: {}", e))?;
+    let backup = path.with_extension(format!("v{}.bak.json", version));
+    std::fs::copy(&path, &backup)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to back up settings: {}", e))
+}
+
+// Brings the settings file up to CURRENT_VERSION. Runs at startup before
+// anything reads settings.
+pub fn run(app: &AppHandle) -> Result<(), String> {
+    let store = portable::store(app, SETTINGS_STORE)?;
+    let entries = store.entries();
+
+    let stored = store.get(VERSION_KEY).and_then(|value| value.as_u64());
+    let version = match stored {
+        Some(version) => version.max(1),
+        // A fresh install has nothing to migrate
+        None if entries.is_empty() => CURRENT_VERSION,
+        None => 1,
+    };
+    if stored == Some(CURRENT_VERSION) {
+        return Ok(());
+    }
+    if version > CURRENT_VERSION {
+        // Written by a newer release; read what we understand and leave the rest
+        tracing::warn!(
+            "Settings schema version {} is newer than {}, skipping migrations",
+            version, CURRENT_VERSION
+        );
+        return Ok(());
+    }
+
+    if version < CURRENT_VERSION {
+        back_up(app, version)?;
+
+        let mut settings: Map<String, Value> = entries.into_iter().collect();
+        upgrade(&mut settings, version);
+
+        for key in store.keys() {
+            if !settings.contains_key(&key) {
+                store.delete(&key);
+            }
+        }
+        for (key, value) in settings {
+            store.set(key, value);
+        }
+    }
+
+    store.set(VERSION_KEY, CURRENT_VERSION);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
@@ -0,0 +1,695 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::api::ApiServerSettings;
+use crate::bridge::BridgeSettings;
+use crate::dictionary::user::{self, UserWord};
+use crate::dnd::DndSchedule;
+use crate::documents::folders::{self, WatchedFolder};
+use crate::documents::DocumentOutput;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::history::anki::AnkiSettings;
+use crate::history::HistoryOrigin;
+use crate::history::retention::RetentionPolicy;
+use crate::hotkeys::double_tap::{self, DoubleTapConfig};
+use crate::hotkeys::mouse::{self, MouseTriggerConfig};
+use crate::hotkeys::{self, HotkeyAction};
+use crate::japanese::tokenizer;
+use crate::mpv::MpvSettings;
+use crate::nudge::{self, NudgeSettings};
+use crate::onboarding::OnboardingStep;
+use crate::theme::{self, ThemePreference};
+use crate::speech::autoplay::AutoSpeak;
+use crate::speech::pronunciation::PronunciationSettings;
+use crate::speech::voices::{self, VoiceSettings};
+use crate::ocr::profiles::{default_profiles, validate_profiles, CaptureProfile};
+use crate::translation::presets::{self, LanguagePreset};
+use crate::translation::{self, usage, GlossaryEntry, ProviderKind};
+use crate::tray::clicks::{self, TrayClickBindings};
+use crate::stream::StreamOutputSettings;
+use crate::tray::{dock, TRAY_ID};
+use crate::updater::UpdateChannel;
+use crate::webhooks::WebhookSettings;
+use crate::{api, bridge, history, portable, stream, SETTINGS_STORE};
+use profiles::{SettingsProfile, DEFAULT_PROFILE};
+use sync::SyncSettings;
+
+pub mod migrations;
+pub mod profiles;
+pub mod reload;
+pub mod sync;
+pub mod transfer;
+
+// Machine-specific settings. Exports and the sync folder never carry them,
+// so a shared file can't leak or overwrite API keys. Speech voices are
+// named after what this machine's engine has installed, and the API server
+// and extension bridge tokens belong to the clients set up on this machine,
+// as do the paths of watched folders and the stream output file. Webhook
+// URLs often carry a token.
+const LOCAL_KEYS: [&str; 8] = [
+    "apiKeys",
+    "sync",
+    "speechVoices",
+    "apiServer",
+    "extensionBridge",
+    "watchedFolders",
+    "webhooks",
+    "streamOutput",
+];
+
+// Stands in for secret values in everything sent to webviews
+const SECRET_MASK: &str = "••••••••";
+
+const FORMALITIES: [&str; 5] = ["default", "more", "less", "prefer_more", "prefer_less"];
+
+// Held while settings are written out and while hot reload swaps in the
+// file, so a reload can't land between a save's changes and its write
+static STORE_WRITE: Mutex<()> = Mutex::new(());
+
+// Every user preference the backend knows about. Each top-level field is its
+// own key in the settings store, so the file stays readable and an invalid
+// value only resets that one key to its default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub translation_provider: ProviderKind,
+    pub api_keys: HashMap<String, String>,
+    pub source_language: String,
+    pub target_language: String,
+    pub formality: Option<String>,
+    pub preserve_formatting: bool,
+    pub glossary: Vec<GlossaryEntry>,
+    pub language_presets: Vec<LanguagePreset>,
+    // Preset last applied, so cycling knows where it is
+    pub active_preset: Option<String>,
+    pub hotkeys: HashMap<HotkeyAction, String>,
+    pub double_tap: DoubleTapConfig,
+    pub mouse_triggers: MouseTriggerConfig,
+    pub nudge: NudgeSettings,
+    pub window_defaults: WindowDefaults,
+    pub watcher: WatcherSettings,
+    pub capture_profiles: Vec<CaptureProfile>,
+    pub auto_translate: bool,
+    pub notifications_enabled: bool,
+    pub do_not_disturb: DndSchedule,
+    pub tray_click_bindings: TrayClickBindings,
+    pub menubar_only: bool,
+    // Leave the main window closed at launch, as --minimized does
+    pub start_hidden: bool,
+    pub theme: ThemePreference,
+    pub anki: AnkiSettings,
+    pub history_retention: RetentionPolicy,
+    // At-rest encryption of the history database, keyed from the OS keychain
+    pub encrypt_history: bool,
+    // Repeats of a translation update its entry rather than adding another
+    pub deduplicate_history: bool,
+    // Reopen the floating panels left open last time, with their content
+    pub restore_session: bool,
+    // Minutes a hidden window may sit unused before its webview is destroyed
+    // to free memory; 0 keeps them
+    pub reclaim_hidden_after: u64,
+    // Mark rare words in per-word breakdowns of Japanese text
+    pub highlight_rare_words: bool,
+    // List JMnedict names after regular dictionary results
+    pub lookup_names: bool,
+    // Word list that overrides the bundled dictionaries and the analyzer
+    pub user_dictionary: Vec<UserWord>,
+    // Text-to-speech voice, rate and pitch by language code
+    pub speech_voices: HashMap<String, VoiceSettings>,
+    pub pronunciation: PronunciationSettings,
+    // Read out finished translations, chosen per origin; missing means off
+    pub auto_speak: HashMap<HistoryOrigin, AutoSpeak>,
+    pub api_server: ApiServerSettings,
+    pub extension_bridge: BridgeSettings,
+    // Where translations of dropped text and Markdown files go
+    pub document_output: DocumentOutput,
+    // Folders whose new files are translated automatically
+    pub watched_folders: Vec<WatchedFolder>,
+    pub webhooks: WebhookSettings,
+    pub stream_output: StreamOutputSettings,
+    // Translated subtitles for videos playing in mpv
+    pub mpv: MpvSettings,
+    pub profiles: Vec<SettingsProfile>,
+    pub active_profile: String,
+    pub onboarding_completed: Vec<OnboardingStep>,
+    pub sync: SyncSettings,
+    pub update_channel: UpdateChannel,
+    // Look for a new release at launch and daily; downloading still asks
+    pub auto_check_updates: bool,
+    // Count feature use locally for export_metrics; off unless opted into
+    pub usage_metrics: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let mut settings = Self {
+            translation_provider: ProviderKind::Deepl,
+            api_keys: HashMap::new(),
+            source_language: "auto".to_string(),
+            target_language: "ja".to_string(),
+            formality: None,
+            preserve_formatting: false,
+            glossary: Vec::new(),
+            language_presets: presets::default_presets(),
+            active_preset: None,
+            hotkeys: HotkeyAction::ALL
+                .iter()
+                .map(|action| (*action, action.default_accelerator().to_string()))
+                .collect(),
+            double_tap: DoubleTapConfig::default(),
+            mouse_triggers: MouseTriggerConfig::default(),
+            nudge: NudgeSettings::default(),
+            window_defaults: WindowDefaults::default(),
+            watcher: WatcherSettings::default(),
+            capture_profiles: default_profiles(),
+            auto_translate: true,
+            notifications_enabled: true,
+            do_not_disturb: DndSchedule::default(),
+            tray_click_bindings: TrayClickBindings::default(),
+            menubar_only: false,
+            start_hidden: false,
+            theme: ThemePreference::System,
+            anki: AnkiSettings::default(),
+            history_retention: RetentionPolicy::default(),
+            encrypt_history: false,
+            deduplicate_history: true,
+            restore_session: true,
+            reclaim_hidden_after: 15,
+            highlight_rare_words: false,
+            lookup_names: true,
+            user_dictionary: Vec::new(),
+            speech_voices: HashMap::new(),
+            pronunciation: PronunciationSettings::default(),
+            auto_speak: HashMap::new(),
+            api_server: ApiServerSettings::default(),
+            extension_bridge: BridgeSettings::default(),
+            document_output: DocumentOutput::default(),
+            watched_folders: Vec::new(),
+            webhooks: WebhookSettings::default(),
+            stream_output: StreamOutputSettings::default(),
+            mpv: MpvSettings::default(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE.to_string(),
+            onboarding_completed: Vec::new(),
+            sync: SyncSettings::default(),
+            update_channel: UpdateChannel::Stable,
+            auto_check_updates: true,
+            usage_metrics: false,
+        };
+        let profile = SettingsProfile::from_settings(DEFAULT_PROFILE, &settings);
+        settings.profiles.push(profile);
+        settings
+    }
+}
+
+// Geometry and behaviour of newly opened floating panels, in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WindowDefaults {
+    pub width: f64,
+    pub height: f64,
+    pub x: f64,
+    pub y: f64,
+    pub always_on_top: bool,
+}
+
+impl Default for WindowDefaults {
+    fn default() -> Self {
+        Self {
+            width: 400.0,
+            height: 300.0,
+            x: 100.0,
+            y: 100.0,
+            always_on_top: true,
+        }
+    }
+}
+
+impl WindowDefaults {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width < nudge::MIN_WIDTH || self.height < nudge::MIN_HEIGHT {
+            return Err(format!(
+                "Panels must be at least {}×{}",
+                nudge::MIN_WIDTH,
+                nudge::MIN_HEIGHT
+            ));
+        }
+        if !self.x.is_finite() || !self.y.is_finite() || self.width > 10000.0 || self.height > 10000.0 {
+            return Err("Panel geometry is out of range".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Clipboard watcher tuning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatcherSettings {
+    pub enabled: bool,
+    pub interval_ms: u64,
+    // Clipboard text outside this many characters is left alone
+    pub min_length: usize,
+    pub max_length: usize,
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 500,
+            min_length: 1,
+            max_length: 5000,
+        }
+    }
+}
+
+impl WatcherSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_ms < 100 || self.interval_ms > 10000 {
+            return Err("Watcher interval must be between 100 and 10000 ms".to_string());
+        }
+        if self.min_length == 0 || self.min_length > self.max_length {
+            return Err("Watcher length limits must satisfy 1 ≤ min ≤ max".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    // Settings key, or key.subkey for individual hotkeys
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// Returned by update_settings so the settings UI can mark each bad field
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsError {
+    pub message: String,
+    pub fields: Vec<FieldError>,
+}
+
+impl From<String> for SettingsError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            fields: Vec::new(),
+        }
+    }
+}
+
+fn check_language(field: &str, code: &str, allow_auto: bool) -> Option<FieldError> {
+    if code == "auto" && !allow_auto {
+        return Some(FieldError::new(field, "Target language can't be auto-detected"));
+    }
+    let valid = !code.is_empty() && code.chars().all(|c| c.is_ascii_alphabetic() || c == '-');
+    (!valid).then(|| FieldError::new(field, format!("'{}' is not a language code", code)))
+}
+
+fn check_formality(field: &str, formality: Option<&str>) -> Option<FieldError> {
+    let formality = formality?;
+    (!FORMALITIES.contains(&formality))
+        .then(|| FieldError::new(field, format!("Formality must be one of {}", FORMALITIES.join(", "))))
+}
+
+impl Settings {
+    // Copy of these settings with one store key replaced, or the reason the
+    // value doesn't fit that key. Unknown keys are left alone.
+    fn with_field(&self, key: &str, value: Value) -> Result<Settings, serde_json::Error> {
+        let Ok(Value::Object(mut object)) = serde_json::to_value(self) else {
+            return Ok(self.clone());
+        };
+        if !object.contains_key(key) {
+            return Ok(self.clone());
+        }
+        object.insert(key.to_string(), value);
+        serde_json::from_value(Value::Object(object))
+    }
+
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        errors.extend(check_language("sourceLanguage", &self.source_language, true));
+        errors.extend(check_language("targetLanguage", &self.target_language, false));
+        errors.extend(check_formality("formality", self.formality.as_deref()));
+
+        for (index, preset) in self.language_presets.iter().enumerate() {
+            let field = format!("languagePresets.{}", index);
+            if preset.name.trim().is_empty() {
+                errors.push(FieldError::new(&field, "Preset name must not be empty"));
+            } else if self.language_presets[..index].iter().any(|p| p.name == preset.name) {
+                errors.push(FieldError::new(&field, format!("Preset '{}' is defined twice", preset.name)));
+            }
+            errors.extend(check_language(&field, &preset.source_language, true));
+            errors.extend(check_language(&field, &preset.target_language, false));
+            errors.extend(check_formality(&field, preset.formality.as_deref()));
+        }
+
+        for (action, message) in hotkeys::find_map_errors(&self.hotkeys) {
+            errors.push(FieldError::new(format!("hotkeys.{}", hotkeys::action_name(action)), message));
+        }
+
+        let sections: [(&str, Result<(), String>); 18] = [
+            ("doubleTap", self.double_tap.validate()),
+            ("mouseTriggers", self.mouse_triggers.validate()),
+            ("nudge", self.nudge.validate()),
+            ("windowDefaults", self.window_defaults.validate()),
+            ("watcher", self.watcher.validate()),
+            ("captureProfiles", validate_profiles(&self.capture_profiles)),
+            ("doNotDisturb", self.do_not_disturb.validate()),
+            ("anki", self.anki.validate()),
+            ("historyRetention", self.history_retention.validate()),
+            ("userDictionary", user::validate(&self.user_dictionary)),
+            ("speechVoices", voices::validate(&self.speech_voices)),
+            ("pronunciation", self.pronunciation.validate()),
+            ("apiServer", self.api_server.validate()),
+            ("extensionBridge", self.extension_bridge.validate()),
+            ("watchedFolders", folders::validate(&self.watched_folders)),
+            ("webhooks", self.webhooks.validate()),
+            ("streamOutput", self.stream_output.validate()),
+            ("mpv", self.mpv.validate()),
+        ];
+        for (field, result) in sections {
+            if let Err(message) = result {
+                errors.push(FieldError::new(field, message));
+            }
+        }
+        if let Err(message) = self.tray_click_bindings.validate() {
+            errors.push(FieldError::new("trayClickBindings", message));
+        }
+        if let Err(message) = translation::validate_glossary(&self.glossary) {
+            errors.push(FieldError::new("glossary", message));
+        }
+        if let Err(message) = profiles::validate(&self.profiles, &self.active_profile) {
+            errors.push(FieldError::new("profiles", message));
+        }
+        errors
+    }
+
+    // Problems in the settings that differ from `previous`. A value that was
+    // already there, such as a shortcut the desktop has since claimed, must
+    // not block saving something unrelated.
+    pub fn validate_changes(&self, previous: &Settings) -> Vec<FieldError> {
+        let (Ok(current), Ok(before)) = (serde_json::to_value(self), serde_json::to_value(previous)) else {
+            return self.validate();
+        };
+        self.validate()
+            .into_iter()
+            .filter(|error| {
+                // Nested fields like hotkeys.translateClipboard belong to their top-level key
+                let key = error.field.split('.').next().unwrap_or(&error.field);
+                current.get(key) != before.get(key)
+            })
+            .collect()
+    }
+}
+
+// Beside the executable in portable mode
+pub fn store_path() -> PathBuf {
+    portable::store_path(SETTINGS_STORE)
+}
+
+pub fn load(app: &AppHandle) -> Settings {
+    let mut settings = Settings::default();
+    let Ok(store) = portable::store(app, SETTINGS_STORE) else {
+        return settings;
+    };
+
+    for (key, value) in store.entries() {
+        match settings.with_field(&key, value) {
+            Ok(updated) => settings = updated,
+            Err(e) => tracing::warn!("Ignoring invalid setting '{}': {}", key, e),
+        }
+    }
+    settings.sync_active_profile();
+    settings
+}
+
+// Writes the keys that differ from what is stored, after copying the
+// per-profile values into the active profile. The file itself is written
+// shortly after, once changes stop coming.
+// Written through at once rather than on the store's delay: the hot reload
+// poller takes the file as the truth, and would otherwise revert changes
+// still waiting to be written
+pub fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let mut settings = settings.clone();
+    settings.sync_active_profile();
+    let store = portable::store(app, SETTINGS_STORE)?;
+    let _write = STORE_WRITE.lock().unwrap();
+    let mut changed = false;
+    for (key, value) in to_object(&settings)? {
+        if store.get(&key).as_ref() != Some(&value) {
+            store.set(key, value);
+            changed = true;
+        }
+    }
+    if changed {
+        store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
+    }
+    Ok(())
+}
+
+// Read-modify-write for code that owns one part of the settings
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let mut settings = load(app);
+    change(&mut settings);
+    save(app, &settings)?;
+    Ok(settings)
+}
+
+// Objects merge key by key so a patch can touch one nested value; anything
+// else replaces what was there
+fn merge(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+// Pushes changed settings to the subsystems that hold them in memory or in
+// the OS (registered shortcuts, tray, activation policy)
+pub fn apply(app: &AppHandle, previous: &Settings, settings: &Settings) {
+    if previous == settings {
+        return;
+    }
+    if previous.hotkeys != settings.hotkeys {
+        hotkeys::reregister_all(app);
+    }
+    if previous.double_tap != settings.double_tap {
+        double_tap::apply(app, settings.double_tap.clone());
+    }
+    if previous.mouse_triggers != settings.mouse_triggers {
+        mouse::apply(app, settings.mouse_triggers);
+    }
+    if previous.tray_click_bindings != settings.tray_click_bindings {
+        if let (Some(tray), Some(menu)) = (app.tray_by_id(TRAY_ID), app.try_state::<clicks::TrayMenu>()) {
+            if let Err(e) = clicks::apply(&tray, &menu.0, settings.tray_click_bindings) {
+                tracing::error!("Failed to update tray: {}", e);
+            }
+        }
+    }
+    if previous.menubar_only != settings.menubar_only {
+        if let Err(e) = dock::set_policy(app, settings.menubar_only) {
+            tracing::error!("{}", e);
+        }
+    }
+    if previous.encrypt_history != settings.encrypt_history {
+        history::reopen(app);
+        if settings.encrypt_history {
+            if let Err(e) = history::thumbnails::discard_all(app) {
+                tracing::error!("Failed to remove thumbnails: {}", e);
+            }
+        }
+    }
+    if previous.user_dictionary != settings.user_dictionary {
+        tokenizer::reset(app);
+    }
+    if previous.api_server != settings.api_server {
+        api::apply(app);
+    }
+    if previous.extension_bridge != settings.extension_bridge {
+        bridge::apply(app);
+    }
+    if previous.stream_output != settings.stream_output {
+        stream::apply(app);
+    }
+    if previous.theme != settings.theme {
+        theme::apply(app, settings.theme);
+    }
+    if previous.auto_translate != settings.auto_translate {
+        events::broadcast(app, AppEvent::AutoTranslateChanged(settings.auto_translate));
+    }
+    if previous.translation_provider != settings.translation_provider || previous.api_keys != settings.api_keys {
+        // The quota shown in the tray belongs to the old provider or key
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = usage::refresh(&app).await;
+        });
+    }
+    // Panels re-read languages, watcher options and the rest from this
+    events::broadcast(app, AppEvent::SettingsChanged(Box::new(settings.redacted())));
+}
+
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<Settings, AppError> {
+    Ok(load(&app).redacted())
+}
+
+fn to_object(settings: &Settings) -> Result<Map<String, Value>, String> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(object)) => Ok(object),
+        Ok(_) => Err("Failed to serialize settings".to_string()),
+        Err(e) => Err(format!("Failed to serialize settings: {}", e)),
+    }
+}
+
+// Everything but LOCAL_KEYS, for writing outside the app's own store
+pub(crate) fn shareable(settings: &Settings) -> Result<Map<String, Value>, String> {
+    let mut object = to_object(settings)?;
+    for key in LOCAL_KEYS {
+        object.remove(key);
+    }
+    Ok(object)
+}
+
+fn mask(secret: &str) -> String {
+    // The last few characters let users tell keys apart
+    let tail: String = secret.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+    format!("{}{}", SECRET_MASK, tail)
+}
+
+impl Settings {
+    // Copy safe to hand to a webview: API keys, the API server and bridge
+    // tokens and webhook secrets are replaced by placeholders. Backend code keeps using `load`
+    // for the real values.
+    pub fn redacted(&self) -> Settings {
+        let mut settings = self.clone();
+        for key in settings.api_keys.values_mut() {
+            if !key.is_empty() {
+                *key = mask(key);
+            }
+        }
+        let hooks = settings.webhooks.hooks.iter_mut().map(|hook| &mut hook.secret);
+        for token in [&mut settings.api_server.token, &mut settings.extension_bridge.token].into_iter().chain(hooks) {
+            if !token.is_empty() {
+                *token = mask(token);
+            }
+        }
+        settings
+    }
+}
+
+// A webview that sends settings it got from `get_settings` back unchanged
+// must not overwrite the real keys with their placeholders
+fn restore_secrets(previous: &Settings, patch: &mut Map<String, Value>) {
+    for (key, real) in [
+        ("apiServer", &previous.api_server.token),
+        ("extensionBridge", &previous.extension_bridge.token),
+    ] {
+        if let Some(token) = patch.get_mut(key).and_then(|section| section.get_mut("token")) {
+            if token.as_str() == Some(mask(real).as_str()) {
+                *token = Value::String(real.clone());
+            }
+        }
+    }
+    // Hooks may have been reordered, so a placeholder matches by value
+    if let Some(Value::Array(hooks)) = patch.get_mut("webhooks").and_then(|section| section.get_mut("hooks")) {
+        for secret in hooks.iter_mut().filter_map(|hook| hook.get_mut("secret")) {
+            let real = previous.webhooks.hooks.iter().find(|hook| secret.as_str() == Some(mask(&hook.secret).as_str()));
+            if let Some(real) = real.filter(|hook| !hook.secret.is_empty()) {
+                *secret = Value::String(real.secret.clone());
+            }
+        }
+    }
+    let Some(Value::Object(api_keys)) = patch.get_mut("apiKeys") else {
+        return;
+    };
+    for (provider, value) in api_keys.iter_mut() {
+        let Some(real) = previous.api_keys.get(provider) else {
+            continue;
+        };
+        if value.as_str() == Some(mask(real).as_str()) {
+            *value = Value::String(real.clone());
+        }
+    }
+}
+
+// Settings with a partial settings object laid over them, or every field that
+// is wrong with the result
+fn patched(previous: &Settings, mut patch: Map<String, Value>) -> Result<Settings, SettingsError> {
+    restore_secrets(previous, &mut patch);
+    let current = serde_json::to_value(previous).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    let mut settings = previous.clone();
+    let mut errors = Vec::new();
+    for (key, value) in patch {
+        let Some(mut merged) = current.get(&key).cloned() else {
+            errors.push(FieldError::new(key, "Unknown setting"));
+            continue;
+        };
+        merge(&mut merged, value);
+        match settings.with_field(&key, merged) {
+            Ok(updated) => settings = updated,
+            Err(e) => errors.push(FieldError::new(key, e.to_string())),
+        }
+    }
+    if errors.is_empty() {
+        errors = settings.validate_changes(previous);
+    }
+    if !errors.is_empty() {
+        return Err(SettingsError {
+            message: "Some settings are invalid".to_string(),
+            fields: errors,
+        });
+    }
+    Ok(settings)
+}
+
+// Settings from an export, a backup or the sync folder laid over `previous`, after
+// upgrading them from the schema version they were written with
+pub(crate) fn from_shared(previous: &Settings, mut shared: Map<String, Value>, version: u64) -> Result<Settings, SettingsError> {
+    if version > migrations::CURRENT_VERSION {
+        return Err(format!(
+            "Settings were written by a newer version (schema {}, this build reads up to {})",
+            version,
+            migrations::CURRENT_VERSION
+        )
+        .into());
+    }
+    migrations::upgrade(&mut shared, version);
+    for key in LOCAL_KEYS {
+        shared.remove(key);
+    }
+    patched(previous, shared)
+}
+
+// Takes a partial settings object. Nothing is saved unless every field in it
+// is valid.
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, patch: Map<String, Value>) -> Result<Settings, AppError> {
+    let previous = load(&app);
+    let settings = patched(&previous, patch)?;
+    save(&app, &settings)?;
+    apply(&app, &previous, &settings);
+    Ok(settings.redacted())
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::{apply, load, save, Settings, WindowDefaults};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::translation::{validate_glossary, GlossaryEntry, ProviderKind};
+
+pub const DEFAULT_PROFILE: &str = "Default";
+
+// A named set of the settings that differ between uses of the app, e.g.
+// "Japanese study" vs "Work docs". The active profile's values live in the
+// top-level settings, so everything else keeps reading those.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub translation_provider: ProviderKind,
+    pub source_language: String,
+    pub target_language: String,
+    pub glossary: Vec<GlossaryEntry>,
+    pub window_defaults: WindowDefaults,
+}
+
+impl Default for SettingsProfile {
+    fn default() -> Self {
+        SettingsProfile::from_settings(DEFAULT_PROFILE, &Settings::default())
+    }
+}
+
+impl SettingsProfile {
+    pub(super) fn from_settings(name: &str, settings: &Settings) -> Self {
+        Self {
+            name: name.to_string(),
+            translation_provider: settings.translation_provider,
+            source_language: settings.source_language.clone(),
+            target_language: settings.target_language.clone(),
+            glossary: settings.glossary.clone(),
+            window_defaults: settings.window_defaults,
+        }
+    }
+}
+
+impl Settings {
+    pub(super) fn sync_active_profile(&mut self) {
+        let snapshot = SettingsProfile::from_settings(&self.active_profile, self);
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == snapshot.name) {
+            *profile = snapshot;
+        }
+    }
+
+    fn activate(&mut self, name: &str) -> Result<(), String> {
+        self.sync_active_profile();
+        let profile = self
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("Profile '{}' not found", name))?;
+
+        self.active_profile = profile.name;
+        self.translation_provider = profile.translation_provider;
+        self.source_language = profile.source_language;
+        self.target_language = profile.target_language;
+        self.glossary = profile.glossary;
+        self.window_defaults = profile.window_defaults;
+        Ok(())
+    }
+}
+
+pub fn validate(profiles: &[SettingsProfile], active: &str) -> Result<(), String> {
+    for (index, profile) in profiles.iter().enumerate() {
+        if profile.name.trim().is_empty() {
+            return Err("Profile name must not be empty".to_string());
+        }
+        if profiles[..index].iter().any(|p| p.name == profile.name) {
+            return Err(format!("Profile '{}' is defined twice", profile.name));
+        }
+        profile.window_defaults.validate()?;
+        validate_glossary(&profile.glossary)?;
+    }
+    if !profiles.iter().any(|p| p.name == active) {
+        return Err(format!("Active profile '{}' does not exist", active));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub profiles: Vec<SettingsProfile>,
+    pub active: String,
+}
+
+fn profile_list(settings: Settings) -> ProfileList {
+    ProfileList {
+        profiles: settings.profiles,
+        active: settings.active_profile,
+    }
+}
+
+fn commit(app: &AppHandle, previous: &Settings, settings: Settings) -> Result<ProfileList, String> {
+    let errors = settings.validate_changes(previous);
+    if let Some(error) = errors.first() {
+        return Err(error.message.clone());
+    }
+    save(app, &settings)?;
+    apply(app, previous, &settings);
+    if previous.active_profile != settings.active_profile {
+        events::broadcast(app, AppEvent::ProfileChanged(settings.active_profile.clone()));
+    }
+    Ok(profile_list(settings))
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle) -> Result<ProfileList, AppError> {
+    Ok(profile_list(load(&app)))
+}
+
+pub fn switch(app: &AppHandle, name: &str) -> Result<ProfileList, String> {
+    let previous = load(app);
+    let mut settings = previous.clone();
+    settings.activate(name)?;
+    commit(app, &previous, settings)
+}
+
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, name: String) -> Result<ProfileList, AppError> {
+    Ok(switch(&app, &name)?)
+}
+
+// Copies a profile under a new name, leaving the active profile alone
+#[tauri::command]
+pub async fn duplicate_profile(app: AppHandle, name: String, new_name: String) -> Result<ProfileList, AppError> {
+    let previous = load(&app);
+    let mut settings = previous.clone();
+    let mut copy = settings
+        .profiles
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Profile '{}' not found", name)))?;
+    copy.name = new_name.trim().to_string();
+    settings.profiles.push(copy);
+    Ok(commit(&app, &previous, settings)?)
+}
+
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, name: String) -> Result<ProfileList, AppError> {
+    let previous = load(&app);
+    if previous.active_profile == name {
+        return Err(AppError::InvalidInput("Switch to another profile before deleting this one".to_string()));
+    }
+    let mut settings = previous.clone();
+    let before = settings.profiles.len();
+    settings.profiles.retain(|p| p.name != name);
+    if settings.profiles.len() == before {
+        return Err(AppError::NotFound(format!("Profile '{}' not found", name)));
+    }
+    Ok(commit(&app, &previous, settings)?)
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+use tauri_plugin_store::resolve_store_path;
+
+use super::{apply, load, save, store_path, STORE_WRITE};
+use crate::{portable, SETTINGS_STORE};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Re-reads the file after an outside edit. Values that fail validation keep
+// their previous setting rather than breaking the subsystem that uses them.
+fn reload(app: &AppHandle) -> Result<(), String> {
+    let store = portable::store(app, SETTINGS_STORE)?;
+    let (previous, mut settings) = {
+        let _write = STORE_WRITE.lock().unwrap();
+        let previous = load(app);
+        store
+            .reload_ignore_defaults()
+            .map_err(|e| format!("Failed to reload settings: {}", e))?;
+        (previous, load(app))
+    };
+    // Our own saves land here too and change nothing
+    if settings == previous {
+        return Ok(());
+    }
+
+    let errors = settings.validate_changes(&previous);
+    if !errors.is_empty() {
+        let before = serde_json::to_value(&previous).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        for error in &errors {
+            tracing::warn!("Ignoring edited setting '{}': {}", error.field, error.message);
+            // Hotkey errors name the action too; the whole key is restored
+            let key = error.field.split('.').next().unwrap_or(&error.field);
+            if let Some(value) = before.get(key) {
+                settings = settings
+                    .with_field(key, value.clone())
+                    .map_err(|e| format!("Failed to restore setting '{}': {}", key, e))?;
+            }
+        }
+        save(app, &settings)?;
+    }
+
+    apply(app, &previous, &settings);
+    Ok(())
+}
+
+// Polls the settings file for outside edits. A file watcher would need a new
+// dependency for something that changes a few times a day at most.
+pub fn start(app: &AppHandle) {
+    let path = match resolve_store_path(app, store_path()) {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!("Settings hot reload disabled: {}", e);
+            return;
+        }
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last = modified(&path);
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if modified(&path) == last {
+                continue;
+            }
+            if let Err(e) = reload(&app) {
+                tracing::error!("{}", e);
+            }
+            // Read again since restoring rejected values rewrites the file
+            last = modified(&path);
+        }
+    });
+}
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use super::migrations::CURRENT_VERSION;
+use super::{apply, from_shared, load, save, shareable, update, SettingsError};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+const SYNC_FILE: &str = "shunyaku-settings.json";
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+// Sync folder and what this machine last exchanged with it. Kept out of the
+// synced file itself (see LOCAL_KEYS).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    // e.g. a Dropbox or Syncthing folder; None turns syncing off
+    pub folder: Option<String>,
+    pub device_id: String,
+    // Revision of the synced file this machine last wrote or read
+    pub last_revision: u64,
+    // Hash of the shared settings at that point, to notice local edits since
+    pub last_hash: u64,
+}
+
+impl SyncSettings {
+    // Checked only when the folder is chosen: a synced drive that isn't
+    // mounted right now mustn't make the rest of the settings unsavable
+    pub fn check_folder(&self) -> Result<(), String> {
+        match self.folder.as_deref() {
+            Some(folder) if !std::path::Path::new(folder).is_dir() => {
+                Err(format!("Sync folder '{}' does not exist", folder))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncFile {
+    schema_version: u64,
+    revision: u64,
+    device_id: String,
+    device_name: String,
+    updated_at: DateTime<Utc>,
+    settings: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum SyncOutcome {
+    Disabled,
+    UpToDate,
+    Pushed { revision: u64 },
+    Pulled { revision: u64, device_name: String },
+    // Both sides changed since the last sync; nothing was written
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        remote_device: String,
+        remote_updated_at: DateTime<Utc>,
+        local: Map<String, Value>,
+        remote: Map<String, Value>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    KeepLocal,
+    KeepRemote,
+}
+
+fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "Unknown device".to_string())
+}
+
+// Random rather than derived from the machine, so two devices with the
+// same name can't end up sharing one
+fn new_device_id() -> Result<String, String> {
+    crate::api::new_token()
+}
+
+fn hash(settings: &Map<String, Value>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    Value::Object(settings.clone()).to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn read_remote(path: &PathBuf) -> Result<Option<SyncFile>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Invalid sync file: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read sync file: {}", e)),
+    }
+}
+
+fn push(app: &AppHandle, path: &PathBuf, local: Map<String, Value>, revision: u64) -> Result<SyncOutcome, SettingsError> {
+    let device_id = load(app).sync.device_id;
+    let file = SyncFile {
+        schema_version: CURRENT_VERSION,
+        revision,
+        device_id,
+        device_name: device_name(),
+        updated_at: Utc::now(),
+        settings: local.clone(),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    // Written beside the target and renamed so other devices never see half a file
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json).map_err(|e| format!("Failed to write sync file: {}", e))?;
+    std::fs::rename(&partial, path).map_err(|e| format!("Failed to write sync file: {}", e))?;
+
+    let local_hash = hash(&local);
+    update(app, |settings| {
+        settings.sync.last_revision = revision;
+        settings.sync.last_hash = local_hash;
+    })?;
+    Ok(SyncOutcome::Pushed { revision })
+}
+
+fn pull(app: &AppHandle, remote: SyncFile) -> Result<SyncOutcome, SettingsError> {
+    let previous = load(app);
+    let mut settings = from_shared(&previous, remote.settings, remote.schema_version)?;
+    // Hash what `load` will return from now on
+    settings.sync_active_profile();
+    settings.sync.last_revision = remote.revision;
+    settings.sync.last_hash = hash(&shareable(&settings)?);
+    save(app, &settings)?;
+    apply(app, &previous, &settings);
+    Ok(SyncOutcome::Pulled {
+        revision: remote.revision,
+        device_name: remote.device_name,
+    })
+}
+
+fn sync_file(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    let settings = load(app);
+    let Some(folder) = settings.sync.folder else {
+        return Ok(None);
+    };
+    if settings.sync.device_id.is_empty() {
+        let device_id = new_device_id()?;
+        update(app, |settings| settings.sync.device_id = device_id)?;
+    }
+    Ok(Some(PathBuf::from(folder).join(SYNC_FILE)))
+}
+
+pub fn sync(app: &AppHandle, resolution: Option<ConflictResolution>) -> Result<SyncOutcome, SettingsError> {
+    let Some(path) = sync_file(app)? else {
+        return Ok(SyncOutcome::Disabled);
+    };
+    let settings = load(app);
+    let local = shareable(&settings)?;
+    let local_changed = hash(&local) != settings.sync.last_hash;
+
+    let Some(remote) = read_remote(&path)? else {
+        return push(app, &path, local, 1);
+    };
+    let remote_changed = remote.revision != settings.sync.last_revision;
+
+    // Both sides may have made the same edit, e.g. on joining a folder
+    if local_changed && remote_changed && hash(&local) == hash(&remote.settings) {
+        let local_hash = hash(&local);
+        update(app, |settings| {
+            settings.sync.last_revision = remote.revision;
+            settings.sync.last_hash = local_hash;
+        })?;
+        return Ok(SyncOutcome::UpToDate);
+    }
+
+    match (local_changed, remote_changed, resolution) {
+        (false, false, _) => Ok(SyncOutcome::UpToDate),
+        (true, false, _) | (_, _, Some(ConflictResolution::KeepLocal)) => {
+            push(app, &path, local, remote.revision + 1)
+        }
+        (false, true, _) | (_, _, Some(ConflictResolution::KeepRemote)) => pull(app, remote),
+        (true, true, None) => Ok(SyncOutcome::Conflict {
+            remote_device: remote.device_name,
+            remote_updated_at: remote.updated_at,
+            local,
+            remote: remote.settings,
+        }),
+    }
+}
+
+fn report(app: &AppHandle, outcome: &SyncOutcome) {
+    match outcome {
+        SyncOutcome::Conflict { .. } => {
+            events::broadcast(app, AppEvent::SyncConflict(outcome.clone()));
+        }
+        SyncOutcome::Pushed { .. } | SyncOutcome::Pulled { .. } => {
+            events::broadcast(app, AppEvent::SyncCompleted(outcome.clone()));
+        }
+        SyncOutcome::Disabled | SyncOutcome::UpToDate => {}
+    }
+}
+
+// Syncs in the background while a folder is set. A conflict is reported once
+// per poll until the user resolves it.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match sync(&app, None) {
+                Ok(outcome) => report(&app, &outcome),
+                Err(e) => tracing::error!("Settings sync failed: {}", e.message),
+            }
+            tokio::time::sleep(SYNC_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_sync_folder(app: AppHandle) -> Result<Option<String>, AppError> {
+    Ok(load(&app).sync.folder)
+}
+
+// A new folder starts with no history, so joining one that already holds
+// another machine's settings is reported as a conflict for the user to settle
+#[tauri::command]
+pub async fn set_sync_folder(app: AppHandle, folder: Option<String>) -> Result<SyncOutcome, AppError> {
+    let sync_settings = SyncSettings {
+        folder: folder.filter(|folder| !folder.trim().is_empty()),
+        ..load(&app).sync
+    };
+    sync_settings.check_folder()?;
+    update(&app, |settings| {
+        settings.sync = SyncSettings {
+            last_revision: 0,
+            last_hash: 0,
+            ..sync_settings
+        };
+    })?;
+
+    let outcome = sync(&app, None)?;
+    report(&app, &outcome);
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn sync_settings_now(app: AppHandle) -> Result<SyncOutcome, AppError> {
+    let outcome = sync(&app, None)?;
+    report(&app, &outcome);
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn resolve_sync_conflict(
+    app: AppHandle,
+    resolution: ConflictResolution,
+) -> Result<SyncOutcome, AppError> {
+    let outcome = sync(&app, Some(resolution))?;
+    report(&app, &outcome);
+    Ok(outcome)
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+
+use super::migrations::CURRENT_VERSION;
+use super::{apply, from_shared, load, save, shareable, to_object, Settings};
+use crate::error::AppError;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsExport {
+    schema_version: u64,
+    exported_at: DateTime<Utc>,
+    settings: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingChange {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub changes: Vec<SettingChange>,
+    // False for a dry run
+    pub applied: bool,
+}
+
+fn diff(previous: &Settings, settings: &Settings) -> Result<Vec<SettingChange>, String> {
+    let before = to_object(previous)?;
+    Ok(to_object(settings)?
+        .into_iter()
+        .filter(|(key, value)| before.get(key) != Some(value))
+        .map(|(key, to)| SettingChange {
+            from: before.get(&key).cloned().unwrap_or(Value::Null),
+            key,
+            to,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn export_settings(app: AppHandle, path: String) -> Result<(), AppError> {
+    let settings = shareable(&load(&app))?;
+
+    let export = SettingsExport {
+        schema_version: CURRENT_VERSION,
+        exported_at: Utc::now(),
+        settings,
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    Ok(std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?)
+}
+
+// Files from older releases are migrated first. With `dry_run` nothing is
+// saved and the preview just lists what would change.
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    path: String,
+    dry_run: Option<bool>,
+) -> Result<ImportPreview, AppError> {
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let export: SettingsExport =
+        serde_json::from_str(&json).map_err(|e| format!("Not a settings export: {}", e))?;
+    let previous = load(&app);
+    let settings = from_shared(&previous, export.settings, export.schema_version)?;
+    let changes = diff(&previous, &settings)?;
+
+    let applied = !dry_run.unwrap_or(false);
+    if applied {
+        save(&app, &settings)?;
+        apply(&app, &previous, &settings);
+    }
+    Ok(ImportPreview { changes, applied })
+}
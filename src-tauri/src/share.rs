@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::events::{self, AppEvent};
+use crate::history::HistoryOrigin;
+use crate::popup::{self, PopupAnchor};
+use crate::{documents, ocr};
+
+// Text files up to this long go to the popup; longer ones become a document job
+const POPUP_TEXT: usize = 5000;
+const MAX_IMAGE_BYTES: u64 = 50 * 1024 * 1024;
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "bmp"];
+const TEXT_EXTENSIONS: [&str; 3] = ["txt", "md", "markdown"];
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+async fn recognize(app: &AppHandle, path: PathBuf) -> Result<(), String> {
+    let size = std::fs::metadata(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(format!("{} is too large to recognize", path.display()));
+    }
+    let image = tauri::async_runtime::spawn_blocking(move || {
+        image::open(&path)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))
+    })
+    .await
+    .map_err(|e| format!("Decode task failed: {}", e))??;
+    let recognition = ocr::recognize(app, image, None, None, None).await?;
+    if recognition.result.text.trim().is_empty() {
+        return Err("No text found in the image".to_string());
+    }
+    popup::show_translation(app, &recognition.result.text, PopupAnchor::Cursor, HistoryOrigin::Ocr, None, None).await?;
+    Ok(())
+}
+
+async fn translate(app: &AppHandle, text: &str) -> Result<(), String> {
+    popup::show_translation(app, text.trim(), PopupAnchor::Cursor, HistoryOrigin::Manual, None, None).await?;
+    Ok(())
+}
+
+// A file another app handed to Shunyaku through "Open with" or a file
+// association, as a launch argument or, on macOS, an open event. Images are
+// recognized and translated in the popup, short text files translated there
+// too, and other documents go through the same job as a dropped file.
+// Everything else is left to the main window.
+pub fn open(app: &AppHandle, path: PathBuf) {
+    let extension = extension(&path);
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = recognize(&app, path).await {
+                tracing::error!("Failed to translate shared image: {}", e);
+            }
+        });
+        return;
+    }
+    if TEXT_EXTENSIONS.contains(&extension.as_str()) {
+        match std::fs::read_to_string(&path) {
+            Ok(text) if text.trim().is_empty() => return,
+            Ok(text) if text.chars().count() <= POPUP_TEXT => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = translate(&app, &text).await {
+                        tracing::error!("Failed to translate shared text: {}", e);
+                    }
+                });
+                return;
+            }
+            _ => {}
+        }
+    }
+    if documents::is_supported(&path) {
+        documents::dropped(app, &[path]);
+        return;
+    }
+    events::broadcast(app, AppEvent::OpenFile(path.to_string_lossy().into_owned()));
+}
@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, ExitRequestApi, Manager};
+
+use crate::{history, hotkeys, instance, portable, session, tasks, updater, SETTINGS_STORE};
+
+// How long running jobs get to reach their next check and stop
+const JOB_GRACE: Duration = Duration::from_secs(5);
+const JOB_POLL: Duration = Duration::from_millis(100);
+
+// `requested` is set once quitting starts, `done` once everything has been
+// written and the exit may go ahead
+#[derive(Default)]
+pub struct ShutdownState {
+    requested: AtomicBool,
+    done: AtomicBool,
+}
+
+// For in-flight jobs, to stop at their next check rather than start more work
+pub fn exit_requested(app: &AppHandle) -> bool {
+    app.state::<ShutdownState>().requested.load(Ordering::SeqCst)
+}
+
+// Called for an explicit exit (tray Quit, the app menu, a signal). The first
+// request is held back while jobs wind down and state is written; the exit
+// asked for again afterwards goes through.
+pub fn exit(app: &AppHandle, api: &ExitRequestApi, code: i32) {
+    let state = app.state::<ShutdownState>();
+    if state.done.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    if state.requested.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::info!("Shutting down");
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        while tasks::unfinished(&app) > 0 && started.elapsed() < JOB_GRACE {
+            tokio::time::sleep(JOB_POLL).await;
+        }
+        if tasks::unfinished(&app) > 0 {
+            tracing::warn!("Quitting with {} tasks still running", tasks::unfinished(&app));
+        }
+        finish(&app);
+        app.exit(code);
+    });
+}
+
+// Writes everything out and tears the windows down. Also run from
+// RunEvent::Exit, for when the system ends the app (logout on macOS) without
+// an exit request first; only the first call does anything.
+pub fn finish(app: &AppHandle) {
+    let state = app.state::<ShutdownState>();
+    state.requested.store(true, Ordering::SeqCst);
+    if state.done.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    // Before the panels go, so they're saved as open
+    session::finish(app);
+    for (label, window) in app.webview_windows() {
+        if let Err(e) = window.destroy() {
+            tracing::warn!("Failed to close {}: {}", label, e);
+        }
+    }
+    hotkeys::unregister_all(app);
+    history::flush(app);
+    if let Err(e) = portable::save_store(app, SETTINGS_STORE) {
+        tracing::error!("{}", e);
+    }
+    instance::release();
+    tracing::info!("Shutdown complete");
+    // Last, since on Windows the installer takes over from here
+    updater::install_pending(app);
+}
+
+#[cfg(unix)]
+async fn signal() -> std::io::Result<&'static str> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    Ok(tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = hangup.recv() => "SIGHUP",
+        _ = interrupt.recv() => "SIGINT",
+    })
+}
+
+#[cfg(windows)]
+async fn signal() -> std::io::Result<&'static str> {
+    use tokio::signal::windows;
+    let mut interrupt = windows::ctrl_c()?;
+    let mut close = windows::ctrl_close()?;
+    let mut logoff = windows::ctrl_logoff()?;
+    let mut shutdown = windows::ctrl_shutdown()?;
+    Ok(tokio::select! {
+        _ = interrupt.recv() => "Ctrl+C",
+        _ = close.recv() => "console close",
+        _ = logoff.recv() => "logoff",
+        _ = shutdown.recv() => "system shutdown",
+    })
+}
+
+// Turns termination signals into the same exit tray Quit takes. A second
+// signal while that's under way ends the process straight away.
+pub fn listen(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let name = match signal().await {
+                Ok(name) => name,
+                Err(e) => {
+                    tracing::warn!("Failed to listen for termination signals: {}", e);
+                    return;
+                }
+            };
+            if exit_requested(&app) {
+                tracing::warn!("{} received again; exiting now", name);
+                std::process::exit(1);
+            }
+            tracing::info!("{} received", name);
+            app.exit(0);
+        }
+    });
+}
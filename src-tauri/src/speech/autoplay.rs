@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::{start, SpeechState, POLL_INTERVAL};
+use crate::history::HistoryOrigin;
+use crate::translation::TranslationResult;
+use crate::{dnd, settings};
+
+// What is read out when a translation from a given origin completes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoSpeak {
+    #[default]
+    Off,
+    Source,
+    Translation,
+    // Source first, then the translation
+    Both,
+}
+
+// Blocks until the utterance ends. False when something else was spoken
+// over it or it was stopped.
+fn wait(app: &AppHandle, generation: u64) -> bool {
+    let state = app.state::<SpeechState>();
+    loop {
+        {
+            let speaking = state.0.lock().unwrap();
+            if speaking.0 != generation {
+                return false;
+            }
+            if speaking.1.is_none() {
+                return true;
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn speak_all(app: &AppHandle, parts: &[(String, String)]) -> Result<(), String> {
+    for (index, (text, lang)) in parts.iter().enumerate() {
+        let generation = start(app, text, Some(lang))?;
+        if index + 1 < parts.len() && !wait(app, generation) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Called for every completed translation. Quiet hours keep it silent.
+pub fn translated(app: &AppHandle, result: &TranslationResult, origin: HistoryOrigin) {
+    let mode = settings::load(app).auto_speak.get(&origin).copied().unwrap_or_default();
+    if mode == AutoSpeak::Off || dnd::is_active(app) {
+        return;
+    }
+    let source = (result.original_text.clone(), result.source_lang.to_ascii_lowercase());
+    let translation = (result.translated_text.clone(), result.target_lang.to_ascii_lowercase());
+    let parts = match mode {
+        AutoSpeak::Off => return,
+        AutoSpeak::Source => vec![source],
+        AutoSpeak::Translation => vec![translation],
+        AutoSpeak::Both => vec![source, translation],
+    };
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = speak_all(&app, &parts) {
+            tracing::error!("Failed to speak translation: {}", e);
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::AppHandle;
+
+use super::platform::{self, AUDIO_EXTENSION};
+use super::spawn;
+use super::voices::VoiceSettings;
+use crate::error::AppError;
+use crate::portable;
+
+const CACHE_DIR: &str = "speech-cache";
+// The least recently played files go once the cache is bigger than this
+const MAX_BYTES: u64 = 100 * 1024 * 1024;
+// Longer text is spoken straight away; waiting for a whole article to be
+// recorded first would delay it, and it's rarely repeated
+pub const MAX_TEXT: usize = 500;
+
+pub fn dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(portable::data_dir(app)?.join(CACHE_DIR))
+}
+
+fn stem(lang: &str, voice: &VoiceSettings, text: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    lang.hash(&mut hasher);
+    voice.voice.hash(&mut hasher);
+    voice.rate.to_bits().hash(&mut hasher);
+    voice.pitch.to_bits().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+// Bumps the modification time, which eviction treats as last played
+pub fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+pub fn evict(dir: &Path) -> Result<(), String> {
+    let files = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = files
+        .flatten()
+        .filter_map(|file| {
+            let metadata = file.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            metadata.is_file().then(|| (file.path(), metadata.len(), modified))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= MAX_BYTES {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+    Ok(())
+}
+
+// The recording of `text`, made now unless an earlier one is on disk
+pub fn audio(app: &AppHandle, lang: &str, voice: &VoiceSettings, text: &str) -> Result<PathBuf, String> {
+    let dir = dir(app)?;
+    let stem = stem(lang, voice, text);
+    let path = dir.join(format!("{}.{}", stem, AUDIO_EXTENSION));
+    if path.is_file() {
+        touch(&path);
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    // Written aside first so a recording cut short is never played back
+    let partial = dir.join(format!("{}.part.{}", stem, AUDIO_EXTENSION));
+    let (command, input) = platform::command(lang, voice, text, Some(&partial))?;
+    let status = spawn(command, &input)?
+        .wait()
+        .map_err(|e| format!("Failed to record speech: {}", e))?;
+    if !status.success() || !partial.is_file() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(format!("Failed to record speech: {}", status));
+    }
+    std::fs::rename(&partial, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    evict(&dir)?;
+    Ok(path)
+}
+
+// Returns the bytes freed
+#[tauri::command]
+pub async fn clear_speech_cache(app: AppHandle) -> Result<u64, AppError> {
+    let dir = dir(&app)?;
+    let Ok(files) = std::fs::read_dir(&dir) else {
+        return Ok(0);
+    };
+    let mut freed = 0;
+    for file in files.flatten() {
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        if std::fs::remove_file(file.path()).is_ok() {
+            freed += size;
+        }
+    }
+    Ok(freed)
+}
@@ -0,0 +1,130 @@
+#[cfg(feature = "voice-input")]
+mod whisper;
+
+use serde::Serialize;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+use super::validate_language;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::history::{self, HistoryOrigin};
+use crate::translation::{self, TranslationResult};
+
+#[cfg(feature = "voice-input")]
+pub use whisper::WhisperModelState;
+
+// Language spoken unless the caller says otherwise
+const DEFAULT_LANGUAGE: &str = "ja";
+
+struct Capture {
+    stop: Arc<AtomicBool>,
+    worker: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct VoiceCaptureState(Mutex<Option<Capture>>);
+
+// Utterances are numbered per capture so partial results can be replaced by
+// the final one
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTranscript {
+    pub utterance: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTranslation {
+    pub utterance: u64,
+    pub result: Option<TranslationResult>,
+    pub error: Option<String>,
+}
+
+#[cfg_attr(not(feature = "voice-input"), allow(dead_code))]
+fn partial(app: &AppHandle, utterance: u64, text: String) {
+    events::broadcast(app, AppEvent::VoiceCapturePartial(VoiceTranscript { utterance, text }));
+}
+
+// A finished utterance goes through the translation pipeline like any other
+// text, and into history
+#[cfg_attr(not(feature = "voice-input"), allow(dead_code))]
+fn finish(app: &AppHandle, utterance: u64, text: String, language: &str) {
+    events::broadcast(
+        app,
+        AppEvent::VoiceCaptureResult(VoiceTranscript {
+            utterance,
+            text: text.clone(),
+        }),
+    );
+    let app = app.clone();
+    let source = (language != "auto").then(|| language.to_string());
+    tauri::async_runtime::spawn(async move {
+        let translated = translation::translate(&app, &text, source.as_deref(), None).await;
+        if let Ok(result) = &translated {
+            history::record(&app, result, HistoryOrigin::Voice, None);
+        }
+        let (result, error) = match translated {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let translation = VoiceTranslation { utterance, result, error };
+        events::broadcast(&app, AppEvent::VoiceCaptureTranslation(translation));
+    });
+}
+
+#[cfg(feature = "voice-input")]
+fn spawn_worker(app: &AppHandle, stop: Arc<AtomicBool>, language: String) -> Result<JoinHandle<()>, String> {
+    let app = app.clone();
+    std::thread::Builder::new()
+        .name("voice-capture".to_string())
+        .spawn(move || {
+            let error = whisper::run(&app, &stop, &language).err();
+            events::broadcast(&app, AppEvent::VoiceCaptureStopped(error));
+        })
+        .map_err(|e| format!("Failed to start voice capture: {}", e))
+}
+
+#[cfg(not(feature = "voice-input"))]
+fn spawn_worker(_app: &AppHandle, _stop: Arc<AtomicBool>, _language: String) -> Result<JoinHandle<()>, String> {
+    Err("This build does not include speech recognition".to_string())
+}
+
+// Listens on the default microphone until stop_voice_capture. Emits
+// voice-capture-partial while someone is speaking, voice-capture-result at
+// each pause, voice-capture-translation once that is translated, and
+// voice-capture-stopped (with an error, if any) at the end. `language` is a
+// Whisper code, or "auto".
+#[tauri::command]
+pub async fn start_voice_capture(app: AppHandle, language: Option<String>) -> Result<(), AppError> {
+    let language = language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    if language != "auto" {
+        validate_language(&language)?;
+    }
+    let state = app.state::<VoiceCaptureState>();
+    let mut capture = state.0.lock().unwrap();
+    if capture.as_ref().is_some_and(|capture| !capture.worker.is_finished()) {
+        return Err("Voice capture is already running".into());
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = spawn_worker(&app, stop.clone(), language)?;
+    *capture = Some(Capture { stop, worker });
+    Ok(())
+}
+
+// Returns once the last utterance has been transcribed
+#[tauri::command]
+pub async fn stop_voice_capture(app: AppHandle) -> Result<(), AppError> {
+    let capture = app.state::<VoiceCaptureState>().0.lock().unwrap().take();
+    let Some(capture) = capture else {
+        return Ok(());
+    };
+    capture.stop.store(true, std::sync::atomic::Ordering::SeqCst);
+    Ok(tauri::async_runtime::spawn_blocking(move || capture.worker.join())
+        .await
+        .map_err(|e| format!("Failed to stop voice capture: {}", e))?
+        .map_err(|_| "Voice capture crashed".to_string())?)
+}
@@ -0,0 +1,218 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use super::{finish, partial};
+use crate::portable;
+
+// Loaded on first capture and kept; loading takes seconds
+pub type WhisperModelState = Mutex<Option<Recognizer>>;
+
+// What Whisper expects: 16 kHz mono
+const SAMPLE_RATE: u32 = 16_000;
+const TICK: Duration = Duration::from_millis(100);
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(1500);
+// Root mean square level below which a chunk counts as silence
+const SILENCE_LEVEL: f32 = 0.01;
+// A pause this long ends an utterance
+const END_SILENCE: f32 = 0.8;
+// Whisper works on 30 s windows, so longer speech is cut before that
+const MAX_UTTERANCE: f32 = 25.0;
+// Kept from before speech starts so the first syllable isn't clipped
+const PRE_ROLL: f32 = 0.3;
+// ggml models from huggingface.co/ggerganov/whisper.cpp, best first
+const MODEL_FILES: [&str; 6] = [
+    "ggml-large-v3-turbo.bin",
+    "ggml-large-v3.bin",
+    "ggml-medium.bin",
+    "ggml-small.bin",
+    "ggml-base.bin",
+    "ggml-tiny.bin",
+];
+
+pub struct Recognizer {
+    context: WhisperContext,
+    path: PathBuf,
+}
+
+impl Recognizer {
+    fn load(path: &Path) -> Result<Self, String> {
+        let context = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        Ok(Self {
+            context,
+            path: path.to_path_buf(),
+        })
+    }
+
+    fn transcribe(&self, audio: &[f32], language: &str) -> Result<String, String> {
+        let mut state = self
+            .context
+            .create_state()
+            .map_err(|e| format!("Failed to start transcription: {}", e))?;
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(language));
+        params.set_no_context(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get().min(8));
+        params.set_n_threads(threads as i32);
+        state
+            .full(params, audio)
+            .map_err(|e| format!("Transcription failed: {}", e))?;
+
+        let mut text = String::new();
+        for segment in state.as_iter() {
+            if let Ok(segment) = segment.to_str_lossy() {
+                text.push_str(segment.trim());
+            }
+        }
+        Ok(text)
+    }
+}
+
+fn model_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = portable::data_dir(app)?.join("models").join("whisper");
+    MODEL_FILES
+        .iter()
+        .map(|file| dir.join(file))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("No Whisper model in {} (e.g. ggml-small.bin)", dir.display()))
+}
+
+fn level(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+// Linear interpolation is plenty for speech
+fn resample(samples: &[f32], rate: u32) -> Vec<f32> {
+    if rate == SAMPLE_RATE || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let step = rate as f64 / SAMPLE_RATE as f64;
+    let length = (samples.len() as f64 / step) as usize;
+    (0..length)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            let fraction = (position - index as f64) as f32;
+            samples[index] + (next - samples[index]) * fraction
+        })
+        .collect()
+}
+
+fn build<T>(device: &cpal::Device, config: StreamConfig, buffer: Arc<Mutex<Vec<f32>>>) -> Result<Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mono = data
+                    .chunks(channels)
+                    .map(|frame| frame.iter().map(|sample| sample.to_sample::<f32>()).sum::<f32>() / channels as f32);
+                buffer.lock().unwrap().extend(mono);
+            },
+            |e| tracing::error!("Microphone error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open the microphone: {}", e))
+}
+
+// The default input device, mixed down to mono into `buffer`. Returns the
+// stream, which stops when dropped, and its sample rate.
+fn record(buffer: Arc<Mutex<Vec<f32>>>) -> Result<(Stream, u32), String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("No microphone found")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to open the microphone: {}", e))?;
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build::<f32>(&device, config, buffer)?,
+        SampleFormat::I16 => build::<i16>(&device, config, buffer)?,
+        SampleFormat::U16 => build::<u16>(&device, config, buffer)?,
+        SampleFormat::I32 => build::<i32>(&device, config, buffer)?,
+        format => return Err(format!("Unsupported microphone sample format {}", format)),
+    };
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start the microphone: {}", e))?;
+    Ok((stream, config.sample_rate))
+}
+
+// Splits the microphone input at pauses and transcribes each utterance,
+// with partial results while it is still going. Runs until `stop` is set.
+pub fn run(app: &AppHandle, stop: &AtomicBool, language: &str) -> Result<(), String> {
+    let state = app.state::<WhisperModelState>();
+    let mut model = state.lock().unwrap();
+    let path = model_path(app)?;
+    if model.as_ref().map_or(true, |model| model.path != path) {
+        *model = Some(Recognizer::load(&path)?);
+    }
+    let recognizer = model.as_ref().unwrap();
+
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let (_stream, rate) = record(buffer.clone())?;
+    let mut utterance = 0;
+    let mut audio: Vec<f32> = Vec::new();
+    let mut speaking = false;
+    let mut silence = 0.0;
+    let mut last_partial = Instant::now();
+    loop {
+        std::thread::sleep(TICK);
+        let stopping = stop.load(Ordering::SeqCst);
+        let chunk = resample(&std::mem::take(&mut *buffer.lock().unwrap()), rate);
+        let seconds = chunk.len() as f32 / SAMPLE_RATE as f32;
+        if level(&chunk) >= SILENCE_LEVEL {
+            if !speaking {
+                last_partial = Instant::now();
+            }
+            speaking = true;
+            silence = 0.0;
+        } else if speaking {
+            silence += seconds;
+        }
+        audio.extend(chunk);
+
+        if !speaking {
+            let keep = (PRE_ROLL * SAMPLE_RATE as f32) as usize;
+            let excess = audio.len().saturating_sub(keep);
+            audio.drain(..excess);
+        } else if silence >= END_SILENCE || audio.len() as f32 >= MAX_UTTERANCE * SAMPLE_RATE as f32 || stopping {
+            let text = recognizer.transcribe(&audio, language)?;
+            if !text.is_empty() {
+                finish(app, utterance, text, language);
+                utterance += 1;
+            }
+            audio.clear();
+            speaking = false;
+            silence = 0.0;
+        } else if last_partial.elapsed() >= PARTIAL_INTERVAL {
+            let text = recognizer.transcribe(&audio, language)?;
+            if !text.is_empty() {
+                partial(app, utterance, text);
+            }
+            last_partial = Instant::now();
+        }
+
+        if stopping {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::japanese::kana::{is_kana, is_kanji};
+use crate::settings;
+
+pub mod autoplay;
+pub mod cache;
+pub mod listen;
+pub mod pronunciation;
+pub mod voices;
+
+// How often a running utterance is checked for having finished
+pub(super) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// The speech process currently talking, and a count that tells a finished
+// utterance from one that was replaced
+#[derive(Default)]
+pub struct SpeechState(Mutex<(u64, Option<Child>)>);
+
+// Japanese when the text has any kana or kanji, English otherwise
+fn guess_language(text: &str) -> &'static str {
+    if text.chars().any(|c| is_kana(c) || is_kanji(c)) {
+        "ja"
+    } else {
+        "en"
+    }
+}
+
+// Language codes reach shell command lines, so only letters and dashes
+pub fn validate_language(lang: &str) -> Result<(), String> {
+    if lang.is_empty() || lang.len() > 16 || !lang.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+        return Err(format!("Invalid speech language '{}'", lang));
+    }
+    Ok(())
+}
+
+// Moves the count on too, so anything waiting for the utterance to end
+// knows it was cut off
+pub fn stop(app: &AppHandle) {
+    let state = app.state::<SpeechState>();
+    let mut speaking = state.0.lock().unwrap();
+    if let Some(mut child) = speaking.1.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+        platform::cancel();
+        events::broadcast(app, AppEvent::SpeechFinished(speaking.0));
+        speaking.0 += 1;
+    }
+}
+
+fn watch(app: &AppHandle, generation: u64) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let state = app.state::<SpeechState>();
+        let mut speaking = state.0.lock().unwrap();
+        if speaking.0 != generation {
+            return;
+        }
+        let finished = match speaking.1.as_mut() {
+            Some(child) => !matches!(child.try_wait(), Ok(None)),
+            None => true,
+        };
+        if finished {
+            speaking.1 = None;
+            events::broadcast(&app, AppEvent::SpeechFinished(generation));
+            return;
+        }
+    });
+}
+
+// Text goes through stdin so it's never parsed as options or script
+fn spawn(mut command: Command, input: &str) -> Result<Child, String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start speech: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to send text to speech: {}", e))?;
+    }
+    Ok(child)
+}
+
+// Replaces whatever is being said, in the voice chosen for the language.
+// Short text is recorded once and played back from the cache after that.
+// Returns once speech has started; "speech-finished" follows when it ends.
+pub fn start(app: &AppHandle, text: &str, lang: Option<&str>) -> Result<u64, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Nothing to speak".to_string());
+    }
+    let lang = lang.filter(|lang| *lang != "auto").unwrap_or_else(|| guess_language(text));
+    validate_language(lang)?;
+
+    stop(app);
+    let voice = voices::for_language(&settings::load(app), lang);
+    let recorded = if platform::can_record() && text.chars().count() <= cache::MAX_TEXT {
+        cache::audio(app, lang, &voice, text)
+            .map_err(|e| tracing::warn!("Speaking without the cache: {}", e))
+            .ok()
+    } else {
+        None
+    };
+    let child = match recorded {
+        Some(path) => spawn(platform::play(&path), "")?,
+        None => {
+            let (command, input) = platform::command(lang, &voice, text, None)?;
+            spawn(command, &input)?
+        }
+    };
+    Ok(track(app, child))
+}
+
+// Plays an audio file in place of whatever is being said
+pub fn play(app: &AppHandle, path: &Path) -> Result<u64, String> {
+    stop(app);
+    let child = spawn(platform::play(path), "")?;
+    Ok(track(app, child))
+}
+
+fn track(app: &AppHandle, child: Child) -> u64 {
+    let generation = {
+        let state = app.state::<SpeechState>();
+        let mut speaking = state.0.lock().unwrap();
+        speaking.0 += 1;
+        speaking.1 = Some(child);
+        speaking.0
+    };
+    watch(app, generation);
+    generation
+}
+
+// Rates and pitches are multiples of normal; each engine has its own scale
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::voices::{Voice, VoiceSettings};
+
+    pub const AUDIO_EXTENSION: &str = "aiff";
+    // Words per minute at normal speed
+    const NORMAL_RATE: f32 = 175.0;
+
+    // `say -v ?` lists "Name   locale   # sample"; names can have spaces
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let output = Command::new("say")
+            .args(["-v", "?"])
+            .output()
+            .map_err(|e| format!("Failed to run say: {}", e))?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (voice, _) = line.split_once('#')?;
+                let (name, locale) = voice.trim().rsplit_once(char::is_whitespace)?;
+                Some(Voice {
+                    id: name.trim().to_string(),
+                    name: name.trim().to_string(),
+                    language: locale.replace('_', "-"),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(
+        lang: &str,
+        settings: &VoiceSettings,
+        text: &str,
+        output: Option<&Path>,
+    ) -> Result<(Command, String), String> {
+        let mut command = Command::new("say");
+        command.args(["-f", "-"]);
+        if let Some(output) = output {
+            command.arg("-o").arg(output);
+        }
+        let prefix = lang.to_ascii_lowercase();
+        let voice = settings.voice.clone().or_else(|| {
+            voices()
+                .ok()?
+                .into_iter()
+                .find(|voice| voice.language.to_ascii_lowercase().starts_with(&prefix))
+                .map(|voice| voice.id)
+        });
+        if let Some(voice) = voice {
+            command.args(["-v", &voice]);
+        }
+        if settings.rate != 1.0 {
+            command.args(["-r", &(NORMAL_RATE * settings.rate).round().to_string()]);
+        }
+        // An embedded command shifts the baseline pitch, here in semitones
+        let input = if settings.pitch != 1.0 {
+            let semitones = (12.0 * settings.pitch.log2()).round() as i32;
+            format!("[[pbas {:+}]] {}", semitones, text)
+        } else {
+            text.to_string()
+        };
+        Ok((command, input))
+    }
+
+    pub fn can_record() -> bool {
+        true
+    }
+
+    pub fn play(path: &Path) -> Command {
+        let mut command = Command::new("afplay");
+        command.arg(path);
+        command
+    }
+
+    pub fn cancel() {}
+}
+
+// System.Speech (SAPI) through PowerShell. Without a chosen voice it takes
+// the first installed one for the language.
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::voices::{Voice, VoiceSettings};
+
+    pub const AUDIO_EXTENSION: &str = "wav";
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const PREAMBLE: &str = "\
+        $ErrorActionPreference = 'Stop'; \
+        Add-Type -AssemblyName System.Speech; \
+        [Console]::InputEncoding = [Text.Encoding]::UTF8; \
+        [Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+        $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; ";
+    const LIST_SCRIPT: &str = "\
+        $s.GetInstalledVoices() | Where-Object { $_.Enabled } | ForEach-Object { \
+            \"$($_.VoiceInfo.Name)`t$($_.VoiceInfo.Culture.Name)\" }";
+    // Pitch needs SSML; the text is escaped into it
+    const SPEAK_SCRIPT: &str = "\
+        $v = $s.GetInstalledVoices() | Where-Object { $_.Enabled -and \
+            ($_.VoiceInfo.Name -eq $env:SHUNYAKU_SPEECH_VOICE -or (-not $env:SHUNYAKU_SPEECH_VOICE -and \
+            $_.VoiceInfo.Culture.Name -like \"$($env:SHUNYAKU_SPEECH_LANG)*\")) } | Select-Object -First 1; \
+        if ($v) { $s.SelectVoice($v.VoiceInfo.Name) }; \
+        $s.Rate = [int]$env:SHUNYAKU_SPEECH_RATE; \
+        if ($env:SHUNYAKU_SPEECH_OUTPUT) { $s.SetOutputToWaveFile($env:SHUNYAKU_SPEECH_OUTPUT) }; \
+        $t = [Security.SecurityElement]::Escape([Console]::In.ReadToEnd()); \
+        $s.SpeakSsml(\"<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' \
+            xml:lang='$($s.Voice.Culture.Name)'><prosody pitch='$($env:SHUNYAKU_SPEECH_PITCH)%'>$t</prosody></speak>\"); \
+        $s.Dispose()";
+    // WPF's player takes MP3 as well as WAV, but only reports the length
+    // once the file has opened
+    const PLAY_SCRIPT: &str = "\
+        Add-Type -AssemblyName PresentationCore; \
+        $p = New-Object System.Windows.Media.MediaPlayer; \
+        $p.Open([uri]$env:SHUNYAKU_SPEECH_OUTPUT); \
+        for ($i = 0; -not $p.NaturalDuration.HasTimeSpan -and $i -lt 100; $i++) { Start-Sleep -Milliseconds 50 }; \
+        $p.Play(); \
+        Start-Sleep -Milliseconds ($p.NaturalDuration.TimeSpan.TotalMilliseconds + 100); \
+        $p.Close()";
+
+    fn powershell(script: &str) -> Command {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .creation_flags(CREATE_NO_WINDOW);
+        command
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let output = powershell(&format!("{}{}", PREAMBLE, LIST_SCRIPT))
+            .output()
+            .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to list voices: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (name, culture) = line.trim().split_once('\t')?;
+                Some(Voice {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    language: culture.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(
+        lang: &str,
+        settings: &VoiceSettings,
+        text: &str,
+        output: Option<&Path>,
+    ) -> Result<(Command, String), String> {
+        // SAPI rates run from -10 (a third of normal) to 10 (three times)
+        let rate = (10.0 * settings.rate.ln() / 3f32.ln()).round().clamp(-10.0, 10.0) as i32;
+        let pitch = ((settings.pitch - 1.0) * 100.0).round() as i32;
+        let mut command = powershell(&format!("{}{}", PREAMBLE, SPEAK_SCRIPT));
+        if let Some(output) = output {
+            command.env("SHUNYAKU_SPEECH_OUTPUT", output);
+        }
+        command
+            .env("SHUNYAKU_SPEECH_LANG", lang)
+            .env("SHUNYAKU_SPEECH_VOICE", settings.voice.as_deref().unwrap_or(""))
+            .env("SHUNYAKU_SPEECH_RATE", rate.to_string())
+            .env("SHUNYAKU_SPEECH_PITCH", format!("{:+}", pitch));
+        Ok((command, text.to_string()))
+    }
+
+    pub fn can_record() -> bool {
+        true
+    }
+
+    pub fn play(path: &Path) -> Command {
+        let mut command = powershell(PLAY_SCRIPT);
+        command.env("SHUNYAKU_SPEECH_OUTPUT", path);
+        command
+    }
+
+    pub fn cancel() {}
+}
+
+// Speech Dispatcher, which desktop screen readers already use, or eSpeak NG
+// where it isn't installed
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+    use std::sync::OnceLock;
+
+    use super::voices::{Voice, VoiceSettings};
+
+    pub const AUDIO_EXTENSION: &str = "wav";
+    // eSpeak NG's words per minute and pitch (0-99) at normal
+    const ESPEAK_RATE: f32 = 175.0;
+    const ESPEAK_PITCH: f32 = 50.0;
+
+    fn installed(program: &str) -> bool {
+        Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok()
+    }
+
+    fn has_speech_dispatcher() -> bool {
+        static AVAILABLE: OnceLock<bool> = OnceLock::new();
+        *AVAILABLE.get_or_init(|| installed("spd-say"))
+    }
+
+    // Speech Dispatcher's -100 to 100, with the ends at half and double
+    fn dispatcher_scale(value: f32) -> String {
+        (100.0 * value.log2()).round().clamp(-100.0, 100.0).to_string()
+    }
+
+    fn run(command: &mut Command) -> Result<String, String> {
+        let output = command.output().map_err(|e| {
+            format!("Failed to run speech (install speech-dispatcher or espeak-ng): {}", e)
+        })?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        if has_speech_dispatcher() {
+            // NAME LANGUAGE VARIANT under a header; names can have spaces
+            let listing = run(Command::new("spd-say").arg("-L"))?;
+            return Ok(listing
+                .lines()
+                .skip(1)
+                .filter_map(|line| {
+                    let mut columns = line.split_whitespace().rev();
+                    let _variant = columns.next()?;
+                    let language = columns.next()?.to_string();
+                    let name = columns.rev().collect::<Vec<_>>().join(" ");
+                    (!name.is_empty()).then(|| Voice {
+                        id: name.clone(),
+                        name,
+                        language,
+                    })
+                })
+                .collect());
+        }
+        // Pty Language Age/Gender VoiceName File Other
+        let listing = run(Command::new("espeak-ng").arg("--voices"))?;
+        Ok(listing
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                let (language, name) = (columns.get(1)?, columns.get(3)?);
+                Some(Voice {
+                    id: name.to_string(),
+                    name: name.replace('_', " "),
+                    language: language.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    pub fn command(
+        lang: &str,
+        settings: &VoiceSettings,
+        text: &str,
+        output: Option<&Path>,
+    ) -> Result<(Command, String), String> {
+        if has_speech_dispatcher() {
+            if output.is_some() {
+                return Err("Speech Dispatcher can't record to a file".to_string());
+            }
+            let mut command = Command::new("spd-say");
+            // -e reads stdin, -w blocks until spoken so finishing can be seen
+            command.args(["-e", "-w", "-l", lang]);
+            if let Some(voice) = &settings.voice {
+                command.args(["-y", voice]);
+            }
+            command.args(["-r", &dispatcher_scale(settings.rate), "-p", &dispatcher_scale(settings.pitch)]);
+            return Ok((command, text.to_string()));
+        }
+        let mut command = Command::new("espeak-ng");
+        command.args(["--stdin", "-v", settings.voice.as_deref().unwrap_or(lang)]);
+        command.args([
+            "-s",
+            &(ESPEAK_RATE * settings.rate).round().to_string(),
+            "-p",
+            &(ESPEAK_PITCH * settings.pitch).round().min(99.0).to_string(),
+        ]);
+        if let Some(output) = output {
+            command.arg("-w").arg(output);
+        }
+        Ok((command, text.to_string()))
+    }
+
+    // Speech Dispatcher only ever speaks out loud
+    pub fn can_record() -> bool {
+        !has_speech_dispatcher()
+    }
+
+    // GStreamer, which WebKitGTK already pulls in, plays any format;
+    // PulseAudio/PipeWire's paplay and ALSA's aplay are enough for WAV
+    pub fn play(path: &Path) -> Command {
+        static PLAYER: OnceLock<&str> = OnceLock::new();
+        let player = PLAYER.get_or_init(|| {
+            ["gst-play-1.0", "paplay"]
+                .into_iter()
+                .find(|player| installed(player))
+                .unwrap_or("aplay")
+        });
+        let mut command = Command::new(player);
+        if *player == "gst-play-1.0" {
+            command.arg("--quiet");
+        }
+        command.arg(path);
+        command
+    }
+
+    // Killing spd-say leaves the daemon talking
+    pub fn cancel() {
+        if has_speech_dispatcher() {
+            let _ = Command::new("spd-say").arg("-C").status();
+        }
+    }
+}
+
+// Without `lang` (or with "auto") the language is guessed from the text
+#[tauri::command]
+pub async fn speak(app: AppHandle, text: String, lang: Option<String>) -> Result<u64, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || start(&app, &text, lang.as_deref()))
+        .await
+        .map_err(|e| format!("Speech failed: {}", e))??)
+}
+
+#[tauri::command]
+pub async fn stop_speaking(app: AppHandle) -> Result<(), AppError> {
+    stop(&app);
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use super::cache;
+use crate::dictionary;
+use crate::error::AppError;
+use crate::japanese::kana::{is_kana, to_hiragana};
+use crate::{portable, settings, translation};
+
+const CACHE_DIR: &str = "pronunciations";
+const EXTENSIONS: [&str; 5] = ["mp3", "ogg", "opus", "m4a", "wav"];
+// JapanesePod101 answers for words it has no recording of with a clip of
+// exactly this size saying so
+const JAPANESEPOD_MISSING_BYTES: usize = 52288;
+
+// Recordings of native speakers saying dictionary words. The URL template
+// takes {term} (the word as written) and {reading} (in hiragana).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PronunciationSettings {
+    pub enabled: bool,
+    pub url: String,
+}
+
+impl Default for PronunciationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            url: "https://assets.languagepod101.com/dictionary/japanese/audiomp3.php?kanji={term}&kana={reading}"
+                .to_string(),
+        }
+    }
+}
+
+impl PronunciationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err("Pronunciation URL must start with http:// or https://".to_string());
+        }
+        if !self.url.contains("{term}") && !self.url.contains("{reading}") {
+            return Err("Pronunciation URL must contain {term} or {reading}".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn url(template: &str, term: &str, reading: &str) -> String {
+    template.replace("{term}", &encode(term)).replace("{reading}", &encode(reading))
+}
+
+fn stem(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn extension(content_type: &str) -> &'static str {
+    match content_type.split(';').next().unwrap_or("").trim() {
+        "audio/ogg" | "audio/vorbis" => "ogg",
+        "audio/opus" => "opus",
+        "audio/mp4" | "audio/aac" | "audio/x-m4a" => "m4a",
+        "audio/wav" | "audio/wave" | "audio/x-wav" => "wav",
+        _ => "mp3",
+    }
+}
+
+// The headword and reading the recording is filed under. Without a reading
+// the first dictionary result for the word supplies both, so 食べた plays
+// 食べる.
+fn resolve(app: &AppHandle, word: &str, reading: Option<&str>) -> Result<(String, String), String> {
+    if let Some(reading) = reading.filter(|reading| !reading.trim().is_empty()) {
+        return Ok((word.to_string(), to_hiragana(reading.trim())));
+    }
+    if let Some(found) = dictionary::lookup(app, word, 1)?.into_iter().next() {
+        let reading = found.entry.readings.first().map(|form| form.text.clone());
+        let term = found
+            .entry
+            .kanji
+            .first()
+            .map(|form| form.text.clone())
+            .or_else(|| reading.clone());
+        if let (Some(term), Some(reading)) = (term, reading) {
+            return Ok((term, to_hiragana(&reading)));
+        }
+    }
+    if word.chars().all(is_kana) {
+        return Ok((word.to_string(), to_hiragana(word)));
+    }
+    Err(format!("Can't tell how '{}' is read", word))
+}
+
+// Downloaded once, then played from disk
+async fn recording(app: &AppHandle, url: &str) -> Result<PathBuf, String> {
+    let dir = portable::data_dir(app)?.join(CACHE_DIR);
+    let stem = stem(url);
+    if let Some(path) = EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{}.{}", stem, extension)))
+        .find(|path| path.is_file())
+    {
+        cache::touch(&path);
+        return Ok(path);
+    }
+
+    let response = translation::http_client()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch pronunciation: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch pronunciation: {}", response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let audio = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to fetch pronunciation: {}", e))?;
+    if audio.is_empty() || (url.contains("languagepod101.com") && audio.len() == JAPANESEPOD_MISSING_BYTES) {
+        return Err("No recording of this word".to_string());
+    }
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.{}", stem, extension(&content_type)));
+    std::fs::write(&path, &audio).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    cache::evict(&dir)?;
+    Ok(path)
+}
+
+// Plays a native speaker saying `word`, e.g. from a dictionary result's
+// headword and reading. Speech-finished follows as with speak.
+#[tauri::command]
+pub async fn play_pronunciation(app: AppHandle, word: String, reading: Option<String>) -> Result<u64, AppError> {
+    let word = word.trim().to_string();
+    if word.is_empty() {
+        return Err(AppError::InvalidInput("No word to pronounce".to_string()));
+    }
+    let settings = settings::load(&app).pronunciation;
+    if !settings.enabled {
+        return Err("Pronunciation audio is turned off".into());
+    }
+    let (term, reading) = {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || resolve(&app, &word, reading.as_deref()))
+            .await
+            .map_err(|e| format!("Pronunciation failed: {}", e))??
+    };
+    let path = recording(&app, &url(&settings.url, &term, &reading)).await?;
+    Ok(super::play(&app, &path)?)
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use super::{platform, validate_language};
+use crate::error::AppError;
+use crate::settings::{self, Settings};
+
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 2.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Voice {
+    // What the engine is given to select it; usually the name
+    pub id: String,
+    pub name: String,
+    // BCP 47 where the engine reports it, e.g. ja-JP
+    pub language: String,
+}
+
+// Chosen per language in the speech settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoiceSettings {
+    // None speaks with the engine's first voice for the language
+    pub voice: Option<String>,
+    // Multiples of the voice's normal speed and pitch
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+impl VoiceSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.voice.as_ref().is_some_and(|voice| voice.trim().is_empty()) {
+            return Err("Voice must not be empty".to_string());
+        }
+        for (name, value) in [("Rate", self.rate), ("Pitch", self.pitch)] {
+            if !(MIN_SCALE..=MAX_SCALE).contains(&value) {
+                return Err(format!("{} must be between {} and {}", name, MIN_SCALE, MAX_SCALE));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn validate(voices: &HashMap<String, VoiceSettings>) -> Result<(), String> {
+    for (lang, voice) in voices {
+        validate_language(lang)?;
+        voice.validate().map_err(|e| format!("{}: {}", lang, e))?;
+    }
+    Ok(())
+}
+
+// An entry for ja-JP wins over one for ja
+pub fn for_language(settings: &Settings, lang: &str) -> VoiceSettings {
+    let lang = lang.to_ascii_lowercase();
+    let primary = lang.split(['-', '_']).next().unwrap_or(&lang);
+    settings
+        .speech_voices
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&lang))
+        .or_else(|| settings.speech_voices.iter().find(|(key, _)| key.eq_ignore_ascii_case(primary)))
+        .map(|(_, voice)| voice.clone())
+        .unwrap_or_default()
+}
+
+// Every voice the platform engine has installed, for a voice picker
+#[tauri::command]
+pub async fn list_tts_voices() -> Result<Vec<Voice>, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(platform::voices)
+        .await
+        .map_err(|e| format!("Listing voices failed: {}", e))??)
+}
+
+// None goes back to the default voice at normal speed and pitch
+#[tauri::command]
+pub async fn set_tts_voice(app: AppHandle, lang: String, voice: Option<VoiceSettings>) -> Result<(), AppError> {
+    validate_language(&lang)?;
+    let lang = lang.to_ascii_lowercase();
+    Ok(settings::update(&app, |settings| match voice {
+        Some(voice) => {
+            settings.speech_voices.insert(lang, voice);
+        }
+        None => {
+            settings.speech_voices.remove(&lang);
+        }
+    })
+    .map(|_| ())?)
+}
@@ -0,0 +1,337 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api::ConnectionLimit;
+use crate::error::AppError;
+use crate::history::HistoryOrigin;
+use crate::settings;
+use crate::translation::TranslationResult;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_HEADERS: usize = 64;
+// Page loads and polls handled at once; each has a thread
+const MAX_CONNECTIONS: usize = 16;
+// How often the browser source asks for the latest text
+const POLL_MS: u32 = 500;
+
+// Live output for streaming software: the latest translation written to a
+// text file (an OBS text source can read it from there) and served on
+// 127.0.0.1 as a page to add as a browser source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StreamOutputSettings {
+    pub file_enabled: bool,
+    pub file_path: String,
+    pub server_enabled: bool,
+    pub port: u16,
+    // Empty means every origin; usually just OCR for in-game text
+    pub origins: Vec<HistoryOrigin>,
+    pub show_original: bool,
+    // Browser source styling, as CSS values
+    pub font_family: String,
+    pub font_size: u16,
+    pub text_color: String,
+    pub background: String,
+}
+
+impl Default for StreamOutputSettings {
+    fn default() -> Self {
+        Self {
+            file_enabled: false,
+            file_path: String::new(),
+            server_enabled: false,
+            port: 47825,
+            origins: vec![HistoryOrigin::Ocr],
+            show_original: false,
+            font_family: "sans-serif".to_string(),
+            font_size: 36,
+            text_color: "#ffffff".to_string(),
+            background: "transparent".to_string(),
+        }
+    }
+}
+
+// Styling goes into the page's CSS as is, so it can't contain anything that
+// ends a declaration or the style block
+fn check_css(name: &str, value: &str) -> Result<(), String> {
+    let safe = value.chars().all(|c| c.is_alphanumeric() || " #(),.%-'\"".contains(c));
+    if value.trim().is_empty() || !safe {
+        return Err(format!("{} '{}' is not a plain CSS value", name, value));
+    }
+    Ok(())
+}
+
+impl StreamOutputSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.file_enabled && !Path::new(&self.file_path).is_absolute() {
+            return Err("Stream output file must be an absolute path".to_string());
+        }
+        if self.port < 1024 {
+            return Err("Stream output port must be 1024 or higher".to_string());
+        }
+        if !(8..=200).contains(&self.font_size) {
+            return Err("Stream output font size must be between 8 and 200".to_string());
+        }
+        check_css("Font", &self.font_family)?;
+        check_css("Text color", &self.text_color)?;
+        check_css("Background", &self.background)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamText {
+    pub original: String,
+    pub translated: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+struct Server {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    worker: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct StreamState {
+    server: Mutex<(Option<Server>, Option<String>)>,
+    latest: Arc<Mutex<Option<StreamText>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamOutputStatus {
+    pub running: bool,
+    // Address to add as a browser source
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+fn page(settings: &StreamOutputSettings) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\
+         html,body{{margin:0;background:{background};}}\
+         body{{font-family:{font};font-size:{size}px;color:{color};padding:.3em;\
+         text-shadow:0 0 4px #000,0 0 2px #000;white-space:pre-wrap}}\
+         #original{{font-size:.6em;opacity:.8}}</style>\n</head>\n<body>\n\
+         <div id=\"original\"></div><div id=\"translated\"></div>\n<script>\n\
+         const showOriginal = {show_original};\n\
+         let shown = null;\n\
+         async function poll() {{\n\
+           try {{\n\
+             const latest = await (await fetch('/latest.json', {{ cache: 'no-store' }})).json();\n\
+             const stamp = latest ? latest.updatedAt : null;\n\
+             if (stamp !== shown) {{\n\
+               shown = stamp;\n\
+               document.getElementById('original').textContent = latest && showOriginal ? latest.original : '';\n\
+               document.getElementById('translated').textContent = latest ? latest.translated : '';\n\
+             }}\n\
+           }} catch (e) {{}}\n\
+           setTimeout(poll, {poll});\n\
+         }}\n\
+         poll();\n</script>\n</body>\n</html>\n",
+        background = settings.background,
+        font = settings.font_family,
+        size = settings.font_size,
+        color = settings.text_color,
+        show_original = settings.show_original,
+        poll = POLL_MS,
+    )
+}
+
+fn respond(mut stream: TcpStream, status: u16, content_type: &str, body: &str) {
+    let reason = match status {
+        200 => "OK",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Bad Request",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    );
+    let _ = stream.write_all(format!("{}\r\n{}", head, body).as_bytes());
+}
+
+// Only GETs, and only addressed to 127.0.0.1 or localhost, so a web page
+// can't reach the server through a rebound domain name
+fn handle(app: &AppHandle, stream: TcpStream, port: u16, latest: &Mutex<Option<StreamText>>) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(stream, 400, "text/plain", "Malformed request");
+    };
+    let (method, path) = (method.to_string(), target.split('?').next().unwrap_or("").to_string());
+    let mut host = None;
+    for _ in 0..MAX_HEADERS {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+    let allowed = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    if !host.is_some_and(|host| allowed.contains(&host)) {
+        return respond(stream, 403, "text/plain", "Open this page at 127.0.0.1");
+    }
+    if method != "GET" {
+        return respond(stream, 405, "text/plain", "Only GET is supported");
+    }
+
+    let latest = latest.lock().unwrap().clone();
+    match path.as_str() {
+        "/" => respond(stream, 200, "text/html; charset=utf-8", &page(&settings::load(app).stream_output)),
+        "/latest.json" => {
+            let body = serde_json::to_string(&latest).unwrap_or_else(|_| "null".to_string());
+            respond(stream, 200, "application/json", &body)
+        }
+        "/latest.txt" => {
+            let text = latest.map(|latest| latest.translated).unwrap_or_default();
+            respond(stream, 200, "text/plain; charset=utf-8", &text)
+        }
+        _ => respond(stream, 404, "text/plain", "Not found"),
+    }
+}
+
+fn serve(app: AppHandle, listener: TcpListener, port: u16, latest: Arc<Mutex<Option<StreamText>>>, stop: Arc<AtomicBool>) {
+    let limit = ConnectionLimit::new(MAX_CONNECTIONS);
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        let (Ok(stream), Some(slot)) = (stream, limit.acquire()) else {
+            continue;
+        };
+        let app = app.clone();
+        let latest = latest.clone();
+        std::thread::spawn(move || {
+            handle(&app, stream, port, &latest);
+            drop(slot);
+        });
+    }
+}
+
+fn stop(state: &StreamState) {
+    let Some(server) = state.server.lock().unwrap().0.take() else {
+        return;
+    };
+    server.stop.store(true, Ordering::SeqCst);
+    // Wakes the accept loop so it sees the flag and drops the listener
+    let _ = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, server.port).into(), READ_TIMEOUT);
+    let _ = server.worker.join();
+}
+
+fn start(app: &AppHandle, port: u16, latest: Arc<Mutex<Option<StreamText>>>) -> Result<Server, String> {
+    let listener =
+        TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(|e| format!("Failed to open port {}: {}", port, e))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let app = app.clone();
+        let stop = stop.clone();
+        std::thread::Builder::new()
+            .name("stream-output".to_string())
+            .spawn(move || serve(app, listener, port, latest, stop))
+            .map_err(|e| format!("Failed to start the stream output server: {}", e))?
+    };
+    Ok(Server { port, stop, worker })
+}
+
+// Called at startup and whenever the stream output settings change
+pub fn apply(app: &AppHandle) {
+    let state = app.state::<StreamState>();
+    stop(&state);
+    let settings = settings::load(app).stream_output;
+    let mut current = state.server.lock().unwrap();
+    *current = (None, None);
+    if !settings.server_enabled {
+        return;
+    }
+    match start(app, settings.port, state.latest.clone()) {
+        Ok(server) => current.0 = Some(server),
+        Err(e) => {
+            tracing::error!("{}", e);
+            current.1 = Some(e);
+        }
+    }
+}
+
+// Written beside the target and renamed over it, so a source reading the
+// file never sees half a translation
+fn write_file(path: &Path, text: &str) -> Result<(), String> {
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, text).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+    std::fs::rename(&partial, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Called from history::record, next to auto-speak
+pub fn translated(app: &AppHandle, result: &TranslationResult, origin: HistoryOrigin) {
+    let settings = settings::load(app).stream_output;
+    if !settings.file_enabled && !settings.server_enabled {
+        return;
+    }
+    if !settings.origins.is_empty() && !settings.origins.contains(&origin) {
+        return;
+    }
+    let text = StreamText {
+        original: result.original_text.clone(),
+        translated: result.translated_text.clone(),
+        source_lang: result.source_lang.clone(),
+        target_lang: result.target_lang.clone(),
+        updated_at: Utc::now(),
+    };
+    if settings.file_enabled {
+        let contents = match settings.show_original {
+            true => format!("{}\n{}", text.original, text.translated),
+            false => text.translated.clone(),
+        };
+        if let Err(e) = write_file(Path::new(&settings.file_path), &contents) {
+            tracing::error!("{}", e);
+        }
+    }
+    *app.state::<StreamState>().latest.lock().unwrap() = Some(text);
+}
+
+#[tauri::command]
+pub async fn get_stream_output_status(app: AppHandle) -> Result<StreamOutputStatus, AppError> {
+    let state = app.state::<StreamState>();
+    let current = state.server.lock().unwrap();
+    Ok(StreamOutputStatus {
+        running: current.0.is_some(),
+        url: current.0.as_ref().map(|server| format!("http://127.0.0.1:{}/", server.port)),
+        error: current.1.clone(),
+    })
+}
+
+// Blanks the file and the browser source, e.g. between scenes
+#[tauri::command]
+pub async fn clear_stream_output(app: AppHandle) -> Result<(), AppError> {
+    *app.state::<StreamState>().latest.lock().unwrap() = None;
+    let settings = settings::load(&app).stream_output;
+    if settings.file_enabled {
+        write_file(Path::new(&settings.file_path), "")?;
+    }
+    Ok(())
+}
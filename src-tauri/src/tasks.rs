@@ -0,0 +1,241 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::shutdown;
+
+// Finished tasks kept for list_tasks, beyond those still running
+const FINISHED_LIMIT: usize = 50;
+// How often a paused task looks for resume or cancel
+const PAUSE_POLL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskKind {
+    // Document, subtitle and PDF translation, dropped or from a watched folder
+    Document,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Running,
+    Paused,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl TaskStatus {
+    fn is_finished(self) -> bool {
+        !matches!(self, TaskStatus::Running | TaskStatus::Paused)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: u64,
+    pub kind: TaskKind,
+    // What the task works on, e.g. the document's path
+    pub label: String,
+    pub status: TaskStatus,
+    // In whatever unit the task counts; total is 0 until it's known
+    pub done: usize,
+    pub total: usize,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct Control {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+struct Task {
+    info: TaskInfo,
+    control: Arc<Control>,
+}
+
+#[derive(Default)]
+struct Tasks {
+    next: u64,
+    tasks: HashMap<u64, Task>,
+}
+
+// Long-running jobs with their progress, for any window to list, pause and
+// cancel. Every change is emitted as task-updated with the task's TaskInfo.
+#[derive(Default)]
+pub struct TaskManager(Mutex<Tasks>);
+
+impl TaskManager {
+    fn update(&self, id: u64, change: impl FnOnce(&mut TaskInfo)) -> Option<TaskInfo> {
+        let mut tasks = self.0.lock().unwrap();
+        let task = tasks.tasks.get_mut(&id)?;
+        change(&mut task.info);
+        Some(task.info.clone())
+    }
+
+    fn control(&self, id: u64) -> Option<Arc<Control>> {
+        let tasks = self.0.lock().unwrap();
+        tasks.tasks.get(&id).filter(|task| !task.info.status.is_finished()).map(|task| task.control.clone())
+    }
+}
+
+// Held by the code doing the work
+pub struct TaskHandle {
+    app: AppHandle,
+    id: u64,
+    control: Arc<Control>,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn update(&self, change: impl FnOnce(&mut TaskInfo)) {
+        if let Some(info) = self.app.state::<TaskManager>().update(self.id, change) {
+            events::broadcast(&self.app, AppEvent::TaskUpdated(info));
+        }
+    }
+
+    pub fn progress(&self, done: usize, total: usize) {
+        self.update(|info| {
+            info.done = done;
+            info.total = total;
+        });
+    }
+
+    // Called between units of work: waits out a pause, then false when the
+    // task has been cancelled or the app is quitting, and should stop
+    pub async fn proceed(&self) -> bool {
+        while self.control.paused.load(Ordering::SeqCst) && !self.control.cancelled.load(Ordering::SeqCst) {
+            if shutdown::exit_requested(&self.app) {
+                break;
+            }
+            tokio::time::sleep(PAUSE_POLL).await;
+        }
+        !self.control.cancelled.load(Ordering::SeqCst) && !shutdown::exit_requested(&self.app)
+    }
+
+    pub fn finish(self, result: Result<(), String>) {
+        let cancelled = self.control.cancelled.load(Ordering::SeqCst) || shutdown::exit_requested(&self.app);
+        self.update(|info| {
+            info.status = match (&result, cancelled) {
+                (Err(_), _) => TaskStatus::Failed,
+                (Ok(()), true) => TaskStatus::Cancelled,
+                (Ok(()), false) => TaskStatus::Completed,
+            };
+            info.error = result.err();
+            info.finished_at = Some(Utc::now());
+        });
+    }
+}
+
+// Registers a task as running; the caller reports through the handle
+pub fn start(app: &AppHandle, kind: TaskKind, label: String) -> TaskHandle {
+    let state = app.state::<TaskManager>();
+    let control = Arc::new(Control::default());
+    let info = {
+        let mut tasks = state.0.lock().unwrap();
+        tasks.next += 1;
+        let info = TaskInfo {
+            id: tasks.next,
+            kind,
+            label,
+            status: TaskStatus::Running,
+            done: 0,
+            total: 0,
+            error: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        };
+        let task = Task {
+            info: info.clone(),
+            control: control.clone(),
+        };
+        tasks.tasks.insert(info.id, task);
+        prune(&mut tasks);
+        info
+    };
+    events::broadcast(app, AppEvent::TaskUpdated(info.clone()));
+    TaskHandle {
+        app: app.clone(),
+        id: info.id,
+        control,
+    }
+}
+
+fn prune(tasks: &mut Tasks) {
+    let mut finished: Vec<u64> =
+        tasks.tasks.values().filter(|task| task.info.status.is_finished()).map(|task| task.info.id).collect();
+    finished.sort_unstable();
+    let excess = finished.len().saturating_sub(FINISHED_LIMIT);
+    for id in &finished[..excess] {
+        tasks.tasks.remove(id);
+    }
+}
+
+// Asks a task to stop at its next check; false when it already finished
+pub fn cancel(app: &AppHandle, id: u64) -> bool {
+    let Some(control) = app.state::<TaskManager>().control(id) else {
+        return false;
+    };
+    control.cancelled.store(true, Ordering::SeqCst);
+    true
+}
+
+// Tasks not yet finished, paused ones included
+pub fn unfinished(app: &AppHandle) -> usize {
+    let tasks = app.state::<TaskManager>();
+    let tasks = tasks.0.lock().unwrap();
+    tasks.tasks.values().filter(|task| !task.info.status.is_finished()).count()
+}
+
+fn set_paused(app: &AppHandle, id: u64, paused: bool) -> Result<TaskInfo, AppError> {
+    let manager = app.state::<TaskManager>();
+    let control = manager
+        .control(id)
+        .ok_or_else(|| AppError::NotFound(format!("No running task {}", id)))?;
+    control.paused.store(paused, Ordering::SeqCst);
+    let status = if paused { TaskStatus::Paused } else { TaskStatus::Running };
+    let info = manager
+        .update(id, |info| info.status = status)
+        .ok_or_else(|| AppError::NotFound(format!("No running task {}", id)))?;
+    events::broadcast(app, AppEvent::TaskUpdated(info.clone()));
+    Ok(info)
+}
+
+// Running and paused tasks, then recently finished ones, oldest first
+#[tauri::command]
+pub async fn list_tasks(task_manager: State<'_, TaskManager>) -> Result<Vec<TaskInfo>, AppError> {
+    let mut tasks: Vec<TaskInfo> =
+        task_manager.0.lock().unwrap().tasks.values().map(|task| task.info.clone()).collect();
+    tasks.sort_by_key(|info| (info.status.is_finished(), info.id));
+    Ok(tasks)
+}
+
+// Takes effect at the task's next check, e.g. before the next batch
+#[tauri::command]
+pub async fn pause_task(app: AppHandle, id: u64) -> Result<TaskInfo, AppError> {
+    set_paused(&app, id, true)
+}
+
+#[tauri::command]
+pub async fn resume_task(app: AppHandle, id: u64) -> Result<TaskInfo, AppError> {
+    set_paused(&app, id, false)
+}
+
+#[tauri::command]
+pub async fn cancel_task(app: AppHandle, id: u64) -> Result<bool, AppError> {
+    Ok(cancel(&app, id))
+}
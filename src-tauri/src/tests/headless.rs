@@ -0,0 +1,19 @@
+use tauri::test::{mock_builder, mock_context, noop_assets};
+use tauri::Manager;
+
+use crate::cli;
+use crate::metrics::MetricsState;
+use crate::translation::queue::TranslationQueue;
+
+// --text, --stdin and the native messaging host translate through an app
+// built by headless_builder; everything the translation reaches for must be
+// managed there as well as in the full app
+#[test]
+fn headless_app_manages_translation_state() {
+    let app = cli::headless_builder(mock_builder())
+        .build(mock_context(noop_assets()))
+        .expect("headless app");
+
+    assert!(app.try_state::<TranslationQueue>().is_some());
+    assert!(app.try_state::<MetricsState>().is_some());
+}
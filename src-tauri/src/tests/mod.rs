@@ -0,0 +1,57 @@
+// Headless tests of the backend's pipelines: the mock provider stands in for
+// the network and a scratch database for the user's history, and nothing
+// opens a window. Run with `cargo test --features mock-provider`.
+
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::history::db;
+use crate::translation::{ProviderKind, TranslationConfig};
+
+mod headless;
+mod pipeline;
+
+// Settings as a fresh install has them, with the mock configured by `mock`
+// (see MockOptions::parse)
+fn mock_config(mock: &str) -> TranslationConfig {
+    TranslationConfig {
+        provider: ProviderKind::Mock,
+        api_keys: HashMap::from([("mock".to_string(), mock.to_string())]),
+        source_language: "auto".to_string(),
+        target_language: "ja".to_string(),
+        formality: None,
+        preserve_formatting: false,
+        glossary: Vec::new(),
+    }
+}
+
+// A migrated history database of its own in the temp dir, deleted on drop
+struct ScratchDb {
+    path: PathBuf,
+    conn: Option<Connection>,
+}
+
+impl ScratchDb {
+    fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let name = format!("shunyaku-test-{}-{}.sqlite3", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst));
+        let path = std::env::temp_dir().join(name);
+        let conn = db::open(&path, None).expect("scratch history database");
+        Self { path, conn: Some(conn) }
+    }
+
+    fn conn(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for ScratchDb {
+    fn drop(&mut self) {
+        drop(self.conn.take());
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
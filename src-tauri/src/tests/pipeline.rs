@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use super::{mock_config, ScratchDb};
+use crate::error::AppError;
+use crate::history::{db, HistoryEntry, HistoryOrigin};
+use crate::popup;
+use crate::translation::mock::MockOptions;
+use crate::translation::{self, ProviderKind};
+
+// The tray quick-translate path from the clipboard's contents to the history
+// entry, minus the popup window
+async fn clipboard_to_history(
+    history: &ScratchDb,
+    clipboard: Option<&str>,
+    mock: &str,
+    deduplicate: bool,
+) -> Result<HistoryEntry, AppError> {
+    let text = popup::clipboard_text(clipboard.map(str::to_string))?;
+    let mut results = translation::translate_with(&mock_config(mock), &[&text], None, None).await?;
+    let result = results.pop().expect("one result per text");
+    db::insert(history.conn(), &result, HistoryOrigin::Clipboard, None, deduplicate)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+fn stored(history: &ScratchDb) -> Vec<HistoryEntry> {
+    db::page(history.conn(), 50, 0).unwrap()
+}
+
+#[tokio::test]
+async fn clipboard_text_is_translated_into_history() {
+    let history = ScratchDb::new();
+    let entry = clipboard_to_history(&history, Some("  Good morning\n"), "", true).await.unwrap();
+
+    assert_eq!(entry.result.original_text, "Good morning");
+    assert_eq!(entry.result.translated_text, "[ja] Good morning");
+    assert_eq!(entry.result.source_lang, "en");
+    assert_eq!(entry.result.target_lang, "ja");
+    assert_eq!(entry.result.provider, ProviderKind::Mock);
+    assert_eq!(entry.origin, HistoryOrigin::Clipboard);
+    assert_eq!(entry.hit_count, 1);
+
+    let stored = stored(&history);
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, entry.id);
+    assert_eq!(db::get(history.conn(), entry.id).unwrap().unwrap().result.translated_text, "[ja] Good morning");
+}
+
+#[tokio::test]
+async fn empty_clipboard_records_nothing() {
+    let history = ScratchDb::new();
+    for clipboard in [None, Some(""), Some(" \n\t ")] {
+        let error = clipboard_to_history(&history, clipboard, "", true).await.unwrap_err();
+        assert_eq!(error.code(), "invalidInput");
+    }
+    assert!(stored(&history).is_empty());
+}
+
+#[tokio::test]
+async fn provider_failures_keep_their_code_and_record_nothing() {
+    let history = ScratchDb::new();
+    for (code, expected) in [
+        ("network", "network"),
+        ("unauthorized", "unauthorized"),
+        ("quotaExceeded", "quotaExceeded"),
+        ("rateLimited", "rateLimited"),
+        ("teapot", "provider"),
+    ] {
+        let error = clipboard_to_history(&history, Some("Hello"), &format!("fail={}", code), true)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), expected);
+    }
+    assert!(stored(&history).is_empty());
+}
+
+#[tokio::test]
+async fn only_marked_texts_fail() {
+    let history = ScratchDb::new();
+    let mock = "fail=network,failWhen=#fail";
+    clipboard_to_history(&history, Some("Hello"), mock, true).await.unwrap();
+    let error = clipboard_to_history(&history, Some("Hello #fail"), mock, true).await.unwrap_err();
+    assert_eq!(error.code(), "network");
+    assert_eq!(stored(&history).len(), 1);
+}
+
+#[tokio::test]
+async fn repeats_bump_the_entry_when_deduplicating() {
+    let history = ScratchDb::new();
+    let first = clipboard_to_history(&history, Some("Hello"), "", true).await.unwrap();
+    let again = clipboard_to_history(&history, Some("Hello"), "", true).await.unwrap();
+    assert_eq!(again.id, first.id);
+    assert_eq!(again.hit_count, 2);
+    assert_eq!(stored(&history).len(), 1);
+
+    clipboard_to_history(&history, Some("Hello"), "", false).await.unwrap();
+    assert_eq!(stored(&history).len(), 2);
+}
+
+#[tokio::test]
+async fn latency_is_waited_out_and_timed() {
+    let history = ScratchDb::new();
+    let started = Instant::now();
+    let entry = clipboard_to_history(&history, Some("Hello"), "latency=60", true).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(60));
+    assert!(entry.result.processing_time >= 60);
+}
+
+#[tokio::test]
+async fn languages_given_override_the_settings() {
+    let texts = ["Guten Tag", "Danke"];
+    let results = translation::translate_with(&mock_config(""), &texts, Some("de"), Some("fr"))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    for (text, result) in texts.iter().zip(&results) {
+        assert_eq!(result.original_text, *text);
+        assert_eq!(result.translated_text, format!("[fr] {}", text));
+        assert_eq!(result.source_lang, "de");
+        assert_eq!(result.target_lang, "fr");
+    }
+}
+
+#[test]
+fn mock_options_parse() {
+    let options = MockOptions::parse("latency=250, fail=rateLimited, failWhen=#x").unwrap();
+    assert_eq!(options.latency, Duration::from_millis(250));
+    assert_eq!(options.failure.as_deref(), Some("rateLimited"));
+    assert_eq!(options.fail_when.as_deref(), Some("#x"));
+    assert_eq!(MockOptions::parse("").unwrap(), MockOptions::default());
+    assert!(MockOptions::parse("latency=soon").is_err());
+    assert!(MockOptions::parse("speed=1").is_err());
+    assert!(MockOptions::parse("fail").is_err());
+}
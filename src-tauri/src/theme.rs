@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::window::Color;
+use tauri::{AppHandle, Manager, Theme, WebviewWindowBuilder, Wry};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::main_window;
+use crate::settings;
+
+// Matches the frontend's dark and light page backgrounds, so a window shows
+// the right colour before its page has painted
+const DARK_BACKGROUND: Color = Color(24, 24, 27, 255);
+const LIGHT_BACKGROUND: Color = Color(255, 255, 255, 255);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    fn forced(self) -> Option<Theme> {
+        match self {
+            ThemePreference::System => None,
+            ThemePreference::Light => Some(Theme::Light),
+            ThemePreference::Dark => Some(Theme::Dark),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    pub system: Theme,
+    pub preference: ThemePreference,
+    // What windows should actually use
+    pub effective: Theme,
+}
+
+// Last system theme announced, since every open window reports the same switch
+#[derive(Default)]
+pub struct ThemeState(Mutex<Option<Theme>>);
+
+// The main window never has a forced theme, so it always reflects the OS
+fn system_theme(app: &AppHandle) -> Theme {
+    if let Some(theme) = *app.state::<ThemeState>().0.lock().unwrap() {
+        return theme;
+    }
+    app.get_webview_window(main_window::MAIN_LABEL)
+        .and_then(|window| window.theme().ok())
+        .unwrap_or(Theme::Light)
+}
+
+fn info(app: &AppHandle, preference: ThemePreference) -> ThemeInfo {
+    let system = system_theme(app);
+    ThemeInfo {
+        system,
+        preference,
+        effective: preference.forced().unwrap_or(system),
+    }
+}
+
+// Themes a window before it is built, so popups don't flash white in dark mode
+pub fn styled<'a, M: Manager<Wry>>(
+    builder: WebviewWindowBuilder<'a, Wry, M>,
+    app: &AppHandle,
+) -> WebviewWindowBuilder<'a, Wry, M> {
+    let preference = settings::load(app).theme;
+    let background = match info(app, preference).effective {
+        Theme::Dark => DARK_BACKGROUND,
+        _ => LIGHT_BACKGROUND,
+    };
+    builder.theme(preference.forced()).background_color(background)
+}
+
+// Windows with a forced theme report that instead of the OS, so only the
+// main window or unforced ones count
+pub fn window_theme_changed(app: &AppHandle, label: &str, theme: Theme) {
+    let preference = settings::load(app).theme;
+    if label != "main" && preference != ThemePreference::System {
+        return;
+    }
+    let last = app.state::<ThemeState>().0.lock().unwrap().replace(theme);
+    if last == Some(theme) {
+        return;
+    }
+    events::broadcast(app, AppEvent::ThemeChanged(info(app, preference)));
+}
+
+// Restyles open windows after the preference changed in settings
+pub fn apply(app: &AppHandle, preference: ThemePreference) {
+    for window in app.webview_windows().values() {
+        if window.label() != "main" {
+            let _ = window.set_theme(preference.forced());
+        }
+    }
+    events::broadcast(app, AppEvent::ThemeChanged(info(app, preference)));
+}
+
+#[tauri::command]
+pub async fn get_system_theme(app: AppHandle) -> Result<ThemeInfo, AppError> {
+    Ok(info(&app, settings::load(&app).theme))
+}
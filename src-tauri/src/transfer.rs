@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::error::AppError;
+
+// Larger texts and images don't go through a single IPC message: serializing
+// one stalls the webview, and past a point the message fails outright. They
+// are moved in chunks instead, begin/append/commit from the webview and
+// read_transfer towards it.
+pub const MAX_INLINE: usize = 512 * 1024;
+pub const CHUNK_SIZE: usize = 256 * 1024;
+const MAX_TRANSFER: usize = 64 * 1024 * 1024;
+// Uploads in progress at once, across all windows
+const MAX_UPLOADS: usize = 4;
+// Abandoned by a webview that reloaded or closed mid-transfer
+const STALE_AFTER: Duration = Duration::from_secs(300);
+
+enum Transfer {
+    // From a webview, taken by the command it was uploaded for
+    Upload { data: String, size: usize, committed: bool },
+    // Staged for one window to read; a broadcast shares the data between
+    // the copies staged for each window
+    Download { data: Arc<str>, window: String },
+}
+
+#[derive(Default)]
+pub struct TransferState {
+    next: Mutex<u64>,
+    transfers: Mutex<HashMap<u64, (Transfer, Instant)>>,
+}
+
+// Sent in place of an event whose payload was too large to emit; the
+// payload is the JSON the event would have carried
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargePayload {
+    pub event: String,
+    pub transfer: u64,
+    pub size: usize,
+}
+
+// A command result that is either the value itself or, when too large, a
+// transfer holding its JSON
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Payload<T> {
+    Inline(T),
+    #[serde(rename_all = "camelCase")]
+    Staged { transfer: u64, size: usize },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferChunk {
+    pub data: String,
+    // Where the next read starts, in bytes
+    pub offset: usize,
+    pub done: bool,
+}
+
+// Runs `f` on the open transfers once the stale ones are gone
+fn with_transfers<T>(app: &AppHandle, f: impl FnOnce(&mut HashMap<u64, (Transfer, Instant)>) -> T) -> T {
+    let state = app.state::<TransferState>();
+    let mut transfers = state.transfers.lock().unwrap();
+    transfers.retain(|_, (_, touched)| touched.elapsed() < STALE_AFTER);
+    f(&mut transfers)
+}
+
+fn next_id(app: &AppHandle) -> u64 {
+    let state = app.state::<TransferState>();
+    let mut next = state.next.lock().unwrap();
+    *next += 1;
+    *next
+}
+
+// Holds a payload for the window to read with read_transfer
+pub fn stage(app: &AppHandle, window: &str, data: Arc<str>) -> u64 {
+    let id = next_id(app);
+    let download = Transfer::Download {
+        data,
+        window: window.to_string(),
+    };
+    with_transfers(app, |transfers| transfers.insert(id, (download, Instant::now())));
+    id
+}
+
+// `value` as is, or staged for `window` when its JSON is over the limit
+pub fn payload<T: Serialize>(app: &AppHandle, window: &str, value: T) -> Result<Payload<T>, AppError> {
+    let json = serde_json::to_string(&value).map_err(|e| format!("Failed to serialize result: {}", e))?;
+    if json.len() <= MAX_INLINE {
+        return Ok(Payload::Inline(value));
+    }
+    let size = json.len();
+    Ok(Payload::Staged {
+        transfer: stage(app, window, json.into()),
+        size,
+    })
+}
+
+// A command's text argument: given inline, or uploaded beforehand and
+// referred to by its transfer id
+pub fn text_argument(app: &AppHandle, inline: String, transfer: Option<u64>) -> Result<String, AppError> {
+    let Some(id) = transfer else {
+        if inline.len() > MAX_INLINE {
+            return Err(AppError::InvalidInput(format!(
+                "Text of {} bytes is over the {} byte limit; send it with begin_transfer",
+                inline.len(),
+                MAX_INLINE
+            )));
+        }
+        return Ok(inline);
+    };
+    with_transfers(app, |transfers| match transfers.remove(&id) {
+        Some((Transfer::Upload { data, committed: true, .. }, _)) => Ok(data),
+        Some(other) => {
+            let upload = matches!(other.0, Transfer::Upload { .. });
+            transfers.insert(id, other);
+            match upload {
+                true => Err(AppError::InvalidInput(format!("Transfer {} has not been committed", id))),
+                false => Err(AppError::NotFound(format!("No transfer {}", id))),
+            }
+        }
+        None => Err(AppError::NotFound(format!("No transfer {}", id))),
+    })
+}
+
+// Starts an upload of `size` bytes of UTF-8 text
+#[tauri::command]
+pub async fn begin_transfer(app: AppHandle, size: usize) -> Result<u64, AppError> {
+    if size > MAX_TRANSFER {
+        return Err(AppError::InvalidInput(format!(
+            "Transfers are limited to {} bytes, not {}",
+            MAX_TRANSFER, size
+        )));
+    }
+    let id = next_id(&app);
+    with_transfers(&app, |transfers| {
+        let uploads = transfers
+            .values()
+            .filter(|(transfer, _)| matches!(transfer, Transfer::Upload { .. }))
+            .count();
+        if uploads >= MAX_UPLOADS {
+            return Err(AppError::RateLimited(format!(
+                "{} uploads are already in progress",
+                uploads
+            )));
+        }
+        // Grown chunk by chunk rather than reserved, so a claimed size costs nothing
+        let upload = Transfer::Upload {
+            data: String::new(),
+            size,
+            committed: false,
+        };
+        transfers.insert(id, (upload, Instant::now()));
+        Ok(id)
+    })
+}
+
+#[tauri::command]
+pub async fn append_transfer(app: AppHandle, transfer: u64, chunk: String) -> Result<(), AppError> {
+    if chunk.len() > MAX_INLINE {
+        return Err(AppError::InvalidInput(format!(
+            "Chunks are limited to {} bytes, not {}",
+            MAX_INLINE,
+            chunk.len()
+        )));
+    }
+    with_transfers(&app, |transfers| {
+        let (upload, touched) = transfers
+            .get_mut(&transfer)
+            .ok_or_else(|| AppError::NotFound(format!("No transfer {}", transfer)))?;
+        match upload {
+            Transfer::Upload { data, size, committed: false } if data.len() + chunk.len() <= *size => {
+                data.push_str(&chunk);
+                *touched = Instant::now();
+                Ok(())
+            }
+            Transfer::Upload { size, committed: false, .. } => Err(AppError::InvalidInput(format!(
+                "Transfer {} would exceed the {} bytes it began with",
+                transfer, size
+            ))),
+            _ => Err(AppError::InvalidInput(format!("Transfer {} is not accepting chunks", transfer))),
+        }
+    })
+}
+
+// Seals an upload once every byte has arrived; its id can then be passed to
+// a command in place of the text
+#[tauri::command]
+pub async fn commit_transfer(app: AppHandle, transfer: u64) -> Result<(), AppError> {
+    with_transfers(&app, |transfers| {
+        let (upload, touched) = transfers
+            .get_mut(&transfer)
+            .ok_or_else(|| AppError::NotFound(format!("No transfer {}", transfer)))?;
+        match upload {
+            Transfer::Upload { data, size, committed } if data.len() == *size => {
+                *committed = true;
+                *touched = Instant::now();
+                Ok(())
+            }
+            Transfer::Upload { data, size, .. } => Err(AppError::InvalidInput(format!(
+                "Transfer {} has {} of {} bytes",
+                transfer,
+                data.len(),
+                size
+            ))),
+            Transfer::Download { .. } => {
+                Err(AppError::InvalidInput(format!("Transfer {} is not an upload", transfer)))
+            }
+        }
+    })
+}
+
+// Reads a payload staged for this window a chunk at a time from `offset`;
+// the transfer is dropped once the last chunk has been read
+#[tauri::command]
+pub async fn read_transfer(
+    app: AppHandle,
+    window: WebviewWindow,
+    transfer: u64,
+    offset: usize,
+) -> Result<TransferChunk, AppError> {
+    with_transfers(&app, |transfers| {
+        let Some((Transfer::Download { data, window: reader }, touched)) = transfers.get_mut(&transfer) else {
+            return Err(AppError::NotFound(format!("No transfer {}", transfer)));
+        };
+        if reader != window.label() {
+            return Err(AppError::NotFound(format!("No transfer {}", transfer)));
+        }
+        if offset > data.len() || !data.is_char_boundary(offset) {
+            return Err(AppError::InvalidInput(format!("Offset {} is not within transfer {}", offset, transfer)));
+        }
+
+        // Cut on a character boundary so each chunk is valid text
+        let mut end = (offset + CHUNK_SIZE).min(data.len());
+        while !data.is_char_boundary(end) {
+            end -= 1;
+        }
+        let chunk = TransferChunk {
+            data: data[offset..end].to_string(),
+            offset: end,
+            done: end == data.len(),
+        };
+        *touched = Instant::now();
+        if chunk.done {
+            transfers.remove(&transfer);
+        }
+        Ok(chunk)
+    })
+}
+
+#[tauri::command]
+pub async fn cancel_transfer(app: AppHandle, transfer: u64) -> Result<bool, AppError> {
+    Ok(with_transfers(&app, |transfers| transfers.remove(&transfer).is_some()))
+}
@@ -0,0 +1,216 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use super::GlossaryEntry;
+use crate::error::AppError;
+
+const FREE_API_URL: &str = "https://api-free.deepl.com";
+const PRO_API_URL: &str = "https://api.deepl.com";
+
+#[derive(Deserialize)]
+struct TranslateResponse {
+    translations: Vec<Translation>,
+}
+
+#[derive(Deserialize)]
+struct Translation {
+    detected_source_language: String,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct GlossaryResponse {
+    glossary_id: String,
+}
+
+#[derive(Deserialize)]
+struct UsageResponse {
+    character_count: u64,
+    character_limit: u64,
+}
+
+pub struct DeepLClient {
+    http: reqwest::Client,
+    api_key: String,
+    base_url: &'static str,
+}
+
+// Same wording the frontend service shows for DeepL HTTP statuses
+pub fn message_for_status(status: u16) -> String {
+    match status {
+        400 => "Bad request. Please check your request parameters.".to_string(),
+        403 => "Forbidden. Invalid API key or insufficient permissions.".to_string(),
+        404 => "Resource not found.".to_string(),
+        413 => "Request entity too large. Text is too long.".to_string(),
+        429 => "Too many requests. Rate limit exceeded.".to_string(),
+        456 => "Quota exceeded. You have reached your usage limit.".to_string(),
+        503 => "Service unavailable. Please try again later.".to_string(),
+        _ => format!("HTTP {} error", status),
+    }
+}
+
+// Lets the UI act on key and quota problems without parsing the message
+pub fn error_for_status(status: u16) -> AppError {
+    let message = message_for_status(status);
+    match status {
+        403 => AppError::Unauthorized(message),
+        429 => AppError::RateLimited(message),
+        456 => AppError::QuotaExceeded(message),
+        _ => AppError::Provider { status, message },
+    }
+}
+
+fn map_language_code(lang: &str) -> String {
+    match lang.to_lowercase().as_str() {
+        "no" => "NB".to_string(),
+        other => other.to_uppercase(),
+    }
+}
+
+impl DeepLClient {
+    pub fn new(http: reqwest::Client, api_key: &str) -> Self {
+        // Free-tier keys carry a ":fx" suffix and are only valid on the free endpoint
+        let base_url = if api_key.ends_with(":fx") {
+            FREE_API_URL
+        } else {
+            PRO_API_URL
+        };
+
+        Self {
+            http,
+            api_key: api_key.to_string(),
+            base_url,
+        }
+    }
+
+    // Returns the translated text and the (possibly detected) source language
+    // of each text, in order
+    pub async fn translate(
+        &self,
+        texts: &[&str],
+        source_lang: Option<&str>,
+        target_lang: &str,
+        formality: Option<&str>,
+        preserve_formatting: bool,
+        glossary_id: Option<&str>,
+    ) -> Result<Vec<(String, String)>, AppError> {
+        let mut params: Vec<(&str, String)> = texts.iter().map(|text| ("text", text.to_string())).collect();
+        params.push(("target_lang", map_language_code(target_lang)));
+        if let Some(source) = source_lang.filter(|s| *s != "auto") {
+            params.push(("source_lang", map_language_code(source)));
+        }
+        if let Some(formality) = formality {
+            params.push(("formality", formality.to_string()));
+        }
+        if preserve_formatting {
+            params.push(("preserve_formatting", "1".to_string()));
+        }
+        if let Some(glossary_id) = glossary_id {
+            params.push(("glossary_id", glossary_id.to_string()));
+        }
+
+        let response = self
+            .http
+            .post(format!("{}/v2/translate", self.base_url))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach DeepL: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(error_for_status(status.as_u16()));
+        }
+
+        let body: TranslateResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid DeepL response: {}", e))?;
+
+        if body.translations.len() != texts.len() {
+            return Err("DeepL returned no translations".into());
+        }
+        Ok(body
+            .translations
+            .into_iter()
+            .map(|t| (t.text, t.detected_source_language.to_lowercase()))
+            .collect())
+    }
+
+    // DeepL glossaries are immutable, so one is created per distinct term list
+    // and reused for the rest of the session
+    pub async fn glossary_id(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        entries: &[GlossaryEntry],
+    ) -> Result<String, AppError> {
+        static GLOSSARIES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+        let glossaries = GLOSSARIES.get_or_init(Default::default);
+
+        let tsv = entries
+            .iter()
+            .map(|entry| format!("{}\t{}", entry.source.trim(), entry.target.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cache_key = format!("{}\n{}\n{}\n{}", self.api_key, source_lang, target_lang, tsv);
+        if let Some(id) = glossaries.lock().unwrap().get(&cache_key) {
+            return Ok(id.clone());
+        }
+
+        let params = [
+            ("name", "Shunyaku".to_string()),
+            ("source_lang", map_language_code(source_lang)),
+            ("target_lang", map_language_code(target_lang)),
+            ("entries", tsv),
+            ("entries_format", "tsv".to_string()),
+        ];
+        let response = self
+            .http
+            .post(format!("{}/v2/glossaries", self.base_url))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach DeepL: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(error_for_status(status.as_u16()));
+        }
+
+        let body: GlossaryResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid DeepL response: {}", e))?;
+        glossaries
+            .lock()
+            .unwrap()
+            .insert(cache_key, body.glossary_id.clone());
+        Ok(body.glossary_id)
+    }
+
+    // Characters used and allowed in the current billing period
+    pub async fn usage(&self) -> Result<(u64, u64), AppError> {
+        let response = self
+            .http
+            .get(format!("{}/v2/usage", self.base_url))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach DeepL: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(error_for_status(status.as_u16()));
+        }
+
+        let body: UsageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid DeepL response: {}", e))?;
+        Ok((body.character_count, body.character_limit))
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::error::AppError;
+
+// Behaviour of the mock provider, read from its entry in apiKeys, e.g.
+// "latency=200,fail=rateLimited,failWhen=#fail". Output is always the text
+// tagged with the target language, so tests and screenshots are stable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockOptions {
+    pub latency: Duration,
+    // AppError code every failing call returns, e.g. "network"
+    pub failure: Option<String>,
+    // Only calls with a text containing this fail; all do when unset
+    pub fail_when: Option<String>,
+}
+
+impl MockOptions {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut options = MockOptions::default();
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Mock option '{}' needs a value", pair))?;
+            match key.trim() {
+                "latency" => {
+                    let millis = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Mock latency '{}' is not a number of milliseconds", value))?;
+                    options.latency = Duration::from_millis(millis);
+                }
+                "fail" => options.failure = Some(value.trim().to_string()),
+                "failWhen" => options.fail_when = Some(value.to_string()),
+                other => return Err(format!("Unknown mock option '{}'", other)),
+            }
+        }
+        Ok(options)
+    }
+
+    fn fails(&self, texts: &[&str]) -> Option<&str> {
+        let failure = self.failure.as_deref()?;
+        match &self.fail_when {
+            Some(marker) => texts.iter().any(|text| text.contains(marker.as_str())).then_some(failure),
+            None => Some(failure),
+        }
+    }
+}
+
+fn error(code: &str) -> AppError {
+    let message = format!("Mock provider failed with {}", code);
+    match code {
+        "unauthorized" => AppError::Unauthorized(message),
+        "quotaExceeded" => AppError::QuotaExceeded(message),
+        "rateLimited" => AppError::RateLimited(message),
+        "network" => AppError::Network(message),
+        _ => AppError::Provider { status: 500, message },
+    }
+}
+
+// Same shape as DeepLClient::translate: each text's translation and the
+// detected source language
+pub async fn translate(
+    options: &MockOptions,
+    texts: &[&str],
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<Vec<(String, String)>, AppError> {
+    if !options.latency.is_zero() {
+        tokio::time::sleep(options.latency).await;
+    }
+    if let Some(code) = options.fails(texts) {
+        return Err(error(code));
+    }
+    let detected = if source_lang == "auto" { "en" } else { source_lang };
+    Ok(texts
+        .iter()
+        .map(|text| (format!("[{}] {}", target_lang, text), detected.to_string()))
+        .collect())
+}
@@ -0,0 +1,305 @@
+mod deepl;
+#[cfg(feature = "mock-provider")]
+pub mod mock;
+pub mod presets;
+pub mod queue;
+pub mod usage;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::history::{self, HistoryOrigin};
+use crate::{metrics, notifications, settings, transfer};
+use deepl::DeepLClient;
+use queue::Priority;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Deepl,
+    // Offline stand-in for tests and UI work; see mock::MockOptions
+    #[cfg(feature = "mock-provider")]
+    Mock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationResult {
+    pub original_text: String,
+    pub translated_text: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub provider: ProviderKind,
+    // Milliseconds spent waiting for the provider
+    pub processing_time: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Emitted as "translation-status" around every provider call, for the tray
+// indicator and any panel that wants a busy state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum TranslationActivity {
+    Started,
+    Completed,
+    Failed {
+        error: String,
+        // AppError's code, e.g. quotaExceeded
+        #[serde(default)]
+        code: String,
+    },
+}
+
+// Term the provider must always translate the same way
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryEntry {
+    pub source: String,
+    pub target: String,
+}
+
+pub fn validate_glossary(glossary: &[GlossaryEntry]) -> Result<(), String> {
+    for entry in glossary {
+        if entry.source.trim().is_empty() || entry.target.trim().is_empty() {
+            return Err("Glossary terms must not be empty".to_string());
+        }
+        // DeepL takes glossaries as tab-separated lines
+        let text = format!("{}{}", entry.source, entry.target);
+        if text.contains(['\t', '\n', '\r']) {
+            return Err(format!("Glossary term '{}' contains a tab or line break", entry.source));
+        }
+    }
+    Ok(())
+}
+
+pub struct TranslationConfig {
+    pub provider: ProviderKind,
+    pub api_keys: HashMap<String, String>,
+    pub source_language: String,
+    pub target_language: String,
+    pub formality: Option<String>,
+    pub preserve_formatting: bool,
+    pub glossary: Vec<GlossaryEntry>,
+}
+
+impl ProviderKind {
+    // Entry in the apiKeys setting
+    fn key_name(self) -> &'static str {
+        match self {
+            ProviderKind::Deepl => "deepl",
+            #[cfg(feature = "mock-provider")]
+            ProviderKind::Mock => "mock",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ProviderKind::Deepl => "DeepL",
+            #[cfg(feature = "mock-provider")]
+            ProviderKind::Mock => "Mock",
+        }
+    }
+
+    // Calls allowed in flight at once; DeepL starts answering 429 past a few
+    fn max_concurrent(self) -> usize {
+        match self {
+            ProviderKind::Deepl => 4,
+            #[cfg(feature = "mock-provider")]
+            ProviderKind::Mock => 4,
+        }
+    }
+}
+
+impl TranslationConfig {
+    pub fn api_key(&self, provider: ProviderKind) -> Result<&str, String> {
+        self.api_keys
+            .get(provider.key_name())
+            .map(String::as_str)
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| format!("{} API key is not configured", provider.display_name()))
+    }
+}
+
+pub fn http_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new).clone()
+}
+
+pub fn load_config(app: &AppHandle) -> Result<TranslationConfig, String> {
+    let settings = settings::load(app);
+    Ok(TranslationConfig {
+        provider: settings.translation_provider,
+        api_keys: settings.api_keys,
+        source_language: settings.source_language,
+        target_language: settings.target_language,
+        formality: settings.formality,
+        preserve_formatting: settings.preserve_formatting,
+        glossary: settings.glossary,
+    })
+}
+
+// Translates with the configured provider. Language arguments override the
+// defaults from settings when given.
+pub async fn translate(
+    app: &AppHandle,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> Result<TranslationResult, AppError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AppError::InvalidInput("Nothing to translate".to_string()));
+    }
+
+    translate_batch(app, &[text], source_lang, target_lang, Priority::Interactive)
+        .await
+        .map(|mut results| results.remove(0))
+}
+
+// Several texts in one provider request, translated independently and
+// returned in order. Callers trim them and leave out empty ones. The request
+// waits its turn with the provider by `priority`.
+pub async fn translate_batch(
+    app: &AppHandle,
+    texts: &[&str],
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+    priority: Priority,
+) -> Result<Vec<TranslationResult>, AppError> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    events::broadcast(app, AppEvent::TranslationStatus(TranslationActivity::Started));
+    let result = run_provider(app, texts, source_lang, target_lang, priority).await;
+    metrics::provider_call(app, texts.iter().map(|text| text.chars().count()).sum(), &result);
+
+    let activity = match &result {
+        Ok(_) => TranslationActivity::Completed,
+        Err(error) => TranslationActivity::Failed {
+            error: error.to_string(),
+            code: error.code().to_string(),
+        },
+    };
+    events::broadcast(app, AppEvent::TranslationStatus(activity));
+    result
+}
+
+async fn run_provider(
+    app: &AppHandle,
+    texts: &[&str],
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+    priority: Priority,
+) -> Result<Vec<TranslationResult>, AppError> {
+    let config = load_config(app)?;
+    let _permit = queue::acquire(app, config.provider, priority).await?;
+    translate_with(&config, texts, source_lang, target_lang).await
+}
+
+// The provider call itself, with nothing from the app but its config, so it
+// runs the same from tests
+pub async fn translate_with(
+    config: &TranslationConfig,
+    texts: &[&str],
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> Result<Vec<TranslationResult>, AppError> {
+    let source = source_lang.unwrap_or(&config.source_language);
+    let target = target_lang.unwrap_or(&config.target_language);
+    let started = Instant::now();
+
+    let translated = match config.provider {
+        ProviderKind::Deepl => {
+            let client = DeepLClient::new(http_client(), config.api_key(ProviderKind::Deepl)?);
+            // DeepL glossaries are per language pair, so they need a known source
+            let glossary_id = if config.glossary.is_empty() || source == "auto" {
+                None
+            } else {
+                Some(client.glossary_id(source, target, &config.glossary).await?)
+            };
+            client
+                .translate(
+                    texts,
+                    Some(source),
+                    target,
+                    config.formality.as_deref(),
+                    config.preserve_formatting,
+                    glossary_id.as_deref(),
+                )
+                .await?
+        }
+        #[cfg(feature = "mock-provider")]
+        ProviderKind::Mock => {
+            let spec = config.api_keys.get(ProviderKind::Mock.key_name()).map_or("", String::as_str);
+            mock::translate(&mock::MockOptions::parse(spec)?, texts, source, target).await?
+        }
+    };
+
+    let processing_time = started.elapsed().as_millis() as u64;
+    Ok(texts
+        .iter()
+        .zip(translated)
+        .map(|(text, (translated_text, detected_source))| TranslationResult {
+            original_text: text.to_string(),
+            translated_text,
+            source_lang: if source == "auto" {
+                detected_source
+            } else {
+                source.to_string()
+            },
+            target_lang: target.to_string(),
+            provider: config.provider,
+            processing_time,
+            timestamp: Utc::now(),
+        })
+        .collect())
+}
+
+// Panels pass their own label when auto-translating, so a result that lands
+// while they're in the background can be surfaced as a notification. `origin`
+// tells history where the text came from and defaults to manual input;
+// `thumbnail` is what recognize_region returned for OCR text. Text over the
+// IPC limit is uploaded first and passed as `transfer`, with `text` empty.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn translate_text(
+    app: AppHandle,
+    text: String,
+    source_lang: Option<String>,
+    target_lang: Option<String>,
+    window_id: Option<String>,
+    origin: Option<HistoryOrigin>,
+    thumbnail: Option<String>,
+    transfer: Option<u64>,
+) -> Result<TranslationResult, AppError> {
+    let text = transfer::text_argument(&app, text, transfer)?;
+    let result = translate(&app, &text, source_lang.as_deref(), target_lang.as_deref()).await?;
+    let origin = origin.unwrap_or(HistoryOrigin::Manual);
+    let entry = history::record(&app, &result, origin, window_id.as_deref());
+    if let (Some(entry), Some(thumbnail)) = (&entry, thumbnail) {
+        if let Err(e) = history::thumbnails::attach(&app, entry.id, &thumbnail) {
+            tracing::error!("Failed to attach thumbnail: {}", e);
+        }
+    }
+    if let (Some(entry), Some(window_id)) = (entry, window_id) {
+        notifications::notify_background_result(&app, &entry, &window_id);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn set_api_key(app: AppHandle, provider: ProviderKind, api_key: String) -> Result<(), AppError> {
+    Ok(settings::update(&app, |settings| {
+        settings
+            .api_keys
+            .insert(provider.key_name().to_string(), api_key.trim().to_string());
+    })
+    .map(|_| ())?)
+}
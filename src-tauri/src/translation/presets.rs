@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::ProviderKind;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::settings::{self, Settings};
+
+// Named language pair to flip between, e.g. JA→EN while reading and EN→JA
+// while writing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePreset {
+    pub name: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub provider: ProviderKind,
+    // DeepL formality ("more", "less", ...); None keeps the provider default
+    #[serde(default)]
+    pub formality: Option<String>,
+}
+
+pub fn default_presets() -> Vec<LanguagePreset> {
+    let preset = |name: &str, source: &str, target: &str| LanguagePreset {
+        name: name.to_string(),
+        source_language: source.to_string(),
+        target_language: target.to_string(),
+        provider: ProviderKind::Deepl,
+        formality: None,
+    };
+    vec![preset("JA → EN", "ja", "en"), preset("EN → JA", "en", "ja")]
+}
+
+fn store(app: &AppHandle, previous: &Settings, settings: Settings) -> Result<Vec<LanguagePreset>, String> {
+    if let Some(error) = settings.validate_changes(previous).first() {
+        return Err(error.message.clone());
+    }
+    settings::save(app, &settings)?;
+    settings::apply(app, previous, &settings);
+    Ok(settings.language_presets)
+}
+
+fn activate(app: &AppHandle, name: &str) -> Result<LanguagePreset, String> {
+    let previous = settings::load(app);
+    let preset = previous
+        .language_presets
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| format!("Language preset '{}' not found", name))?;
+
+    let mut settings = previous.clone();
+    settings.source_language = preset.source_language.clone();
+    settings.target_language = preset.target_language.clone();
+    settings.translation_provider = preset.provider;
+    settings.formality = preset.formality.clone();
+    settings.active_preset = Some(preset.name.clone());
+    store(app, &previous, settings)?;
+
+    events::broadcast(app, AppEvent::LanguagePresetChanged(preset.clone()));
+    Ok(preset)
+}
+
+// Moves to the preset after the active one, wrapping around
+pub fn cycle(app: &AppHandle) -> Result<LanguagePreset, String> {
+    let settings = settings::load(app);
+    let presets = &settings.language_presets;
+    if presets.is_empty() {
+        return Err("No language presets defined".to_string());
+    }
+    let next = settings
+        .active_preset
+        .as_ref()
+        .and_then(|active| presets.iter().position(|p| &p.name == active))
+        .map_or(0, |index| (index + 1) % presets.len());
+    activate(app, &presets[next].name)
+}
+
+#[tauri::command]
+pub async fn list_language_presets(app: AppHandle) -> Result<Vec<LanguagePreset>, AppError> {
+    Ok(settings::load(&app).language_presets)
+}
+
+// Adds the preset, or replaces the one with the same name
+#[tauri::command]
+pub async fn save_language_preset(app: AppHandle, preset: LanguagePreset) -> Result<Vec<LanguagePreset>, AppError> {
+    let previous = settings::load(&app);
+    let mut settings = previous.clone();
+    match settings.language_presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => settings.language_presets.push(preset),
+    }
+    Ok(store(&app, &previous, settings)?)
+}
+
+#[tauri::command]
+pub async fn delete_language_preset(app: AppHandle, name: String) -> Result<Vec<LanguagePreset>, AppError> {
+    let previous = settings::load(&app);
+    let mut settings = previous.clone();
+    let before = settings.language_presets.len();
+    settings.language_presets.retain(|p| p.name != name);
+    if settings.language_presets.len() == before {
+        return Err(AppError::NotFound(format!("Language preset '{}' not found", name)));
+    }
+    if settings.active_preset.as_deref() == Some(name.as_str()) {
+        settings.active_preset = None;
+    }
+    Ok(store(&app, &previous, settings)?)
+}
+
+#[tauri::command]
+pub async fn apply_language_preset(app: AppHandle, name: String) -> Result<LanguagePreset, AppError> {
+    Ok(activate(&app, &name)?)
+}
+
+#[tauri::command]
+pub async fn cycle_language_preset(app: AppHandle) -> Result<LanguagePreset, AppError> {
+    Ok(cycle(&app)?)
+}
@@ -0,0 +1,133 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use super::ProviderKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    // Someone is waiting on the result: hotkeys, the popup, panels, the API
+    Interactive,
+    // Document batches, dropped or from a watched folder
+    Background,
+}
+
+#[derive(Default)]
+struct Slots {
+    running: usize,
+    // Of those running, how many are background
+    background: usize,
+    interactive_waiting: VecDeque<oneshot::Sender<Permit>>,
+    background_waiting: VecDeque<oneshot::Sender<Permit>>,
+}
+
+// One provider's calls. Background work never takes the last slot, so a big
+// document can't leave the popup waiting behind it, and freed slots go to
+// interactive requests first.
+struct Lane {
+    limit: usize,
+    slots: Mutex<Slots>,
+}
+
+// A provider call slot, given back when dropped
+pub struct Permit {
+    lane: Option<Arc<Lane>>,
+    priority: Priority,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(lane) = self.lane.take() {
+            let mut slots = lane.slots.lock().unwrap();
+            Lane::release(&mut slots, self.priority);
+            lane.wake(&mut slots);
+        }
+    }
+}
+
+impl Lane {
+    fn can_start(&self, slots: &Slots, priority: Priority) -> bool {
+        slots.running < self.limit
+            && match priority {
+                Priority::Interactive => true,
+                Priority::Background => slots.background < self.limit.saturating_sub(1).max(1),
+            }
+    }
+
+    fn start(self: &Arc<Self>, slots: &mut Slots, priority: Priority) -> Permit {
+        slots.running += 1;
+        if priority == Priority::Background {
+            slots.background += 1;
+        }
+        Permit {
+            lane: Some(self.clone()),
+            priority,
+        }
+    }
+
+    fn release(slots: &mut Slots, priority: Priority) {
+        slots.running -= 1;
+        if priority == Priority::Background {
+            slots.background -= 1;
+        }
+    }
+
+    // Hands free slots to whoever has waited longest, interactive first.
+    // Waiters that gave up are skipped.
+    fn wake(self: &Arc<Self>, slots: &mut Slots) {
+        for priority in [Priority::Interactive, Priority::Background] {
+            while self.can_start(slots, priority) {
+                let waiting = match priority {
+                    Priority::Interactive => &mut slots.interactive_waiting,
+                    Priority::Background => &mut slots.background_waiting,
+                };
+                let Some(waiter) = waiting.pop_front() else {
+                    break;
+                };
+                if let Err(mut permit) = waiter.send(self.start(slots, priority)) {
+                    permit.lane = None;
+                    Lane::release(slots, priority);
+                }
+            }
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: Priority) -> Result<Permit, String> {
+        let waiter = {
+            let mut slots = self.slots.lock().unwrap();
+            if self.can_start(&slots, priority) {
+                return Ok(self.start(&mut slots, priority));
+            }
+            let (sender, receiver) = oneshot::channel();
+            match priority {
+                Priority::Interactive => slots.interactive_waiting.push_back(sender),
+                Priority::Background => slots.background_waiting.push_back(sender),
+            }
+            receiver
+        };
+        waiter.await.map_err(|_| "The translation queue was closed".to_string())
+    }
+}
+
+// Provider calls in flight, capped per provider
+#[derive(Default)]
+pub struct TranslationQueue(Mutex<HashMap<ProviderKind, Arc<Lane>>>);
+
+// Waits for a free slot with the provider; hold the permit for the call
+pub async fn acquire(app: &AppHandle, provider: ProviderKind, priority: Priority) -> Result<Permit, String> {
+    let lane = app
+        .state::<TranslationQueue>()
+        .0
+        .lock()
+        .unwrap()
+        .entry(provider)
+        .or_insert_with(|| {
+            Arc::new(Lane {
+                limit: provider.max_concurrent(),
+                slots: Mutex::default(),
+            })
+        })
+        .clone();
+    lane.acquire(priority).await
+}
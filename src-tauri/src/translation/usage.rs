@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager, State};
+
+use super::deepl::DeepLClient;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::webhooks;
+use super::{http_client, load_config, ProviderKind, TranslationActivity};
+
+// Quota is re-read on this period, and after translations at most this often
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MIN_REFRESH_GAP: Duration = Duration::from_secs(30);
+
+// Characters used against the provider's quota for the current billing period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUsage {
+    pub provider: ProviderKind,
+    pub character_count: u64,
+    pub character_limit: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ProviderUsage {
+    // e.g. "DeepL: 312k/500k chars"
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {}/{} chars",
+            self.provider.display_name(),
+            short_count(self.character_count),
+            short_count(self.character_limit)
+        )
+    }
+}
+
+fn short_count(count: u64) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    } else if count >= 1_000 {
+        format!("{}k", count / 1_000)
+    } else {
+        count.to_string()
+    }
+}
+
+pub type UsageState = Mutex<Option<ProviderUsage>>;
+
+// The provider's current quota, without updating the cached one. Errors keep
+// their kind, so a rejected key and an unreachable provider tell apart.
+pub async fn fetch(app: &AppHandle) -> Result<ProviderUsage, AppError> {
+    let config = load_config(app)?;
+    let (character_count, character_limit) = match config.provider {
+        ProviderKind::Deepl => {
+            DeepLClient::new(http_client(), config.api_key(ProviderKind::Deepl)?)
+                .usage()
+                .await?
+        }
+        // Nothing to count against
+        #[cfg(feature = "mock-provider")]
+        ProviderKind::Mock => (0, 0),
+    };
+
+    Ok(ProviderUsage {
+        provider: config.provider,
+        character_count,
+        character_limit,
+        updated_at: Utc::now(),
+    })
+}
+
+// Re-reads the quota and emits "usage-updated" for the tray and settings screen
+pub async fn refresh(app: &AppHandle) -> Result<ProviderUsage, String> {
+    let usage = fetch(app).await?;
+    *app.state::<UsageState>().lock().unwrap() = Some(usage.clone());
+    events::broadcast(app, AppEvent::UsageUpdated(usage.clone()));
+    webhooks::usage_updated(app, &usage);
+    Ok(usage)
+}
+
+fn is_stale(app: &AppHandle) -> bool {
+    let usage = app.state::<UsageState>();
+    let usage = usage.lock().unwrap();
+    usage.as_ref().map_or(true, |usage| {
+        (Utc::now() - usage.updated_at).to_std().unwrap_or_default() >= MIN_REFRESH_GAP
+    })
+}
+
+// Polls the quota in the background and after completed translations.
+// Failures (no key yet, offline) just leave the last reading in place.
+pub fn start(app: &AppHandle) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = refresh(&handle).await;
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+
+    let handle = app.clone();
+    app.listen_any("translation-status", move |event| {
+        let completed = matches!(
+            serde_json::from_str::<TranslationActivity>(event.payload()),
+            Ok(TranslationActivity::Completed)
+        );
+        if completed && is_stale(&handle) {
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = refresh(&handle).await;
+            });
+        }
+    });
+}
+
+// Last known quota; `refresh` forces a provider round-trip first
+#[tauri::command]
+pub async fn get_usage(
+    app: AppHandle,
+    usage_state: State<'_, UsageState>,
+    refresh: Option<bool>,
+) -> Result<Option<ProviderUsage>, AppError> {
+    if refresh.unwrap_or(false) {
+        return Ok(self::refresh(&app).await.map(Some)?);
+    }
+    Ok(usage_state.lock().unwrap().clone())
+}
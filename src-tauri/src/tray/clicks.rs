@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::menu::Menu;
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconEvent};
+use tauri::{AppHandle, Manager, Rect, Wry};
+
+use super::TRAY_ID;
+use crate::error::AppError;
+use crate::main_window;
+use crate::monitoring;
+use crate::popup::{self, PopupAnchor};
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrayClickAction {
+    ShowMenu,
+    QuickTranslate,
+    TogglePause,
+    OpenMainWindow,
+    NewPanel,
+    Ignore,
+}
+
+// What each kind of click on the tray icon does. Linux doesn't report tray
+// clicks at all and double clicks are only reported on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrayClickBindings {
+    pub left: TrayClickAction,
+    pub right: TrayClickAction,
+    pub double_click: TrayClickAction,
+    pub middle: TrayClickAction,
+}
+
+impl Default for TrayClickBindings {
+    fn default() -> Self {
+        Self {
+            left: TrayClickAction::ShowMenu,
+            right: TrayClickAction::ShowMenu,
+            double_click: TrayClickAction::Ignore,
+            middle: TrayClickAction::Ignore,
+        }
+    }
+}
+
+impl TrayClickBindings {
+    // The OS opens an attached menu on right click by itself, so the menu is
+    // only attached while some click is meant to show it
+    fn menu_attached(&self) -> bool {
+        self.left == TrayClickAction::ShowMenu || self.right == TrayClickAction::ShowMenu
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.double_click == TrayClickAction::ShowMenu || self.middle == TrayClickAction::ShowMenu {
+            return Err("Only left and right click can show the tray menu".to_string());
+        }
+        // Without the menu or the main window there would be no way to quit
+        let reachable = [self.left, self.right, self.double_click, self.middle]
+            .iter()
+            .any(|action| matches!(action, TrayClickAction::ShowMenu | TrayClickAction::OpenMainWindow));
+        if !reachable {
+            return Err("At least one tray click must show the menu or open the main window".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Tray menu kept around so it can be detached and reattached
+pub struct TrayMenu(pub Menu<Wry>);
+
+// The bindings in effect, so a tray event doesn't read settings from disk
+pub type TrayClickState = Mutex<TrayClickBindings>;
+
+pub fn load_bindings(app: &AppHandle) -> TrayClickBindings {
+    settings::load(app).tray_click_bindings
+}
+
+pub fn apply(tray: &TrayIcon, menu: &Menu<Wry>, bindings: TrayClickBindings) -> tauri::Result<()> {
+    *tray.app_handle().state::<TrayClickState>().lock().unwrap() = bindings;
+    // On Linux the menu is the only way to interact with the icon
+    let attached = bindings.menu_attached() || cfg!(target_os = "linux");
+    tray.set_menu(attached.then(|| menu.clone()))?;
+    tray.set_show_menu_on_left_click(bindings.left == TrayClickAction::ShowMenu)
+}
+
+fn show_main_window(app: &AppHandle) {
+    main_window::show(app);
+}
+
+fn run(app: &AppHandle, action: TrayClickAction, rect: Rect) {
+    match action {
+        // The menu itself is opened natively
+        TrayClickAction::ShowMenu | TrayClickAction::Ignore => {}
+        TrayClickAction::QuickTranslate => {
+            // Tray rects are always physical, so the scale factor is unused
+            let position = rect.position.to_physical::<f64>(1.0);
+            let size = rect.size.to_physical::<f64>(1.0);
+            let anchor = PopupAnchor::Rect {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            };
+
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = popup::translate_clipboard(app, anchor).await {
+                    tracing::error!("Quick translate failed: {}", e);
+                }
+            });
+        }
+        TrayClickAction::TogglePause => {
+            monitoring::toggle(app);
+        }
+        TrayClickAction::OpenMainWindow => show_main_window(app),
+        TrayClickAction::NewPanel => {
+            if let Err(e) = crate::open_floating_window(app) {
+                tracing::error!("Tray action failed: {}", e);
+            }
+        }
+    }
+}
+
+pub fn handle_event(app: &AppHandle, event: TrayIconEvent) {
+    // Moves and hovers come through here too; only clicks need the bindings
+    if !matches!(event, TrayIconEvent::Click { .. } | TrayIconEvent::DoubleClick { .. }) {
+        return;
+    }
+    let bindings = *app.state::<TrayClickState>().lock().unwrap();
+    match event {
+        TrayIconEvent::Click {
+            button,
+            button_state: MouseButtonState::Up,
+            rect,
+            ..
+        } => {
+            let action = match button {
+                MouseButton::Left => bindings.left,
+                MouseButton::Right => bindings.right,
+                MouseButton::Middle => bindings.middle,
+            };
+            run(app, action, rect);
+        }
+        TrayIconEvent::DoubleClick {
+            button: MouseButton::Left,
+            rect,
+            ..
+        } => run(app, bindings.double_click, rect),
+        _ => {}
+    }
+}
+
+#[tauri::command]
+pub async fn get_tray_click_bindings(app: AppHandle) -> Result<TrayClickBindings, AppError> {
+    Ok(load_bindings(&app))
+}
+
+#[tauri::command]
+pub async fn set_tray_click_bindings(app: AppHandle, bindings: TrayClickBindings) -> Result<(), AppError> {
+    bindings.validate()?;
+    settings::update(&app, |settings| settings.tray_click_bindings = bindings)?;
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        apply(&tray, &app.state::<TrayMenu>().0, bindings)
+            .map_err(|e| format!("Failed to update tray: {}", e))?;
+    }
+    Ok(())
+}
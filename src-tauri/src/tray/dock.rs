@@ -0,0 +1,49 @@
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::settings;
+
+pub fn menubar_only(app: &AppHandle) -> bool {
+    settings::load(app).menubar_only
+}
+
+// Accessory apps have no Dock icon or app menu but can still show windows,
+// so panels and the popup keep working from the tray
+#[cfg(target_os = "macos")]
+pub fn set_policy(app: &AppHandle, menubar_only: bool) -> Result<(), String> {
+    let policy = if menubar_only {
+        tauri::ActivationPolicy::Accessory
+    } else {
+        tauri::ActivationPolicy::Regular
+    };
+    app.set_activation_policy(policy)
+        .map_err(|e| format!("Failed to set activation policy: {}", e))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_policy(_app: &AppHandle, menubar_only: bool) -> Result<(), String> {
+    if menubar_only {
+        return Err("Menubar-only mode is only available on macOS".to_string());
+    }
+    Ok(())
+}
+
+// Applies the saved setting; called once the tray exists to fall back on
+pub fn apply(app: &AppHandle) {
+    if menubar_only(app) {
+        if let Err(e) = set_policy(app, true) {
+            tracing::error!("{}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_menubar_only(app: AppHandle) -> Result<bool, AppError> {
+    Ok(menubar_only(&app))
+}
+
+#[tauri::command]
+pub async fn set_menubar_only(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    set_policy(&app, enabled)?;
+    Ok(settings::update(&app, |settings| settings.menubar_only = enabled).map(|_| ())?)
+}
@@ -0,0 +1,126 @@
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Listener, Manager};
+
+pub mod clicks;
+pub mod dock;
+mod recent;
+mod status;
+
+use crate::events::{self, AppEvent};
+use crate::main_window;
+use crate::monitoring::{self, MonitoringStatus};
+use crate::settings;
+
+pub const TRAY_ID: &str = "main";
+
+const AUTO_TRANSLATE_ITEM: &str = "auto-translate";
+const NEW_PANEL_ITEM: &str = "new-panel";
+const SETTINGS_ITEM: &str = "settings";
+const PAUSE_ITEM: &str = "pause-monitoring";
+const QUIT_ITEM: &str = "quit";
+
+pub fn auto_translate_enabled(app: &AppHandle) -> bool {
+    settings::load(app).auto_translate
+}
+
+fn set_auto_translate(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(app, |settings| settings.auto_translate = enabled)?;
+    events::broadcast(app, AppEvent::AutoTranslateChanged(enabled));
+    Ok(())
+}
+
+fn open_settings(app: &AppHandle) {
+    if let Some(window) = main_window::show(app) {
+        events::send(app, window.label(), AppEvent::OpenSettings);
+    }
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let auto_translate = CheckMenuItem::with_id(
+        app,
+        AUTO_TRANSLATE_ITEM,
+        "Auto-translate",
+        true,
+        auto_translate_enabled(app),
+        None::<&str>,
+    )?;
+    let pause = CheckMenuItem::with_id(
+        app,
+        PAUSE_ITEM,
+        "Pause Monitoring",
+        true,
+        monitoring::is_paused(app),
+        None::<&str>,
+    )?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &auto_translate,
+            &MenuItem::with_id(app, NEW_PANEL_ITEM, "New Floating Panel", true, None::<&str>)?,
+            &MenuItem::with_id(app, SETTINGS_ITEM, "Settings…", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &recent::submenu(app)?,
+            &PredefinedMenuItem::separator(app)?,
+            &pause,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, QUIT_ITEM, "Quit Shunyaku", true, None::<&str>)?,
+        ],
+    )?;
+
+    // Keep the check marks in sync when these are changed elsewhere
+    let auto_translate_item = auto_translate.clone();
+    app.listen_any("auto-translate-changed", move |event| {
+        if let Ok(enabled) = serde_json::from_str::<bool>(event.payload()) {
+            let _ = auto_translate_item.set_checked(enabled);
+        }
+    });
+    let pause_item = pause.clone();
+    app.listen_any("monitoring-status", move |event| {
+        if let Ok(status) = serde_json::from_str::<MonitoringStatus>(event.payload()) {
+            let _ = pause_item.set_checked(status.paused);
+        }
+    });
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Shunyaku")
+        .on_tray_icon_event(|tray, event| clicks::handle_event(tray.app_handle(), event))
+        .on_menu_event(move |app, event| {
+            let result = match event.id().as_ref() {
+                AUTO_TRANSLATE_ITEM => {
+                    set_auto_translate(app, auto_translate.is_checked().unwrap_or(false))
+                }
+                NEW_PANEL_ITEM => crate::open_floating_window(app).map(|_| ()),
+                SETTINGS_ITEM => {
+                    open_settings(app);
+                    Ok(())
+                }
+                PAUSE_ITEM => {
+                    monitoring::set_paused(app, pause.is_checked().unwrap_or(false));
+                    Ok(())
+                }
+                QUIT_ITEM => {
+                    app.exit(0);
+                    Ok(())
+                }
+                id => recent::handle_menu_event(app, id).map(|_| ()),
+            };
+            if let Err(e) = result {
+                tracing::error!("Tray action failed: {}", e);
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    // Managed before the icon exists, since its events read it
+    let bindings = clicks::load_bindings(app);
+    app.manage(clicks::TrayClickState::new(bindings));
+    let tray = builder.build(app)?;
+    clicks::apply(&tray, &menu, bindings)?;
+    app.manage(clicks::TrayMenu(menu));
+
+    status::init(app);
+    dock::apply(app);
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use tauri::menu::{MenuItem, Submenu};
+use tauri::{AppHandle, Listener};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::events::{self, AppEvent};
+use crate::{history, session};
+
+const RECENT_COUNT: usize = 5;
+const LABEL_LIMIT: usize = 40;
+
+// Menu ids of recent entries are this prefix plus the history id
+const ITEM_PREFIX: &str = "recent:";
+
+fn fill(app: &AppHandle, submenu: &Submenu<tauri::Wry>) -> tauri::Result<()> {
+    while submenu.remove_at(0)?.is_some() {}
+
+    let entries = history::recent(app, RECENT_COUNT).unwrap_or_else(|e| {
+        tracing::error!("Failed to read recent translations: {}", e);
+        Vec::new()
+    });
+    if entries.is_empty() {
+        submenu.append(&MenuItem::new(app, "No translations yet", false, None::<&str>)?)?;
+    }
+    for entry in entries {
+        submenu.append(&MenuItem::with_id(
+            app,
+            format!("{}{}", ITEM_PREFIX, entry.id),
+            history::preview(&entry.result.translated_text, LABEL_LIMIT),
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    Ok(())
+}
+
+// Builds the submenu and keeps it in step with history from then on
+pub fn submenu(app: &AppHandle) -> tauri::Result<Submenu<tauri::Wry>> {
+    let submenu = Submenu::new(app, "Recent Translations", true)?;
+    fill(app, &submenu)?;
+
+    let handle = app.clone();
+    let recent = submenu.clone();
+    app.listen_any("history-changed", move |_| {
+        if let Err(e) = fill(&handle, &recent) {
+            tracing::error!("Failed to rebuild recent translations menu: {}", e);
+        }
+    });
+    Ok(submenu)
+}
+
+// Raises the panel the translation is showing in, or copies it to the
+// clipboard once that panel is gone. Returns false for other menu ids.
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> Result<bool, String> {
+    let Some(entry_id) = id.strip_prefix(ITEM_PREFIX).and_then(|id| id.parse().ok()) else {
+        return Ok(false);
+    };
+    let entry = history::get(app, entry_id)?.ok_or_else(|| "Translation is no longer in history".to_string())?;
+
+    let window = entry
+        .window_id
+        .as_deref()
+        .and_then(|window_id| session::window(app, window_id));
+    match window {
+        Some(window) => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+            events::send(app, window.label(), AppEvent::ShowHistoryEntry(entry));
+        }
+        None => app
+            .clipboard()
+            .write_text(entry.result.translated_text)
+            .map_err(|e| format!("Failed to copy translation: {}", e))?,
+    }
+    Ok(true)
+}
@@ -0,0 +1,239 @@
+use std::f64::consts::TAU;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::{AppHandle, Listener, Manager};
+
+use super::TRAY_ID;
+use crate::monitoring::{self, MonitoringStatus};
+use crate::translation::usage::{ProviderUsage, UsageState};
+use crate::translation::TranslationActivity;
+
+const FALLBACK_SIZE: u32 = 32;
+const SPINNER_FRAMES: usize = 8;
+const SPINNER_INTERVAL: Duration = Duration::from_millis(120);
+
+const PAUSED_COLOR: [u8; 3] = [142, 142, 147];
+const TRANSLATING_COLOR: [u8; 3] = [10, 132, 255];
+const ERROR_COLOR: [u8; 3] = [255, 59, 48];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayStatus {
+    Active,
+    Paused,
+    Translating,
+    Error,
+}
+
+// Inputs the icon is derived from. A failed translation keeps the error badge
+// up until the next one succeeds.
+#[derive(Default)]
+pub struct TrayIndicator {
+    paused: bool,
+    in_flight: usize,
+    error: Option<String>,
+    spinning: bool,
+    // Quota line such as "DeepL: 312k/500k chars"
+    usage: Option<String>,
+}
+
+impl TrayIndicator {
+    fn status(&self) -> TrayStatus {
+        if self.in_flight > 0 {
+            TrayStatus::Translating
+        } else if self.error.is_some() {
+            TrayStatus::Error
+        } else if self.paused {
+            TrayStatus::Paused
+        } else {
+            TrayStatus::Active
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let status = match (self.status(), &self.error) {
+            (TrayStatus::Translating, _) => "Shunyaku — translating…".to_string(),
+            (TrayStatus::Error, Some(error)) => format!("Shunyaku — {}", error),
+            (TrayStatus::Paused, _) => "Shunyaku — paused".to_string(),
+            _ => "Shunyaku".to_string(),
+        };
+        match &self.usage {
+            Some(usage) => format!("{}\n{}", status, usage),
+            None => status,
+        }
+    }
+}
+
+pub type TrayIndicatorState = Mutex<TrayIndicator>;
+
+fn base_icon(app: &AppHandle) -> (Vec<u8>, u32, u32) {
+    if let Some(icon) = app.default_window_icon() {
+        return (icon.rgba().to_vec(), icon.width(), icon.height());
+    }
+
+    // No bundled icon (e.g. dev builds without icons): a plain dark disc
+    let size = FALLBACK_SIZE;
+    let mut rgba = vec![0u8; (size * size * 4) as usize];
+    let center = size as f64 / 2.0;
+    for y in 0..size {
+        for x in 0..size {
+            let distance = ((x as f64 + 0.5 - center).powi(2) + (y as f64 + 0.5 - center).powi(2)).sqrt();
+            if distance <= center - 1.0 {
+                let i = ((y * size + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&[40, 40, 48, 255]);
+            }
+        }
+    }
+    (rgba, size, size)
+}
+
+// Base icon with a status badge in the bottom-right corner. The translating
+// badge is a ring with a gap that rotates with `frame`.
+fn render(app: &AppHandle, status: TrayStatus, frame: usize) -> Image<'static> {
+    let (mut rgba, width, height) = base_icon(app);
+    let color = match status {
+        TrayStatus::Active => return Image::new_owned(rgba, width, height),
+        TrayStatus::Paused => PAUSED_COLOR,
+        TrayStatus::Translating => TRANSLATING_COLOR,
+        TrayStatus::Error => ERROR_COLOR,
+    };
+
+    let size = width.min(height) as f64;
+    let radius = size * 0.22;
+    let (cx, cy) = (width as f64 - radius - 1.0, height as f64 - radius - 1.0);
+    let gap_angle = frame as f64 / SPINNER_FRAMES as f64 * TAU;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance > radius {
+                continue;
+            }
+
+            let i = ((y * width + x) * 4) as usize;
+            let pixel = if status == TrayStatus::Translating {
+                let angle = dy.atan2(dx).rem_euclid(TAU);
+                let in_gap = (angle - gap_angle).rem_euclid(TAU) < TAU / 4.0;
+                if distance < radius * 0.55 || in_gap {
+                    [255, 255, 255, 255]
+                } else {
+                    [color[0], color[1], color[2], 255]
+                }
+            } else if distance > radius - 1.5 {
+                // Thin white outline keeps the badge visible on any menubar
+                [255, 255, 255, 255]
+            } else {
+                [color[0], color[1], color[2], 255]
+            };
+            rgba[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+fn refresh(app: &AppHandle, frame: usize) {
+    let (status, tooltip) = {
+        let indicator = app.state::<TrayIndicatorState>();
+        let indicator = indicator.lock().unwrap();
+        (indicator.status(), indicator.tooltip())
+    };
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_icon(Some(render(app, status, frame)));
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+// Animates the translating badge until nothing is in flight any more
+fn start_spinner(app: &AppHandle) {
+    {
+        let indicator = app.state::<TrayIndicatorState>();
+        let mut indicator = indicator.lock().unwrap();
+        if indicator.spinning {
+            return;
+        }
+        indicator.spinning = true;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut frame = 0;
+        loop {
+            {
+                let indicator = app.state::<TrayIndicatorState>();
+                let mut indicator = indicator.lock().unwrap();
+                if indicator.in_flight == 0 {
+                    indicator.spinning = false;
+                    break;
+                }
+            }
+            refresh(&app, frame);
+            frame = (frame + 1) % SPINNER_FRAMES;
+            thread::sleep(SPINNER_INTERVAL);
+        }
+        refresh(&app, 0);
+    });
+}
+
+pub fn init(app: &AppHandle) {
+    let usage = app
+        .state::<UsageState>()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(ProviderUsage::summary);
+    app.manage(TrayIndicatorState::new(TrayIndicator {
+        paused: monitoring::is_paused(app),
+        usage,
+        ..Default::default()
+    }));
+    refresh(app, 0);
+
+    let handle = app.clone();
+    app.listen_any("usage-updated", move |event| {
+        if let Ok(usage) = serde_json::from_str::<ProviderUsage>(event.payload()) {
+            handle.state::<TrayIndicatorState>().lock().unwrap().usage = Some(usage.summary());
+            refresh(&handle, 0);
+        }
+    });
+
+    let handle = app.clone();
+    app.listen_any("monitoring-status", move |event| {
+        if let Ok(status) = serde_json::from_str::<MonitoringStatus>(event.payload()) {
+            handle.state::<TrayIndicatorState>().lock().unwrap().paused = status.paused;
+            refresh(&handle, 0);
+        }
+    });
+
+    let handle = app.clone();
+    app.listen_any("translation-status", move |event| {
+        let Ok(activity) = serde_json::from_str::<TranslationActivity>(event.payload()) else {
+            return;
+        };
+
+        let started = matches!(activity, TranslationActivity::Started);
+        {
+            let indicator = handle.state::<TrayIndicatorState>();
+            let mut indicator = indicator.lock().unwrap();
+            match activity {
+                TranslationActivity::Started => indicator.in_flight += 1,
+                TranslationActivity::Completed => {
+                    indicator.in_flight = indicator.in_flight.saturating_sub(1);
+                    indicator.error = None;
+                }
+                TranslationActivity::Failed { error, .. } => {
+                    indicator.in_flight = indicator.in_flight.saturating_sub(1);
+                    indicator.error = Some(error);
+                }
+            }
+        }
+
+        if started {
+            start_spinner(&handle);
+        } else {
+            refresh(&handle, 0);
+        }
+    });
+}
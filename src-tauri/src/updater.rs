@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::settings;
+
+// Stable follows the latest release; beta follows a rolling "beta" release
+// that prereleases are uploaded to
+const STABLE_ENDPOINT: &str = "https://github.com/RYUKOU-OKUMURA/Shunyaku/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str = "https://github.com/RYUKOU-OKUMURA/Shunyaku/releases/download/beta/latest.json";
+// The first automatic check waits for startup to settle
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+// Download progress is emitted at most this often
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    // Downloaded and waiting to be installed when the app quits
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub version: String,
+    pub downloaded: u64,
+    // None when the server doesn't say
+    pub total: Option<u64>,
+}
+
+// `available` is what the last check found; `pending` is a verified download
+// installed on quit, so the next launch runs the new version
+#[derive(Default)]
+pub struct UpdateState {
+    available: Mutex<Option<(Update, UpdateChannel)>>,
+    pending: Mutex<Option<(Update, Vec<u8>)>>,
+    downloading: AtomicBool,
+}
+
+fn info(update: &Update, channel: UpdateChannel, ready: bool) -> UpdateInfo {
+    UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel,
+        notes: update.body.clone(),
+        date: update.date.map(|date| date.to_string()),
+        ready,
+    }
+}
+
+fn pending_version(app: &AppHandle) -> Option<String> {
+    let state = app.state::<UpdateState>();
+    let pending = state.pending.lock().unwrap();
+    pending.as_ref().map(|(update, _)| update.version.clone())
+}
+
+// Releases are only installable when the build carries the key they're
+// signed with
+fn has_pubkey(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|config| config.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .is_some_and(|pubkey| !pubkey.trim().is_empty())
+}
+
+async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    if !has_pubkey(app) {
+        return Err(AppError::Other("This build isn't set up to receive updates".to_string()));
+    }
+    let channel = settings::load(app).update_channel;
+    let endpoint = Url::parse(channel.endpoint()).map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to check for updates: {}", e)))?;
+
+    let state = app.state::<UpdateState>();
+    let Some(update) = update else {
+        *state.available.lock().unwrap() = None;
+        return Ok(None);
+    };
+    let ready = pending_version(app).as_deref() == Some(update.version.as_str());
+    let found = info(&update, channel, ready);
+    *state.available.lock().unwrap() = Some((update, channel));
+    events::broadcast(app, AppEvent::UpdateAvailable(found.clone()));
+    Ok(Some(found))
+}
+
+// Checks on startup and then daily while automatic checks are on. Finding an
+// update only announces it; downloading waits for the user.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            if settings::load(&app).auto_check_updates && has_pubkey(&app) {
+                if let Err(e) = check(&app).await {
+                    tracing::warn!("{}", e);
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Installs a downloaded update; called last thing on quit. On Windows this
+// hands over to the installer, which ends the process.
+pub fn install_pending(app: &AppHandle) {
+    let Some((update, bytes)) = app.state::<UpdateState>().pending.lock().unwrap().take() else {
+        return;
+    };
+    tracing::info!("Installing update {}", update.version);
+    if let Err(e) = update.install(bytes) {
+        tracing::error!("Failed to install update {}: {}", update.version, e);
+    }
+}
+
+// Looks for a newer release on the channel in settings. None when this is
+// the latest.
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    check(&app).await
+}
+
+// Downloads and verifies what the last check found, emitting update-progress
+// along the way and update-ready when it's in place. The install happens on
+// quit.
+#[tauri::command]
+pub async fn download_update(app: AppHandle) -> Result<UpdateInfo, AppError> {
+    let state = app.state::<UpdateState>();
+    let (update, channel) = state
+        .available
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NotFound("No update to download; check for updates first".to_string()))?;
+    if pending_version(&app).as_deref() == Some(update.version.as_str()) {
+        return Ok(info(&update, channel, true));
+    }
+    if state.downloading.swap(true, Ordering::SeqCst) {
+        return Err(AppError::InvalidInput("An update is already downloading".to_string()));
+    }
+
+    let mut downloaded: u64 = 0;
+    let mut last_emit: Option<Instant> = None;
+    let result = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                if last_emit.is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL) {
+                    return;
+                }
+                last_emit = Some(Instant::now());
+                let progress = UpdateProgress {
+                    version: update.version.clone(),
+                    downloaded,
+                    total,
+                };
+                events::broadcast(&app, AppEvent::UpdateProgress(progress));
+            },
+            || {},
+        )
+        .await;
+    state.downloading.store(false, Ordering::SeqCst);
+    let bytes = result.map_err(|e| AppError::Network(format!("Failed to download update: {}", e)))?;
+
+    let ready = info(&update, channel, true);
+    *state.pending.lock().unwrap() = Some((update, bytes));
+    events::broadcast(&app, AppEvent::UpdateReady(ready.clone()));
+    Ok(ready)
+}
@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::history::HistoryOrigin;
+use crate::settings;
+use crate::translation::usage::ProviderUsage;
+use crate::translation::{http_client, TranslationResult};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Waits before each retry; a delivery is given up after the last
+const RETRY_DELAYS: [Duration; 3] = [Duration::from_secs(2), Duration::from_secs(10), Duration::from_secs(30)];
+const SIGNATURE_HEADER: &str = "X-Shunyaku-Signature";
+const EVENT_HEADER: &str = "X-Shunyaku-Event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    // Every translation that reaches history, whatever its origin
+    TranslationComplete,
+    // Provider usage reached `quotaThreshold` percent of the limit
+    QuotaThreshold,
+    // Sent by test_webhook only
+    Test,
+}
+
+// A URL that gets a JSON POST for each of `events`. With a secret, the body
+// is signed with HMAC-SHA256 and the hex digest sent as
+// X-Shunyaku-Signature: sha256=<digest>.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Webhook {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub secret: String,
+    pub enabled: bool,
+}
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            events: vec![WebhookEvent::TranslationComplete],
+            secret: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookSettings {
+    pub hooks: Vec<Webhook>,
+    // Percent of the provider's character limit
+    pub quota_threshold: u8,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            hooks: Vec::new(),
+            quota_threshold: 80,
+        }
+    }
+}
+
+impl WebhookSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.quota_threshold) {
+            return Err("Quota threshold must be between 1 and 100 percent".to_string());
+        }
+        for (index, hook) in self.hooks.iter().enumerate() {
+            let valid = reqwest::Url::parse(&hook.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !valid {
+                return Err(format!("Webhook {}: '{}' is not an http(s) URL", index + 1, hook.url));
+            }
+            if hook.events.is_empty() {
+                return Err(format!("Webhook {}: choose at least one event", index + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Whether the quota has been reported since it was last under the threshold
+#[derive(Default)]
+pub struct WebhookState(AtomicBool);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload<T: Serialize> {
+    event: WebhookEvent,
+    sent_at: DateTime<Utc>,
+    data: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslationData<'a> {
+    result: &'a TranslationResult,
+    origin: HistoryOrigin,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaData<'a> {
+    usage: &'a ProviderUsage,
+    threshold: u8,
+}
+
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", digest)
+}
+
+fn event_name(event: WebhookEvent) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+// One POST and the status it was answered with
+async fn post(hook: &Webhook, event: WebhookEvent, body: &[u8]) -> Result<u16, String> {
+    let mut request = http_client()
+        .post(&hook.url)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::USER_AGENT, concat!("Shunyaku/", env!("CARGO_PKG_VERSION")))
+        .header(EVENT_HEADER, event_name(event))
+        .body(body.to_vec());
+    if !hook.secret.is_empty() {
+        request = request.header(SIGNATURE_HEADER, signature(&hook.secret, body));
+    }
+    let response = request.send().await.map_err(|e| format!("Failed to reach {}: {}", hook.url, e))?;
+    Ok(response.status().as_u16())
+}
+
+// Client errors other than 429 won't change on a retry
+async fn deliver(hook: Webhook, event: WebhookEvent, body: Vec<u8>) {
+    let mut delays = RETRY_DELAYS.iter();
+    loop {
+        let outcome = post(&hook, event, &body).await;
+        let retry = match &outcome {
+            Ok(status) => *status == 429 || *status >= 500,
+            Err(_) => true,
+        };
+        if !retry {
+            if let Some(status) = outcome.ok().filter(|status| !(200..300).contains(status)) {
+                tracing::warn!("Webhook {} answered {}", hook.url, status);
+            }
+            return;
+        }
+        let Some(delay) = delays.next() else {
+            match outcome {
+                Ok(status) => tracing::error!("Webhook {} gave up after {}", hook.url, status),
+                Err(e) => tracing::error!("Webhook gave up: {}", e),
+            }
+            return;
+        };
+        tokio::time::sleep(*delay).await;
+    }
+}
+
+fn send<T: Serialize>(app: &AppHandle, event: WebhookEvent, data: T) {
+    let hooks: Vec<Webhook> = settings::load(app)
+        .webhooks
+        .hooks
+        .into_iter()
+        .filter(|hook| hook.enabled && hook.events.contains(&event))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let payload = Payload {
+        event,
+        sent_at: Utc::now(),
+        data,
+    };
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to serialize webhook payload: {}", e);
+            return;
+        }
+    };
+    for hook in hooks {
+        tauri::async_runtime::spawn(deliver(hook, event, body.clone()));
+    }
+}
+
+// Called from history::record, next to auto-speak
+pub fn translated(app: &AppHandle, result: &TranslationResult, origin: HistoryOrigin) {
+    send(app, WebhookEvent::TranslationComplete, TranslationData { result, origin });
+}
+
+// Fires once when usage crosses the threshold, and again only after it has
+// dropped back under, as it does when a new billing period starts
+pub fn usage_updated(app: &AppHandle, usage: &ProviderUsage) {
+    if usage.character_limit == 0 {
+        return;
+    }
+    let threshold = settings::load(app).webhooks.quota_threshold;
+    let over = usage.character_count * 100 >= usage.character_limit * u64::from(threshold);
+    let alerted = app.state::<WebhookState>();
+    if !over {
+        alerted.0.store(false, Ordering::SeqCst);
+    } else if !alerted.0.swap(true, Ordering::SeqCst) {
+        send(app, WebhookEvent::QuotaThreshold, QuotaData { usage, threshold });
+    }
+}
+
+// Sends a test event to one configured webhook once, without retries, and
+// returns the status it answered with
+#[tauri::command]
+pub async fn test_webhook(app: AppHandle, index: usize) -> Result<u16, AppError> {
+    let hook = settings::load(&app)
+        .webhooks
+        .hooks
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| AppError::NotFound(format!("Webhook {} not found", index + 1)))?;
+    let payload = Payload {
+        event: WebhookEvent::Test,
+        sent_at: Utc::now(),
+        data: serde_json::json!({}),
+    };
+    let body = serde_json::to_vec(&payload).map_err(|e| format!("Failed to serialize webhook payload: {}", e))?;
+    Ok(post(&hook, WebhookEvent::Test, &body).await?)
+}
@@ -0,0 +1,79 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+// A cold webview on a slow machine takes a few seconds; much longer and the
+// page has failed to load
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Windows whose page has loaded and set up its listeners. An event emitted
+// before then is lost, so anything a new window needs at start waits here.
+#[derive(Default)]
+pub struct ReadyState {
+    ready: Mutex<HashSet<String>>,
+    waiting: Mutex<HashMap<String, Vec<oneshot::Sender<()>>>>,
+}
+
+// Page (re)loading: its listeners are gone until it says it's ready again
+pub fn loading(app: &AppHandle, label: &str) {
+    app.state::<ReadyState>().ready.lock().unwrap().remove(label);
+}
+
+pub fn forget(app: &AppHandle, label: &str) {
+    let state = app.state::<ReadyState>();
+    state.ready.lock().unwrap().remove(label);
+    // Dropping the senders wakes the waiters with an error
+    state.waiting.lock().unwrap().remove(label);
+}
+
+pub async fn wait(app: &AppHandle, label: &str) -> Result<(), AppError> {
+    let receiver = {
+        let state = app.state::<ReadyState>();
+        // Checked under the waiting lock so a ready call can't slip in between
+        let mut waiting = state.waiting.lock().unwrap();
+        if state.ready.lock().unwrap().contains(label) {
+            return Ok(());
+        }
+        let (sender, receiver) = oneshot::channel();
+        waiting.entry(label.to_string()).or_default().push(sender);
+        receiver
+    };
+    match tokio::time::timeout(READY_TIMEOUT, receiver).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) => Err(AppError::WindowNotFound(label.to_string())),
+        Err(_) => Err(AppError::Other(format!(
+            "Window {} did not finish loading within {}s",
+            label,
+            READY_TIMEOUT.as_secs()
+        ))),
+    }
+}
+
+// Delivers an initialization payload once the window can hear it
+pub fn send_when_ready(app: &AppHandle, label: &str, event: AppEvent) {
+    let app = app.clone();
+    let label = label.to_string();
+    tauri::async_runtime::spawn(async move {
+        match wait(&app, &label).await {
+            Ok(()) => events::send(&app, &label, event),
+            Err(e) => tracing::error!("Failed to initialize {}: {}", label, e),
+        }
+    });
+}
+
+// Called by each page once its event listeners are registered
+#[tauri::command]
+pub async fn window_ready(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    let state = app.state::<ReadyState>();
+    let mut waiting = state.waiting.lock().unwrap();
+    state.ready.lock().unwrap().insert(window.label().to_string());
+    for sender in waiting.remove(window.label()).unwrap_or_default() {
+        let _ = sender.send(());
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+// Floating panels that are open, keyed by window label. Each accessor takes
+// the lock only for the map operation itself, so nothing holds it across an
+// await or while a window call runs.
+#[derive(Default)]
+pub struct WindowStore {
+    // Label to the order it was opened in
+    windows: RwLock<HashMap<String, u64>>,
+    next: AtomicU64,
+}
+
+impl WindowStore {
+    pub fn insert(&self, id: &str) {
+        let order = self.next.fetch_add(1, Ordering::SeqCst);
+        self.windows.write().unwrap().insert(id.to_string(), order);
+    }
+
+    // True when the window was in the store
+    pub fn remove(&self, id: &str) -> bool {
+        self.windows.write().unwrap().remove(id).is_some()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.windows.read().unwrap().contains_key(id)
+    }
+
+    // Oldest first, so the last one is the most recently opened
+    pub fn ids(&self) -> Vec<String> {
+        let windows: Vec<(String, u64)> =
+            self.windows.read().unwrap().iter().map(|(id, order)| (id.clone(), *order)).collect();
+        in_order(windows)
+    }
+
+    // Empties the store, returning what was in it oldest first
+    pub fn take_all(&self) -> Vec<String> {
+        let windows: Vec<(String, u64)> = self.windows.write().unwrap().drain().collect();
+        in_order(windows)
+    }
+}
+
+fn in_order(mut windows: Vec<(String, u64)>) -> Vec<String> {
+    windows.sort_by_key(|(_, order)| *order);
+    windows.into_iter().map(|(id, _)| id).collect()
+}
@@ -1,4 +1,5 @@
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import PipelinePrototype from './components/PipelinePrototype';
 import PerformanceTestSuite from './components/PerformanceTestSuite';
 import KeyboardShortcutsDemo from './components/KeyboardShortcutsDemo';
@@ -9,6 +10,12 @@ type AppView = 'pipeline' | 'tests' | 'ui-demo' | 'preprocessing-test';
 function App() {
   const [activeView, setActiveView] = useState<AppView>('pipeline');
 
+  // Main window and floating panels both load this page; the backend holds
+  // their initialization events (and create_floating_window) until this call
+  useEffect(() => {
+    invoke('window_ready').catch((err) => console.error('Failed to report window ready:', err));
+  }, []);
+
   if (activeView === 'tests') {
     return (
       <div className="min-h-screen bg-gray-100">
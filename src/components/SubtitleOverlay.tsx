@@ -0,0 +1,60 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+interface SubtitleLine {
+  original: string;
+  // Null while the line is being translated
+  translated: string | null;
+  showOriginal: boolean;
+}
+
+// Transparent, click-through strip along the bottom of the screen showing
+// the translation of mpv's current subtitle line
+const SubtitleOverlay: React.FC = () => {
+  const [line, setLine] = useState<SubtitleLine | null>(null);
+
+  useEffect(() => {
+    document.documentElement.style.background = 'transparent';
+    document.body.style.background = 'transparent';
+
+    let unlisten: (() => void) | undefined;
+    const setup = async () => {
+      try {
+        unlisten = await listen<SubtitleLine>('subtitle-line', (event) => {
+          setLine(event.payload);
+        });
+        // The line on screen before this page finished loading
+        setLine(await invoke<SubtitleLine>('get_subtitle_line'));
+        await invoke('window_ready');
+      } catch (err) {
+        console.error('Failed to set up subtitle overlay:', err);
+      }
+    };
+
+    setup();
+    return () => unlisten?.();
+  }, []);
+
+  if (!line || !line.original) {
+    return null;
+  }
+
+  const shadow = { textShadow: '0 0 4px rgba(0, 0, 0, 0.9), 0 1px 2px rgba(0, 0, 0, 0.9)' };
+  return (
+    <div className="fixed inset-0 pointer-events-none select-none flex flex-col items-center justify-end pb-4 text-center text-white">
+      {line.showOriginal && (
+        <p className="whitespace-pre-line text-lg opacity-80" style={shadow}>
+          {line.original}
+        </p>
+      )}
+      {line.translated !== null && (
+        <p className="whitespace-pre-line text-2xl font-semibold" style={shadow}>
+          {line.translated}
+        </p>
+      )}
+    </div>
+  );
+};
+
+export default SubtitleOverlay;
@@ -76,6 +76,8 @@ const TauriMultiWindowTest: React.FC = () => {
             setWindowType('floating');
           }
         });
+        // Tells the backend initialization events can now be delivered
+        await invoke('window_ready');
 
         return unlisten;
       } catch (err) {
@@ -0,0 +1,58 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+interface OverlayLabel {
+  text: string;
+  bbox: { x0: number; y0: number; x1: number; y1: number };
+}
+
+// Full-monitor, click-through window that draws each translation over the
+// box OCR found its source text in. Coordinates arrive in CSS pixels.
+const TranslationOverlay: React.FC = () => {
+  const [labels, setLabels] = useState<OverlayLabel[]>([]);
+
+  useEffect(() => {
+    document.documentElement.style.background = 'transparent';
+    document.body.style.background = 'transparent';
+
+    let unlisten: (() => void) | undefined;
+    const setup = async () => {
+      try {
+        unlisten = await listen<OverlayLabel[]>('overlay-labels', (event) => {
+          setLabels(event.payload);
+        });
+        // Labels shown before this page finished loading
+        setLabels(await invoke<OverlayLabel[]>('get_overlay_labels'));
+        await invoke('window_ready');
+      } catch (err) {
+        console.error('Failed to set up overlay:', err);
+      }
+    };
+
+    setup();
+    return () => unlisten?.();
+  }, []);
+
+  return (
+    <div className="fixed inset-0 pointer-events-none select-none">
+      {labels.map((label, index) => (
+        <div
+          key={index}
+          className="absolute overflow-hidden rounded bg-black/75 px-1 text-white leading-tight"
+          style={{
+            left: label.bbox.x0,
+            top: label.bbox.y0,
+            minWidth: Math.max(label.bbox.x1 - label.bbox.x0, 0),
+            minHeight: Math.max(label.bbox.y1 - label.bbox.y0, 0),
+            fontSize: Math.max((label.bbox.y1 - label.bbox.y0) * 0.7, 10),
+          }}
+        >
+          {label.text}
+        </div>
+      ))}
+    </div>
+  );
+};
+
+export default TranslationOverlay;
@@ -0,0 +1,98 @@
+import React, { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+interface TranslationResult {
+  originalText: string;
+  translatedText: string;
+  sourceLang: string;
+  targetLang: string;
+  provider: string;
+  processingTime: number;
+  timestamp: string;
+}
+
+type PopupContent =
+  | { status: 'pending'; text: string }
+  | { status: 'done'; result: TranslationResult }
+  | { status: 'failed'; error: string; code: string };
+
+// The small window shown next to the cursor for translate-selection and the
+// tray's quick translate. It is built hidden at startup and reused, so the
+// content comes by event; get_popup_content covers anything sent before
+// this page finished loading.
+const TranslationPopup: React.FC = () => {
+  const [content, setContent] = useState<PopupContent | null>(null);
+
+  const hide = useCallback(() => {
+    invoke('hide_translation_popup').catch((err) => console.error('Failed to hide popup:', err));
+  }, []);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    const setup = async () => {
+      try {
+        unlisten = await listen<PopupContent>('popup-content', (event) => {
+          setContent(event.payload);
+        });
+        const current = await invoke<PopupContent | null>('get_popup_content');
+        if (current) {
+          setContent(current);
+        }
+        await invoke('window_ready');
+      } catch (err) {
+        console.error('Failed to set up popup:', err);
+      }
+    };
+
+    setup();
+    return () => unlisten?.();
+  }, []);
+
+  useEffect(() => {
+    const onKeyDown = (event: KeyboardEvent) => {
+      if (event.key === 'Escape') {
+        hide();
+      }
+    };
+    window.addEventListener('keydown', onKeyDown);
+    return () => window.removeEventListener('keydown', onKeyDown);
+  }, [hide]);
+
+  return (
+    <div className="flex h-screen flex-col overflow-hidden bg-white text-sm text-gray-900">
+      <div className="flex items-center justify-between border-b border-gray-200 px-3 py-1.5 text-xs text-gray-500">
+        <span>
+          {content?.status === 'done'
+            ? `${content.result.sourceLang} → ${content.result.targetLang}`
+            : 'Shunyaku'}
+        </span>
+        <button
+          type="button"
+          onClick={hide}
+          className="rounded px-1.5 leading-none text-gray-400 hover:bg-gray-100 hover:text-gray-700"
+          aria-label="Close"
+        >
+          ×
+        </button>
+      </div>
+      <div className="flex-1 overflow-y-auto px-3 py-2">
+        {content === null && <p className="text-gray-400">Nothing translated yet</p>}
+        {content?.status === 'pending' && (
+          <>
+            <p className="line-clamp-2 text-gray-400">{content.text}</p>
+            <p className="mt-2 animate-pulse text-gray-500">Translating…</p>
+          </>
+        )}
+        {content?.status === 'done' && (
+          <p className="whitespace-pre-wrap select-text">{content.result.translatedText}</p>
+        )}
+        {content?.status === 'failed' && (
+          <p className="whitespace-pre-wrap text-red-600">{content.error}</p>
+        )}
+      </div>
+    </div>
+  );
+};
+
+export default TranslationPopup;
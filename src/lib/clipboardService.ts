@@ -1,5 +1,6 @@
 // クリップボード監視サービス
 import { invoke } from '@tauri-apps/api/core';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { ImageInput, ApiResponse } from '../types';
 
 export interface ClipboardImageData {
@@ -13,6 +14,9 @@ export class ClipboardService {
   private monitoringInterval: ReturnType<typeof setInterval> | null = null;
   private lastClipboardHash: string | null = null;
   private callbacks: Array<(data: ClipboardImageData) => void> = [];
+  // 監視の一時停止（トレイ・ホットキー）中はポーリングしても何もしない
+  private paused = false;
+  private unlistenStatus: UnlistenFn | null = null;
 
   constructor(private pollInterval: number = 1000) {}
 
@@ -24,8 +28,24 @@ export class ClipboardService {
     }
 
     this.isMonitoring = true;
+    invoke<{ paused: boolean }>('get_monitoring_status')
+      .then((status) => {
+        this.paused = status.paused;
+      })
+      .catch((error) => console.error('Failed to read monitoring status:', error));
+    listen<{ paused: boolean }>('monitoring-status', (event) => {
+      this.paused = event.payload.paused;
+    }).then((unlisten) => {
+      if (this.isMonitoring) {
+        this.unlistenStatus = unlisten;
+      } else {
+        unlisten();
+      }
+    });
     this.monitoringInterval = setInterval(() => {
-      this.checkClipboard();
+      if (!this.paused) {
+        this.checkClipboard();
+      }
     }, this.pollInterval);
 
     console.log('Clipboard monitoring started');
@@ -42,6 +62,8 @@ export class ClipboardService {
       clearInterval(this.monitoringInterval);
       this.monitoringInterval = null;
     }
+    this.unlistenStatus?.();
+    this.unlistenStatus = null;
 
     console.log('Clipboard monitoring stopped');
   }
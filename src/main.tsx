@@ -1,10 +1,28 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import TranslationOverlay from "./components/TranslationOverlay";
+import TranslationPopup from "./components/TranslationPopup";
+import SubtitleOverlay from "./components/SubtitleOverlay";
 import "./styles.css";
 
+// Windows other than the main window and panels load index.html with a hash
+// naming their page
+function Page() {
+  switch (window.location.hash) {
+    case "#overlay":
+      return <TranslationOverlay />;
+    case "#popup":
+      return <TranslationPopup />;
+    case "#subtitles":
+      return <SubtitleOverlay />;
+    default:
+      return <App />;
+  }
+}
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    <Page />
   </React.StrictMode>,
 );
\ No newline at end of file